@@ -0,0 +1,476 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use regex::Regex;
+
+use crate::diff;
+use crate::{LogLevel, LogLine};
+
+// What a watch rule looks for in each new line
+pub enum WatchMatcher {
+    Level(LogLevel),
+    Pattern(Regex),
+    Silence(Duration), // Dead-man switch: fires when no lines arrive for this long
+    Escalation(HashMap<String, LogLevel>), // Highest level each message template was seen at
+}
+
+// A registered watch pattern
+pub struct WatchRule {
+    pub spec: String,
+    pub matcher: WatchMatcher,
+    pub bell: bool,
+    pub notify: bool,
+    pub exec: Option<String>,
+    pub exec_cooldown: Duration,
+    pub last_exec: Option<Instant>,
+    pub webhook: Option<String>,
+    pub webhook_template: String,
+    pub threshold: Option<(usize, Duration)>, // Only fire for more than N matches within the window
+    pub recent_matches: VecDeque<Instant>,
+    pub silence_fired: bool,
+    pub snoozed_until: Option<Instant>,
+    pub muted: bool, // Muted for the rest of the session
+}
+
+// Templates an `escalation` rule remembers at most, forgetting them all when full
+const MAX_TEMPLATES: usize = 10_000;
+
+// Minimum time between two runs of a rule's exec command, unless overridden
+const DEFAULT_EXEC_COOLDOWN: Duration = Duration::from_secs(10);
+
+// JSON body posted to a rule's webhook unless it sets its own `template=`
+const DEFAULT_WEBHOOK_TEMPLATE: &str = r#"{"text": "[{rule}] {source}: {line}"}"#;
+
+// Deliveries waiting for the alert worker; past this, new ones are dropped
+// rather than letting a storm of alerts pile up behind a slow webhook
+const MAX_QUEUED_DELIVERIES: usize = 64;
+
+// Shortest time between two desktop notifications; alerts in between are
+// counted into the next one instead of each popping up
+const NOTIFY_INTERVAL: Duration = Duration::from_secs(2);
+
+// A single firing of a watch rule
+pub struct Alert {
+    pub time: DateTime<Local>,
+    pub rule: String,
+    pub line: String,
+    pub source: String,
+    pub seq: Option<u64>, // Sequence number of the matching log line, if any
+    pub acknowledged: bool,
+}
+
+impl WatchRule {
+    /// Parse a watch spec such as `level:error`, `timeout|refused` or `panic; bell`.
+    ///
+    /// The part before the first `;` is `level:<name>`, `silence:<seconds>`,
+    /// `escalation` (a message showing up at a higher level than it was seen
+    /// at before, WARNING or above) or a regex, and the remaining `;`-separated words are options (`bell` rings the
+    /// terminal bell, `notify` sends a desktop notification, `exec=<command>` runs
+    /// a shell command at most once per `cooldown=<seconds>`, `webhook=<url>` posts
+    /// the JSON `template=<json>` to a URL, and `rate=<count>/<seconds>` only
+    /// fires for more than `count` matches within the last `seconds`).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.split(';').map(str::trim);
+        let pattern = parts.next().unwrap_or("");
+        if pattern.is_empty() {
+            return Err("Empty watch pattern".to_string());
+        }
+
+        let matcher = if let Some(level) = pattern.strip_prefix("level:") {
+            WatchMatcher::Level(LogLevel::from_name(level).ok_or_else(|| format!("Unknown level: {}", level))?)
+        } else if let Some(secs) = pattern.strip_prefix("silence:") {
+            WatchMatcher::Silence(Duration::from_secs(parse_secs(secs)?))
+        } else if pattern == "escalation" {
+            WatchMatcher::Escalation(HashMap::new())
+        } else {
+            WatchMatcher::Pattern(Regex::new(pattern).map_err(|e| format!("Invalid regex: {}", e))?)
+        };
+
+        let mut rule = WatchRule {
+            spec: spec.trim().to_string(),
+            matcher,
+            bell: false,
+            notify: false,
+            exec: None,
+            exec_cooldown: DEFAULT_EXEC_COOLDOWN,
+            last_exec: None,
+            webhook: None,
+            webhook_template: DEFAULT_WEBHOOK_TEMPLATE.to_string(),
+            threshold: None,
+            recent_matches: VecDeque::new(),
+            silence_fired: false,
+            snoozed_until: None,
+            muted: false,
+        };
+        for option in parts.filter(|o| !o.is_empty()) {
+            match option {
+                "bell" => rule.bell = true,
+                "notify" => rule.notify = true,
+                _ if option.starts_with("exec=") => rule.exec = Some(option["exec=".len()..].to_string()),
+                _ if option.starts_with("cooldown=") => {
+                    rule.exec_cooldown = Duration::from_secs(parse_secs(&option["cooldown=".len()..])?);
+                }
+                _ if option.starts_with("webhook=") => rule.webhook = Some(option["webhook=".len()..].to_string()),
+                _ if option.starts_with("template=") => rule.webhook_template = option["template=".len()..].to_string(),
+                _ if option.starts_with("rate=") => {
+                    let (count, secs) = option["rate=".len()..]
+                        .split_once('/')
+                        .ok_or_else(|| format!("Expected rate=<count>/<seconds>: {}", option))?;
+                    let count = count.parse().map_err(|_| format!("Invalid rate count: {}", count))?;
+                    rule.threshold = Some((count, Duration::from_secs(parse_secs(secs)?)));
+                }
+                _ => return Err(format!("Unknown watch option: {}", option)),
+            }
+        }
+        Ok(rule)
+    }
+
+    pub fn matches(&self, line: &str, level: LogLevel) -> bool {
+        match &self.matcher {
+            WatchMatcher::Level(wanted) => level == *wanted,
+            WatchMatcher::Pattern(regex) => regex.is_match(line),
+            WatchMatcher::Silence(_) | WatchMatcher::Escalation(_) => false,
+        }
+    }
+
+    /// For an `escalation` rule, the level `log`'s message was seen at so far
+    /// when this line raises it to WARNING or above. Every leveled line is
+    /// taken into account, so call this whether or not the rule is active.
+    pub fn escalated_from(&mut self, log: &LogLine) -> Option<LogLevel> {
+        let WatchMatcher::Escalation(seen) = &mut self.matcher else {
+            return None;
+        };
+        if log.level == LogLevel::Unknown {
+            return None;
+        }
+        let key = escalation_key(log.message());
+        if seen.len() >= MAX_TEMPLATES && !seen.contains_key(&key) {
+            seen.clear();
+        }
+        let highest = seen.entry(key).or_insert(log.level);
+        if log.level.severity() <= highest.severity() {
+            return None;
+        }
+        let from = std::mem::replace(highest, log.level);
+        (log.level.severity() >= LogLevel::Warning.severity()).then_some(from)
+    }
+
+    /// Whether the rule is currently allowed to fire.
+    pub fn active(&self) -> bool {
+        !self.muted && self.snoozed_until.is_none_or(|until| Instant::now() >= until)
+    }
+
+    /// Snooze for `duration` more, extending an existing snooze.
+    pub fn snooze(&mut self, duration: Duration) {
+        let now = Instant::now();
+        let from = self.snoozed_until.filter(|until| *until > now).unwrap_or(now);
+        self.snoozed_until = Some(from + duration);
+    }
+
+    /// Short description of a snooze or mute, empty when the rule is active.
+    pub fn state_label(&self) -> String {
+        if self.muted {
+            return "muted".to_string();
+        }
+        match self.snoozed_until.map(|until| until.saturating_duration_since(Instant::now())) {
+            Some(left) if !left.is_zero() => format!("snoozed {}m{:02}s", left.as_secs() / 60, left.as_secs() % 60),
+            _ => String::new(),
+        }
+    }
+
+    /// Record a match at `now`, returning whether the rule should fire.
+    ///
+    /// Rules without a rate threshold fire on every match. Rate rules fire once
+    /// the rolling window holds more than the threshold, then start counting afresh.
+    pub fn record_match(&mut self, now: Instant) -> bool {
+        let Some((count, window)) = self.threshold else {
+            return true;
+        };
+        self.recent_matches.push_back(now);
+        while self.recent_matches.front().is_some_and(|t| now.duration_since(*t) > window) {
+            self.recent_matches.pop_front();
+        }
+        if self.recent_matches.len() > count {
+            self.recent_matches.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether a silence rule should fire after `quiet_for` without new lines.
+    /// Fires once per quiet period; `rearm_silence` is called when lines arrive.
+    pub fn silence_expired(&mut self, quiet_for: Duration) -> bool {
+        match self.matcher {
+            WatchMatcher::Silence(limit) if quiet_for >= limit && !self.silence_fired => {
+                self.silence_fired = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn rearm_silence(&mut self) {
+        self.silence_fired = false;
+    }
+
+    /// When a silence rule will next fire if no lines arrive after `last_line_at`.
+    pub fn silence_deadline(&self, last_line_at: Instant) -> Option<Instant> {
+        match self.matcher {
+            WatchMatcher::Silence(limit) if !self.silence_fired => Some(last_line_at + limit),
+            _ => None,
+        }
+    }
+
+    /// Run the rule's exec command through `worker` for a line matching at
+    /// `now`, unless it is still cooling down.
+    pub fn run_exec(&mut self, line: &str, now: Instant, worker: &mut AlertWorker) {
+        let Some(template) = &self.exec else {
+            return;
+        };
+        if self.last_exec.is_some_and(|last| now.saturating_duration_since(last) < self.exec_cooldown) {
+            return;
+        }
+        self.last_exec = Some(now);
+        worker.exec(template, &self.spec, line);
+    }
+}
+
+/// Delivers desktop notifications and webhook posts one at a time on a
+/// worker thread of its own, started with the first delivery, so a burst of
+/// alerts doesn't spawn a thread or process for each one. Exec commands,
+/// limited by their cooldown, each run on a thread of their own instead, so
+/// a slow one holds up nothing else.
+#[derive(Default)]
+pub struct AlertWorker {
+    queue: Option<Sender<Delivery>>,
+    thread: Option<JoinHandle<()>>,
+    commands: Vec<JoinHandle<()>>, // Exec commands that may still be running
+}
+
+enum Delivery {
+    Notify { rule: String, line: String },
+    Webhook { url: String, body: String },
+}
+
+impl AlertWorker {
+    /// Show a desktop notification for an alert, unless one was shown less
+    /// than `NOTIFY_INTERVAL` ago.
+    pub fn notify(&mut self, rule: &str, line: &str) {
+        self.send(Delivery::Notify { rule: rule.to_string(), line: line.to_string() });
+    }
+
+    /// Post an alert to a webhook.
+    ///
+    /// `{rule}`, `{line}`, `{source}` and `{time}` in the template are replaced with
+    /// JSON-escaped values (without surrounding quotes, the template provides those).
+    pub fn post_webhook(&mut self, url: &str, template: &str, alert: &Alert) {
+        let body = template
+            .replace("{rule}", &json_escape(&alert.rule))
+            .replace("{line}", &json_escape(&alert.line))
+            .replace("{source}", &json_escape(&alert.source))
+            .replace("{time}", &json_escape(&alert.time.to_rfc3339()));
+        self.send(Delivery::Webhook { url: url.to_string(), body });
+    }
+
+    /// Run a rule's exec command for an alert.
+    pub fn exec(&mut self, template: &str, rule: &str, line: &str) {
+        self.commands.retain(|command| !command.is_finished());
+        self.commands.push(run_alert_command(template, rule, line));
+    }
+
+    /// Wait for everything queued so far to be delivered, and for the exec
+    /// commands to finish, as before exiting.
+    pub fn finish(&mut self) {
+        self.queue = None;
+        for thread in self.thread.take().into_iter().chain(self.commands.drain(..)) {
+            let _ = thread.join();
+        }
+    }
+
+    fn send(&mut self, delivery: Delivery) {
+        let queue = self.queue.get_or_insert_with(|| {
+            let (queue, deliveries) = bounded(MAX_QUEUED_DELIVERIES);
+            self.thread = Some(thread::spawn(move || deliver(deliveries)));
+            queue
+        });
+        let _ = queue.try_send(delivery);
+    }
+}
+
+// The worker's loop, until the app lets go of the queue
+fn deliver(deliveries: Receiver<Delivery>) {
+    let mut last_notification: Option<Instant> = None;
+    let mut held_back = 0;
+    for delivery in deliveries {
+        match delivery {
+            Delivery::Notify { rule, line } => {
+                if last_notification.is_some_and(|at| at.elapsed() < NOTIFY_INTERVAL) {
+                    held_back += 1;
+                    continue;
+                }
+                last_notification = Some(Instant::now());
+                let title = match std::mem::take(&mut held_back) {
+                    0 => format!("Log alert: {}", rule),
+                    more => format!("Log alert: {} (+{} more)", rule, more),
+                };
+                let _ = notification_command(&title, &line).status();
+            }
+            Delivery::Webhook { url, body } => {
+                let _ = ureq::post(&url)
+                    .timeout(Duration::from_secs(10))
+                    .set("Content-Type", "application/json")
+                    .send_string(&body);
+            }
+        }
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Run an alert command through the shell with the matching line on stdin,
+/// on a thread that waits for it.
+///
+/// `{line}` and `{rule}` in the command are replaced with shell-quoted values.
+/// `cmd` has no quoting to rely on, so on Windows they are passed in the
+/// `TLM_LINE` and `TLM_RULE` environment variables instead, read with delayed
+/// expansion once the command line has been parsed.
+fn run_alert_command(template: &str, rule: &str, line: &str) -> JoinHandle<()> {
+    let command_line = alert_command_line(template, rule, line);
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/V:ON").arg("/C").arg(command_line).env("TLM_LINE", line).env("TLM_RULE", rule);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(command_line);
+        command
+    };
+    command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null());
+
+    let input = format!("{}\n", line);
+    thread::spawn(move || {
+        if let Ok(mut child) = command.spawn() {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(input.as_bytes());
+            }
+            let _ = child.wait();
+        }
+    })
+}
+
+fn parse_secs(text: &str) -> Result<u64, String> {
+    text.trim().parse().map_err(|_| format!("Invalid number of seconds: {}", text))
+}
+
+// A message's template without level words, which formats such as `auto`
+// leave in the message and which would tell the WARNING and ERROR lines of
+// one statement apart
+fn escalation_key(message: &str) -> String {
+    diff::template(message)
+        .split_whitespace()
+        .filter(|word| LogLevel::from_name(word.trim_matches(|c: char| !c.is_alphanumeric())).is_none())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The alert command's line, with `{line}` and `{rule}` filled in one pass
+// over the template, so that placeholders in the values are left as they are
+fn alert_command_line(template: &str, rule: &str, line: &str) -> String {
+    let mut command_line = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(at) = rest.find('{') {
+        command_line.push_str(&rest[..at]);
+        rest = &rest[at..];
+        if let Some(after) = rest.strip_prefix("{line}") {
+            command_line.push_str(&placeholder_value("TLM_LINE", line));
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{rule}") {
+            command_line.push_str(&placeholder_value("TLM_RULE", rule));
+            rest = after;
+        } else {
+            command_line.push('{');
+            rest = &rest[1..];
+        }
+    }
+    command_line.push_str(rest);
+    command_line
+}
+
+// A value in the alert command: quoted for `sh`, or for `cmd` a reference to
+// the environment variable `name` holding it, expanded after parsing
+fn placeholder_value(name: &str, value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"!{}!\"", name)
+    } else {
+        shell_quote(value)
+    }
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// What an `escalation` alert says: the level a message had and the one it
+/// has now, then the line.
+pub fn escalation_line(from: LogLevel, to: LogLevel, line: &str) -> String {
+    format!("{} -> {}: {}", from.as_str(), to.as_str(), line)
+}
+
+// The OS command showing a desktop notification: `osascript` on macOS and
+// `notify-send` elsewhere; failures (e.g. no notification daemon) are ignored
+fn notification_command(title: &str, line: &str) -> Command {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            applescript_escape(line),
+            applescript_escape(title)
+        );
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(script);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg(title).arg(line);
+        command
+    };
+    // Keep the child's output off the TUI
+    command.stdout(Stdio::null()).stderr(Stdio::null());
+    command
+}
+
+fn applescript_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn placeholders_in_the_line_stay_quoted() {
+        let command_line = alert_command_line("printf '%s;' {line} {rule}", "error|fatal", "saw {rule} in 'it'");
+        let output = Command::new("sh").arg("-c").arg(command_line).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "saw {rule} in 'it';error|fatal;");
+    }
+}
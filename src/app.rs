@@ -0,0 +1,3704 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use crossbeam_channel::{bounded, Receiver, Select, Sender, TryRecvError};
+use crossterm::event::{Event, KeyCode};
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use serde::Deserialize;
+
+use crate::alerts::{escalation_line, Alert, AlertWorker, WatchRule};
+use crate::audit::{self, AuditLog};
+use crate::browse::Browser;
+use crate::columns::{ColumnFilter, DerivedColumn, ExtraColumn, GroupPanel};
+use crate::compare::Compare;
+use crate::complete::{self, Completion, Fields};
+use crate::config::{Config, FormatConfig};
+use crate::control::{AppHandle, Request, MAX_QUEUED_REQUESTS};
+use crate::detail::Detail;
+use crate::diag::Topic;
+use crate::diff::{Change, Diff};
+use crate::enrich::Enricher;
+use crate::format::LogFormat;
+use crate::highlight::Highlight;
+use crate::index::TrigramIndex;
+use crate::ingest::IngestEvent;
+use crate::intern::Interner;
+use crate::k8s::{PodTarget, PodWatch};
+use crate::keymap::Keymap;
+use crate::links::{self, Link, LinkFinder};
+use crate::latency::PairRule;
+use crate::lookup::LookupTable;
+use crate::metrics::Metrics;
+use crate::notes::{Note, NotesPanel};
+use crate::parse::{parse_level, LogLevel, LogLine};
+use crate::perf::FramePerf;
+use crate::picker::FilePicker;
+use crate::print;
+use crate::problems::Problems;
+use crate::redact::Redactor;
+use crate::remap::{remapped_level, LevelRule};
+use crate::replay::{Recorder, Replay};
+use crate::script::{LineAction, Script};
+use crate::scrubber::Timeline;
+use crate::session::Session;
+use crate::settings::{Setting, SettingsPanel, SETTINGS, TIMESTAMP_PRESETS};
+use crate::source::{Source, SourceFilter, SourceState, SourcesPanel, STDIN_TARGET};
+use crate::startup::StartupMarkers;
+use crate::spill::Spill;
+use crate::stats::{Bucket, LogStats};
+use crate::systemd::{self, UnitWatch};
+use crate::tee::Tee;
+use crate::term::Capabilities;
+use crate::theme::{LevelSymbols, Theme};
+use crate::throttle::Throttle;
+use crate::title;
+use crate::ui::{heatmap_first_row, HEATMAP_CELL_WIDTH, HEATMAP_LABEL_WIDTH, TAB_TITLES};
+use crate::undo::{ViewHistory, ViewState};
+use crate::workspace::{Workspace, WORKSPACES};
+use crate::filter::StatusClasses;
+use crate::{compare, config, diag, diff, export, filter, format, ingest, reorder, rotate, session, settings, tokens};
+
+// Enum for application views
+#[derive(PartialEq)]
+pub(crate) enum ViewMode {
+    LogView,
+    StatsView,
+    HeatmapView,
+    AlertsView,
+    HelpView,
+    FilterView,
+    WatchView,
+    CommandView,
+    BrowseView,
+    CompareView,
+    DiffView,
+    SettingsView,
+    SourcesView,
+    PickerView,
+    DiagnosticsView,
+    DetailView,
+    NotesView,
+    ProblemsView,
+    GroupView,
+}
+
+// What the heatmap cells are colored by
+#[derive(PartialEq, Clone, Copy)]
+pub(crate) enum HeatmapMetric {
+    Volume,
+    Errors,
+}
+
+// Where the followed file is first read from, instead of its last lines
+#[derive(Clone, Copy)]
+pub(crate) enum LoadFrom {
+    Window(NaiveDateTime, NaiveDateTime), // Lines logged in [start, end) (--since/--until)
+    Offset(u64),                          // Everything after this byte (--since-last-run)
+}
+
+// A log file opened for following, not yet taken into the buffer
+struct OpenedLog {
+    source: Source,
+    lines: Vec<String>, // Lines to start with
+    whole: bool,        // Read from the end rather than from where `LoadFrom` said
+}
+
+/// What the numbers in front of log lines count, chosen with `--line-numbers`.
+#[derive(PartialEq, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineNumbering {
+    /// Position in the filtered view
+    View,
+    /// Line number in the log file, or in whichever source the line came from
+    File,
+}
+
+// Narrowest line number column, so short files don't jump when lines arrive
+const MIN_LINE_NUMBER_WIDTH: usize = 4;
+
+// Lines looked back over for the day of the stamped line above, past stack
+// traces and other lines without a timestamp
+const DAY_LOOKBACK: usize = 1000;
+
+// Time of day shown with --compact-dates when no layout is configured
+const COMPACT_TIME_FORMAT: &str = "%H:%M:%S";
+
+// Lines the log view draws above the `scroll` position, so the row at the
+// top of the view is `scroll - SCROLL_LEAD`
+pub(crate) const SCROLL_LEAD: usize = 10;
+
+// How long a command result stays in the status bar
+pub(crate) const STATUS_TIMEOUT: Duration = Duration::from_secs(4);
+
+// Redraw interval while a replay is playing, so its clock keeps moving
+const REPLAY_TICK: Duration = Duration::from_millis(100);
+
+// Longest time spent taking in queued batches before the next frame is drawn
+const INGEST_BUDGET: Duration = Duration::from_millis(25);
+
+// Sample period of the lines/sec indicator in the log view title
+const PRESSURE_WINDOW: Duration = Duration::from_secs(1);
+
+// How much longer each press of Z in the alerts tab snoozes a rule
+const SNOOZE_STEP: Duration = Duration::from_secs(5 * 60);
+
+// Alerts kept for the alerts tab; past this the oldest are forgotten
+const MAX_ALERTS: usize = 1000;
+
+/// State of the monitor: the buffered lines and their sources, the filtered
+/// view and everything the interface shows. Drawn with `ui::draw`.
+pub struct App {
+    pub(crate) log_path: String,
+    pub(crate) log_lines: VecDeque<LogLine>, // Ring buffer, oldest lines are evicted from the front
+    pub(crate) filtered_logs: Vec<usize>, // Indices of logs that match current filter
+    pub(crate) applied_filter: String,    // Lowercased filter text `filtered_logs` was computed with
+    pub(crate) filter_stale: bool,        // A filter pass was cancelled and `filtered_logs` is out of date
+    pub(crate) scroll: usize,
+    pub(crate) selected_tab: usize,
+    pub(crate) follow_mode: bool,
+    pub(crate) unseen_lines: usize, // Lines that matched the filter since follow mode was paused
+    pub(crate) view_mode: ViewMode,
+    pub(crate) stats: LogStats,
+    pub(crate) filter_text: String,
+    pub(crate) filter_editing: bool,
+    pub(crate) filter_before: String, // Filter text when the filter prompt opened, put back by Esc
+    pub(crate) completion: Option<Completion>, // Choices Tab offered for the filter, until another key
+    pub(crate) show_timestamps: bool,
+    pub(crate) show_line_numbers: bool,
+    pub(crate) wrap_lines: bool,
+    pub(crate) history: bool, // Read rotated copies of the file when it holds fewer lines than asked for
+    pub(crate) file_markers: BTreeMap<u64, String>, // Sequence numbers of lines starting a rotated file (and the live one after them)
+    pub(crate) reorder_window: Option<ChronoDuration>, // How far back a late line may be slotted in by timestamp, `None` always appends
+    pub(crate) timestamp_format: Option<String>, // strftime layout for shown timestamps, `None` shows them as logged
+    pub(crate) compact_dates: bool, // Show the date only where it changes from the line above (--compact-dates)
+    pub(crate) line_numbering: LineNumbering,
+    pub(crate) line_number_width: Option<usize>, // Fixed width of the line number column, `None` fits the largest
+    pub(crate) max_lines: usize,
+    pub(crate) buffer_bytes: usize,      // Approximate memory held by `log_lines`
+    pub(crate) sources: Vec<Source>,     // The followed file (or replayed recording) first, then those attached with :open
+    pub(crate) pod_watches: Vec<PodWatch>, // Label selectors opened with :open k8s://, whose pods come and go
+    pub unit_watches: Vec<UnitWatch>,      // Systemd units whose journal a source follows
+    pub(crate) next_source_id: u16,
+    pub(crate) input: Option<Receiver<io::Result<Event>>>, // Terminal events from the input thread
+    pub(crate) redraw: bool,              // Something on screen changed since the last frame
+    pub(crate) time_range: Option<(NaiveDateTime, NaiveDateTime)>, // Only show logs in [start, end)
+    pub(crate) load_from: Option<LoadFrom>, // Read the file from here rather than its end, once
+    pub(crate) stats_bucket: Bucket, // Time each bar of the stats view's chart covers
+    pub(crate) throttle: Option<Throttle>, // Per-template display rate limit (--throttle)
+    pub(crate) drill_words: Vec<String>, // Words picked in the stats view's token panel, narrowing the view
+    pub(crate) token_selected: usize,    // Row selected in the token panel
+    pub(crate) top_tokens: Option<(Instant, Vec<(String, usize)>)>, // The token panel's words and when they were counted
+    pub(crate) heatmap_metric: HeatmapMetric,
+    pub(crate) heatmap_cursor: (usize, usize), // (day row, hour column)
+    pub(crate) heatmap_area: Rect, // Where the heatmap grid was last drawn, for mouse hit-testing
+    pub(crate) show_scrubber: bool,  // Time scrubber above the log view, when there is room
+    pub(crate) scrubber_area: Rect,  // Where the scrubber was last drawn, empty if it wasn't
+    pub(crate) narrow: bool,         // Last drawn narrower than ui::NARROW_WIDTH, in the compact layout
+    pub(crate) watches: Vec<WatchRule>,
+    pub(crate) alerts: Vec<Alert>,
+    pub(crate) alert_worker: AlertWorker, // Desktop notifications, webhook posts and exec commands
+    pub(crate) notes: Vec<Note>, // Annotations on lines, in the order of their lines
+    pub(crate) notes_panel: NotesPanel,
+    pub(crate) hidden_lines: usize,        // Buffered lines taken out of the view with x
+    pub(crate) hide_history: Vec<Vec<u64>>, // Sequence numbers of each batch hidden, the latest last, for Shift+X
+    pub(crate) block_start: Option<u64>,   // Sequence number of the line a block selection started at (v)
+    pub(crate) view_history: ViewHistory,  // View changes to undo with u and redo with Ctrl+R
+    pub(crate) workspaces: Vec<Workspace>, // Views kept over the same lines, switched with F1-F4; the shown one's slot keeps only its name
+    pub(crate) workspace: usize,           // Index of the workspace shown
+    pub(crate) watch_input: String,
+    pub(crate) watch_error: Option<String>,
+    pub(crate) alert_flash_until: Option<Instant>,
+    pub(crate) ring_bell: bool, // Ring the terminal bell after the next draw
+    pub(crate) alerts_selected: usize, // Selected row in the alerts tab, 0 is the newest alert
+    pub(crate) lines_ingested: u64,    // Total lines ever added, used as the next line sequence number
+    pub(crate) last_line_at: Instant,  // When the last new line arrived, for silence watches
+    pub(crate) command_input: String,
+    pub(crate) status_message: Option<(String, Instant)>, // Result of the last command, shown briefly in the status bar
+    pub(crate) metrics: Arc<Metrics>,
+    pub(crate) summary: Arc<Mutex<String>>, // What `tlm-ctl status` answers, shared with the control socket
+    pub(crate) rate_window: (Instant, u64), // Start of the current ingest rate sample and lines ingested at that time
+    pub(crate) pressure_window: (Instant, u64), // Same for the on-screen lines/sec indicator
+    pub(crate) lines_per_sec: f64,
+    pub(crate) perf: FramePerf,           // Frame timings and load-shedding state
+    pub(crate) min_level: Option<LogLevel>, // Hide lines below this level (--level)
+    pub(crate) status_classes: StatusClasses, // Only show access log lines with these statuses, toggled with 4 and 5
+    pub(crate) format: LogFormat, // Profile new sources start with; `auto` guesses one per source
+    pub(crate) formats: HashMap<String, FormatConfig>, // Profiles from the config file, for `:format`
+    pub(crate) theme: Theme,
+    pub(crate) symbols: LevelSymbols,
+    pub(crate) show_symbols: bool, // Level glyphs in front of lines (--symbols)
+    pub(crate) term: Capabilities, // What the terminal can display, degraded to when drawing
+    pub(crate) keymap: Keymap,
+    pub(crate) highlights: Vec<Highlight>, // Recoloring rules from the config file
+    pub(crate) level_rules: Vec<LevelRule>, // Level corrections from the config file
+    pub(crate) startup_markers: StartupMarkers, // Lines that mark a restart of the service
+    pub(crate) redactor: Redactor, // Masks sensitive text in lines as they arrive (config `redact`)
+    pub(crate) enricher: Enricher, // Country, ASN and host name of the addresses in lines
+    pub(crate) geo_filter: Option<String>, // Only show lines with an address in this country or AS (:geo), lowercase
+    pub(crate) lookups: Vec<LookupTable>,  // Tables from the config's [[lookup]] sections, each an extra column
+    pub(crate) lookup_filter: Option<String>, // Only show lines with a looked-up value containing this (:lookup), lowercase
+    pub(crate) columns: Vec<DerivedColumn>,   // Columns from the config's [[column]] sections, picked out by a regex
+    pub(crate) column_filter: Option<(usize, String)>, // Only show lines whose column has this value (:column), lowercase
+    pub(crate) group_panel: Option<GroupPanel>, // Lines grouped by a column's value (:group)
+    pub(crate) pairs: Vec<PairRule>,            // Start and end patterns from the config's [[pair]] sections
+    pub(crate) latencies: BTreeMap<u64, (usize, Duration)>, // Sequence numbers of end lines, with their pair rule and latency
+    pub(crate) links: LinkFinder,           // URLs and file:line references in lines, underlined and opened with O
+    pub(crate) problems: Option<Problems>,  // Diagnostics in the build output, in cargo mode (--cargo)
+    pub(crate) poll_interval: Arc<AtomicU64>, // Milliseconds between checks of the file, shared with the tail thread
+    pub(crate) settings: SettingsPanel,
+    pub(crate) sources_panel: SourcesPanel,
+    pub(crate) config_path: Option<PathBuf>, // Where the settings panel saves to
+    pub(crate) tee: Option<Tee>,
+    pub(crate) printing: Option<Vec<(String, Color)>>, // Lines passing the filter as they came in, yet to be printed (no terminal)
+    pub(crate) browser: Option<Browser>, // Whole-file browse mode, when open
+    pub(crate) compare: Option<Compare>, // Side-by-side view of two sources, when open
+    pub(crate) diff: Option<Diff>,       // Message templates compared between two selections, when open
+    pub(crate) detail: Option<Detail>,   // A line shown whole, when open
+    pub(crate) log_view_range: Range<usize>, // Rows of the filtered view the log view last showed
+    pub(crate) picker: Option<FilePicker>, // File-open dialog, when open
+    pub(crate) diag_top: Option<usize>,  // First entry shown in the diagnostics view, following the newest when `None`
+    pub(crate) diag_rows: usize,         // Entries the diagnostics view last had room for
+    pub(crate) initial_lines: usize,     // Lines read from the end of a file when it is opened
+    pub(crate) index: TrigramIndex,       // Trigrams of the buffered lines, to narrow down filter passes
+    pub(crate) interner: Interner,        // Message texts shared between buffered lines
+    pub(crate) spill: Option<Spill>,      // Scrollback file receiving evicted lines (--spill)
+    pub(crate) recorder: Option<Recorder>,
+    pub(crate) replay: Option<Replay>,
+    pub(crate) script: Option<Script>,    // Hooks and commands from --script
+    pub(crate) audit: Option<AuditLog>,   // Trail of commands and view changes, in audit mode (--audit)
+    pub(crate) requests: (Sender<Request>, Receiver<Request>), // Queue behind the `AppHandle`s
+    pub(crate) dropped_pushes: Arc<AtomicUsize>, // Lines `AppHandle`s dropped on a full queue, not yet reported
+    pub(crate) quit_requested: bool,      // An `AppHandle` asked the app to exit
+    pub(crate) suspend_requested: bool,   // Ctrl+Z or SIGTSTP, handled by the terminal loop
+    pub(crate) remote: bool,              // Running a command from the control socket, which may not start a shell
+    pub(crate) edit_requested: Option<(PathBuf, u32)>, // File and line to open in $EDITOR, handled by the terminal loop
+}
+
+// A reordering window of `ms` milliseconds; zero turns reordering off
+pub(crate) fn reorder_window(ms: u64) -> Option<ChronoDuration> {
+    (ms > 0).then(|| ChronoDuration::milliseconds(ms as i64))
+}
+
+pub(crate) fn parse_max_lines(value: &str) -> Result<usize, String> {
+    value.parse().ok().filter(|n| *n > 0).ok_or(format!("invalid line count: {}", value))
+}
+
+// Lines between sweeps of interned messages no buffered line uses any more
+const INTERN_PRUNE_INTERVAL: u64 = 4096;
+
+// Lines kept in the buffer unless overridden with --max-lines
+pub(crate) const DEFAULT_MAX_LINES: usize = 1000;
+
+// Poll interval bounds and step for the settings panel
+const MIN_POLL_INTERVAL_MS: u64 = 10;
+const POLL_INTERVAL_STEP_MS: i64 = 50;
+
+// Lines read from the end of the file at startup unless overridden with --lines
+pub(crate) const DEFAULT_INITIAL_LINES: usize = 100;
+
+impl App {
+    /// An empty monitor for `log_path`, with the default settings.
+    pub fn new(log_path: String) -> Self {
+        App {
+            log_path,
+            log_lines: VecDeque::new(),
+            filtered_logs: Vec::new(),
+            applied_filter: String::new(),
+            filter_stale: false,
+            scroll: 0,
+            selected_tab: 0,
+            follow_mode: true,
+            unseen_lines: 0,
+            view_mode: ViewMode::LogView,
+            stats: LogStats::default(),
+            filter_text: String::new(),
+            filter_editing: false,
+            filter_before: String::new(),
+            completion: None,
+            show_timestamps: true,
+            show_line_numbers: true,
+            wrap_lines: false,
+            history: false,
+            file_markers: BTreeMap::new(),
+            reorder_window: None,
+            timestamp_format: None,
+            compact_dates: false,
+            line_numbering: LineNumbering::View,
+            line_number_width: None,
+            max_lines: DEFAULT_MAX_LINES,
+            buffer_bytes: 0,
+            sources: Vec::new(),
+            pod_watches: Vec::new(),
+            unit_watches: Vec::new(),
+            next_source_id: 0,
+            input: None,
+            redraw: true,
+            time_range: None,
+            load_from: None,
+            stats_bucket: Bucket::Hour,
+            throttle: None,
+            drill_words: Vec::new(),
+            token_selected: 0,
+            top_tokens: None,
+            heatmap_metric: HeatmapMetric::Volume,
+            heatmap_cursor: (0, 0),
+            heatmap_area: Rect::default(),
+            show_scrubber: false,
+            scrubber_area: Rect::default(),
+            narrow: false,
+            watches: Vec::new(),
+            alerts: Vec::new(),
+            alert_worker: AlertWorker::default(),
+            notes: Vec::new(),
+            notes_panel: NotesPanel::default(),
+            hidden_lines: 0,
+            hide_history: Vec::new(),
+            block_start: None,
+            view_history: ViewHistory::default(),
+            workspaces: (0..WORKSPACES).map(|_| Workspace::default()).collect(),
+            workspace: 0,
+            watch_input: String::new(),
+            watch_error: None,
+            alert_flash_until: None,
+            ring_bell: false,
+            alerts_selected: 0,
+            lines_ingested: 0,
+            last_line_at: Instant::now(),
+            command_input: String::new(),
+            status_message: None,
+            metrics: Arc::new(Metrics::default()),
+            summary: Arc::new(Mutex::new(String::new())),
+            rate_window: (Instant::now(), 0),
+            pressure_window: (Instant::now(), 0),
+            lines_per_sec: 0.0,
+            perf: FramePerf::default(),
+            min_level: None,
+            status_classes: StatusClasses::default(),
+            format: LogFormat::Auto,
+            formats: HashMap::new(),
+            theme: Theme::Dark,
+            symbols: LevelSymbols::default(),
+            show_symbols: false,
+            term: Capabilities::detect(),
+            keymap: Keymap::default(),
+            highlights: Vec::new(),
+            level_rules: Vec::new(),
+            startup_markers: StartupMarkers::default(),
+            redactor: Redactor::default(),
+            enricher: Enricher::default(),
+            geo_filter: None,
+            lookups: Vec::new(),
+            lookup_filter: None,
+            columns: Vec::new(),
+            column_filter: None,
+            group_panel: None,
+            pairs: Vec::new(),
+            latencies: BTreeMap::new(),
+            links: LinkFinder::default(),
+            problems: None,
+            poll_interval: Arc::new(AtomicU64::new(ingest::DEFAULT_POLL_INTERVAL.as_millis() as u64)),
+            settings: SettingsPanel::default(),
+            sources_panel: SourcesPanel::default(),
+            config_path: None,
+            picker: None,
+            diag_top: None,
+            diag_rows: 0,
+            initial_lines: DEFAULT_INITIAL_LINES,
+            tee: None,
+            printing: None,
+            browser: None,
+            compare: None,
+            diff: None,
+            detail: None,
+            log_view_range: 0..0,
+            index: TrigramIndex::new(),
+            interner: Interner::new(),
+            spill: None,
+            recorder: None,
+            replay: None,
+            script: None,
+            audit: None,
+            requests: bounded(MAX_QUEUED_REQUESTS),
+            dropped_pushes: Arc::new(AtomicUsize::new(0)),
+            quit_requested: false,
+            suspend_requested: false,
+            remote: false,
+            edit_requested: None,
+        }
+    }
+
+    /// Take defaults, key bindings, highlight rules and watches from the config file.
+    pub fn apply_config(&mut self, config: &Config) -> Result<(), String> {
+        if let Some(max_lines) = config.max_lines {
+            self.max_lines = max_lines.max(1);
+        }
+        if let Some(interval) = config.interval {
+            self.poll_interval.store(interval, Ordering::Relaxed);
+        }
+        if let Some(wrap) = config.wrap {
+            self.wrap_lines = wrap;
+        }
+        if let Some(history) = config.history {
+            self.history = history;
+        }
+        if let Some(ms) = config.reorder_window {
+            self.reorder_window = reorder_window(ms);
+        }
+        if let Some(layout) = &config.timestamp_format {
+            self.set_timestamp_format(layout).map_err(|e| format!("{} (timestamp_format in config)", e))?;
+        }
+        if let Some(compact) = config.compact_dates {
+            self.compact_dates = compact;
+        }
+        if let Some(numbering) = config.line_numbers {
+            self.line_numbering = numbering;
+        }
+        if config.line_number_width.is_some() {
+            self.line_number_width = config.line_number_width;
+        }
+        if let Some(follow) = config.follow {
+            self.follow_mode = follow;
+        }
+        if let Some(theme) = config.theme {
+            self.theme = theme;
+        }
+        if let Some(colors) = config.colors {
+            self.term.colors = colors;
+        }
+        if config.ascii == Some(true) {
+            self.term.unicode = false;
+        }
+        if let Some(per_second) = config.throttle {
+            self.set_throttle(per_second);
+        }
+        if let Some(scrubber) = config.scrubber {
+            self.show_scrubber = scrubber;
+        }
+        if let Some(symbols) = config.symbols {
+            self.show_symbols = symbols;
+        }
+        self.symbols = LevelSymbols::from_config(&config.level_symbols)?;
+        self.formats = config.formats.clone();
+        if let Some(name) = &config.format {
+            self.format = LogFormat::resolve(name, &self.formats)?;
+        }
+        if let Some(filter) = &config.filter {
+            self.filter_text = filter.clone();
+        }
+        if let Some(level) = &config.level {
+            self.min_level = Some(parse_level(level)?);
+        }
+        self.keymap = Keymap::from_config(&config.keymap)?;
+        self.highlights = config.highlight.iter()
+            .map(|rule| Highlight::new(&rule.pattern, &rule.color))
+            .collect::<Result<_, _>>()?;
+        self.level_rules = config.remap.iter()
+            .map(|rule| LevelRule::new(rule.pattern.as_deref(), rule.logger.as_deref(), &rule.level))
+            .collect::<Result<_, _>>()?;
+        if let Some(patterns) = &config.startup_markers {
+            self.startup_markers = StartupMarkers::new(patterns)?;
+        }
+        if let Some(patterns) = &config.redact {
+            self.redactor = Redactor::new(patterns)?;
+        }
+        self.lookups = config.lookup.iter().map(LookupTable::new).collect::<Result<_, _>>()?;
+        self.columns = config.column.iter().map(DerivedColumn::new).collect::<Result<_, _>>()?;
+        self.pairs = config.pair.iter().map(PairRule::new).collect::<Result<_, _>>()?;
+        if config.geoip_db.is_some() || config.asn_db.is_some() || config.reverse_dns.is_some() {
+            self.enricher = Enricher::new(config.geoip_db.as_deref(), config.asn_db.as_deref(), config.reverse_dns == Some(true))?;
+        }
+        for spec in &config.watches {
+            self.watches.push(WatchRule::parse(spec).map_err(|e| format!("Invalid watch {:?} in config: {}", spec, e))?);
+        }
+        // Directory-local settings can be surprising, so say where they came from
+        if let Some(local) = config.sources.iter().find(|path| path.ends_with(config::LOCAL_FILE)) {
+            self.set_status(format!("Using settings from {}", local.display()));
+        }
+        if let Some(local) = &config.untrusted {
+            self.set_status(format!(
+                "Ignored watches, script and control in {}: not owned by you, or writable by others",
+                local.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// A handle for steering the app from other code or threads.
+    pub fn handle(&self) -> AppHandle {
+        AppHandle {
+            tx: self.requests.0.clone(),
+            summary: self.summary.clone(),
+            dropped: self.dropped_pushes.clone(),
+        }
+    }
+
+    // The terminal's title: what is followed and the errors logged since the
+    // start. The summary `tlm-ctl status` answers with is brought up to date too.
+    pub(crate) fn window_title(&self) -> String {
+        let name = self.sources.first().map_or("log_monitor", |source| source.name.as_str());
+        let errors = self.metrics.error_lines.load(Ordering::Relaxed) + self.metrics.fatal_lines.load(Ordering::Relaxed);
+        let warnings = self.metrics.warning_lines.load(Ordering::Relaxed);
+        if let Ok(mut summary) = self.summary.lock() {
+            *summary = title::status_text(name, errors, warnings);
+        }
+        title::title_text(name, errors)
+    }
+
+    /// Apply the requests queued by `AppHandle`s, returning whether there were any.
+    /// Check `quit_requested` afterwards to see whether one asked the app to exit.
+    pub fn process_requests(&mut self) -> bool {
+        let requests: Vec<Request> = self.requests.1.try_iter().collect();
+        let any = !requests.is_empty();
+        let mut lines = Vec::new();
+        for request in requests {
+            if let Request::PushLine(line) = request {
+                lines.push(line);
+                continue;
+            }
+            // Keep lines pushed before a filter or scroll change in front of it
+            self.push_lines(&lines.iter().map(String::as_str).collect::<Vec<_>>());
+            lines.clear();
+            let _ = self.apply_request(request);
+        }
+        self.push_lines(&lines.iter().map(String::as_str).collect::<Vec<_>>());
+        let dropped = self.dropped_pushes.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            self.set_status(format!("Dropped {} pushed lines: too many were queued", dropped));
+        }
+        any
+    }
+
+    // Apply one request, returning what a `:` command reports
+    pub(crate) fn apply_request(&mut self, request: Request) -> Result<String, String> {
+        match request {
+            Request::PushLine(line) => self.push_lines(&[&line]),
+            Request::SetFilter(text) => {
+                self.filter_text = text;
+                self.update_filter();
+            }
+            Request::ScrollTo(row) => self.scroll_to(row),
+            Request::Follow => {
+                self.follow_mode = true;
+                self.scroll = self.filtered_logs.len();
+            }
+            Request::Command(command) => return self.execute_command(&command),
+            Request::RemoteCommand(command) => {
+                self.remote = true;
+                let result = self.execute_command(&command);
+                self.remote = false;
+                return result;
+            }
+            Request::Suspend => self.suspend_requested = true,
+            Request::Quit => self.quit_requested = true,
+            Request::Answered(request, reply) => {
+                let result = self.apply_request(*request);
+                let _ = reply.send(result.clone());
+                return result;
+            }
+        }
+        Ok(String::new())
+    }
+
+    /// Whether an `AppHandle` asked the app to exit.
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    // Add lines to the main source as if it had just read them
+    pub(crate) fn push_lines(&mut self, lines: &[&str]) {
+        if lines.is_empty() {
+            return;
+        }
+        let format = self.sources.first().map_or_else(|| self.format.clone(), |source| source.current_format());
+        self.ingest_batch(0, lines.iter().map(|line| LogLine::parse(line, &format)).collect());
+        self.finish_ingest();
+    }
+
+    // Where this investigation stands, to be saved for `--resume`
+    pub(crate) fn session_state(&self) -> Session {
+        let line_text = |idx: usize| self.log_lines[idx].content();
+        Session {
+            filter: self.filter_text.clone(),
+            min_level: self.min_level.map(|level| level.as_str().to_lowercase()),
+            time_range: self.time_range,
+            follow: self.follow_mode,
+            tab: self.selected_tab,
+            show_timestamps: self.show_timestamps,
+            show_line_numbers: self.show_line_numbers,
+            scroll_line: self.filtered_logs.get(self.scroll).copied().map(line_text),
+            scroll_from_end: self.filtered_logs.len().saturating_sub(self.scroll),
+            mark: self.log_lines.iter().find(|log| log.highlighted).map(LogLine::content),
+            browse_top: self.browser.as_ref().filter(|browser| browser.path == self.log_path).map(|browser| browser.top),
+            // Workspaces are not saved, so the notes of all of them are
+            notes: self.notes.iter().chain(self.workspaces.iter().flat_map(|workspace| &workspace.notes)).cloned().collect(),
+        }
+    }
+
+    // Restore the filters and toggles of a saved session, before any lines are read
+    pub(crate) fn restore_session(&mut self, session: &Session) {
+        self.filter_text = session.filter.clone();
+        self.min_level = session.min_level.as_deref().and_then(LogLevel::from_name);
+        self.time_range = session.time_range;
+        self.follow_mode = session.follow;
+        self.show_timestamps = session.show_timestamps;
+        self.show_line_numbers = session.show_line_numbers;
+        self.select_tab(session.tab.min(4));
+    }
+
+    // Restore the scroll position, mark, notes and browse position of a saved
+    // session, once the buffer is loaded and filtered. Lines are found again
+    // by their text, since the buffer may have moved on since.
+    pub(crate) fn restore_position(&mut self, session: &Session) {
+        if !self.follow_mode {
+            let found = session.scroll_line.as_ref().and_then(|text| {
+                self.filtered_logs.iter().rposition(|&idx| self.log_lines[idx].content() == *text)
+            });
+            self.scroll = found.unwrap_or_else(|| self.filtered_logs.len().saturating_sub(session.scroll_from_end));
+        }
+        let marked = session.mark.as_ref()
+            .and_then(|mark| self.log_lines.iter_mut().rev().find(|log| log.content() == *mark));
+        if let Some(log) = marked {
+            log.highlighted = true;
+        }
+        // One pass over the buffer, putting together only the lines as long as a note's
+        self.notes = session.notes.clone();
+        let mut unplaced: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, note) in session.notes.iter().enumerate() {
+            unplaced.entry(note.line.as_str()).or_default().push(i);
+        }
+        let lengths: HashSet<usize> = unplaced.keys().map(|line| line.len()).collect();
+        for log in self.log_lines.iter().rev() {
+            if unplaced.is_empty() {
+                break;
+            }
+            if !lengths.contains(&(log.head.len() + log.message.len())) {
+                continue;
+            }
+            // The newest line with the text gets its notes
+            if let Some(notes) = unplaced.remove(log.content().as_str()) {
+                for i in notes {
+                    self.notes[i].seq = Some(log.seq);
+                }
+            }
+        }
+        // Those of other workspaces come after, so back in line order (those evicted first)
+        self.notes.sort_by_key(|note| note.seq);
+        if let Some(top) = session.browse_top {
+            self.open_browser();
+            if let Some(browser) = &mut self.browser {
+                browser.scroll_by(top as isize);
+            }
+        }
+    }
+
+    // Read the last N lines from the log file (or from where `load_from`
+    // says), then follow it in the background
+    pub(crate) fn initialize_logs(&mut self, num_lines: usize) -> io::Result<()> {
+        let opened = self.open_log(num_lines)?;
+        self.load_log(opened, num_lines);
+        Ok(())
+    }
+
+    // Open the log file for following, with the lines to start with.
+    // Scan backwards from the end, so startup cost doesn't grow with the file;
+    // a time window is searched for by timestamp instead. A trailing line without a
+    // newline is left for the tail thread. File line numbers need the lines in
+    // front of those read, which costs a pass over them.
+    fn open_log(&mut self, num_lines: usize) -> io::Result<OpenedLog> {
+        let id = self.next_source_id();
+        let count_before = self.line_numbering == LineNumbering::File;
+        let load_from = self.load_from.take();
+        let whole = load_from.is_none();
+        let (source, lines) = match load_from {
+            Some(LoadFrom::Window(start, end)) => Source::open_window(
+                id,
+                &self.log_path,
+                (start, end),
+                self.max_lines,
+                count_before,
+                self.poll_interval.clone(),
+                self.format.clone(),
+            )?,
+            Some(LoadFrom::Offset(offset)) => Source::open_from(
+                id,
+                &self.log_path,
+                offset,
+                self.max_lines,
+                count_before,
+                self.poll_interval.clone(),
+                self.format.clone(),
+            )?,
+            None if self.log_path == STDIN_TARGET => {
+                Source::open(id, &self.log_path, 0, false, self.poll_interval.clone(), self.format.clone())?
+            }
+            None => Source::open_file(id, &self.log_path, num_lines, count_before, self.poll_interval.clone(), self.format.clone())?,
+        };
+        Ok(OpenedLog { source, lines, whole })
+    }
+
+    // Start following an opened log file, taking in its lines
+    fn load_log(&mut self, OpenedLog { source, lines, whole }: OpenedLog, num_lines: usize) {
+        let lines_before = source.lines;
+        self.sources = vec![source];
+        // Rotated copies only top up a file too short to fill --lines, and
+        // not a window or offset read, which covers just the current file
+        if self.history && whole && lines.len() < num_lines && self.load_rotated(num_lines - lines.len()) {
+            let name = Path::new(&self.log_path).file_name().map_or_else(|| self.log_path.clone(), |name| name.to_string_lossy().into_owned());
+            self.file_markers.insert(self.lines_ingested, name);
+            self.sources[0].lines = lines_before;
+        }
+        let format = self.sources[0].current_format();
+        let lines = lines.iter().map(|line| LogLine::parse(line, &format)).collect();
+        for log in self.run_line_hook(0, lines) {
+            self.add_log_line(0, log);
+        }
+        self.sources[0].mark_caught_up();
+        
+        self.update_filter();
+        self.update_stats();
+        if self.sources[0].is_file() {
+            let _ = session::add_recent_file(&self.log_path);
+        }
+    }
+
+    // Read up to `wanted` lines from the rotated copies of the file, oldest
+    // first, marking where each begins; returns whether any were read
+    pub(crate) fn load_rotated(&mut self, mut wanted: usize) -> bool {
+        let count_before = self.line_numbering == LineNumbering::File;
+        let mut files = Vec::new(); // Newest first
+        for path in rotate::siblings(&self.log_path) {
+            if wanted == 0 {
+                break;
+            }
+            match rotate::read_tail(&path, wanted, count_before) {
+                Ok((lines, before)) => {
+                    wanted -= lines.len();
+                    files.push((path, lines, before));
+                }
+                Err(e) => {
+                    // Anything older would leave a gap in the history
+                    self.set_status(format!("Stopped reading history at {}: {}", path.display(), e));
+                    break;
+                }
+            }
+        }
+        let format = self.sources[0].current_format();
+        let mut loaded = false;
+        for (path, lines, before) in files.into_iter().rev().filter(|(_, lines, _)| !lines.is_empty()) {
+            let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+            self.file_markers.insert(self.lines_ingested, name);
+            self.sources[0].lines = before;
+            let lines = lines.iter().map(|line| LogLine::parse(line, &format)).collect();
+            for log in self.run_line_hook(0, lines) {
+                self.add_log_line(0, log);
+            }
+            loaded = true;
+        }
+        loaded
+    }
+
+    // Drop every buffered line, e.g. to start over with another file
+    pub(crate) fn clear_buffer(&mut self) {
+        self.log_lines.clear();
+        self.file_markers.clear();
+        self.latencies.clear();
+        self.hidden_lines = 0;
+        self.hide_history.clear();
+        self.block_start = None;
+        for seq in self.line_seqs_mut() {
+            *seq = None;
+        }
+        self.index.clear();
+        self.interner.clear();
+        self.buffer_bytes = 0;
+        // A scrollback being browsed would only show what is gone
+        if self.spill.as_ref().zip(self.browser.as_ref()).is_some_and(|(spill, browser)| browser.path == spill.path) {
+            self.close_browser();
+        }
+        if let Some(spill) = &mut self.spill {
+            let _ = spill.clear();
+        }
+    }
+
+    // Show the file picker, starting in the directory of the current file
+    pub(crate) fn open_picker(&mut self) {
+        if self.replay.is_some() {
+            self.set_status("Can't open another file during a replay".to_string());
+            return;
+        }
+        let dir = std::path::absolute(&self.log_path).ok()
+            .and_then(|path| path.parent().map(PathBuf::from))
+            .filter(|dir| dir.is_dir())
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("/"));
+        self.picker = Some(FilePicker::open(dir, session::recent_files()));
+        self.view_mode = ViewMode::PickerView;
+    }
+
+    // Stop following the current file and any attached sources, and follow `path` instead
+    pub(crate) fn open_file(&mut self, path: String) {
+        // Open the new file first, so one that cannot be read leaves the
+        // current one being followed
+        let previous = std::mem::replace(&mut self.log_path, path);
+        match self.open_log(self.initial_lines) {
+            Ok(opened) => {
+                self.sources.clear(); // The old tail threads stop once they have lines to send
+                self.browser = None;
+                self.clear_buffer();
+                self.filtered_logs.clear();
+                self.scroll = 0;
+                self.load_log(opened, self.initial_lines);
+                self.picker = None;
+                self.finish_ingest();
+                self.select_tab(0);
+            }
+            Err(e) => {
+                let path = std::mem::replace(&mut self.log_path, previous);
+                if let Some(picker) = &mut self.picker {
+                    picker.error = Some(format!("Cannot open {}: {}", path, e));
+                }
+            }
+        }
+    }
+
+    pub(crate) fn next_source_id(&mut self) -> u16 {
+        self.next_source_id += 1;
+        self.next_source_id - 1
+    }
+
+    // Give a line (whose text is `content`) the level of the first
+    // `[[remap]]` rule it matches
+    pub(crate) fn remap_level(&self, log: &mut LogLine, content: &str) {
+        if let Some(level) = remapped_level(&self.level_rules, content) {
+            log.level = level;
+        }
+    }
+
+    // Add a line from `sources[source]` to the buffer, at the back unless the
+    // reordering window places it further up. Returns where it ended up
+    // (`None` if it was evicted straight away) and how many old lines were evicted.
+    pub(crate) fn add_log_line(&mut self, source: usize, mut log: LogLine) -> (Option<usize>, usize) {
+        if self.redactor.enabled() && let Cow::Owned(text) = self.redactor.redact(&log.content()) {
+            let format = self.sources.get(source).map_or_else(|| self.format.clone(), Source::current_format);
+            log = LogLine::parse(&text, &format);
+        }
+        let position = self.arrival_position(source, &log);
+        let first_seq = self.lines_ingested - self.log_lines.len() as u64;
+        log.seq = first_seq + position as u64;
+        if position < self.log_lines.len() {
+            self.shift_lines_from(position);
+        }
+        if let Some(source) = self.sources.get_mut(source) {
+            source.lines += 1;
+            source.last_seq = Some(log.seq);
+            log.source = source.id;
+            log.line = source.lines;
+        }
+        // The line's text, put together once for every check below
+        let content = log.content();
+        self.remap_level(&mut log, &content);
+        if let Some(problems) = &mut self.problems && let Some(level) = problems.observe(&content, log.seq) {
+            log.level = level;
+        }
+        log.restart = log.restart || self.startup_markers.enabled() && self.startup_markers.matches(&content);
+        for column in &mut self.columns {
+            column.fit(&content);
+        }
+        // Lines without a timestamp are timed by when they arrived
+        if !self.pairs.is_empty() {
+            let time = log.time.unwrap_or_else(|| chrono::Local::now().naive_local());
+            for (rule, pair) in self.pairs.iter_mut().enumerate() {
+                if let Some(elapsed) = pair.observe(&content, time) {
+                    self.latencies.insert(log.seq, (rule, elapsed));
+                }
+            }
+        }
+        log.intern(&mut self.interner);
+        if self.lines_ingested.is_multiple_of(INTERN_PRUNE_INTERVAL) {
+            self.interner.prune();
+        }
+        self.buffer_bytes += log.memory_size();
+        self.lines_ingested += 1;
+        self.metrics.record_line(log.level);
+        if let Some(recorder) = &mut self.recorder
+            && recorder.record(&content).is_err()
+        {
+            self.recorder = None;
+        }
+        self.index.insert(log.seq, log.lower_bytes());
+        self.stats.count(&log, true);
+        self.log_lines.insert(position, log);
+        let evicted = self.evict_excess();
+        (position.checked_sub(evicted), evicted)
+    }
+
+    // Where a newly arrived line goes in the buffer. With a reordering window,
+    // a line stamped earlier than the newest ones is slotted in by time, and a
+    // line without a timestamp follows the previous line of its source.
+    pub(crate) fn arrival_position(&self, source: usize, log: &LogLine) -> usize {
+        let len = self.log_lines.len();
+        let Some(window) = self.reorder_window else {
+            return len;
+        };
+        match log.time {
+            Some(time) => reorder::insert_position(&self.log_lines, time, window),
+            None => {
+                let first_seq = self.lines_ingested - len as u64;
+                self.sources.get(source)
+                    .and_then(|source| source.last_seq)
+                    .and_then(|seq| seq.checked_sub(first_seq))
+                    .map_or(len, |offset| (offset as usize + 1).min(len))
+            }
+        }
+    }
+
+    // Make room for a line inserted at buffer position `position`: the lines
+    // from there on, and everything referring to them by seq, move up by one
+    pub(crate) fn shift_lines_from(&mut self, position: usize) {
+        let Some(from_seq) = self.log_lines.get(position).map(|log| log.seq) else {
+            return;
+        };
+        for log in self.log_lines.range_mut(position..).rev() {
+            self.index.renumber(log.seq, log.seq + 1, log.lower_bytes());
+            log.seq += 1;
+        }
+        for alert in &mut self.alerts {
+            alert.seq = alert.seq.map(|seq| if seq >= from_seq { seq + 1 } else { seq });
+        }
+        for line in self.line_seqs_mut() {
+            *line = line.map(|seq| if seq >= from_seq { seq + 1 } else { seq });
+        }
+        for problem in self.problems.iter_mut().flat_map(|problems| &mut problems.list) {
+            problem.seq = problem.seq.map(|seq| if seq >= from_seq { seq + 1 } else { seq });
+        }
+        for source in &mut self.sources {
+            source.last_seq = source.last_seq.map(|seq| if seq >= from_seq { seq + 1 } else { seq });
+        }
+        let moved = self.file_markers.split_off(&from_seq);
+        self.file_markers.extend(moved.into_iter().map(|(seq, name)| (seq + 1, name)));
+        let moved = self.latencies.split_off(&from_seq);
+        self.latencies.extend(moved.into_iter().map(|(seq, latency)| (seq + 1, latency)));
+        for seq in self.hidden_batches_mut().flatten().flatten() {
+            if *seq >= from_seq {
+                *seq += 1;
+            }
+        }
+        self.block_start = self.block_start.map(|seq| if seq >= from_seq { seq + 1 } else { seq });
+    }
+
+    // Every line kept track of by its sequence number for the notes, and
+    // the anchors, marks and notes of the other workspaces
+    fn line_seqs_mut(&mut self) -> impl Iterator<Item = &mut Option<u64>> {
+        self.notes.iter_mut()
+            .map(|note| &mut note.seq)
+            .chain(self.workspaces.iter_mut().flat_map(Workspace::seqs_mut))
+    }
+
+    // Every list of batches of hidden lines kept: the view's, its undo
+    // history's and those of the other workspaces
+    fn hidden_batches_mut(&mut self) -> impl Iterator<Item = &mut Vec<Vec<u64>>> {
+        self.view_history.hidden_mut()
+            .chain([&mut self.hide_history])
+            .chain(self.workspaces.iter_mut().flat_map(Workspace::hidden_mut))
+    }
+
+    // Remove the oldest lines while the buffer is over its limit, returning how many went
+    pub(crate) fn evict_excess(&mut self) -> usize {
+        let mut evicted_count = 0;
+        while self.log_lines.len() > self.max_lines {
+            if let Some(evicted) = self.log_lines.pop_front() {
+                self.buffer_bytes -= evicted.memory_size();
+                self.index.remove_oldest(evicted.seq, evicted.lower_bytes());
+                self.stats.count(&evicted, false);
+                self.spill_line(&evicted.content());
+                self.hidden_lines -= evicted.hidden as usize;
+                evicted_count += 1;
+            }
+        }
+        // Markers of evicted lines go with them
+        if let Some(first) = self.log_lines.front().map(|log| log.seq).filter(|_| evicted_count > 0) {
+            self.file_markers = self.file_markers.split_off(&first);
+            self.latencies = self.latencies.split_off(&first);
+            for batches in self.hidden_batches_mut() {
+                for batch in batches.iter_mut() {
+                    batch.retain(|&seq| seq >= first);
+                }
+                batches.retain(|batch| !batch.is_empty());
+            }
+            self.block_start = self.block_start.filter(|&seq| seq >= first);
+            // Notes outlive their lines, dimmed in the annotations panel
+            for line in self.line_seqs_mut() {
+                *line = line.filter(|&seq| seq >= first);
+            }
+        }
+        evicted_count
+    }
+
+    // Change the buffer limit at runtime, evicting at once if it shrank;
+    // returns how many lines went
+    pub(crate) fn set_max_lines(&mut self, max_lines: usize) -> usize {
+        self.max_lines = max_lines;
+        let evicted = self.evict_excess();
+        if evicted > 0 {
+            self.extend_filter(evicted, 0);
+            self.finish_ingest();
+        }
+        evicted
+    }
+
+    // `:buffer <lines>`, or `+` and `-` to double and halve the limit; alone,
+    // it tells how full the buffer is
+    fn resize_buffer(&mut self, arg: &str) -> Result<String, String> {
+        let lines = match arg {
+            "" => {
+                return Ok(format!(
+                    "Buffer holds {} of {} lines, {:.1} MB",
+                    self.log_lines.len(),
+                    self.max_lines,
+                    self.buffer_bytes as f64 / (1024.0 * 1024.0)
+                ));
+            }
+            "+" => self.max_lines.saturating_mul(2),
+            "-" => (self.max_lines / 2).max(1),
+            _ => parse_max_lines(arg).map_err(|_| "Usage: :buffer <lines>|+|-".to_string())?,
+        };
+        let evicted = self.set_max_lines(lines);
+        let mut message = format!("Buffer holds up to {} lines", lines);
+        if evicted > 0 {
+            let fate = if self.spill.is_some() { "spilled to scrollback" } else { "dropped" };
+            message.push_str(&format!(", {} oldest {}", evicted, fate));
+        }
+        Ok(message)
+    }
+
+    // Keep an evicted line in the scrollback file, if spilling is on
+    pub(crate) fn spill_line(&mut self, line: &str) {
+        let Some(spill) = &mut self.spill else {
+            return;
+        };
+        if let Err(e) = spill.append(line) {
+            self.spill = None;
+            self.set_status(format!("Stopped spilling scrollback: {}", e));
+        }
+    }
+
+    /// Pick up new lines from the ingest threads (or the session being replayed),
+    /// returning whether any arrived. Sources that fail, the main file
+    /// included, are marked as stopped and shown in a banner above the view
+    /// while the rest of the session carries on.
+    pub fn update_logs(&mut self) -> bool {
+        let started = Instant::now();
+        let mut arrived = 0;
+        if let Some(replay) = &mut self.replay {
+            let format = self.sources[0].current_format();
+            let lines: Vec<LogLine> = replay.due_lines().iter().map(|line| LogLine::parse(line, &format)).collect();
+            arrived += lines.len();
+            self.ingest_batch(0, lines);
+        }
+        
+        // Coalesce as many queued batches as fit in the ingest budget, so a log
+        // storm costs one redraw per frame rather than one per batch. Sources
+        // take turns, so a busy one can't starve the others.
+        self.check_pod_watches();
+        arrived += self.check_unit_watches();
+        // A host name came in for the detail view
+        while self.enricher.resolved.try_recv().is_ok() {
+            self.redraw = true;
+        }
+        while started.elapsed() < INGEST_BUDGET {
+            let mut received = false;
+            for idx in 0..self.sources.len() {
+                let Some(rx) = &self.sources[idx].rx else {
+                    continue;
+                };
+                match rx.try_recv() {
+                    Ok(IngestEvent::Lines(batch, offset)) => {
+                        self.sources[idx].read_offset = offset.or(self.sources[idx].read_offset);
+                        arrived += batch.len();
+                        received = true;
+                        self.ingest_batch(idx, batch);
+                    }
+                    Ok(IngestEvent::Error(e)) => {
+                        let source = &mut self.sources[idx];
+                        source.rx = None;
+                        source.state = SourceState::Failed;
+                        source.error = Some(e.to_string());
+                        let message = format!("Source {} stopped: {}", source.name, e);
+                        diag::record(Topic::Source, &message);
+                        self.set_status(message);
+                    }
+                    Ok(IngestEvent::Waiting) => {
+                        let source = &mut self.sources[idx];
+                        source.state = SourceState::WaitingForFile;
+                        let message = format!("{} is gone; waiting for it to come back", source.name);
+                        diag::record(Topic::Source, &message);
+                        self.set_status(message);
+                    }
+                    Ok(IngestEvent::Reconnecting(e)) => {
+                        let source = &mut self.sources[idx];
+                        let message = format!("Lost {}: {}; reconnecting", source.name, e);
+                        diag::record(Topic::Source, &message);
+                        // Report the drop once, not every failed attempt
+                        let dropped = source.state != SourceState::Reconnecting;
+                        source.state = SourceState::Reconnecting;
+                        source.error = Some(e.to_string());
+                        if dropped {
+                            self.set_status(message);
+                        }
+                    }
+                    Ok(IngestEvent::Connected) => {
+                        let source = &mut self.sources[idx];
+                        source.state = SourceState::Connected;
+                        source.error = None;
+                        let message = format!("{} is back", source.name);
+                        diag::record(Topic::Source, &message);
+                        self.set_status(message);
+                    }
+                    Err(TryRecvError::Empty) => {}
+                    Err(TryRecvError::Disconnected) => {
+                        let source = &mut self.sources[idx];
+                        source.rx = None;
+                        source.state = SourceState::Ended;
+                        diag::record(Topic::Source, format!("{} ended", source.name));
+                    }
+                }
+            }
+            if !received {
+                break;
+            }
+        }
+        
+        if arrived > 0 {
+            self.finish_ingest();
+        }
+        diag::report_counts();
+        self.update_rate_metrics();
+        arrived > 0
+    }
+
+    // Add a batch of freshly arrived lines, firing watches, forwarding them to
+    // the tee and the printer and extending the filtered view
+    pub(crate) fn ingest_batch(&mut self, source: usize, lines: Vec<LogLine>) {
+        let lines = self.run_line_hook(source, lines);
+        let added = lines.len();
+        if let Some(source) = self.sources.get_mut(source).filter(|_| added > 0) {
+            source.last_line_at = Some(Instant::now());
+        }
+        // The view is brought up to date once the batch is in: `evicted` lines
+        // have left the front since (the view's indices are still off by that
+        // much) and the last `appended` lines are yet to be filtered
+        let (mut evicted, mut appended) = (0, 0);
+        for log in lines {
+            let (position, evicted_now) = self.add_log_line(source, log);
+            diag::EVICTED_LINES.fetch_add(evicted_now as u64, Ordering::Relaxed);
+            let Some(position) = position else {
+                // Slotted in at the front and evicted straight away
+                evicted += evicted_now - 1;
+                continue;
+            };
+            evicted += evicted_now;
+            if position + 1 + appended >= self.log_lines.len() {
+                appended += 1;
+            } else {
+                self.filter_inserted_line(position, evicted);
+            }
+            self.check_watches(position);
+            self.forward_line(position);
+        }
+        self.extend_filter(evicted, appended);
+    }
+
+    // Fit a line slotted in at buffer position `position`, above the lines
+    // still to be filtered, into the view, whose indices are `evicted` ahead
+    fn filter_inserted_line(&mut self, position: usize, evicted: usize) {
+        let at = self.filtered_logs.partition_point(|&i| i < position + evicted);
+        for i in &mut self.filtered_logs[at..] {
+            *i += 1;
+        }
+        let criteria = self.criteria(&self.applied_filter);
+        let matched = filter::matching_indices(&self.log_lines, &[position], &criteria, &|| false).unwrap_or_default();
+        let admitted = self.throttle.as_mut().is_none_or(|throttle| throttle.admit(&self.log_lines[position]));
+        if !matched.is_empty() && admitted {
+            self.filtered_logs.insert(at, position + evicted);
+            if !self.follow_mode {
+                self.unseen_lines += 1;
+            }
+        }
+    }
+
+    // Pass freshly read lines through the script's `on_line` hook, which may
+    // rewrite or drop them. Replayed sessions were recorded after the hook, so
+    // they skip it. A failing hook is turned off rather than reported per line.
+    pub(crate) fn run_line_hook(&mut self, source: usize, lines: Vec<LogLine>) -> Vec<LogLine> {
+        let replaying = self.replay.is_some();
+        let Some(script) = self.script.as_mut().filter(|script| script.on_line && !replaying) else {
+            return lines;
+        };
+        let (name, format) = self.sources.get(source)
+            .map_or_else(|| (self.log_path.clone(), self.format.clone()), |source| (source.name.clone(), source.current_format()));
+        let mut kept = Vec::with_capacity(lines.len());
+        let mut error = None;
+        for log in lines {
+            if !script.on_line {
+                kept.push(log);
+                continue;
+            }
+            match script.on_line(&log.content(), &name) {
+                Ok(LineAction::Keep) => kept.push(log),
+                Ok(LineAction::Replace(line)) => kept.push(LogLine::parse(&line, &format)),
+                Ok(LineAction::Drop) => {
+                    diag::HOOK_DROPPED_LINES.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    script.on_line = false;
+                    error = Some(e);
+                    kept.push(log);
+                }
+            }
+        }
+        self.show_script_messages();
+        if let Some(e) = error {
+            self.set_status(format!("Script hook turned off: {}", e));
+        }
+        kept
+    }
+
+    // Show the last message the script passed to `status` or `print`, if any
+    pub(crate) fn show_script_messages(&mut self) {
+        if let Some(message) = self.script.as_mut().and_then(|script| script.take_messages().pop()) {
+            self.set_status(message);
+        }
+    }
+
+    // Once-per-frame work after ingesting: flush the sinks and follow the tail
+    pub(crate) fn finish_ingest(&mut self) {
+        if let Some(tee) = &mut self.tee
+            && let Err(e) = tee.flush()
+        {
+            self.stop_tee(e);
+        }
+        if let Some(recorder) = &mut self.recorder {
+            let _ = recorder.flush();
+        }
+        if let Some(spill) = &mut self.spill {
+            let _ = spill.flush();
+        }
+        self.clamp_heatmap_cursor();
+        
+        // Auto-scroll if follow mode is enabled
+        if self.follow_mode {
+            self.scroll = self.filtered_logs.len();
+        }
+    }
+
+    // Move the replay clock, rebuilding the buffer when seeking backwards
+    pub(crate) fn seek_replay(&mut self, delta_ms: f64) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        let restart = replay.seek(delta_ms);
+        let lines = replay.due_lines();
+        if restart {
+            self.clear_buffer();
+            for source in &mut self.sources {
+                source.lines = 0;
+            }
+        }
+        let format = self.sources[0].current_format();
+        for line in &lines {
+            self.add_log_line(0, LogLine::parse(line, &format));
+        }
+        self.update_stats();
+        self.update_filter();
+        if self.follow_mode {
+            self.scroll = self.filtered_logs.len();
+        }
+    }
+
+    // Refresh the buffer gauge and, every few seconds, the ingest rate
+    pub(crate) fn update_rate_metrics(&mut self) {
+        self.metrics.buffered_lines.store(self.log_lines.len() as u64, Ordering::Relaxed);
+        self.metrics.buffer_bytes.store(self.buffer_bytes as u64, Ordering::Relaxed);
+        let (started, lines_at_start) = self.rate_window;
+        let elapsed = started.elapsed();
+        if elapsed >= Duration::from_secs(5) {
+            let rate = (self.lines_ingested - lines_at_start) as f64 / elapsed.as_secs_f64();
+            self.metrics.set_ingest_rate(rate);
+            self.rate_window = (Instant::now(), self.lines_ingested);
+        }
+        let (started, lines_at_start) = self.pressure_window;
+        let elapsed = started.elapsed();
+        if elapsed >= PRESSURE_WINDOW {
+            let rate = (self.lines_ingested - lines_at_start) as f64 / elapsed.as_secs_f64();
+            self.redraw |= rate != self.lines_per_sec;
+            self.lines_per_sec = rate;
+            self.pressure_window = (Instant::now(), self.lines_ingested);
+        }
+        for source in &mut self.sources {
+            self.redraw |= source.sample_rate(PRESSURE_WINDOW);
+        }
+    }
+
+    // Fire an alert for every watch rule matching the line just added at `position`
+    pub(crate) fn check_watches(&mut self, position: usize) {
+        let Some(log) = self.log_lines.get(position) else {
+            return;
+        };
+        let now = Instant::now();
+        self.last_line_at = now;
+        let (line, seq) = (log.content(), log.seq);
+        let mut fired = Vec::new();
+        for (idx, rule) in self.watches.iter_mut().enumerate() {
+            rule.rearm_silence();
+            let escalated = rule.escalated_from(log);
+            if rule.active() && (escalated.is_some() || rule.matches(&line, log.level)) && rule.record_match(now) {
+                fired.push((idx, escalated));
+            }
+        }
+        let level = log.level;
+        for (idx, escalated) in fired {
+            let line = match escalated {
+                Some(from) => escalation_line(from, level, &line),
+                None => line.clone(),
+            };
+            self.fire_alert(idx, line, Some(seq));
+        }
+    }
+
+    // Fire dead-man alerts for silence watches once no lines arrived for long enough
+    pub(crate) fn check_silence_watches(&mut self) {
+        let quiet_for = self.last_line_at.elapsed();
+        for idx in 0..self.watches.len() {
+            if self.watches[idx].active() && self.watches[idx].silence_expired(quiet_for) {
+                let message = format!("No new lines for {}s", quiet_for.as_secs());
+                self.fire_alert(idx, message, None);
+            }
+        }
+    }
+
+    pub(crate) fn fire_alert(&mut self, rule_idx: usize, line: String, seq: Option<u64>) {
+        let source = self.alert_source(seq);
+        let rule = &mut self.watches[rule_idx];
+        self.metrics.alerts_fired.fetch_add(1, Ordering::Relaxed);
+        self.redraw = true;
+        self.alert_flash_until = Some(Instant::now() + Duration::from_millis(1500));
+        self.ring_bell |= rule.bell;
+        if rule.notify {
+            self.alert_worker.notify(&rule.spec, &line);
+        }
+        rule.run_exec(&line, Instant::now(), &mut self.alert_worker);
+        let alert = Alert {
+            time: chrono::Local::now(),
+            rule: rule.spec.clone(),
+            line,
+            source,
+            seq,
+            acknowledged: false,
+        };
+        if let Some(url) = &rule.webhook {
+            self.alert_worker.post_webhook(url, &rule.webhook_template, &alert);
+        }
+        let hooked = self.script.as_mut()
+            .filter(|script| script.on_alert)
+            .map(|script| script.on_alert(&alert.rule, &alert.line));
+        if self.alerts.len() == MAX_ALERTS {
+            self.alerts.remove(0);
+        }
+        self.alerts.push(alert);
+        self.show_script_messages();
+        if let Some(Err(e)) = hooked {
+            if let Some(script) = &mut self.script {
+                script.on_alert = false;
+            }
+            self.set_status(format!("Script hook turned off: {}", e));
+        }
+    }
+
+    // Where the line with `seq` came from, or the followed file for alerts without a line
+    pub(crate) fn alert_source(&self, seq: Option<u64>) -> String {
+        seq.and_then(|seq| self.log_lines.binary_search_by_key(&seq, |log| log.seq).ok())
+            .and_then(|idx| self.source_of(&self.log_lines[idx]))
+            .map_or_else(|| self.log_path.clone(), |source| source.target.clone())
+    }
+
+    pub(crate) fn source_of(&self, log: &LogLine) -> Option<&Source> {
+        self.sources.iter().find(|source| source.id == log.source)
+    }
+
+    pub(crate) fn add_watch(&mut self) {
+        match WatchRule::parse(&self.watch_input) {
+            Ok(rule) => {
+                self.watches.push(rule);
+                self.watch_input.clear();
+                self.watch_error = None;
+                self.sync_view_to_tab();
+            }
+            Err(e) => self.watch_error = Some(e),
+        }
+    }
+
+    // Check the watch pattern as it is typed, so a bad regex shows before Enter
+    pub(crate) fn check_watch_input(&mut self) {
+        self.watch_error = match self.watch_input.trim() {
+            "" => None,
+            input => WatchRule::parse(input).err(),
+        };
+    }
+
+    pub(crate) fn unread_alerts(&self) -> usize {
+        self.alerts.iter().filter(|a| !a.acknowledged).count()
+    }
+
+    // Index into `alerts` of the row selected in the alerts tab (newest first)
+    pub(crate) fn selected_alert(&self) -> Option<usize> {
+        self.alerts.len().checked_sub(self.alerts_selected + 1)
+    }
+
+    // Handle keys specific to the alerts view, returns true if the key was consumed
+    pub(crate) fn handle_alerts_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Up => self.alerts_selected = self.alerts_selected.saturating_sub(1),
+            KeyCode::Down => {
+                self.alerts_selected = std::cmp::min(self.alerts_selected + 1, self.alerts.len().saturating_sub(1));
+            }
+            KeyCode::Char('a') => {
+                if let Some(idx) = self.selected_alert() {
+                    self.alerts[idx].acknowledged = true;
+                }
+            }
+            KeyCode::Char('A') => self.alerts.iter_mut().for_each(|a| a.acknowledged = true),
+            KeyCode::Char('z') => {
+                if let Some(rule) = self.selected_alert_rule() {
+                    rule.snooze(SNOOZE_STEP);
+                }
+            }
+            KeyCode::Char('m') => {
+                if let Some(rule) = self.selected_alert_rule() {
+                    rule.muted = !rule.muted;
+                }
+            }
+            KeyCode::Char('u') => {
+                if let Some(rule) = self.selected_alert_rule() {
+                    rule.muted = false;
+                    rule.snoozed_until = None;
+                }
+            }
+            KeyCode::Char('x') => {
+                self.alerts.retain(|a| !a.acknowledged);
+                // The flash shows the latest alert, which may have just gone
+                self.alert_flash_until = None;
+                self.alerts_selected = std::cmp::min(self.alerts_selected, self.alerts.len().saturating_sub(1));
+            }
+            KeyCode::Enter => {
+                if let Some(idx) = self.selected_alert() {
+                    self.alerts[idx].acknowledged = true;
+                    if let Some(seq) = self.alerts[idx].seq {
+                        self.jump_to_seq(seq);
+                    }
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // The watch rule that fired the selected alert, if it is still registered
+    pub(crate) fn selected_alert_rule(&mut self) -> Option<&mut WatchRule> {
+        let spec = &self.alerts[self.selected_alert()?].rule;
+        self.watches.iter_mut().find(|w| &w.spec == spec)
+    }
+
+    // Show the line with the given sequence number in the log view, highlighted
+    pub(crate) fn jump_to_seq(&mut self, seq: u64) {
+        let Ok(line_idx) = self.log_lines.binary_search_by_key(&seq, |l| l.seq) else {
+            return; // Already evicted from the buffer
+        };
+        if !self.filtered_logs.contains(&line_idx) {
+            // The line is hidden by the current filter, so drop it
+            self.clear_filter();
+        }
+        let Some(pos) = self.filtered_logs.iter().position(|&i| i == line_idx) else {
+            return;
+        };
+        for (i, log) in self.log_lines.iter_mut().enumerate() {
+            log.highlighted = i == line_idx;
+        }
+        self.pause_follow();
+        self.scroll = pos + SCROLL_LEAD;
+        self.select_tab(0);
+    }
+
+    // Run the ':' command typed in the command line
+    pub(crate) fn run_command(&mut self) {
+        let input = std::mem::take(&mut self.command_input);
+        let _ = self.execute_command(&input);
+    }
+
+    // Run a ':' command, showing its outcome in the status bar and returning it
+    fn execute_command(&mut self, input: &str) -> Result<String, String> {
+        let (command, arg) = input.trim().split_once(' ').unwrap_or((input.trim(), ""));
+        let arg = arg.trim();
+        let result = match command {
+            "w" | "write" if !arg.is_empty() => self.write_export(arg),
+            "w" | "write" => Err("Usage: :write <path>".to_string()),
+            "timeline" if self.notes.is_empty() && !self.log_lines.iter().any(|log| log.highlighted) => {
+                Err("Nothing for a timeline yet: write notes on lines with a".to_string())
+            }
+            "timeline" if !arg.is_empty() => export::write_timeline(self, arg)
+                .map(|count| format!("Wrote a timeline of {} entries to {}", count, arg))
+                .map_err(|e| format!("Failed to write {}: {}", arg, e)),
+            "timeline" => Err("Usage: :timeline <path.md>".to_string()),
+            "throttle" if self.audit.is_some() => Err("No throttle in audit mode: it holds lines back from the view".to_string()),
+            "throttle" if arg == "off" => {
+                self.set_throttle(0);
+                self.update_filter();
+                Ok("Throttle off".to_string())
+            }
+            "throttle" => match arg.parse::<usize>() {
+                Ok(per_second) if per_second > 0 => {
+                    self.set_throttle(per_second);
+                    self.update_filter();
+                    Ok(format!("Showing at most {} lines a second of each message template", per_second))
+                }
+                _ => Err("Usage: :throttle <lines per second>|off".to_string()),
+            },
+            "tee" if arg == "off" => {
+                self.tee = None;
+                Ok("Tee stopped".to_string())
+            }
+            "tee" if !arg.is_empty() => self.start_tee(arg)
+                .map(|_| format!("Forwarding filtered lines to {}", arg))
+                .map_err(|e| format!("Failed to open {}: {}", arg, e)),
+            "tee" => Err("Usage: :tee <path | |command | tcp://host:port | off>".to_string()),
+            "open" if !arg.is_empty() => self.open_source(arg),
+            "open" => Err("Usage: :open <path | |command | tcp://host:port | http(s)://url | k8s://[ns/]pod-or-selector>".to_string()),
+            "close" if !arg.is_empty() => self.close_source(arg),
+            "close" => Err("Usage: :close <source name or number>".to_string()),
+            "retry" => self.retry_sources(arg),
+            "compare" if !arg.is_empty() => self.start_compare(arg),
+            "compare" => Err("Usage: :compare <path or source>".to_string()),
+            "diff" => self.start_diff(arg),
+            "format" => self.set_source_format(arg),
+            "geo" => self.set_geo_filter(arg),
+            "lookup" => self.set_lookup_filter(arg),
+            "column" => self.set_column_filter(arg),
+            "group" => self.open_group_panel(arg),
+            "workspace" => self.name_workspace(arg.trim()),
+            "buffer" => self.resize_buffer(arg),
+            "" => Ok(String::new()),
+            _ => self.script.as_mut()
+                .and_then(|script| script.command(command, arg))
+                .unwrap_or_else(|| Err(format!("Unknown command: {}", command))),
+        };
+        let (Ok(message) | Err(message)) = &result;
+        if let Some(audit) = &mut self.audit {
+            audit.record(format!(":{} ({})", input.trim(), message));
+        }
+        self.set_status(message.clone());
+        self.show_script_messages();
+        // Commands that open a view of their own have switched to it already
+        if self.view_mode == ViewMode::CommandView {
+            self.sync_view_to_tab();
+        }
+        result
+    }
+
+    /// Attach another source to the merged view, as with `:open`.
+    pub fn open_source(&mut self, target: &str) -> Result<String, String> {
+        if self.replay.is_some() {
+            return Err("Can't attach sources during a replay".to_string());
+        }
+        // Anyone able to write to the control socket shouldn't get a shell through it
+        if self.remote && target.trim_start().starts_with('|') {
+            return Err(format!("{} is refused over the control socket", target.trim()));
+        }
+        match PodTarget::parse(target) {
+            Some(pod) => self.watch_pods(target, pod),
+            None => self.attach_source(target, None),
+        }
+    }
+
+    // Open `target` as a new source, named `name` rather than after the target
+    fn attach_source(&mut self, target: &str, name: Option<&str>) -> Result<String, String> {
+        let id = self.next_source_id();
+        let (mut source, lines) = Source::open(
+            id,
+            target,
+            self.initial_lines,
+            self.line_numbering == LineNumbering::File,
+            self.poll_interval.clone(),
+            self.format.clone(),
+        )
+        .map_err(|e| format!("Failed to open {}: {}", target, e))?;
+        if let Some(name) = name {
+            source.name = name.to_string();
+        }
+        // Two sources with the same name couldn't be told apart
+        if self.sources.iter().any(|other| other.name == source.name) {
+            source.name = format!("{}#{}", source.name, self.sources.len() + 1);
+        }
+        let message = format!("Opened {} as {}", target, source.name);
+        diag::record(Topic::Source, &message);
+        self.sources.push(source);
+        let idx = self.sources.len() - 1;
+        let format = self.sources[idx].current_format();
+        self.ingest_batch(idx, lines.iter().map(|line| LogLine::parse(line, &format)).collect());
+        self.sources[idx].mark_caught_up();
+        self.finish_ingest();
+        if let Some((unit, user)) = systemd::journal_unit(target) {
+            self.unit_watches.push(UnitWatch::start(&unit, user, id));
+        }
+        Ok(message)
+    }
+
+    // Follow the named pod or every running pod a label selector matches,
+    // and keep watching for pods starting, stopping and restarting
+    fn watch_pods(&mut self, target: &str, pod: PodTarget) -> Result<String, String> {
+        if self.pod_watches.iter().any(|watch| watch.target == target) {
+            return Err(format!("Already watching {}", target));
+        }
+        let pods = pod.running_pods()?;
+        for name in &pods {
+            self.attach_source(&pod.logs_command(name, Some(self.initial_lines)), Some(name))?;
+        }
+        let message = format!("Following {} pods matching {}", pods.len(), pod.name);
+        diag::record(Topic::Source, &message);
+        self.pod_watches.push(PodWatch::start(target, pod, pods));
+        Ok(message)
+    }
+
+    // Attach the pods that started since the last look at each selector, and
+    // the ones whose log ended while they kept running, from their first
+    // line; detach the ones that stopped
+    fn check_pod_watches(&mut self) {
+        self.drop_gone_pods();
+        for idx in 0..self.pod_watches.len() {
+            let pods = match self.pod_watches[idx].poller.rx.try_recv() {
+                Ok(Ok(pods)) => pods,
+                Ok(Err(e)) => {
+                    let message = format!("Can't list pods matching {}: {}", self.pod_watches[idx].pod.name, e);
+                    diag::record(Topic::Source, &message);
+                    self.set_status(message);
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            let stopped: Vec<String> = self.pod_watches[idx].pods.iter().filter(|name| !pods.contains(name)).cloned().collect();
+            let mut changes = Vec::new();
+            for name in &pods {
+                let pod = &self.pod_watches[idx].pod;
+                match self.sources.iter().position(|source| &source.name == name) {
+                    None => match self.attach_source(&pod.logs_command(name, None), Some(name)) {
+                        Ok(_) => changes.push(format!("+{}", name)),
+                        Err(e) => changes.push(e),
+                    },
+                    // Its container restarted, or the pod was recreated under
+                    // the same name
+                    Some(source) if self.sources[source].rx.is_none() => {
+                        let source = &mut self.sources[source];
+                        source.target = match source.last_line_at {
+                            Some(at) => pod.logs_command_since(name, at.elapsed()),
+                            None => pod.logs_command(name, None),
+                        };
+                        match source.reopen(self.poll_interval.clone()) {
+                            Ok(()) => changes.push(format!("{} restarted", name)),
+                            Err(e) => changes.push(format!("Failed to reopen {}: {}", name, e)),
+                        }
+                        let id = source.id;
+                        self.pod_watches[idx].gone.retain(|&gone| gone != id);
+                    }
+                    Some(_) => {}
+                }
+            }
+            for name in &stopped {
+                let live = |source: &&mut Source| &source.name == name && source.rx.is_some();
+                if let Some(source) = self.sources.iter_mut().find(live) {
+                    source.detach("pod terminated");
+                    self.pod_watches[idx].gone.push(source.id);
+                    changes.push(format!("-{}", name));
+                }
+            }
+            self.pod_watches[idx].pods = pods;
+            if !changes.is_empty() {
+                let message = format!("Pods matching {}: {}", self.pod_watches[idx].pod.name, changes.join(", "));
+                diag::record(Topic::Source, &message);
+                self.set_status(message);
+            }
+        }
+    }
+
+    // Drop the sources of pods that went away once the last of their lines
+    // has left the buffer, so rollouts don't pile up ended sources
+    fn drop_gone_pods(&mut self) {
+        let oldest = self.log_lines.front().map(|log| log.seq);
+        let sources = &self.sources;
+        let mut dropped = Vec::new();
+        for watch in &mut self.pod_watches {
+            watch.gone.retain(|&id| {
+                let buffered = sources.iter()
+                    .find(|source| source.id == id)
+                    .and_then(|source| source.last_seq)
+                    .is_some_and(|seq| oldest.is_some_and(|oldest| seq >= oldest));
+                if !buffered {
+                    dropped.push(id);
+                }
+                buffered
+            });
+        }
+        if !dropped.is_empty() {
+            self.sources.retain(|source| !dropped.contains(&source.id));
+            self.sources_panel.selected = self.sources_panel.selected.min(self.sources.len().saturating_sub(1));
+        }
+    }
+
+    // Take in the state changes of the units being watched, marking each in
+    // the unit's source. Returns how many marker lines were added.
+    fn check_unit_watches(&mut self) -> usize {
+        let mut added = 0;
+        for idx in 0..self.unit_watches.len() {
+            let watch = &mut self.unit_watches[idx];
+            let state = match watch.poller.rx.try_recv() {
+                Ok(Ok(state)) => state,
+                Ok(Err(e)) => {
+                    let message = format!("Can't get the state of {}: {}", watch.unit, e);
+                    diag::record(Topic::Source, &message);
+                    self.set_status(message);
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            let change = watch.state.as_ref().and_then(|old| state.change_from(old));
+            watch.state = Some(state);
+            let Some((text, level, restart)) = change else {
+                continue;
+            };
+            let Some(source) = self.sources.iter().position(|source| source.id == self.unit_watches[idx].source) else {
+                continue;
+            };
+            // Stamped now, so it sorts in with the journal's lines
+            let line = format!("{} systemd: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"), text);
+            let mut log = LogLine::parse(&line, &LogFormat::Auto);
+            log.level = level;
+            log.restart = restart;
+            self.ingest_batch(source, vec![log]);
+            diag::record(Topic::Source, &text);
+            self.set_status(text);
+            added += 1;
+        }
+        added
+    }
+
+    // Open the sources that failed or ended again, or only the one named by `key`
+    pub(crate) fn retry_sources(&mut self, key: &str) -> Result<String, String> {
+        let stopped = |source: &Source| matches!(source.state, SourceState::Failed | SourceState::Ended);
+        let picked: Vec<usize> = self.sources.iter()
+            .enumerate()
+            .filter(|(idx, source)| if key.is_empty() { stopped(source) } else { source.matches(key, *idx) })
+            .map(|(idx, _)| idx)
+            .collect();
+        if picked.is_empty() {
+            return Err(if key.is_empty() { "No source has stopped".to_string() } else { format!("No source {}", key) });
+        }
+        let mut reopened = Vec::new();
+        for idx in picked {
+            let source = &mut self.sources[idx];
+            if source.state == SourceState::Replay {
+                return Err(format!("{} is replayed from a recording", source.name));
+            }
+            source.reopen(self.poll_interval.clone())
+                .map_err(|e| format!("Failed to reopen {}: {}", source.target, e))?;
+            reopened.push(source.name.clone());
+        }
+        let message = format!("Reopened {}", reopened.join(", "));
+        diag::record(Topic::Source, &message);
+        Ok(message)
+    }
+
+    /// What is wrong with the sources, if any is failing or waiting: the
+    /// first problem, and how many other sources have one.
+    pub fn source_problem(&self) -> Option<(String, usize)> {
+        let mut problems = self.sources.iter().filter_map(Source::problem);
+        let first = problems.next()?;
+        Some((first, problems.count()))
+    }
+
+    // Detach a source named by `key`, dropping its lines from the buffer
+    pub(crate) fn close_source(&mut self, key: &str) -> Result<String, String> {
+        if let Some(idx) = self.pod_watches.iter().position(|watch| watch.target == key) {
+            let watch = self.pod_watches.remove(idx);
+            let message = format!("Stopped watching pods matching {}", watch.pod.name);
+            diag::record(Topic::Source, &message);
+            return Ok(message);
+        }
+        let idx = self.sources.iter()
+            .enumerate()
+            .position(|(idx, source)| source.matches(key, idx))
+            .ok_or_else(|| format!("No source {}", key))?;
+        if idx == 0 {
+            return Err(format!("{} was opened at startup; press o to follow another file instead", self.sources[0].name));
+        }
+        let source = self.sources.remove(idx);
+        self.unit_watches.retain(|watch| watch.source != source.id);
+        if self.compare.as_ref().is_some_and(|compare| compare.sources.contains(&source.id)) {
+            self.close_compare();
+        }
+        let before = self.log_lines.len();
+        self.log_lines.retain(|log| log.source != source.id);
+        if self.log_lines.len() < before {
+            self.renumber_buffer();
+            self.update_stats();
+            self.update_filter();
+            self.scroll = self.scroll.min(self.filtered_logs.len());
+        }
+        let message = format!("Closed {} ({} lines removed)", source.name, before - self.log_lines.len());
+        diag::record(Topic::Source, &message);
+        Ok(message)
+    }
+
+    // Parse one source's lines with another format profile, including the
+    // ones already buffered; `auto` guesses the profile again from them
+    pub(crate) fn set_source_format(&mut self, arg: &str) -> Result<String, String> {
+        let usage = || "Usage: :format <source name or number> <auto | plain | json | syslog | access | profile>".to_string();
+        let (key, name) = arg.rsplit_once(' ').ok_or_else(usage)?;
+        let idx = self.sources.iter()
+            .enumerate()
+            .position(|(idx, source)| source.matches(key.trim(), idx))
+            .ok_or_else(|| format!("No source {}", key.trim()))?;
+        let id = self.sources[idx].id;
+        let mut format = LogFormat::resolve(name, &self.formats)?;
+        if matches!(format, LogFormat::Auto) {
+            let sample: Vec<String> = self.log_lines.iter()
+                .filter(|log| log.source == id)
+                .take(format::DETECT_SAMPLE_LINES)
+                .map(LogLine::content)
+                .collect();
+            format = LogFormat::detect(sample.iter().map(String::as_str)).unwrap_or(LogFormat::Auto);
+        }
+        *self.sources[idx].format.write().unwrap_or_else(|e| e.into_inner()) = format.clone();
+        let mut reparsed = 0;
+        for log in self.log_lines.iter_mut().filter(|log| log.source == id) {
+            let content = log.content();
+            let mut parsed = LogLine::parse(&content, &format);
+            parsed.seq = log.seq;
+            parsed.source = log.source;
+            parsed.line = log.line;
+            parsed.highlighted = log.highlighted;
+            parsed.restart = log.restart;
+            parsed.hidden = log.hidden;
+            if let Some(level) = remapped_level(&self.level_rules, &content) {
+                parsed.level = level;
+            }
+            parsed.intern(&mut self.interner);
+            self.buffer_bytes = self.buffer_bytes - log.memory_size() + parsed.memory_size();
+            *log = parsed;
+            reparsed += 1;
+        }
+        if reparsed > 0 {
+            self.update_stats();
+            self.update_filter();
+        }
+        Ok(format!("Parsing {} as {} ({} buffered lines re-parsed)", self.sources[idx].name, format.name(), reparsed))
+    }
+
+    // Show the main file side by side with `target`, an attached source or
+    // a file to attach first
+    pub(crate) fn start_compare(&mut self, target: &str) -> Result<String, String> {
+        let Some(main) = self.sources.first().map(|source| source.id) else {
+            return Err("Nothing to compare against".to_string());
+        };
+        let existing = self.sources.iter()
+            .enumerate()
+            .position(|(idx, source)| source.matches(target, idx));
+        let idx = match existing {
+            Some(idx) => idx,
+            None => {
+                self.open_source(target)?;
+                self.sources.len() - 1
+            }
+        };
+        if idx == 0 {
+            return Err("Pick a source other than the main file to compare with".to_string());
+        }
+        self.compare = Some(Compare::new(main, self.sources[idx].id));
+        self.view_mode = ViewMode::CompareView;
+        Ok(format!("Comparing {} with {}", self.sources[0].name, self.sources[idx].name))
+    }
+
+    pub(crate) fn close_compare(&mut self) {
+        self.compare = None;
+        self.sync_view_to_tab();
+    }
+
+    // The filtered lines of the left and right compare panes
+    pub(crate) fn compare_panes(&self) -> [Vec<usize>; 2] {
+        let Some(compare) = &self.compare else {
+            return [Vec::new(), Vec::new()];
+        };
+        compare.sources.map(|source| compare::pane_lines(&self.log_lines, &self.filtered_logs, source))
+    }
+
+    // Scroll the focused compare pane, or hand the focus to the other one where it is lined up
+    pub(crate) fn handle_compare_key(&mut self, code: KeyCode) {
+        let panes = self.compare_panes();
+        let Some(compare) = &mut self.compare else {
+            return;
+        };
+        let len = panes[compare.focus].len();
+        let page = compare.height.max(1) as isize;
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => self.close_compare(),
+            KeyCode::Up => compare.scroll_by(-1, len),
+            KeyCode::Down => compare.scroll_by(1, len),
+            KeyCode::PageUp => compare.scroll_by(-page, len),
+            KeyCode::PageDown => compare.scroll_by(page, len),
+            KeyCode::Home | KeyCode::Char('g') => compare.top = Some(0),
+            KeyCode::End | KeyCode::Char('G') => compare.top = None,
+            KeyCode::Left | KeyCode::Right | KeyCode::Tab => {
+                let (leading, following) = (&panes[compare.focus], &panes[1 - compare.focus]);
+                if compare.top.is_some() {
+                    let top = compare.focus_top(leading.len());
+                    compare.top = Some(compare::align(&self.log_lines, leading, top, following));
+                }
+                compare.focus = 1 - compare.focus;
+            }
+            _ => {}
+        }
+    }
+
+    // Compare message templates between two selections, each a time range or a source
+    pub(crate) fn start_diff(&mut self, arg: &str) -> Result<String, String> {
+        let usage = || "Usage: :diff <from> <to>, each HH:MM-HH:MM or a source".to_string();
+        let mut parts = arg.split_whitespace();
+        let (Some(left), Some(right), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(usage());
+        };
+        let (left_label, left_lines) = self.diff_selection(left)?;
+        let (right_label, right_lines) = self.diff_selection(right)?;
+        let messages = |lines: &[usize]| -> Vec<&str> {
+            lines.iter().map(|&idx| &*self.log_lines[idx].message).collect()
+        };
+        let diff = Diff::new([left_label, right_label], &messages(&left_lines), &messages(&right_lines));
+        let message = format!(
+            "{} new, {} gone, {} changed templates",
+            diff.rows.iter().filter(|row| row.change == Change::New).count(),
+            diff.rows.iter().filter(|row| row.change == Change::Gone).count(),
+            diff.rows.iter().filter(|row| matches!(row.change, Change::Rate(_))).count(),
+        );
+        self.diff = Some(diff);
+        self.view_mode = ViewMode::DiffView;
+        Ok(message)
+    }
+
+    // A label and the filtered lines for a `:diff` selection: a time range on
+    // the day of the newest line, an attached source, or a file to attach first
+    pub(crate) fn diff_selection(&mut self, text: &str) -> Result<(String, Vec<usize>), String> {
+        let day = self.log_lines.iter()
+            .rev()
+            .find_map(|log| log.time)
+            .map_or_else(|| chrono::Local::now().date_naive(), |time| time.date());
+        let (label, lines) = if let Some((start, end)) = diff::parse_time_range(text, day) {
+            let lines = self.filtered_logs.iter()
+                .copied()
+                .filter(|&idx| self.log_lines[idx].time.is_some_and(|time| time >= start && time < end))
+                .collect();
+            (text.to_string(), lines)
+        } else {
+            let existing = self.sources.iter()
+                .enumerate()
+                .position(|(idx, source)| source.matches(text, idx));
+            let idx = match existing {
+                Some(idx) => idx,
+                None => {
+                    self.open_source(text)?;
+                    self.sources.len() - 1
+                }
+            };
+            let source = &self.sources[idx];
+            (source.name.clone(), compare::pane_lines(&self.log_lines, &self.filtered_logs, source.id))
+        };
+        if lines.is_empty() {
+            return Err(format!("No lines in {}", text));
+        }
+        Ok((label, lines))
+    }
+
+    pub(crate) fn handle_diff_key(&mut self, code: KeyCode, page: isize) {
+        let Some(diff) = &mut self.diff else {
+            return;
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.diff = None;
+                self.sync_view_to_tab();
+            }
+            KeyCode::Up => diff.scroll_by(-1),
+            KeyCode::Down => diff.scroll_by(1),
+            KeyCode::PageUp => diff.scroll_by(-page),
+            KeyCode::PageDown => diff.scroll_by(page),
+            KeyCode::Home | KeyCode::Char('g') => diff.top = 0,
+            KeyCode::End | KeyCode::Char('G') => diff.scroll_by(isize::MAX),
+            _ => {}
+        }
+    }
+
+    // The line the log view is on: the highlighted one while it is in view,
+    // otherwise the bottom row. Returns its index in the buffer.
+    pub(crate) fn current_line(&self) -> Option<usize> {
+        let visible = self.filtered_logs.get(self.log_view_range.clone())?;
+        visible.iter()
+            .find(|&&idx| self.log_lines[idx].highlighted)
+            .or_else(|| visible.last())
+            .copied()
+    }
+
+    // Show the current line of the log view whole
+    pub(crate) fn open_detail(&mut self) {
+        let Some(idx) = self.current_line() else {
+            return;
+        };
+        self.detail = Some(Detail::new(self.log_lines[idx].seq, self.log_lines[idx].content()));
+        self.view_mode = ViewMode::DetailView;
+    }
+
+    // Start a block selection at the current line, or drop the one started
+    pub(crate) fn toggle_block(&mut self) {
+        if self.block_start.take().is_some() {
+            return;
+        }
+        self.block_start = self.current_line().map(|idx| self.log_lines[idx].seq);
+    }
+
+    // Buffer indices of the lines from where the block selection started
+    // through the current line, whichever comes first
+    pub(crate) fn block_range(&self) -> Option<Range<usize>> {
+        let start = self.log_lines.binary_search_by_key(&self.block_start?, |log| log.seq).ok()?;
+        let current = self.current_line()?;
+        Some(start.min(current)..start.max(current) + 1)
+    }
+
+    // Take the lines of the block selection, or the current line, out of the
+    // view without touching the filters; Shift+X brings them back
+    pub(crate) fn hide_lines(&mut self) {
+        let range = match self.block_range() {
+            Some(range) => range,
+            None => match self.current_line() {
+                Some(idx) => idx..idx + 1,
+                None => return,
+            },
+        };
+        self.block_start = None;
+        let shown: Vec<usize> = self.filtered_logs.iter().copied().filter(|idx| range.contains(idx)).collect();
+        let mut batch = Vec::with_capacity(shown.len());
+        for idx in shown {
+            let log = &mut self.log_lines[idx];
+            log.hidden = true;
+            batch.push(log.seq);
+        }
+        self.hidden_lines += batch.len();
+        let count = batch.len();
+        self.hide_history.push(batch);
+        self.update_filter();
+        self.set_status(format!("Hid {} lines, {} hidden in all (Shift+X brings them back)", count, self.hidden_lines));
+    }
+
+    // Bring back the lines hidden last
+    pub(crate) fn unhide_last(&mut self) {
+        let Some(batch) = self.hide_history.pop() else {
+            self.set_status("No hidden lines to bring back".to_string());
+            return;
+        };
+        for seq in &batch {
+            if let Ok(idx) = self.log_lines.binary_search_by_key(seq, |log| log.seq) {
+                self.log_lines[idx].hidden = false;
+                self.hidden_lines -= 1;
+            }
+        }
+        self.update_filter();
+        self.set_status(format!("Brought back {} lines, {} still hidden", batch.len(), self.hidden_lines));
+    }
+
+    // Open link `n` (from 0) of the line in the detail view, or of the current
+    // line of the log view: a URL in the browser, a file reference in $EDITOR
+    // at its line
+    pub(crate) fn open_link(&mut self, n: usize) {
+        let idx = match &self.detail {
+            Some(detail) if self.view_mode == ViewMode::DetailView => {
+                self.log_lines.binary_search_by_key(&detail.seq, |log| log.seq).ok()
+            }
+            _ => self.current_line(),
+        };
+        let Some(idx) = idx else {
+            return;
+        };
+        let log = &self.log_lines[idx];
+        let Some((_, link)) = self.links.find(&log.content()).into_iter().nth(n) else {
+            self.set_status(if n == 0 { "No link in this line".to_string() } else { format!("No link {} in this line", n + 1) });
+            return;
+        };
+        match link {
+            Link::Url(url) => {
+                let message = links::open_url(&url).map_or_else(|e| e, |()| format!("Opened {}", url));
+                self.set_status(message);
+            }
+            Link::File { path, line } => self.edit_file(&path, line, idx),
+        }
+    }
+
+    // Open `path` in $EDITOR at `line`; a relative path is tried from here,
+    // then from the directory of the file the line at `idx` came from
+    fn edit_file(&mut self, path: &str, line: u32, idx: usize) {
+        let beside_log = self.source_of(&self.log_lines[idx])
+            .filter(|source| source.is_file())
+            .and_then(|source| Path::new(&source.target).parent())
+            .map(|dir| dir.join(path));
+        match [Some(PathBuf::from(path)), beside_log].into_iter().flatten().find(|candidate| candidate.is_file()) {
+            Some(found) => self.edit_requested = Some((found, line)),
+            None => self.set_status(format!("No such file: {}", path)),
+        }
+    }
+
+    // Page through the line in the detail view, or step to the lines around it
+    pub(crate) fn handle_detail_key(&mut self, code: KeyCode, page: isize) {
+        let Some(detail) = &mut self.detail else {
+            return;
+        };
+        match code {
+            // Esc drops a search before it closes the view
+            KeyCode::Esc if !detail.query.is_empty() => {
+                detail.query.clear();
+                detail.matches.clear();
+            }
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                self.detail = None;
+                self.sync_view_to_tab();
+            }
+            KeyCode::Char('/') => {
+                detail.query.clear();
+                detail.matches.clear();
+                detail.typing = true;
+            }
+            KeyCode::Char('n') => detail.step(true),
+            KeyCode::Char('N') => detail.step(false),
+            KeyCode::Up => detail.scroll_by(-1),
+            KeyCode::Down => detail.scroll_by(1),
+            KeyCode::PageUp => detail.scroll_by(-page),
+            KeyCode::PageDown => detail.scroll_by(page),
+            KeyCode::Home | KeyCode::Char('g') => detail.top = 0,
+            KeyCode::End | KeyCode::Char('G') => detail.scroll_by(isize::MAX),
+            KeyCode::Char('O') => self.open_link(0),
+            KeyCode::Char(c @ '1'..='9') => self.open_link(c as usize - '1' as usize),
+            KeyCode::Left | KeyCode::Right => {
+                let Ok(idx) = self.log_lines.binary_search_by_key(&detail.seq, |log| log.seq) else {
+                    return; // Evicted while open
+                };
+                let pos = match self.filtered_logs.binary_search(&idx) {
+                    Ok(pos) if code == KeyCode::Left => pos.checked_sub(1),
+                    Ok(pos) => Some(pos + 1),
+                    Err(pos) if code == KeyCode::Left => pos.checked_sub(1),
+                    Err(pos) => Some(pos),
+                };
+                // The search carries over to the next line
+                if let Some(&next) = pos.and_then(|pos| self.filtered_logs.get(pos)) {
+                    let query = std::mem::take(&mut detail.query);
+                    *detail = Detail::new(self.log_lines[next].seq, self.log_lines[next].content());
+                    detail.query = query;
+                    detail.search();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Handle a key typed into the detail view's search prompt, searching as it is typed
+    pub(crate) fn handle_detail_search_key(&mut self, code: KeyCode) {
+        let Some(detail) = &mut self.detail else {
+            return;
+        };
+        match code {
+            KeyCode::Enter => return detail.typing = false,
+            KeyCode::Esc => {
+                detail.typing = false;
+                detail.query.clear();
+            }
+            KeyCode::Backspace => {
+                detail.query.pop();
+            }
+            KeyCode::Char(c) => detail.query.push(c),
+            _ => return,
+        }
+        detail.search();
+    }
+
+    // The note on the line with `seq`, if it has one
+    pub(crate) fn note_for(&self, seq: u64) -> Option<&Note> {
+        self.notes.iter().find(|note| note.seq == Some(seq))
+    }
+
+    // Start typing a note for the line in the detail view, or the current
+    // line of the log view, from the note it already has
+    pub(crate) fn start_note(&mut self) {
+        let seq = match self.view_mode {
+            ViewMode::DetailView => self.detail.as_ref().map(|detail| detail.seq),
+            _ => self.current_line().map(|idx| self.log_lines[idx].seq),
+        };
+        let Some(seq) = seq else {
+            return;
+        };
+        let text = self.note_for(seq).map_or_else(String::new, |note| note.text.clone());
+        self.notes_panel.editing = Some((seq, text));
+    }
+
+    // Handle a key while a note is being typed
+    pub(crate) fn handle_note_input(&mut self, code: KeyCode) {
+        let Some((seq, input)) = &mut self.notes_panel.editing else {
+            return;
+        };
+        match code {
+            KeyCode::Enter => {
+                let (seq, text) = (*seq, input.trim().to_string());
+                self.notes_panel.editing = None;
+                self.set_note(seq, text);
+            }
+            KeyCode::Esc => self.notes_panel.editing = None,
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            _ => {}
+        }
+    }
+
+    // Attach `text` to the line with `seq`, replacing the note it had; empty text removes it
+    pub(crate) fn set_note(&mut self, seq: u64, text: String) {
+        match self.notes.iter().position(|note| note.seq == Some(seq)) {
+            Some(i) if text.is_empty() => {
+                self.notes.remove(i);
+            }
+            Some(i) => self.notes[i].text = text,
+            None if text.is_empty() => {}
+            None => {
+                let Ok(idx) = self.log_lines.binary_search_by_key(&seq, |log| log.seq) else {
+                    return self.set_status("The line has been evicted from the buffer".to_string());
+                };
+                let log = &self.log_lines[idx];
+                let note = Note {
+                    text,
+                    line: log.content(),
+                    source: self.source_of(log).map_or_else(|| self.log_path.clone(), |source| source.name.clone()),
+                    time: log.time,
+                    created: chrono::Local::now(),
+                    seq: Some(seq),
+                };
+                let at = self.notes.iter().position(|note| note.seq.is_some_and(|other| other > seq));
+                self.notes.insert(at.unwrap_or(self.notes.len()), note);
+            }
+        }
+        self.notes_panel.selected = self.notes_panel.selected.min(self.notes.len().saturating_sub(1));
+    }
+
+    // Open the annotations panel over the log view
+    pub(crate) fn open_notes_panel(&mut self) {
+        self.notes_panel.selected = self.notes_panel.selected.min(self.notes.len().saturating_sub(1));
+        self.view_mode = ViewMode::NotesView;
+    }
+
+    // Handle a key in the annotations panel: go to a note's line, edit or remove it
+    pub(crate) fn handle_notes_key(&mut self, code: KeyCode) {
+        let selected = self.notes_panel.selected;
+        let buffered = self.notes.get(selected)
+            .and_then(|note| note.seq)
+            .filter(|&seq| self.log_lines.binary_search_by_key(&seq, |log| log.seq).is_ok());
+        match code {
+            KeyCode::Esc | KeyCode::Char('A') | KeyCode::Char('q') => self.sync_view_to_tab(),
+            KeyCode::Up => self.notes_panel.selected = selected.saturating_sub(1),
+            KeyCode::Down => self.notes_panel.selected = (selected + 1).min(self.notes.len().saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Char('e') if buffered.is_none() && !self.notes.is_empty() => {
+                self.set_status("The line has been evicted from the buffer".to_string());
+            }
+            KeyCode::Enter => {
+                if let Some(seq) = buffered {
+                    self.jump_to_seq(seq);
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(seq) = buffered {
+                    self.notes_panel.editing = Some((seq, self.notes[selected].text.clone()));
+                }
+            }
+            KeyCode::Char('x') | KeyCode::Delete if selected < self.notes.len() => {
+                self.notes.remove(selected);
+                self.notes_panel.selected = selected.min(self.notes.len().saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+
+    // Open the problems panel, listing the diagnostics of the build output
+    pub(crate) fn open_problems_panel(&mut self) {
+        let Some(problems) = &mut self.problems else {
+            self.set_status("The problems panel lists build diagnostics: start with --cargo".to_string());
+            return;
+        };
+        problems.selected = problems.selected.min(problems.list.len().saturating_sub(1));
+        self.view_mode = ViewMode::ProblemsView;
+    }
+
+    // Handle a key in the problems panel: go to a diagnostic's line, or open
+    // the code it points at in $EDITOR
+    pub(crate) fn handle_problems_key(&mut self, code: KeyCode) {
+        let Some(problems) = &mut self.problems else {
+            return self.sync_view_to_tab();
+        };
+        let selected = problems.selected;
+        let last = problems.list.len().saturating_sub(1);
+        let buffered = problems.list.get(selected)
+            .and_then(|problem| problem.seq)
+            .and_then(|seq| self.log_lines.binary_search_by_key(&seq, |log| log.seq).ok());
+        match code {
+            KeyCode::Esc | KeyCode::Char('p') | KeyCode::Char('q') => self.sync_view_to_tab(),
+            KeyCode::Up => problems.selected = selected.saturating_sub(1),
+            KeyCode::Down => problems.selected = (selected + 1).min(last),
+            KeyCode::Home => problems.selected = 0,
+            KeyCode::End => problems.selected = last,
+            KeyCode::Enter if buffered.is_none() && !problems.list.is_empty() => {
+                self.set_status("The line has been evicted from the buffer".to_string());
+            }
+            KeyCode::Enter => {
+                if let Some(idx) = buffered {
+                    self.jump_to_seq(self.log_lines[idx].seq);
+                }
+            }
+            KeyCode::Char('e') => match (problems.list.get(selected).and_then(|problem| problem.location.clone()), buffered) {
+                (Some((path, line, _)), Some(idx)) => self.edit_file(&path, line, idx),
+                (Some((path, line, _)), None) => self.edit_requested = Some((PathBuf::from(path), line)),
+                (None, _) if !problems.list.is_empty() => self.set_status("This problem points at no file".to_string()),
+                (None, _) => {}
+            },
+            _ => {}
+        }
+    }
+
+    // Open the diagnostics view over the current one, or close it again
+    pub(crate) fn toggle_diagnostics(&mut self) {
+        if self.view_mode == ViewMode::DiagnosticsView {
+            self.sync_view_to_tab();
+            return;
+        }
+        self.filter_editing = false;
+        self.diag_top = None;
+        self.view_mode = ViewMode::DiagnosticsView;
+    }
+
+    // Scroll the diagnostics view; reaching the end follows new entries again
+    pub(crate) fn handle_diagnostics_key(&mut self, code: KeyCode, page: isize) {
+        let last_top = diag::len().saturating_sub(self.diag_rows) as isize;
+        let top = self.diag_top.map_or(last_top, |top| top as isize);
+        let top = match code {
+            KeyCode::Esc | KeyCode::Char('q') => return self.sync_view_to_tab(),
+            KeyCode::Up => top - 1,
+            KeyCode::Down => top + 1,
+            KeyCode::PageUp => top - page,
+            KeyCode::PageDown => top + page,
+            KeyCode::Home | KeyCode::Char('g') => 0,
+            KeyCode::End | KeyCode::Char('G') => last_top,
+            _ => return,
+        };
+        self.diag_top = (top < last_top).then_some(top.max(0) as usize);
+    }
+
+    // Give the buffered lines consecutive sequence numbers again after some
+    // were removed from the middle, rebuilding the index and keeping alerts
+    // and notes pointed at their lines
+    pub(crate) fn renumber_buffer(&mut self) {
+        let first_seq = self.lines_ingested - self.log_lines.len() as u64;
+        let mut moved = HashMap::new();
+        self.index.clear();
+        self.buffer_bytes = 0;
+        for (i, log) in self.log_lines.iter_mut().enumerate() {
+            moved.insert(log.seq, first_seq + i as u64);
+            log.seq = first_seq + i as u64;
+            self.index.insert(log.seq, log.lower_bytes());
+            self.buffer_bytes += log.memory_size();
+        }
+        for alert in &mut self.alerts {
+            alert.seq = alert.seq.and_then(|seq| moved.get(&seq).copied());
+        }
+        for line in self.line_seqs_mut() {
+            *line = line.and_then(|seq| moved.get(&seq).copied());
+        }
+        for problem in self.problems.iter_mut().flat_map(|problems| &mut problems.list) {
+            problem.seq = problem.seq.and_then(|seq| moved.get(&seq).copied());
+        }
+        for source in &mut self.sources {
+            source.last_seq = source.last_seq.and_then(|seq| moved.get(&seq).copied());
+        }
+        self.file_markers = std::mem::take(&mut self.file_markers)
+            .into_iter()
+            .filter_map(|(seq, name)| Some((*moved.get(&seq)?, name)))
+            .collect();
+        self.latencies = std::mem::take(&mut self.latencies)
+            .into_iter()
+            .filter_map(|(seq, latency)| Some((*moved.get(&seq)?, latency)))
+            .collect();
+        for batches in self.hidden_batches_mut() {
+            for batch in batches.iter_mut() {
+                *batch = batch.iter().filter_map(|seq| moved.get(seq).copied()).collect();
+            }
+            batches.retain(|batch| !batch.is_empty());
+        }
+        self.hidden_lines = self.log_lines.iter().filter(|log| log.hidden).count();
+        self.block_start = self.block_start.and_then(|seq| moved.get(&seq).copied());
+    }
+
+    // Write the filtered view to a timestamped file in the current directory
+    pub(crate) fn export_filtered(&mut self) {
+        let path = format!("filtered-{}.log", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+        let (Ok(message) | Err(message)) = self.write_export(&path);
+        self.set_status(message);
+    }
+
+    // Write the filtered view to `path`; in audit mode, with a manifest of
+    // hashes next to it, and the export recorded in the trail
+    fn write_export(&mut self, path: &str) -> Result<String, String> {
+        let count = export::write_filtered(self, path).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        if self.audit.is_none() {
+            return Ok(format!("Wrote {} lines to {}", count, path));
+        }
+        let (manifest, hash) = audit::write_manifest(self, path)
+            .map_err(|e| format!("Wrote {} but not its audit manifest: {}", path, e))?;
+        if let Some(audit) = &mut self.audit {
+            audit.record(format!("Wrote {} lines to {} (SHA-256 {}), hashes in {}", count, path, hash, manifest));
+        }
+        Ok(format!("Wrote {} lines to {} and their hashes to {}", count, path, manifest))
+    }
+
+    /// Read the lines as cargo or rustc output, as `--cargo` does: each
+    /// diagnostic's notes and code frames take its level, and the problems
+    /// panel (`p`) lists the diagnostics.
+    pub fn start_cargo_mode(&mut self) {
+        self.problems = Some(Problems::default());
+    }
+
+    /// Enter audit mode, as `--audit` does: lines are numbered as in their
+    /// files so exports can point at their bytes, commands and filter
+    /// changes are recorded, and what would change lines without a record
+    /// (a script, level remapping, the throttle) is refused or turned off.
+    pub fn start_audit(&mut self) -> Result<(), String> {
+        if self.script.is_some() {
+            return Err("--audit can't run a script: its hooks could rewrite or drop lines unrecorded".to_string());
+        }
+        let mut audit = AuditLog::default();
+        audit.record(format!("Audit session started on {}", self.log_path));
+        if !self.level_rules.is_empty() {
+            self.level_rules.clear();
+            audit.record("Level remap rules from the config were not applied");
+        }
+        if self.throttle.is_some() {
+            self.throttle = None;
+            audit.record("The throttle from the config was turned off");
+        }
+        if self.redactor.enabled() {
+            audit.record("Lines are redacted by the config's redact rules");
+        }
+        self.line_numbering = LineNumbering::File;
+        self.audit = Some(audit);
+        Ok(())
+    }
+
+    // What the filters let through, for the audit trail
+    fn view_description(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.filter_text.is_empty() {
+            parts.push(format!("filter {:?}", self.filter_text));
+        }
+        if let Some(level) = self.min_level {
+            parts.push(format!("level {} and above", level.as_str()));
+        }
+        if let Some((start, end)) = self.time_range {
+            parts.push(format!("from {} until {}", start, end));
+        }
+        if self.status_classes.client_errors {
+            parts.push("4xx".to_string());
+        }
+        if self.status_classes.server_errors {
+            parts.push("5xx".to_string());
+        }
+        if !self.drill_words.is_empty() {
+            parts.push(format!("words {}", self.drill_words.join(", ")));
+        }
+        if let Some(place) = &self.geo_filter {
+            parts.push(format!("addresses in {}", place));
+        }
+        if let Some(text) = &self.lookup_filter {
+            parts.push(format!("looked up {:?}", text));
+        }
+        if let Some((column, value)) = &self.column_filter {
+            parts.push(format!("{} {:?}", self.columns[*column].name, value));
+        }
+        if self.hidden_lines > 0 {
+            parts.push(format!("{} lines hidden by hand", self.hidden_lines));
+        }
+        for source in &self.sources {
+            let filter = &source.filter;
+            if filter.hidden {
+                parts.push(format!("{} hidden", source.name));
+            }
+            if !filter.text.is_empty() {
+                parts.push(format!("{} filter {:?}", source.name, filter.text));
+            }
+            if let Some(level) = filter.min_level {
+                parts.push(format!("{} level {} and above", source.name, level.as_str()));
+            }
+        }
+        if parts.is_empty() { "every line".to_string() } else { parts.join("; ") }
+    }
+
+    // Open the whole log file in browse mode
+    pub(crate) fn open_browser(&mut self) {
+        match Browser::open(&self.log_path) {
+            Ok(browser) => {
+                self.browser = Some(browser);
+                self.view_mode = ViewMode::BrowseView;
+            }
+            Err(e) => self.set_status(format!("Failed to browse {}: {}", self.log_path, e)),
+        }
+    }
+
+    // Browse the lines spilled out of the buffer, starting at the most recent
+    pub(crate) fn open_scrollback(&mut self, height: usize) {
+        let Some(spill) = &mut self.spill else {
+            return;
+        };
+        match spill.flush().and_then(|_| Browser::open(&spill.path)) {
+            Ok(mut browser) => {
+                browser.go_to_end(height);
+                browser.message = format!("Scrollback: {} lines evicted from the buffer (Esc to return)", spill.lines);
+                self.browser = Some(browser);
+                self.view_mode = ViewMode::BrowseView;
+            }
+            Err(e) => self.set_status(format!("Failed to open scrollback: {}", e)),
+        }
+    }
+
+    pub(crate) fn has_scrollback(&self) -> bool {
+        self.spill.as_ref().is_some_and(|spill| spill.lines > 0)
+    }
+
+    pub(crate) fn close_browser(&mut self) {
+        self.browser = None;
+        self.sync_view_to_tab();
+    }
+
+    // Open the settings popup over the log view
+    pub(crate) fn open_settings(&mut self) {
+        self.settings.input = None;
+        self.settings.message = None;
+        self.view_mode = ViewMode::SettingsView;
+    }
+
+    // A setting's current value as shown in the settings panel
+    pub(crate) fn setting_value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::PollInterval => self.poll_interval.load(Ordering::Relaxed).to_string(),
+            Setting::BufferSize => self.max_lines.to_string(),
+            Setting::Wrap => (if self.wrap_lines { "on" } else { "off" }).to_string(),
+            Setting::TimestampFormat => self.timestamp_format.clone().unwrap_or_else(|| "as logged".to_string()),
+            Setting::Theme => self.theme.name().to_string(),
+            Setting::Symbols => (if self.show_symbols { "on" } else { "off" }).to_string(),
+        }
+    }
+
+    // Step the selected setting with ←/→: nudge numbers, flip toggles, cycle choices
+    pub(crate) fn adjust_setting(&mut self, step: isize) {
+        match self.settings.setting() {
+            Setting::PollInterval => {
+                let ms = self.poll_interval.load(Ordering::Relaxed).saturating_add_signed(step as i64 * POLL_INTERVAL_STEP_MS);
+                self.poll_interval.store(ms.max(MIN_POLL_INTERVAL_MS), Ordering::Relaxed);
+            }
+            Setting::BufferSize => {
+                let lines = if step > 0 { self.max_lines.saturating_mul(2) } else { self.max_lines / 2 };
+                self.set_max_lines(lines.max(1));
+            }
+            Setting::Wrap => self.wrap_lines = !self.wrap_lines,
+            Setting::TimestampFormat => {
+                let current = self.timestamp_format.as_deref().unwrap_or("");
+                let next = match TIMESTAMP_PRESETS.iter().position(|&preset| preset == current) {
+                    Some(i) => (i as isize + step).rem_euclid(TIMESTAMP_PRESETS.len() as isize) as usize,
+                    None => 0,
+                };
+                self.timestamp_format = Some(TIMESTAMP_PRESETS[next].to_string()).filter(|layout| !layout.is_empty());
+            }
+            Setting::Theme => self.theme = self.theme.cycle(step),
+            Setting::Symbols => self.show_symbols = !self.show_symbols,
+        }
+    }
+
+    // Apply the value typed for the selected setting
+    pub(crate) fn commit_setting_input(&mut self) {
+        let Some(input) = self.settings.input.take() else {
+            return;
+        };
+        let text = input.trim();
+        let result = match self.settings.setting() {
+            Setting::PollInterval => match text.parse::<u64>() {
+                Ok(ms) if ms >= MIN_POLL_INTERVAL_MS => {
+                    self.poll_interval.store(ms, Ordering::Relaxed);
+                    Ok(())
+                }
+                _ => Err(format!("Invalid interval: {} (at least {} ms)", text, MIN_POLL_INTERVAL_MS)),
+            },
+            Setting::BufferSize => parse_max_lines(text).map(|lines| {
+                self.set_max_lines(lines);
+            }),
+            Setting::TimestampFormat => self.set_timestamp_format(text),
+            Setting::Wrap | Setting::Theme | Setting::Symbols => Ok(()),
+        };
+        self.settings.message = result.err();
+    }
+
+    // Write the panel's settings into the config file
+    pub(crate) fn save_settings(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            self.settings.message = Some("No config file location (HOME is not set)".to_string());
+            return;
+        };
+        let values = vec![
+            ("interval", toml_edit::value(self.poll_interval.load(Ordering::Relaxed) as i64)),
+            ("max_lines", toml_edit::value(self.max_lines as i64)),
+            ("wrap", toml_edit::value(self.wrap_lines)),
+            ("timestamp_format", toml_edit::value(self.timestamp_format.as_deref().unwrap_or(""))),
+            ("theme", toml_edit::value(self.theme.name())),
+            ("symbols", toml_edit::value(self.show_symbols)),
+        ];
+        self.settings.message = Some(match settings::save(&path, values) {
+            Ok(()) => format!("Saved to {}", path.display()),
+            Err(e) => e,
+        });
+    }
+
+    // Open the sources panel over the log view
+    pub(crate) fn open_sources_panel(&mut self) {
+        self.sources_panel.input = None;
+        self.sources_panel.selected = self.sources_panel.selected.min(self.sources.len().saturating_sub(1));
+        self.view_mode = ViewMode::SourcesView;
+    }
+
+    // Handle a key in the sources panel: pick a source, then set its level
+    // with ←/→ or type a filter for it
+    pub(crate) fn handle_sources_key(&mut self, code: KeyCode) {
+        let selected = self.sources_panel.selected;
+        if let Some(input) = &mut self.sources_panel.input {
+            match code {
+                KeyCode::Enter => {
+                    let text = input.trim().to_string();
+                    self.sources_panel.input = None;
+                    if let Some(source) = self.sources.get_mut(selected) {
+                        source.filter.set_text(&text);
+                        self.update_filter();
+                    }
+                }
+                KeyCode::Esc => self.sources_panel.input = None,
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        match code {
+            KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('q') => self.sync_view_to_tab(),
+            KeyCode::Up => self.sources_panel.selected = selected.saturating_sub(1),
+            KeyCode::Down => self.sources_panel.selected = (selected + 1).min(self.sources.len().saturating_sub(1)),
+            KeyCode::Left | KeyCode::Right => {
+                let step = if code == KeyCode::Left { -1 } else { 1 };
+                if let Some(source) = self.sources.get_mut(selected) {
+                    source.filter.step_level(step);
+                    self.update_filter();
+                }
+            }
+            KeyCode::Enter | KeyCode::Char('/') => {
+                self.sources_panel.input = self.sources.get(selected).map(|source| source.filter.text.clone());
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                if let Some(source) = self.sources.get_mut(selected) {
+                    source.filter = SourceFilter::default();
+                    self.update_filter();
+                }
+            }
+            KeyCode::Char(' ') | KeyCode::Char('v') => {
+                if let Some(source) = self.sources.get_mut(selected) {
+                    source.filter.hidden = !source.filter.hidden;
+                    self.update_filter();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Handle a key in the settings panel
+    pub(crate) fn handle_settings_key(&mut self, code: KeyCode) {
+        if let Some(input) = &mut self.settings.input {
+            match code {
+                KeyCode::Enter => self.commit_setting_input(),
+                KeyCode::Esc => self.settings.input = None,
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        self.settings.message = None;
+        match code {
+            KeyCode::Esc | KeyCode::Char(',') | KeyCode::Char('q') => self.sync_view_to_tab(),
+            KeyCode::Up => self.settings.selected = self.settings.selected.saturating_sub(1),
+            KeyCode::Down => self.settings.selected = (self.settings.selected + 1).min(SETTINGS.len() - 1),
+            KeyCode::Left => self.adjust_setting(-1),
+            KeyCode::Right => self.adjust_setting(1),
+            KeyCode::Enter if self.settings.setting().editable() => {
+                let current = match self.settings.setting() {
+                    Setting::TimestampFormat => self.timestamp_format.clone().unwrap_or_default(),
+                    setting => self.setting_value(setting),
+                };
+                self.settings.input = Some(current);
+            }
+            KeyCode::Enter => self.adjust_setting(1),
+            KeyCode::Char('s') => self.save_settings(),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn set_status(&mut self, message: String) {
+        self.status_message = if message.is_empty() { None } else { Some((message, Instant::now())) };
+    }
+
+    pub(crate) fn alert_flashing(&self) -> bool {
+        self.alert_flash_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    // The next moment something on screen changes without input or new lines:
+    // a status message or alert flash expiring, a snooze running out, a silence
+    // watch coming due, the replay clock moving on or an ingest rate window closing
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        let now = Instant::now();
+        let mut deadlines = vec![self.rate_window.0 + Duration::from_secs(5)];
+        deadlines.extend(self.status_message.as_ref().map(|(_, at)| *at + STATUS_TIMEOUT));
+        deadlines.extend(self.alert_flash_until);
+        for rule in &self.watches {
+            deadlines.extend(rule.snoozed_until);
+            deadlines.extend(rule.silence_deadline(self.last_line_at));
+        }
+        if self.view_mode == ViewMode::AlertsView && self.watches.iter().any(|rule| !rule.state_label().is_empty()) {
+            deadlines.push(now + Duration::from_secs(1)); // Snooze countdowns
+        }
+        if self.view_mode == ViewMode::SourcesView {
+            deadlines.push(now + Duration::from_secs(1)); // Rates and last-line ages
+        }
+        if self.replay.as_ref().is_some_and(|replay| !replay.paused && !replay.finished()) {
+            deadlines.push(now + REPLAY_TICK);
+        }
+        if self.lines_per_sec > 0.0 {
+            deadlines.push(self.pressure_window.0 + PRESSURE_WINDOW);
+        }
+        deadlines.extend(self.perf.degraded_until);
+        if self.filter_stale {
+            deadlines.push(now);
+        }
+        deadlines.into_iter().filter(|at| *at >= now).min()
+    }
+
+    // Sleep until terminal input arrives, the tail thread queues lines or the
+    // next timer is due. Returns the input event, if that is what woke us.
+    pub(crate) fn wait_for_event(&mut self, events: &Receiver<io::Result<Event>>) -> io::Result<Option<Event>> {
+        let mut select = Select::new();
+        let input_op = select.recv(events);
+        select.recv(&self.requests.1);
+        if self.view_mode != ViewMode::HelpView {
+            for rx in self.sources.iter().filter_map(|source| source.rx.as_ref()) {
+                select.recv(rx);
+            }
+            for watch in &self.pod_watches {
+                select.recv(&watch.poller.rx);
+            }
+            for watch in &self.unit_watches {
+                select.recv(&watch.poller.rx);
+            }
+            select.recv(&self.enricher.resolved);
+        }
+        let ready = match self.next_deadline() {
+            Some(deadline) => select.ready_deadline(deadline),
+            None => Ok(select.ready()),
+        };
+        match ready {
+            Ok(op) if op == input_op => match events.try_recv() {
+                Ok(event) => event.map(Some),
+                Err(TryRecvError::Empty) => Ok(None),
+                Err(TryRecvError::Disconnected) => Err(io::Error::other("terminal input closed")),
+            },
+            Ok(_) => Ok(None), // New lines or requests, picked up by the next `update_logs`
+            Err(_) => {
+                self.redraw = true; // A timer is due
+                Ok(None)
+            }
+        }
+    }
+
+    // Forward the line just added at `position` to the tee target, and to
+    // the lines to print, if it passes the current filter
+    pub(crate) fn forward_line(&mut self, position: usize) {
+        let Some(log) = self.log_lines.get(position) else {
+            return;
+        };
+        if !self.criteria(&self.filter_text.to_lowercase()).matches(log) {
+            return;
+        }
+        let printed = self.printing.is_some().then(|| (log.content(), print::line_color(self, log)));
+        if let (Some(printing), Some(line)) = (&mut self.printing, printed) {
+            printing.push(line);
+        }
+        if let Some(tee) = &mut self.tee
+            && let Err(e) = tee.write_line(&log.content())
+        {
+            self.stop_tee(e);
+        }
+    }
+
+    pub(crate) fn start_tee(&mut self, target: &str) -> io::Result<()> {
+        if self.remote && target.starts_with('|') {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "a |command is refused over the control socket"));
+        }
+        self.tee = Some(Tee::open(target)?);
+        Ok(())
+    }
+
+    pub(crate) fn stop_tee(&mut self, error: io::Error) {
+        if let Some(tee) = self.tee.take() {
+            let message = format!("Stopped tee to {}: {}", tee.target, error);
+            diag::record(Topic::Drop, &message);
+            self.set_status(message);
+        }
+    }
+
+    // The current filter settings, with `text_lower` as the filter text
+    pub(crate) fn criteria<'a>(&'a self, text_lower: &'a str) -> filter::Criteria<'a> {
+        filter::Criteria {
+            text_lower,
+            time_range: self.time_range,
+            min_level: self.min_level,
+            statuses: self.status_classes,
+            words: &self.drill_words,
+            sources: &self.sources,
+            geo: self.geo_filter.as_deref().map(|wanted| (&self.enricher, wanted)),
+            columns: self.column_filters(),
+        }
+    }
+
+    // The :lookup filter, which any lookup table may satisfy, and the
+    // :column one, on the derived column it names
+    fn column_filters(&self) -> Vec<ColumnFilter<'_>> {
+        let lookup = self.lookup_filter.as_deref().map(|wanted| ColumnFilter {
+            columns: self.lookups.iter().map(|table| table as &dyn ExtraColumn).collect(),
+            wanted,
+            partial: true,
+        });
+        let column = self.column_filter.as_ref().map(|(column, wanted)| ColumnFilter {
+            columns: vec![&self.columns[*column]],
+            wanted,
+            partial: false,
+        });
+        lookup.into_iter().chain(column).collect()
+    }
+
+    // Whether lines get columns from lookup tables or derived columns
+    pub(crate) fn has_extra_columns(&self) -> bool {
+        !self.lookups.is_empty() || !self.columns.is_empty()
+    }
+
+    // The lookup table columns, then the derived ones, in the order they are shown
+    pub(crate) fn extra_columns(&self) -> impl Iterator<Item = &dyn ExtraColumn> {
+        let lookups = self.lookups.iter().map(|table| table as &dyn ExtraColumn);
+        lookups.chain(self.columns.iter().map(|column| column as &dyn ExtraColumn))
+    }
+
+    // Show only the lines with a looked-up value containing `arg`, as with `:lookup`
+    fn set_lookup_filter(&mut self, arg: &str) -> Result<String, String> {
+        if arg.is_empty() {
+            return Err("Usage: :lookup <text>|off".to_string());
+        }
+        if arg == "off" {
+            self.lookup_filter = None;
+            self.update_filter();
+            return Ok("Lookup filter off".to_string());
+        }
+        if self.lookups.is_empty() {
+            return Err("No lookup tables: add [[lookup]] sections to the config".to_string());
+        }
+        self.lookup_filter = Some(arg.to_lowercase());
+        self.update_filter();
+        let names: Vec<&str> = self.lookups.iter().map(|table| table.name.as_str()).collect();
+        Ok(format!("Showing {} lines whose {} has {}", self.filtered_logs.len(), names.join(" or "), arg))
+    }
+
+    // The derived column called `name`, or the only one when no name is given
+    fn find_column(&self, name: &str) -> Result<usize, String> {
+        if self.columns.is_empty() {
+            return Err("No derived columns: add [[column]] sections to the config".to_string());
+        }
+        if name.is_empty() && self.columns.len() == 1 {
+            return Ok(0);
+        }
+        self.columns.iter().position(|column| column.name == name).ok_or_else(|| {
+            let names: Vec<&str> = self.columns.iter().map(|column| column.name.as_str()).collect();
+            format!("No column {:?}; there are {}", name, names.join(", "))
+        })
+    }
+
+    // Show only the lines whose derived column has a value, as with `:column req=4f2a`
+    fn set_column_filter(&mut self, arg: &str) -> Result<String, String> {
+        if arg == "off" {
+            self.column_filter = None;
+            self.update_filter();
+            return Ok("Column filter off".to_string());
+        }
+        let Some((name, value)) = arg.split_once('=') else {
+            return Err("Usage: :column <name>=<value>|off".to_string());
+        };
+        let column = self.find_column(name.trim())?;
+        self.column_filter = Some((column, value.trim().to_lowercase()));
+        self.update_filter();
+        Ok(format!("Showing {} lines whose {} is {}", self.filtered_logs.len(), self.columns[column].name, value.trim()))
+    }
+
+    // Open the group panel, splitting the lines shown by the value of a derived column
+    fn open_group_panel(&mut self, arg: &str) -> Result<String, String> {
+        let column = self.find_column(arg)?;
+        let lines = self.filtered_logs.iter().map(|&idx| &self.log_lines[idx]);
+        let panel = GroupPanel::new(column, &self.columns[column], lines);
+        let message = format!("{} values of {}", panel.groups.len(), self.columns[column].name);
+        self.group_panel = Some(panel);
+        self.view_mode = ViewMode::GroupView;
+        Ok(message)
+    }
+
+    // Handle a key in the group panel: Enter shows only the selected group's lines
+    pub(crate) fn handle_group_key(&mut self, code: KeyCode) {
+        let Some(panel) = &mut self.group_panel else {
+            return self.sync_view_to_tab();
+        };
+        let selected = panel.selected;
+        let last = panel.groups.len().saturating_sub(1);
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.group_panel = None;
+                self.sync_view_to_tab();
+            }
+            KeyCode::Up => panel.selected = selected.saturating_sub(1),
+            KeyCode::Down => panel.selected = (selected + 1).min(last),
+            KeyCode::Home => panel.selected = 0,
+            KeyCode::End => panel.selected = last,
+            KeyCode::Enter => {
+                let Some(group) = panel.groups.get(selected) else {
+                    return;
+                };
+                let arg = format!("{}={}", self.columns[panel.column].name, group.value);
+                self.group_panel = None;
+                self.sync_view_to_tab();
+                let message = self.set_column_filter(&arg).unwrap_or_else(|e| e);
+                self.set_status(message);
+            }
+            _ => {}
+        }
+    }
+
+    // Show only the lines with an address in a country or autonomous system, as with `:geo`
+    fn set_geo_filter(&mut self, arg: &str) -> Result<String, String> {
+        if arg.is_empty() {
+            return Err("Usage: :geo <country code | AS number | AS organization | off>".to_string());
+        }
+        if arg == "off" {
+            self.geo_filter = None;
+            self.update_filter();
+            return Ok("Showing lines from every place".to_string());
+        }
+        if !self.enricher.has_databases() {
+            return Err("No GeoIP database: set geoip_db or asn_db in the config".to_string());
+        }
+        self.geo_filter = Some(arg.to_lowercase());
+        self.update_filter();
+        Ok(format!("Showing {} lines with an address in {}", self.filtered_logs.len(), arg))
+    }
+
+    /// Rebuild the filtered view from the whole buffer.
+    pub fn update_filter(&mut self) {
+        self.run_filter(false);
+    }
+
+    // Whether anything narrows the view: filter text, time range, level, status or words,
+    // globally or for one of the sources
+    pub(crate) fn filtering(&self) -> bool {
+        !self.filter_text.is_empty()
+            || self.time_range.is_some()
+            || self.min_level.is_some()
+            || self.status_classes.any()
+            || !self.drill_words.is_empty()
+            || self.geo_filter.is_some()
+            || self.lookup_filter.is_some()
+            || self.column_filter.is_some()
+            || self.hidden_lines > 0
+            || self.sources.iter().any(|source| source.filter.active())
+    }
+
+    // Re-run the filter from scratch; a `cancellable` pass gives up early when
+    // more input is queued, leaving the previous results on screen until one completes
+    pub(crate) fn run_filter(&mut self, cancellable: bool) {
+        self.top_tokens = None;
+        let input = self.input.clone().filter(|_| cancellable);
+        let cancelled = || input_pending(&input);
+        if !self.filtering() {
+            // No filter - show all logs
+            self.filtered_logs = (0..self.log_lines.len()).collect();
+        } else {
+            let filter_lower = self.filter_text.to_lowercase();
+            let candidates = self.filter_candidates(&filter_lower);
+            let criteria = self.criteria(&filter_lower);
+            match filter::matching_indices(&self.log_lines, &candidates, &criteria, &cancelled) {
+                Some(indices) => self.filtered_logs = indices,
+                None => {
+                    self.filter_stale = true;
+                    return;
+                }
+            }
+        }
+        self.throttle_view();
+        // Edits at the filter prompt count as one change once it is closed
+        if self.view_mode != ViewMode::FilterView {
+            let state = self.view_state();
+            self.view_history.record(state);
+        }
+        if self.audit.is_some() {
+            let view = self.view_description();
+            if let Some(audit) = &mut self.audit {
+                audit.record_view(view);
+            }
+        }
+        self.applied_filter = self.filter_text.to_lowercase();
+        self.filter_stale = false;
+        self.redraw = true;
+    }
+
+    // Show timestamps in the strftime `layout`, or as logged if it is empty,
+    // whether set by flag, config or the settings panel
+    pub(crate) fn set_timestamp_format(&mut self, layout: &str) -> Result<(), String> {
+        if !format::valid_time_layout(layout) {
+            return Err(format!("Invalid timestamp format: {}", layout));
+        }
+        self.timestamp_format = Some(layout.to_string()).filter(|layout| !layout.is_empty());
+        Ok(())
+    }
+
+    // Limit the display to `per_second` lines a second of each message
+    // template, or show every line again with 0
+    pub(crate) fn set_throttle(&mut self, per_second: usize) {
+        self.throttle = (per_second > 0).then(|| Throttle::new(per_second));
+    }
+
+    // Hold back the lines of a freshly filtered view that are over the throttle
+    fn throttle_view(&mut self) {
+        let Some(throttle) = &mut self.throttle else {
+            return;
+        };
+        throttle.reset();
+        let log_lines = &self.log_lines;
+        self.filtered_logs.retain(|&i| throttle.admit(&log_lines[i]));
+    }
+
+    // Keep the filtered view in step with the buffer after `evicted` lines
+    // left the front and `added` lines joined the back, matching only the new ones
+    pub(crate) fn extend_filter(&mut self, evicted: usize, added: usize) {
+        self.filtered_logs.retain(|&i| i >= evicted);
+        for i in &mut self.filtered_logs {
+            *i -= evicted;
+        }
+        let first_new = self.log_lines.len().saturating_sub(added);
+        let new: Vec<usize> = (first_new..self.log_lines.len()).collect();
+        // Match against the filter the current view was computed with; a
+        // cancelled pass for a newer filter redoes everything anyway
+        let criteria = self.criteria(&self.applied_filter);
+        let mut matched = filter::matching_indices(&self.log_lines, &new, &criteria, &|| false).unwrap_or_default();
+        if let Some(throttle) = &mut self.throttle {
+            throttle.forget_before(self.log_lines.front().map_or(0, |log| log.seq));
+            matched.retain(|&i| throttle.admit(&self.log_lines[i]));
+        }
+        if !self.follow_mode {
+            self.unseen_lines += matched.len();
+        }
+        self.filtered_logs.extend(matched);
+    }
+
+    // Buffer indices worth checking against the filter: lines holding all of
+    // its trigrams when it is long enough to use the index, otherwise every line
+    pub(crate) fn filter_candidates(&self, filter_lower: &str) -> Vec<usize> {
+        let first_seq = self.log_lines.front().map_or(0, |log| log.seq);
+        match self.index.candidates(filter_lower) {
+            Some(seqs) => seqs.into_iter().map(|seq| (seq - first_seq) as usize).collect(),
+            None => (0..self.log_lines.len()).collect(),
+        }
+    }
+
+    // Finish a filter pass that was abandoned for newer input
+    pub(crate) fn resume_filter(&mut self) {
+        if self.filter_stale {
+            self.run_filter(true);
+        }
+    }
+
+    // Recount the stats over the whole buffer; lines arriving and being
+    // evicted afterwards are counted as they come and go
+    pub(crate) fn update_stats(&mut self) {
+        self.stats = LogStats::default();
+        for log in &self.log_lines {
+            self.stats.count(log, true);
+        }
+        self.clamp_heatmap_cursor();
+    }
+
+    // Keep the heatmap cursor on an existing day
+    pub(crate) fn clamp_heatmap_cursor(&mut self) {
+        let days = self.stats.entries_by_day_hour.len();
+        if self.heatmap_cursor.0 >= days {
+            self.heatmap_cursor.0 = days.saturating_sub(1);
+        }
+    }
+
+    // Show `row` of the filtered view at the top, which stops following
+    pub(crate) fn scroll_to(&mut self, row: usize) {
+        self.follow_mode = false;
+        self.scroll = (row + SCROLL_LEAD).min(self.filtered_logs.len());
+    }
+
+    pub(crate) fn scroll_up(&mut self) {
+        self.pause_follow();
+        if self.scroll > 0 {
+            self.scroll -= 1;
+        }
+    }
+
+    pub(crate) fn scroll_down(&mut self) {
+        if self.scroll < self.filtered_logs.len() {
+            self.scroll += 1;
+        }
+    }
+
+    pub(crate) fn page_up(&mut self) {
+        self.pause_follow();
+        if self.scroll > 10 {
+            self.scroll -= 10;
+        } else {
+            self.scroll = 0;
+        }
+    }
+
+    pub(crate) fn page_down(&mut self) {
+        if self.scroll + 10 < self.filtered_logs.len() {
+            self.scroll += 10;
+        } else {
+            self.scroll = self.filtered_logs.len();
+        }
+    }
+
+    pub(crate) fn toggle_follow_mode(&mut self) {
+        if self.follow_mode {
+            self.pause_follow();
+        } else {
+            self.resume_follow();
+        }
+    }
+
+    // Stop following new lines, as any move up the log does, counting those
+    // that arrive from now on for the paused badge
+    pub(crate) fn pause_follow(&mut self) {
+        if self.follow_mode {
+            self.follow_mode = false;
+            self.unseen_lines = 0;
+        }
+    }
+
+    // Jump to the bottom and follow new lines again
+    pub(crate) fn resume_follow(&mut self) {
+        self.follow_mode = true;
+        self.unseen_lines = 0;
+        self.scroll = self.filtered_logs.len();
+    }
+
+    // Jump to the first line of the filtered view
+    pub(crate) fn scroll_to_top(&mut self) {
+        self.pause_follow();
+        self.scroll = 0;
+    }
+
+    pub(crate) fn toggle_timestamps(&mut self) {
+        self.show_timestamps = !self.show_timestamps;
+    }
+
+    // Number shown in front of a line: its position in the filtered view, or its line in the file
+    pub(crate) fn line_number(&self, view_idx: usize, log: &LogLine) -> u64 {
+        match self.line_numbering {
+            LineNumbering::View => view_idx as u64 + 1,
+            LineNumbering::File => log.line,
+        }
+    }
+
+    // Width of the line number column: as configured, or enough for the largest number
+    pub(crate) fn line_number_width(&self) -> usize {
+        self.line_number_width.unwrap_or_else(|| {
+            let largest = match self.line_numbering {
+                LineNumbering::View => self.filtered_logs.len() as u64,
+                LineNumbering::File => self.sources.iter().map(|source| source.lines).max().unwrap_or(0),
+            };
+            largest.to_string().len().max(MIN_LINE_NUMBER_WIDTH)
+        })
+    }
+
+    // A line's timestamp in the configured layout, or as logged; `view_idx`
+    // is its position in the filtered view. With compact dates, the time of
+    // day is shown alone below a line of the same day.
+    pub(crate) fn display_timestamp(&self, view_idx: usize, log: &LogLine) -> String {
+        match (&self.timestamp_format, log.time) {
+            (layout, Some(time)) if self.compact_dates => {
+                let shown = time.format(layout.as_deref().unwrap_or(COMPACT_TIME_FORMAT));
+                if self.day_above(view_idx) == Some(time.date()) {
+                    shown.to_string()
+                } else {
+                    format!("{} {}", time.format("%Y-%m-%d"), shown)
+                }
+            }
+            (Some(layout), Some(time)) => time.format(layout).to_string(),
+            _ => log.timestamp().to_string(),
+        }
+    }
+
+    // The day of the nearest stamped line above `view_idx` in the view
+    pub(crate) fn day_above(&self, view_idx: usize) -> Option<NaiveDate> {
+        self.filtered_logs[..view_idx.min(self.filtered_logs.len())].iter()
+            .rev()
+            .take(DAY_LOOKBACK)
+            .find_map(|&idx| self.log_lines[idx].time)
+            .map(|time| time.date())
+    }
+
+    pub(crate) fn toggle_line_numbers(&mut self) {
+        self.show_line_numbers = !self.show_line_numbers;
+    }
+
+    // Open the filter prompt; the view narrows live while typing
+    pub(crate) fn start_filter(&mut self) {
+        self.view_mode = ViewMode::FilterView;
+        self.filter_editing = true;
+        self.filter_before = self.filter_text.clone();
+    }
+
+    // What narrows the view now, as undo and redo keep it
+    fn view_state(&self) -> ViewState {
+        ViewState {
+            filter_text: self.filter_text.clone(),
+            min_level: self.min_level,
+            time_range: self.time_range,
+            status_classes: self.status_classes,
+            drill_words: self.drill_words.clone(),
+            geo_filter: self.geo_filter.clone(),
+            lookup_filter: self.lookup_filter.clone(),
+            column_filter: self.column_filter.clone(),
+            hidden: self.hide_history.clone(),
+        }
+    }
+
+    // Go back to the view before the last change to the filters, level,
+    // time range or hidden lines
+    pub(crate) fn undo_view(&mut self) {
+        match self.view_history.undo() {
+            Some(state) => {
+                self.restore_view_state(state);
+                self.set_status(format!("Undone, showing {} (Ctrl+R redoes)", self.view_description()));
+            }
+            None => self.set_status("Nothing to undo".to_string()),
+        }
+    }
+
+    // Apply the view change undone last again
+    pub(crate) fn redo_view(&mut self) {
+        match self.view_history.redo() {
+            Some(state) => {
+                self.restore_view_state(state);
+                self.set_status(format!("Redone, showing {}", self.view_description()));
+            }
+            None => self.set_status("Nothing to redo".to_string()),
+        }
+    }
+
+    // Show workspace `index` (from 0), keeping the one shown as it is to
+    // come back to
+    pub(crate) fn switch_workspace(&mut self, index: usize) {
+        if index == self.workspace {
+            let label = self.workspaces[index].label(index);
+            self.set_status(format!("Already on workspace {}", label));
+            return;
+        }
+        let shown = Workspace {
+            name: self.workspaces[self.workspace].name.take(),
+            view: self.view_state(),
+            history: std::mem::take(&mut self.view_history),
+            anchor: self.filtered_logs.get(self.scroll.saturating_sub(SCROLL_LEAD)).map(|&idx| self.log_lines[idx].seq),
+            mark: self.log_lines.iter().find(|log| log.highlighted).map(|log| log.seq),
+            notes: std::mem::take(&mut self.notes),
+            follow_mode: self.follow_mode,
+        };
+        self.workspaces[self.workspace] = shown;
+        let target = std::mem::take(&mut self.workspaces[index]);
+        self.workspaces[index].name = target.name;
+        self.workspace = index;
+        self.view_history = target.history;
+        self.notes = target.notes;
+        self.notes_panel.selected = self.notes_panel.selected.min(self.notes.len().saturating_sub(1));
+        self.restore_view_state(target.view);
+        if target.follow_mode {
+            self.resume_follow();
+        } else {
+            self.unseen_lines = 0;
+            self.scroll_to(0);
+            // The view may have changed under it, so its top line is looked up again
+            if let Some(seq) = target.anchor {
+                self.jump_to_seq(seq);
+            }
+        }
+        for log in &mut self.log_lines {
+            log.highlighted = Some(log.seq) == target.mark;
+        }
+        let label = self.workspaces[index].label(index);
+        self.set_status(format!("Workspace {}, showing {}", label, self.view_description()));
+    }
+
+    // Name the workspace shown (:workspace)
+    fn name_workspace(&mut self, name: &str) -> Result<String, String> {
+        if name.is_empty() {
+            return Err("Usage: :workspace <name>".to_string());
+        }
+        self.workspaces[self.workspace].name = Some(name.to_string());
+        Ok(format!("Named workspace {}", self.workspaces[self.workspace].label(self.workspace)))
+    }
+
+    fn restore_view_state(&mut self, state: ViewState) {
+        self.filter_text = state.filter_text;
+        self.min_level = state.min_level;
+        self.time_range = state.time_range;
+        self.status_classes = state.status_classes;
+        self.drill_words = state.drill_words;
+        self.geo_filter = state.geo_filter;
+        self.lookup_filter = state.lookup_filter;
+        self.column_filter = state.column_filter.filter(|(column, _)| *column < self.columns.len());
+        // Hidden lines are those of the batches kept with the state
+        for seq in self.hide_history.iter().flatten() {
+            if let Ok(idx) = self.log_lines.binary_search_by_key(seq, |log| log.seq) {
+                self.log_lines[idx].hidden = false;
+            }
+        }
+        self.hidden_lines = 0;
+        for seq in state.hidden.iter().flatten() {
+            if let Ok(idx) = self.log_lines.binary_search_by_key(seq, |log| log.seq) {
+                self.log_lines[idx].hidden = true;
+                self.hidden_lines += 1;
+            }
+        }
+        self.hide_history = state.hidden;
+        self.block_start = None;
+        self.update_filter();
+    }
+
+    // Close the filter prompt, putting back the filter it opened with
+    pub(crate) fn cancel_filter(&mut self) {
+        self.view_mode = ViewMode::LogView;
+        self.filter_editing = false;
+        if self.filter_text != self.filter_before {
+            self.filter_text = std::mem::take(&mut self.filter_before);
+            self.update_filter();
+        }
+    }
+
+    // Complete the filter from the field names and values of the newest
+    // lines; when the choices can't be narrowed, Tab again steps through them
+    pub(crate) fn complete_filter(&mut self) {
+        if let Some(completion) = &mut self.completion {
+            self.filter_text = completion.choices[completion.next].clone();
+            completion.next = (completion.next + 1) % completion.choices.len();
+            self.run_filter(true);
+            return;
+        }
+        let newest = self.log_lines.iter().rev().take(complete::SCAN_LINES).map(LogLine::content);
+        let choices = Fields::collect(newest).choices(&self.filter_text);
+        let prefix = complete::common_prefix(&choices);
+        if prefix.len() > self.filter_text.len() {
+            self.filter_text = prefix.to_string();
+            self.run_filter(true);
+        } else if choices.len() > 1 {
+            self.completion = Some(Completion { choices, next: 0 });
+        }
+    }
+
+    pub(crate) fn add_filter_char(&mut self, c: char) {
+        self.filter_text.push(c);
+        self.refine_filter();
+    }
+
+    // Re-evaluate only the currently matching lines when the filter just got
+    // longer, since a line can only match the new filter if it matched the old one
+    pub(crate) fn refine_filter(&mut self) {
+        let filter_lower = self.filter_text.to_lowercase();
+        // Lines the throttle held back may be let through once fewer lines match
+        if !filter_lower.starts_with(&self.applied_filter) || self.throttle.is_some() {
+            self.run_filter(true);
+            return;
+        }
+        let input = self.input.clone();
+        let criteria = self.criteria(&filter_lower);
+        let refined = filter::matching_indices(&self.log_lines, &self.filtered_logs, &criteria, &|| input_pending(&input));
+        match refined {
+            Some(indices) => {
+                self.filtered_logs = indices;
+                self.applied_filter = filter_lower;
+            }
+            None => self.filter_stale = true,
+        }
+    }
+
+    pub(crate) fn remove_filter_char(&mut self) {
+        self.filter_text.pop();
+        self.run_filter(true);
+    }
+
+    pub(crate) fn clear_filter(&mut self) {
+        self.filter_text.clear();
+        self.time_range = None;
+        self.status_classes = StatusClasses::default();
+        self.drill_words.clear();
+        self.update_filter();
+    }
+
+    // Show only access log lines with a 4xx (`client`) or 5xx status, or
+    // stop narrowing to that class
+    pub(crate) fn toggle_status_class(&mut self, client: bool) {
+        let classes = &mut self.status_classes;
+        let on = if client { &mut classes.client_errors } else { &mut classes.server_errors };
+        *on = !*on;
+        self.update_filter();
+        let message = if self.status_classes.any() {
+            format!("Showing {} responses only", self.status_classes.label())
+        } else {
+            "Showing all responses".to_string()
+        };
+        self.set_status(message);
+    }
+
+    pub(crate) fn toggle_filter_mode(&mut self) {
+        self.filter_editing = !self.filter_editing;
+        if !self.filter_editing {
+            // Apply filter when exiting filter mode
+            self.update_filter();
+        }
+    }
+
+    pub(crate) fn next_tab(&mut self) {
+        self.selected_tab = (self.selected_tab + 1) % TAB_TITLES.len();
+        self.sync_view_to_tab();
+    }
+
+    pub(crate) fn prev_tab(&mut self) {
+        if self.selected_tab > 0 {
+            self.selected_tab -= 1;
+        } else {
+            self.selected_tab = TAB_TITLES.len() - 1;
+        }
+        self.sync_view_to_tab();
+    }
+
+    pub(crate) fn select_tab(&mut self, tab: usize) {
+        self.selected_tab = tab;
+        self.sync_view_to_tab();
+    }
+
+    pub(crate) fn sync_view_to_tab(&mut self) {
+        match self.selected_tab {
+            0 => self.view_mode = ViewMode::LogView,
+            1 => self.view_mode = ViewMode::StatsView,
+            2 => self.view_mode = ViewMode::HeatmapView,
+            3 => self.view_mode = ViewMode::AlertsView,
+            4 => self.view_mode = ViewMode::HelpView,
+            _ => {}
+        }
+    }
+
+    // Handle keys specific to the stats view, returns true if the key was consumed
+    pub(crate) fn handle_stats_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('[') => self.stats_bucket = self.stats_bucket.step(-1),
+            KeyCode::Char(']') => self.stats_bucket = self.stats_bucket.step(1),
+            KeyCode::Up => self.token_selected = self.token_selected.saturating_sub(1),
+            KeyCode::Down => self.token_selected = (self.token_selected + 1).min(tokens::TOP_TOKENS - 1),
+            KeyCode::Enter => self.drill_into_token(),
+            _ => return false,
+        }
+        true
+    }
+
+    // Count the most frequent words of the newest filtered lines for the
+    // token panel, unless they were counted lately; words already picked
+    // are in every line and so left out
+    pub(crate) fn refresh_top_tokens(&mut self) {
+        if self.top_tokens.as_ref().is_some_and(|(counted, _)| counted.elapsed() < tokens::REFRESH) {
+            return;
+        }
+        let newest = self.filtered_logs.iter().rev().take(tokens::SCAN_LINES).map(|&i| &self.log_lines[i]);
+        let mut top = tokens::top_tokens(newest, tokens::TOP_TOKENS + self.drill_words.len());
+        top.retain(|(word, _)| !self.drill_words.contains(word));
+        top.truncate(tokens::TOP_TOKENS);
+        self.top_tokens = Some((Instant::now(), top));
+    }
+
+    // The token panel's words, as last counted
+    pub(crate) fn top_tokens(&self) -> &[(String, usize)] {
+        self.top_tokens.as_ref().map_or(&[], |(_, top)| top)
+    }
+
+    // Narrow the view to lines that also have the selected word of the token panel
+    fn drill_into_token(&mut self) {
+        self.refresh_top_tokens();
+        let top = self.top_tokens();
+        let Some((word, _)) = top.get(self.token_selected.min(top.len().saturating_sub(1))) else {
+            return;
+        };
+        let word = word.clone();
+        self.drill_words.push(word);
+        self.token_selected = 0;
+        self.update_filter();
+        self.set_status(format!("Showing lines with: {} (Ctrl+C to clear)", self.drill_words.join(" + ")));
+    }
+
+    // Handle keys specific to the heatmap view, returns true if the key was consumed
+    pub(crate) fn handle_heatmap_key(&mut self, code: KeyCode) -> bool {
+        let days = self.stats.entries_by_day_hour.len();
+        let (day, hour) = self.heatmap_cursor;
+        match code {
+            KeyCode::Up => self.heatmap_cursor.0 = day.saturating_sub(1),
+            KeyCode::Down => self.heatmap_cursor.0 = std::cmp::min(day + 1, days.saturating_sub(1)),
+            KeyCode::Left => self.heatmap_cursor.1 = hour.saturating_sub(1),
+            KeyCode::Right => self.heatmap_cursor.1 = std::cmp::min(hour + 1, 23),
+            KeyCode::Char('e') => {
+                self.heatmap_metric = match self.heatmap_metric {
+                    HeatmapMetric::Volume => HeatmapMetric::Errors,
+                    HeatmapMetric::Errors => HeatmapMetric::Volume,
+                };
+            }
+            KeyCode::Enter => self.jump_to_heatmap_cell(),
+            _ => return false,
+        }
+        true
+    }
+
+    // Handle a mouse click on the heatmap grid, selecting and jumping to the clicked cell
+    pub(crate) fn click_heatmap(&mut self, column: u16, row: u16) {
+        let area = self.heatmap_area;
+        let grid_x = area.x + HEATMAP_LABEL_WIDTH;
+        if column < grid_x || row < area.y || row >= area.y + area.height {
+            return;
+        }
+        let hour = ((column - grid_x) / HEATMAP_CELL_WIDTH) as usize;
+        let day = heatmap_first_row(self.heatmap_cursor.0, area.height) + (row - area.y) as usize;
+        if hour < 24 && day < self.stats.entries_by_day_hour.len() {
+            self.heatmap_cursor = (day, hour);
+            self.jump_to_heatmap_cell();
+        }
+    }
+
+    // The filtered view's time span as the scrubber shows it
+    pub(crate) fn timeline(&self) -> Option<Timeline> {
+        Timeline::build(&self.log_lines, &self.filtered_logs, self.scrubber_area.width as usize)
+    }
+
+    // Jump the log view to the moment under a click or drag on the scrubber
+    pub(crate) fn click_scrubber(&mut self, column: u16, row: u16) {
+        let area = self.scrubber_area;
+        if column < area.x || column >= area.x + area.width || row < area.y || row >= area.y + area.height {
+            return;
+        }
+        self.scrub_to((column - area.x) as usize);
+    }
+
+    // When the line at the top of the log view was logged, where the scrubber's cursor is
+    pub(crate) fn scrubber_time(&self) -> Option<NaiveDateTime> {
+        let &idx = self.filtered_logs.get(self.scroll.saturating_sub(SCROLL_LEAD))?;
+        self.log_lines[idx].time
+    }
+
+    // Move the scrubber cursor to the next column back (`delta` -1) or
+    // forward (1) that has lines, with [ and ]
+    pub(crate) fn step_scrubber(&mut self, delta: isize) {
+        let Some(timeline) = self.timeline() else {
+            return;
+        };
+        let last = timeline.columns.len() - 1;
+        let mut column = self.scrubber_time().map_or(last, |time| timeline.column(time));
+        loop {
+            column = match column.checked_add_signed(delta) {
+                Some(next) if next <= last => next,
+                _ => return,
+            };
+            if timeline.columns[column].0 > 0 {
+                break;
+            }
+        }
+        self.scrub_to(column);
+    }
+
+    // Show the first line logged at or after the start of the scrubber's `column`
+    fn scrub_to(&mut self, column: usize) {
+        let Some(timeline) = self.timeline() else {
+            return;
+        };
+        let moment = timeline.moment(column);
+        let row = self.filtered_logs.iter()
+            .position(|&idx| self.log_lines[idx].time.is_some_and(|time| time >= moment))
+            .unwrap_or(self.filtered_logs.len().saturating_sub(1));
+        self.pause_follow();
+        self.scroll_to(row);
+        self.set_status(format!("Jumped to {}", moment.format("%Y-%m-%d %H:%M:%S")));
+    }
+
+    // Restrict the log view to the hour under the heatmap cursor and switch to it
+    pub(crate) fn jump_to_heatmap_cell(&mut self) {
+        let (day, hour) = self.heatmap_cursor;
+        let Some(date) = self.stats.entries_by_day_hour.keys().nth(day) else {
+            return;
+        };
+        let Some(start) = date.and_hms_opt(hour as u32, 0, 0) else {
+            return;
+        };
+        self.time_range = Some((start, start + ChronoDuration::hours(1)));
+        self.update_filter();
+        self.scroll_to_top();
+        self.select_tab(0);
+    }
+}
+
+// Whether more terminal input is already queued, used to cancel filter passes mid-way
+fn input_pending(input: &Option<Receiver<io::Result<Event>>>) -> bool {
+    input.as_ref().is_some_and(|rx| !rx.is_empty())
+}
@@ -0,0 +1,161 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+
+use chrono::{DateTime, Local};
+use sha2::{Digest, Sha256};
+
+use crate::clean::clean_line;
+use crate::App;
+
+/// Something done in an audit session, with when it was done.
+pub struct AuditEntry {
+    pub at: DateTime<Local>,
+    pub action: String,
+}
+
+/// The trail kept in audit mode (`--audit`): every command run and every
+/// change to what the view shows, so an export can be traced back to how
+/// it was arrived at.
+#[derive(Default)]
+pub struct AuditLog {
+    pub entries: Vec<AuditEntry>,
+    pub view: String, // The filters last recorded, as `App::view_description` puts them
+}
+
+impl AuditLog {
+    pub fn record(&mut self, action: impl Into<String>) {
+        self.entries.push(AuditEntry { at: Local::now(), action: action.into() });
+    }
+
+    /// Record the filters now in effect, unless they are the ones last recorded.
+    pub fn record_view(&mut self, view: String) {
+        if view != self.view {
+            self.record(format!("View: {}", view));
+            self.view = view;
+        }
+    }
+}
+
+/// Hex SHA-256 digest of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// Where a line of a file starts and its bytes, line ending left off
+type LineBytes = (u64, Vec<u8>);
+
+// The stretch of a file from the start of its first wanted line to the end of
+// its last one: start offset, length and SHA-256
+type Span = (u64, u64, String);
+
+// Read the lines numbered `wanted` (1-based, ascending) from `path`, hashing
+// the stretch they span on the way
+fn read_lines(path: &str, wanted: &[u64]) -> io::Result<(HashMap<u64, LineBytes>, Option<Span>)> {
+    let mut found = HashMap::new();
+    let (Some(&first), Some(&last)) = (wanted.first(), wanted.last()) else {
+        return Ok((found, None));
+    };
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut span = Sha256::new();
+    let mut span_start = None;
+    let mut raw = Vec::new();
+    let (mut number, mut offset) = (0, 0);
+    let mut next = wanted.iter().peekable();
+    while number < last {
+        raw.clear();
+        let read = reader.read_until(b'\n', &mut raw)? as u64;
+        if read == 0 {
+            break;
+        }
+        number += 1;
+        let end = raw.iter().rposition(|&b| b != b'\n' && b != b'\r').map_or(0, |i| i + 1);
+        if number == first {
+            span_start = Some(offset);
+        }
+        if span_start.is_some() {
+            span.update(if number == last { &raw[..end] } else { &raw[..] });
+        }
+        if next.peek() == Some(&&number) {
+            next.next();
+            found.insert(number, (offset, raw[..end].to_vec()));
+        }
+        offset += read;
+    }
+    let span = span_start.map(|start| {
+        let end = found.get(&last).map_or(offset, |(line_start, bytes)| line_start + bytes.len() as u64);
+        (start, end - start, format!("{:x}", span.finalize()))
+    });
+    Ok((found, span))
+}
+
+/// Write the audit manifest of the export just written to `export_path`:
+/// the export's SHA-256, then for every exported line its source, line
+/// number, byte range in the source file and the SHA-256 of those bytes,
+/// whether they still read as the line shown (redactions applied), the stretch of each file the
+/// lines span with its hash, and the session's audit trail. Lines from
+/// commands, sockets and URLs have no byte range, so their text is hashed
+/// as received. Returns the manifest's path and the export's hash.
+pub fn write_manifest(app: &App, export_path: &str) -> io::Result<(String, String)> {
+    let export_hash = sha256_hex(&fs::read(export_path)?);
+    let mut by_source: BTreeMap<u16, Vec<u64>> = BTreeMap::new();
+    for &idx in &app.filtered_logs {
+        let log = &app.log_lines[idx];
+        by_source.entry(log.source).or_default().push(log.line);
+    }
+    let mut files = HashMap::new();
+    let mut sources = Vec::new();
+    for (id, mut wanted) in by_source {
+        let Some(source) = app.sources.iter().find(|source| source.id == id) else {
+            continue;
+        };
+        if !source.is_file() {
+            continue;
+        }
+        wanted.sort_unstable();
+        wanted.dedup();
+        let (lines, span) = read_lines(&source.target, &wanted)?;
+        if let Some((offset, length, sha256)) = span {
+            sources.push(serde_json::json!({
+                "source": source.target,
+                "offset": offset,
+                "length": length,
+                "sha256": sha256,
+            }));
+        }
+        files.insert(id, lines);
+    }
+    let lines: Vec<serde_json::Value> = app.filtered_logs.iter().map(|&idx| {
+        let log = &app.log_lines[idx];
+        let target = app.source_of(log).map_or(app.log_path.as_str(), |source| source.target.as_str());
+        let mut record = serde_json::json!({ "seq": log.seq, "source": target, "line": log.line });
+        match files.get(&log.source).map(|lines| lines.get(&log.line)) {
+            Some(Some((offset, bytes))) => {
+                record["offset"] = (*offset).into();
+                record["length"] = bytes.len().into();
+                record["sha256"] = sha256_hex(bytes).into();
+                let read = clean_line(&String::from_utf8_lossy(bytes)).into_owned();
+                record["matches"] = (app.redactor.redact(&read) == log.content()).into();
+            }
+            // The file no longer reaches that line: it was truncated or rotated
+            Some(None) => record["matches"] = false.into(),
+            None => record["sha256"] = sha256_hex(log.content().as_bytes()).into(),
+        }
+        record
+    }).collect();
+    let trail = app.audit.as_ref().map_or(&[][..], |audit| &audit.entries[..]);
+    let manifest = serde_json::json!({
+        "export": { "path": export_path, "sha256": export_hash, "lines": app.filtered_logs.len() },
+        "written": Local::now().to_rfc3339(),
+        "view": app.audit.as_ref().map(|audit| audit.view.as_str()),
+        "sources": sources,
+        "lines": lines,
+        "trail": trail.iter().map(|entry| serde_json::json!({
+            "at": entry.at.to_rfc3339(),
+            "action": entry.action,
+        })).collect::<Vec<_>>(),
+    });
+    let path = format!("{}.audit.json", export_path);
+    fs::write(&path, serde_json::to_string_pretty(&manifest)? + "\n")?;
+    Ok((path, export_hash))
+}
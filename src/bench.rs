@@ -0,0 +1,154 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, TimeDelta};
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::format::{self, LogFormat};
+use crate::parse::parse_level;
+use crate::source::Source;
+use crate::{config, App, LogLine};
+
+// Lines handed to the app at a time, as the ingest thread batches them
+const BATCH_LINES: usize = 4096;
+
+// Filters timed when none are given: a word found in about one line in ten,
+// a phrase long enough for the trigram index, and a level
+const DEFAULT_FILTERS: [&str; 3] = ["timeout", "payment gateway error", "level:error"];
+
+const MESSAGES: [&str; 8] = [
+    "Processing user request",
+    "Database query completed in 150ms",
+    "Cache miss detected for key 'user_profile'",
+    "Connection attempt failed: timeout",
+    "Payment gateway error: connection reset by peer",
+    "Request received from 192.168.1.1",
+    "Background task started: report generation",
+    "Successfully processed batch job #89754",
+];
+
+/// Time parsing, ingestion, filtering and stats on a file or synthetic lines.
+#[derive(Parser)]
+#[command(name = "tlm-bench", version, about)]
+pub struct BenchOptions {
+    /// Log file to read [default: synthetic lines]
+    file: Option<String>,
+
+    /// Synthetic lines to generate when no file is given
+    #[arg(short = 'n', long, value_name = "N", default_value = "200000")]
+    lines: usize,
+
+    /// Format to parse with: auto, plain, json, syslog, access or a profile from the config file
+    #[arg(long, value_name = "PROFILE", default_value = "auto")]
+    format: String,
+
+    /// Filter to time, repeatable; `level:<name>` times a minimum level instead
+    /// [default: a word, a longer phrase and level:error]
+    #[arg(short, long = "filter", value_name = "TEXT")]
+    filters: Vec<String>,
+}
+
+// One measured stage of the pipeline
+struct Stage {
+    name: String,
+    lines: usize,
+    elapsed: Duration,
+    note: String,
+}
+
+/// Push a file (or made-up lines) through parsing, ingestion, filtering and
+/// stats headlessly, printing the time and lines per second of each stage,
+/// so a change to the pipeline can be measured rather than guessed at.
+pub fn run(options: &BenchOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let (lines, origin) = match &options.file {
+        Some(path) => (read_lines(path)?, path.clone()),
+        None => (synthetic_lines(options.lines), "synthetic".to_string()),
+    };
+    let mut stages = vec![Stage { name: "read".to_string(), lines: lines.len(), elapsed: started.elapsed(), note: String::new() }];
+    let bytes: usize = lines.iter().map(|line| line.len() + 1).sum();
+
+    let config = config::load(None, options.file.as_deref(), None)?;
+    let format = match LogFormat::resolve(&options.format, &config.formats)? {
+        LogFormat::Auto => LogFormat::detect(lines.iter().take(format::DETECT_SAMPLE_LINES).map(String::as_str)).unwrap_or(LogFormat::Auto),
+        format => format,
+    };
+    let started = Instant::now();
+    let parsed: Vec<LogLine> = lines.iter().map(|line| LogLine::parse(line, &format)).collect();
+    stages.push(Stage { name: "parse".to_string(), lines: lines.len(), elapsed: started.elapsed(), note: String::new() });
+    drop(parsed);
+
+    // Ingestion on its own: each batch is parsed before the clock starts
+    let mut app = App::new(origin.clone());
+    app.max_lines = lines.len().max(1);
+    let id = app.next_source_id();
+    app.sources.push(Source::detached(id, &origin, format.clone()));
+    let mut elapsed = Duration::ZERO;
+    for batch in lines.chunks(BATCH_LINES) {
+        let batch: Vec<LogLine> = batch.iter().map(|line| LogLine::parse(line, &format)).collect();
+        let started = Instant::now();
+        app.ingest_batch(0, batch);
+        app.finish_ingest();
+        elapsed += started.elapsed();
+    }
+    stages.push(Stage { name: "ingest".to_string(), lines: lines.len(), elapsed, note: String::new() });
+
+    let filters: Vec<String> = match options.filters.is_empty() {
+        true => DEFAULT_FILTERS.iter().map(|filter| filter.to_string()).collect(),
+        false => options.filters.clone(),
+    };
+    for filter in &filters {
+        app.filter_text.clear();
+        app.min_level = None;
+        match filter.strip_prefix("level:") {
+            Some(level) => app.min_level = Some(parse_level(level)?),
+            None => app.filter_text = filter.clone(),
+        }
+        let started = Instant::now();
+        app.update_filter();
+        let note = format!("{} matches", app.filtered_logs.len());
+        stages.push(Stage { name: format!("filter {:?}", filter), lines: app.log_lines.len(), elapsed: started.elapsed(), note });
+    }
+
+    let started = Instant::now();
+    app.update_stats();
+    stages.push(Stage { name: "stats".to_string(), lines: app.log_lines.len(), elapsed: started.elapsed(), note: String::new() });
+
+    println!("{}: {} lines, {:.1} MB, format {}", origin, lines.len(), bytes as f64 / 1e6, format.name());
+    let width = stages.iter().map(|stage| stage.name.len()).max().unwrap_or(0);
+    for stage in &stages {
+        let rate = stage.lines as f64 / stage.elapsed.as_secs_f64().max(1e-9);
+        println!("  {:<width$}  {:>9.3} s  {:>12.0} lines/s  {}", stage.name, stage.elapsed.as_secs_f64(), rate, stage.note, width = width);
+    }
+    Ok(())
+}
+
+fn read_lines(path: &str) -> io::Result<Vec<String>> {
+    BufReader::new(File::open(path)?)
+        .split(b'\n')
+        .map(|line| line.map(|line| String::from_utf8_lossy(&line).trim_end_matches('\r').to_string()))
+        .collect()
+}
+
+// Lines in the layout of `log_generator`, a few a second over several days
+// so the stats have hours and days to fill, the same on every run
+fn synthetic_lines(count: usize) -> Vec<String> {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut time = NaiveDate::from_ymd_opt(2024, 5, 1).and_then(|date| date.and_hms_opt(0, 0, 0)).unwrap_or_default();
+    (0..count)
+        .map(|sequence| {
+            time += TimeDelta::milliseconds(rng.gen_range(0..400));
+            let level = match rng.gen_range(0..100) {
+                0..=69 => "INFO",
+                70..=89 => "DEBUG",
+                90..=96 => "WARNING",
+                _ => "ERROR",
+            };
+            let message = MESSAGES[rng.gen_range(0..MESSAGES.len())];
+            format!("[{} - {} - #{}] {}", time.format("%Y-%m-%d %H:%M:%S%.3f"), level, sequence, message)
+        })
+        .collect()
+}
@@ -1,64 +1,626 @@
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use rand::Rng;
-
-fn main() {
-    // Add rand to your Cargo.toml: rand = "0.8.5"
-    let log_path = "test_application.log";
-    let log_levels = ["INFO", "DEBUG", "WARNING", "ERROR"];
-    let messages = [
-        "Processing user request",
-        "Database query completed in 150ms",
-        "Cache miss detected for key 'user_profile'",
-        "Connection attempt failed: timeout",
-        "Authentication successful for user 'admin'",
-        "Data validation error: missing required field",
-        "Background task started: report generation",
-        "Memory usage optimized: freed 250MB",
-        "Request received from 192.168.1.1",
-        "File not found: config.json",
-        "API rate limit reached for client ID #1234",
-        "Successfully processed batch job #89754"
-    ];
-    
-    println!("Generating log entries to: {}", log_path);
-    println!("Press Ctrl+C to stop");
-    
-    let mut rng = rand::thread_rng();
-    let mut sequence = 1000;
-    
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Stdout, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset, Local};
+use clap::{Parser, ValueEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Where the timestamps of a seeded run start unless `--start` says otherwise
+const SEEDED_START: &str = "2024-05-01T10:00:00+00:00";
+
+const LEVELS: [&str; 4] = ["INFO", "DEBUG", "WARNING", "ERROR"];
+
+const MESSAGES: [&str; 12] = [
+    "Processing user request",
+    "Database query completed in 150ms",
+    "Cache miss detected for key 'user_profile'",
+    "Connection attempt failed: timeout",
+    "Authentication successful for user 'admin'",
+    "Data validation error: missing required field",
+    "Background task started: report generation",
+    "Memory usage optimized: freed 250MB",
+    "Request received from 192.168.1.1",
+    "File not found: config.json",
+    "API rate limit reached for client ID #1234",
+    "Successfully processed batch job #89754",
+];
+
+// What goes wrong during an error burst
+const ERROR_MESSAGES: [&str; 4] = [
+    "Connection attempt failed: timeout",
+    "Upstream returned 503 Service Unavailable",
+    "Payment gateway error: connection reset by peer",
+    "Transaction rolled back: deadlock detected",
+];
+
+// Exceptions and frames that stack traces are made of
+const EXCEPTIONS: [&str; 3] = [
+    "java.net.SocketTimeoutException: Read timed out",
+    "java.lang.IllegalStateException: pool exhausted",
+    "java.lang.NullPointerException: order.customer is null",
+];
+const FRAMES: [&str; 6] = [
+    "com.example.db.ConnectionPool.acquire(ConnectionPool.java:214)",
+    "com.example.orders.OrderRepository.find(OrderRepository.java:87)",
+    "com.example.orders.OrderService.handle(OrderService.java:42)",
+    "com.example.payments.GatewayClient.charge(GatewayClient.java:156)",
+    "com.example.http.Router.dispatch(Router.java:133)",
+    "com.example.http.Server.run(Server.java:61)",
+];
+
+// Requests and response sizes for the access log format
+const REQUESTS: [&str; 6] = [
+    "GET /api/users HTTP/1.1",
+    "GET /api/orders?page=2 HTTP/1.1",
+    "POST /api/login HTTP/1.1",
+    "PUT /api/cart/42 HTTP/1.1",
+    "GET /static/app.js HTTP/1.1",
+    "DELETE /api/sessions/current HTTP/1.1",
+];
+
+/// Write made-up application logs, for trying out and benchmarking the monitor.
+#[derive(Parser)]
+#[command(version, about)]
+struct Options {
+    /// Where to write, repeatable for several services at once: a file to
+    /// append to, `-` for standard output, `udp://host:port` to send syslog
+    /// datagrams or `tcp://addr` to serve the lines to whoever connects there.
+    /// `NAME=` in front names the service [default: the file name, else app]
+    #[arg(short, long, value_name = "[NAME=]TARGET", default_value = "test_application.log")]
+    output: Vec<String>,
+
+    /// Lines per second [default: one every 0.5 to 3 seconds]
+    #[arg(short, long, value_name = "N", value_parser = parse_rate)]
+    rate: Option<f64>,
+
+    /// Stop after this many seconds [default: run until interrupted]
+    #[arg(short, long, value_name = "SECS")]
+    duration: Option<f64>,
+
+    /// Layout of each line
+    #[arg(short, long, value_enum, default_value = "plain")]
+    format: Format,
+
+    /// Seed for the random levels and messages, so runs can be repeated;
+    /// timestamps then start at a fixed time too
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+
+    /// Timestamp of the first line, as 2024-05-01T10:00:00+00:00; later
+    /// ones follow at the paces set rather than by the clock [default: now,
+    /// or 2024-05-01T10:00:00+00:00 with --seed]
+    #[arg(long, value_name = "TIME", value_parser = parse_start)]
+    start: Option<DateTime<FixedOffset>>,
+
+    /// Every SECS seconds, write a burst of LINES error lines at once
+    #[arg(long, value_name = "SECS:LINES", value_parser = parse_schedule)]
+    burst: Option<Schedule>,
+
+    /// Every SECS seconds, report slow requests (2 to 9 s) for LENGTH seconds
+    #[arg(long, value_name = "SECS:LENGTH", value_parser = parse_schedule)]
+    spike: Option<Schedule>,
+
+    /// Every SECS seconds, write nothing at all for LENGTH seconds
+    #[arg(long, value_name = "SECS:LENGTH", value_parser = parse_schedule)]
+    quiet: Option<Schedule>,
+
+    /// Share of error lines followed by a multi-line stack trace, from 0 to 1
+    #[arg(long, value_name = "SHARE", default_value = "0", value_parser = parse_share)]
+    traces: f64,
+
+    /// Rotate the output file every N lines, or every SECS seconds written as `30s`
+    #[arg(long, value_name = "N|SECSs", value_parser = parse_every)]
+    rotate: Option<Every>,
+
+    /// How the file is rotated
+    #[arg(long, value_enum, default_value = "rename", requires = "rotate")]
+    rotate_mode: RotateMode,
+
+    /// Rotated copies kept (`app.log.1` is the newest)
+    #[arg(long, value_name = "N", default_value = "3", requires = "rotate")]
+    keep: u32,
+
+    /// Truncate the output file in place every N lines or `SECSs` seconds
+    #[arg(long, value_name = "N|SECSs", value_parser = parse_every)]
+    truncate: Option<Every>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum RotateMode {
+    /// Rename the file to `.1` and start a new one, as logrotate does by default
+    Rename,
+    /// Copy the file to `.1` and truncate it in place, as logrotate's copytruncate
+    Copytruncate,
+}
+
+// How often the output file is rotated or truncated
+#[derive(Clone, Copy)]
+enum Every {
+    Lines(u64),
+    Time(Duration),
+}
+
+// An `Every` and when it last came due, by time and by line number
+struct Trigger {
+    every: Every,
+    last: (Instant, u64),
+}
+
+impl Trigger {
+    fn new(every: Every, sequence: u64) -> Self {
+        Trigger { every, last: (Instant::now(), sequence) }
+    }
+
+    // Whether it is due with the next line numbered `sequence`, starting over if so
+    fn due(&mut self, sequence: u64) -> bool {
+        let due = match self.every {
+            Every::Lines(lines) => sequence - self.last.1 >= lines,
+            Every::Time(interval) => self.last.0.elapsed() >= interval,
+        };
+        if due {
+            self.last = (Instant::now(), sequence);
+        }
+        due
+    }
+}
+
+// Where one service's lines go
+enum Output {
+    Stdout(Stdout),
+    File { path: String, out: BufWriter<File> }, // Can be rotated and truncated
+    Udp { socket: UdpSocket, syslog: bool },      // One datagram per line, with a priority when `syslog`
+    Tcp(Arc<Mutex<Vec<TcpStream>>>),             // Everyone connected so far; lines before that are lost
+}
+
+impl Output {
+    fn open(target: &str, format: Format) -> io::Result<Self> {
+        if target == "-" {
+            return Ok(Output::Stdout(io::stdout()));
+        }
+        if let Some(addr) = target.strip_prefix("udp://") {
+            let socket = UdpSocket::bind("0.0.0.0:0")?;
+            socket.connect(addr)?;
+            return Ok(Output::Udp { socket, syslog: matches!(format, Format::Syslog) });
+        }
+        if let Some(addr) = target.strip_prefix("tcp://") {
+            let listener = TcpListener::bind(addr)?;
+            let clients = Arc::new(Mutex::new(Vec::new()));
+            let accepted = clients.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    accepted.lock().unwrap_or_else(|e| e.into_inner()).push(stream);
+                }
+            });
+            return Ok(Output::Tcp(clients));
+        }
+        let file = OpenOptions::new().create(true).append(true).open(target)?;
+        Ok(Output::File { path: target.to_string(), out: BufWriter::new(file) })
+    }
+
+    fn write_line(&mut self, line: &str, level: &str) -> io::Result<()> {
+        match self {
+            Output::Stdout(out) => writeln!(out.lock(), "{}", line),
+            Output::File { out, .. } => writeln!(out, "{}", line),
+            Output::Udp { socket, syslog } => {
+                let datagram = if *syslog { format!("<{}>{}", syslog_priority(level), line) } else { line.to_string() };
+                // Nobody listening is no reason to stop
+                match socket.send(datagram.as_bytes()) {
+                    Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => Ok(()),
+                    result => result.map(|_| ()),
+                }
+            }
+            Output::Tcp(clients) => {
+                let line = format!("{}\n", line);
+                let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+                clients.retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+                Ok(())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(out) => out.flush(),
+            Output::File { out, .. } => out.flush(),
+            Output::Udp { .. } | Output::Tcp(_) => Ok(()),
+        }
+    }
+
+    // Move the file to `.1`, shifting older copies up and dropping the
+    // oldest, and carry on in an empty file under the same name
+    fn rotate(&mut self, mode: RotateMode, keep: u32) -> io::Result<()> {
+        let Output::File { path, out } = self else {
+            return Ok(());
+        };
+        out.flush()?;
+        let path = path.clone();
+        let rotated = |n: u32| format!("{}.{}", path, n);
+        for n in (1..keep).rev() {
+            match fs::rename(rotated(n), rotated(n + 1)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        match mode {
+            RotateMode::Rename if keep == 0 => fs::remove_file(&path)?,
+            RotateMode::Rename => fs::rename(&path, rotated(1))?,
+            RotateMode::Copytruncate => {
+                if keep > 0 {
+                    fs::copy(&path, rotated(1))?;
+                }
+                return out.get_ref().set_len(0);
+            }
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        *self = Output::File { path, out: BufWriter::new(file) };
+        Ok(())
+    }
+
+    // Empty the file in place, as `> app.log` does
+    fn truncate(&mut self) -> io::Result<()> {
+        let Output::File { out, .. } = self else {
+            return Ok(());
+        };
+        out.flush()?;
+        out.get_ref().set_len(0)
+    }
+}
+
+// The time lines are stamped with: the wall clock's, or from a fixed start
+// moving on by the pauses between lines (however long writing them really
+// took), so seeded runs write the very same lines
+enum Clock {
+    Wall(Instant),
+    Fixed { start: DateTime<FixedOffset>, elapsed: Duration },
+}
+
+impl Clock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        match self {
+            Clock::Wall(_) => Local::now().fixed_offset(),
+            Clock::Fixed { start, elapsed } => *start + *elapsed,
+        }
+    }
+
+    // Time into the run, which the schedules go by
+    fn elapsed(&self) -> Duration {
+        match self {
+            Clock::Wall(started) => started.elapsed(),
+            Clock::Fixed { elapsed, .. } => *elapsed,
+        }
+    }
+
+    // Move a fixed clock on by `pause`; the wall clock moves by itself
+    fn advance(&mut self, pause: Duration) {
+        if let Clock::Fixed { elapsed, .. } = self {
+            *elapsed += pause;
+        }
+    }
+}
+
+// Something that happens every `every` from the start, for `amount` seconds or lines
+#[derive(Clone, Copy)]
+struct Schedule {
+    every: Duration,
+    amount: f64,
+}
+
+impl Schedule {
+    // How much of the current window is left, if one is running `elapsed` into the run
+    fn window_left(&self, elapsed: Duration) -> Option<Duration> {
+        if elapsed < self.every {
+            return None;
+        }
+        let every = self.every.as_secs_f64();
+        let into = elapsed.as_secs_f64() % every;
+        (into < self.amount).then(|| Duration::from_secs_f64(self.amount - into))
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// `[2024-05-01 10:00:00.123 - INFO - #1000] message`
+    Plain,
+    /// One JSON object per line with timestamp, level, service and msg keys
+    Json,
+    /// `time=... level=info service=app seq=1000 msg="message"`
+    Logfmt,
+    /// `May  1 10:00:00 host app[4242]: ERROR message`
+    Syslog,
+    /// Common log format access lines, with 5xx for errors and 4xx for warnings
+    Clf,
+}
+
+fn main() -> io::Result<()> {
+    let options = Options::parse();
+    let outputs: Vec<(&str, &str)> = options.output.iter().map(|spec| service_and_target(spec)).collect();
+    let files = outputs.iter().filter(|(_, target)| is_file(target)).count();
+    if files == 0 && (options.rotate.is_some() || options.truncate.is_some()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "--rotate and --truncate need an output file"));
+    }
+    if outputs.iter().any(|(_, target)| *target != "-") {
+        eprintln!("Press Ctrl+C to stop");
+    }
+    // Each service writes on a thread of its own, so their lines interleave as a real system's would
+    thread::scope(|scope| {
+        let emitters: Vec<_> = outputs.iter()
+            .enumerate()
+            .map(|(index, &(service, target))| {
+                let options = &options;
+                scope.spawn(move || match generate(options, service, target, index as u64) {
+                    // Piped into `head` and the like, which stop reading when they have enough
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                    result => result.map_err(|e| io::Error::new(e.kind(), format!("{}: {}", target, e))),
+                })
+            })
+            .collect();
+        emitters.into_iter()
+            .map(|emitter| emitter.join().unwrap_or_else(|_| Err(io::Error::other("generator thread panicked"))))
+            .fold(Ok(()), Result::and)
+    })
+}
+
+// `api=udp://127.0.0.1:514` or a bare target, named after its file
+fn service_and_target(spec: &str) -> (&str, &str) {
+    match spec.split_once('=') {
+        Some((service, target)) if !service.is_empty() && !service.contains(['/', ':', '.']) => (service, target),
+        _ if is_file(spec) => (Path::new(spec).file_stem().and_then(|stem| stem.to_str()).unwrap_or("app"), spec),
+        _ => ("app", spec),
+    }
+}
+
+fn is_file(target: &str) -> bool {
+    target != "-" && !target.starts_with("udp://") && !target.starts_with("tcp://")
+}
+
+// Write one service's lines to `target` until the duration is up
+fn generate(options: &Options, service: &str, target: &str, index: u64) -> io::Result<()> {
+    let mut out = Output::open(target, options.format)?;
+    match target {
+        "-" => {}
+        _ if target.starts_with("tcp://") => eprintln!("Serving {} log entries on {}", service, target),
+        _ => eprintln!("Generating {} log entries to: {}", service, target),
+    }
+    // Services get seeds of their own, or they would all write the same lines
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(index)),
+        None => StdRng::from_entropy(),
+    };
+    let duration = options.duration.map(Duration::from_secs_f64);
+    let start = options.start.or_else(|| options.seed.map(|_| DateTime::parse_from_rfc3339(SEEDED_START).expect("valid start")));
+    let mut clock = match start {
+        Some(start) => Clock::Fixed { start, elapsed: Duration::ZERO },
+        None => Clock::Wall(Instant::now()),
+    };
+
+    let mut next_due = Instant::now();
+    let mut sequence: u64 = 1000;
+    let mut bursts = 0;
+    let mut rotate = options.rotate.map(|every| Trigger::new(every, sequence));
+    let mut truncate = options.truncate.map(|every| Trigger::new(every, sequence));
     loop {
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_path)
-            .expect("Failed to open log file");
-            
-        let now = chrono::Local::now();
-        let timestamp = now.format("%Y-%m-%d %H:%M:%S%.3f").to_string();
-            
+        let elapsed = clock.elapsed();
+        let remaining = duration.map(|duration| duration.saturating_sub(elapsed));
+        if remaining == Some(Duration::ZERO) {
+            break;
+        }
+
+        // A quiet period, after which lines come at the usual pace again
+        // rather than catching up
+        if let Some(left) = options.quiet.and_then(|quiet| quiet.window_left(elapsed)) {
+            out.flush()?;
+            let left = remaining.map_or(left, |remaining| left.min(remaining));
+            sleep(left);
+            clock.advance(left);
+            next_due = Instant::now();
+            continue;
+        }
+        if rotate.as_mut().is_some_and(|trigger| trigger.due(sequence)) {
+            out.rotate(options.rotate_mode, options.keep)?;
+        }
+        if truncate.as_mut().is_some_and(|trigger| trigger.due(sequence)) {
+            out.truncate()?;
+        }
+        let now = clock.now();
+        if let Some(burst) = options.burst.filter(|burst| elapsed >= burst.every * (bursts + 1)) {
+            bursts += 1;
+            for _ in 0..burst.amount as u64 {
+                let message = ERROR_MESSAGES[rng.gen_range(0..ERROR_MESSAGES.len())];
+                let line = format_line(options.format, now, service, "ERROR", sequence, message, &mut rng);
+                write_entry(&mut out, options, &line, "ERROR", &mut rng)?;
+                sequence += 1;
+            }
+        }
+
         // Choose log level with weighted probability (more INFO than ERROR)
-        let level_idx = match rng.gen_range(0..10) {
+        let level = match rng.gen_range(0..10) {
             0..=6 => 0, // 70% INFO
             7..=8 => 1, // 20% DEBUG
-            9 => if rng.gen_bool(0.7) { 2 } else { 3 }, // 7% WARNING, 3% ERROR
-            _ => unreachable!()
+            _ => if rng.gen_bool(0.7) { 2 } else { 3 }, // 7% WARNING, 3% ERROR
+        };
+        let spiking = options.spike.is_some_and(|spike| spike.window_left(elapsed).is_some());
+        let (level, message) = if spiking && rng.gen_bool(0.5) {
+            ("WARNING", format!("Request completed in {}ms", rng.gen_range(2000..9000)))
+        } else {
+            (LEVELS[level], MESSAGES[rng.gen_range(0..MESSAGES.len())].to_string())
         };
-        
-        let level = log_levels[level_idx];
-        let message = messages[rng.gen_range(0..messages.len())];
-        let log_entry = format!("[{} - {} - #{}] {}\n", 
-                               timestamp, 
-                               level, 
-                               sequence,
-                               message);
-        
-        file.write_all(log_entry.as_bytes()).expect("Failed to write to log");
+        let line = format_line(options.format, now, service, level, sequence, &message, &mut rng);
+        write_entry(&mut out, options, &line, level, &mut rng)?;
         sequence += 1;
-        
-        // Random delay between 0.5-3 seconds
-        sleep(Duration::from_millis(rng.gen_range(500..3000)));
+
+        // With a rate, lines that are already due go out in one burst
+        let interval = match options.rate {
+            Some(rate) => Duration::from_secs_f64(1.0 / rate),
+            None => Duration::from_millis(rng.gen_range(500..3000)),
+        };
+        clock.advance(interval);
+        next_due += interval;
+        let pause = next_due.saturating_duration_since(Instant::now());
+        if !pause.is_zero() {
+            out.flush()?;
+            sleep(remaining.map_or(pause, |remaining| pause.min(remaining)));
+        }
+    }
+    out.flush()
+}
+
+// Write one log line, and a stack trace after some of the errors
+fn write_entry(out: &mut Output, options: &Options, line: &str, level: &str, rng: &mut StdRng) -> io::Result<()> {
+    out.write_line(line, level)?;
+    // Access logs have no room for traces
+    let traced = level == "ERROR" && !matches!(options.format, Format::Clf) && rng.gen_bool(options.traces);
+    if traced {
+        stack_trace(rng).iter().try_for_each(|line| out.write_line(line, level))?;
+    }
+    Ok(())
+}
+
+// Facility user (1) and the severity of `level`, as RFC 3164 puts them in front of a message
+fn syslog_priority(level: &str) -> u8 {
+    let severity = match level {
+        "ERROR" => 3,
+        "WARNING" => 4,
+        "INFO" => 6,
+        _ => 7,
+    };
+    8 + severity
+}
+
+fn parse_start(text: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(text).map_err(|_| format!("invalid start: {} (such as 2024-05-01T10:00:00+00:00)", text))
+}
+
+fn parse_rate(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("invalid rate: {} (lines per second, above zero)", text)),
+    }
+}
+
+fn format_line(format: Format, now: DateTime<FixedOffset>, service: &str, level: &str, sequence: u64, message: &str, rng: &mut StdRng) -> String {
+    match format {
+        Format::Plain => format!("[{} - {} - #{}] {}", now.format("%Y-%m-%d %H:%M:%S%.3f"), level, sequence, message),
+        Format::Json => serde_json::json!({
+            "timestamp": now.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            "level": level.to_lowercase(),
+            "service": service,
+            "seq": sequence,
+            "msg": message,
+        })
+        .to_string(),
+        Format::Logfmt => format!(
+            "time={} level={} service={} seq={} msg={:?}",
+            now.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            level.to_lowercase(),
+            service,
+            sequence,
+            message
+        ),
+        Format::Syslog => format!("{} demo-host {}[4242]: {} {}", now.format("%b %e %H:%M:%S"), service, level, message),
+        Format::Clf => {
+            let status = match level {
+                "ERROR" => [500, 502, 503][rng.gen_range(0..3)],
+                "WARNING" => [404, 403, 429][rng.gen_range(0..3)],
+                _ => [200, 200, 201, 304][rng.gen_range(0..4)],
+            };
+            format!(
+                "192.168.{}.{} - - [{}] \"{}\" {} {}",
+                rng.gen_range(0..4),
+                rng.gen_range(1..255),
+                now.format("%d/%b/%Y:%H:%M:%S %z"),
+                REQUESTS[rng.gen_range(0..REQUESTS.len())],
+                status,
+                rng.gen_range(200..20000)
+            )
+        }
+    }
+}
+
+// `SECS:AMOUNT`, such as `30:200`
+fn parse_schedule(text: &str) -> Result<Schedule, String> {
+    let invalid = || format!("invalid schedule: {} (expected SECS:AMOUNT, e.g. 30:200)", text);
+    let (every, amount) = text.split_once(':').ok_or_else(invalid)?;
+    let every: f64 = every.trim().parse().map_err(|_| invalid())?;
+    let amount: f64 = amount.trim().parse().map_err(|_| invalid())?;
+    if !(every.is_finite() && every > 0.0 && amount.is_finite() && amount >= 0.0) {
+        return Err(invalid());
+    }
+    Ok(Schedule { every: Duration::from_secs_f64(every), amount })
+}
+
+// `10000` lines or `30s` seconds
+fn parse_every(text: &str) -> Result<Every, String> {
+    let invalid = || format!("invalid interval: {} (a number of lines, or seconds such as 30s)", text);
+    match text.strip_suffix('s') {
+        Some(secs) => match secs.parse::<f64>() {
+            Ok(secs) if secs.is_finite() && secs > 0.0 => Ok(Every::Time(Duration::from_secs_f64(secs))),
+            _ => Err(invalid()),
+        },
+        None => match text.parse::<u64>() {
+            Ok(lines) if lines > 0 => Ok(Every::Lines(lines)),
+            _ => Err(invalid()),
+        },
+    }
+}
+
+fn parse_share(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(share) if (0.0..=1.0).contains(&share) => Ok(share),
+        _ => Err(format!("invalid share: {} (from 0 to 1)", text)),
+    }
+}
+
+// An exception line and a few `at` frames, as a JVM service logs them
+fn stack_trace(rng: &mut StdRng) -> Vec<String> {
+    let depth = rng.gen_range(3..=FRAMES.len());
+    let first = rng.gen_range(0..=FRAMES.len() - depth);
+    let mut lines = vec![EXCEPTIONS[rng.gen_range(0..EXCEPTIONS.len())].to_string()];
+    lines.extend(FRAMES[first..first + depth].iter().map(|frame| format!("\tat {}", frame)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_clock_moves_by_the_pauses() {
+        let start = parse_start("2024-05-01T10:00:00+02:00").unwrap();
+        let mut clock = Clock::Fixed { start, elapsed: Duration::ZERO };
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.elapsed(), Duration::from_millis(1500));
+        assert_eq!(clock.now().to_rfc3339(), "2024-05-01T10:00:01.500+02:00");
+        assert!(parse_start("yesterday").is_err());
+    }
+
+    #[test]
+    fn parses_rates_and_shares() {
+        assert_eq!(parse_rate("2.5"), Ok(2.5));
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("inf").is_err());
+        assert_eq!(parse_share("0.25"), Ok(0.25));
+        assert!(parse_share("1.5").is_err());
+    }
+
+    #[test]
+    fn parses_schedules() {
+        let schedule = parse_schedule("30:200").unwrap();
+        assert_eq!((schedule.every, schedule.amount), (Duration::from_secs(30), 200.0));
+        assert!(parse_schedule("0:200").is_err());
+        assert!(parse_schedule("30").is_err());
+    }
+
+    #[test]
+    fn parses_intervals_in_lines_or_seconds() {
+        assert!(matches!(parse_every("10000"), Ok(Every::Lines(10000))));
+        assert!(matches!(parse_every("1.5s"), Ok(Every::Time(every)) if every == Duration::from_millis(1500)));
+        assert!(parse_every("0").is_err());
+        assert!(parse_every("-2s").is_err());
     }
 }
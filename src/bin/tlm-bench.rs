@@ -0,0 +1,9 @@
+use std::error::Error;
+
+use clap::Parser;
+
+use log_monitor::bench::{self, BenchOptions};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    bench::run(&BenchOptions::parse())
+}
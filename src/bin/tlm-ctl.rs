@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+
+use log_monitor::ipc;
+
+/// Steer a log_monitor started with --control: tlm-ctl filter "payment error",
+/// tlm-ctl open /var/log/new.log, tlm-ctl follow, tlm-ctl status, tlm-ctl quit, ...
+#[derive(Parser)]
+#[command(version, about)]
+struct Options {
+    /// Control socket of the monitor [default: the path --control uses without one]
+    #[arg(short, long, value_name = "PATH", env = "TLM_SOCKET")]
+    socket: Option<PathBuf>,
+
+    /// Request and its argument: filter, open, close, command, push, scroll, follow, status or quit
+    #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
+    request: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let options = Options::parse();
+    let socket = options.socket.unwrap_or_else(ipc::default_socket_path);
+    match ipc::send(&socket, &options.request.join(" ")) {
+        Ok(reply) if reply.starts_with("ok") => {
+            // What the request reported, such as a command's message or the status
+            if let Some(text) = reply.strip_prefix("ok ") {
+                println!("{}", text);
+            }
+            ExitCode::SUCCESS
+        }
+        Ok(reply) => {
+            eprintln!("{}", reply);
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("Can't reach the monitor at {}: {}", socket.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use regex::bytes::{Regex, RegexBuilder};
+
+// Bytes scanned for line breaks per indexing step, keeping each step short
+const INDEX_STEP_BYTES: usize = 8 * 1024 * 1024;
+
+/// Non-follow view over an entire file, read where it is looked at rather
+/// than loaded into the live buffer. Line start offsets are indexed lazily as
+/// the user scrolls or searches, so opening a multi-gigabyte file is instant.
+///
+/// The browser covers the file's size when opened; lines appended later are
+/// not visible until it is reopened. The file is read with plain positional
+/// reads, never mapped, so one truncated or rotated meanwhile only comes up
+/// short.
+pub struct Browser {
+    pub path: String,
+    file: File,
+    len: usize,              // Size of the file when opened
+    line_starts: Vec<usize>, // Byte offset of every line start found so far
+    indexed_to: usize,       // Bytes scanned for line breaks so far
+    pub top: usize,          // First visible line
+    pub search_input: String,
+    pub search_editing: bool,
+    last_search: Option<Regex>,
+    pub message: String,
+}
+
+impl Browser {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        Ok(Browser {
+            path: path.to_string(),
+            file,
+            len,
+            line_starts: vec![0],
+            indexed_to: 0,
+            top: 0,
+            search_input: String::new(),
+            search_editing: false,
+            last_search: None,
+            message: String::new(),
+        })
+    }
+
+    // The bytes at `start..end`, fewer if the file has shrunk since
+    fn read_range(&self, start: usize, end: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(end.saturating_sub(start));
+        let mut file = &self.file;
+        if file.seek(SeekFrom::Start(start as u64)).is_ok() {
+            let _ = file.take(end.saturating_sub(start) as u64).read_to_end(&mut bytes);
+        }
+        bytes
+    }
+
+    fn fully_indexed(&self) -> bool {
+        self.indexed_to >= self.len
+    }
+
+    // Scan one more step of the file for line breaks
+    fn index_step(&mut self) {
+        let end = (self.indexed_to + INDEX_STEP_BYTES).min(self.len);
+        let chunk = self.read_range(self.indexed_to, end);
+        let base = self.indexed_to;
+        self.line_starts.extend(
+            chunk.iter()
+                .enumerate()
+                .filter(|(_, b)| **b == b'\n')
+                .map(|(i, _)| base + i + 1)
+                .filter(|start| *start < self.len),
+        );
+        // A file cut short ends where the reads do
+        if chunk.len() < end - self.indexed_to {
+            self.len = base + chunk.len();
+            self.line_starts.retain(|&start| start == 0 || start < self.len);
+        }
+        self.indexed_to = end.min(self.len);
+    }
+
+    // Index until line `line` (plus a screenful) is known or the file ends
+    fn ensure_line(&mut self, line: usize) {
+        while self.line_starts.len() <= line && !self.fully_indexed() {
+            self.index_step();
+        }
+    }
+
+    // Index until the line containing byte `offset` is known
+    fn ensure_offset(&mut self, offset: usize) {
+        while self.indexed_to <= offset && !self.fully_indexed() {
+            self.index_step();
+        }
+    }
+
+    /// Number of lines known so far, and whether that is the whole file.
+    pub fn line_count(&self) -> (usize, bool) {
+        let count = if self.len == 0 { 0 } else { self.line_starts.len() };
+        (count, self.fully_indexed())
+    }
+
+    /// Lines `top..top + height`, indexing further into the file if needed.
+    pub fn visible_lines(&mut self, height: usize) -> Vec<(usize, Cow<'_, str>)> {
+        self.ensure_line(self.top + height);
+        let (count, _) = self.line_count();
+        (self.top..(self.top + height).min(count))
+            .map(|n| (n, self.line(n)))
+            .collect()
+    }
+
+    fn line(&self, n: usize) -> Cow<'_, str> {
+        let start = self.line_starts[n];
+        let end = self.line_starts.get(n + 1).copied().unwrap_or(self.len);
+        let bytes = self.read_range(start, end);
+        let bytes = bytes.strip_suffix(b"\n").unwrap_or(&bytes);
+        let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+        Cow::Owned(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        let target = self.top.saturating_add_signed(delta);
+        self.ensure_line(target);
+        let (count, _) = self.line_count();
+        self.top = target.min(count.saturating_sub(1));
+    }
+
+    pub fn go_to_top(&mut self) {
+        self.top = 0;
+    }
+
+    /// Jump to the end of the file, which requires indexing all of it.
+    pub fn go_to_end(&mut self, height: usize) {
+        while !self.fully_indexed() {
+            self.index_step();
+        }
+        self.top = self.line_count().0.saturating_sub(height);
+    }
+
+    /// Search for the text typed in the search prompt (case-insensitive) from
+    /// the line after the top one.
+    pub fn start_search(&mut self) {
+        self.search_editing = false;
+        if self.search_input.is_empty() {
+            return;
+        }
+        self.last_search = RegexBuilder::new(&regex::escape(&self.search_input))
+            .case_insensitive(true)
+            .build()
+            .ok();
+        self.search_next();
+    }
+
+    /// Move to the next match of the last search, wrapping to the start of the file.
+    pub fn search_next(&mut self) {
+        self.ensure_line(self.top + 1);
+        let Some(regex) = &self.last_search else {
+            return;
+        };
+        let from = self.line_starts.get(self.top + 1).copied().unwrap_or(self.len);
+        let found = self.find(regex, from, self.len).or_else(|| self.find(regex, 0, from));
+        match found {
+            Some(offset) => {
+                self.ensure_offset(offset);
+                self.top = self.line_starts.partition_point(|start| *start <= offset) - 1;
+                self.message.clear();
+            }
+            None => self.message = format!("Not found: {}", self.search_input),
+        }
+    }
+
+    // Offset of the first match within `start..end`, read a step at a time.
+    // Each step ends at a line break, so a match (within one line, as the
+    // search text is) is never cut in two.
+    fn find(&self, regex: &Regex, start: usize, end: usize) -> Option<usize> {
+        let mut at = start;
+        while at < end {
+            let mut chunk = self.read_range(at, (at + INDEX_STEP_BYTES).min(end));
+            if chunk.is_empty() {
+                return None;
+            }
+            if at + chunk.len() < end
+                && let Some(last_break) = chunk.iter().rposition(|&b| b == b'\n')
+            {
+                chunk.truncate(last_break + 1);
+            }
+            if let Some(m) = regex.find(&chunk) {
+                return Some(at + m.start());
+            }
+            at += chunk.len();
+        }
+        None
+    }
+}
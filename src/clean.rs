@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether each frame of a redrawn line is kept (--progress-frames) rather than only the last
+static KEEP_FRAMES: AtomicBool = AtomicBool::new(false);
+
+// Put between the frames of a redrawn line when they are all kept
+const FRAME_SEPARATOR: &str = " ↵ ";
+
+pub(crate) fn set_keep_frames(keep: bool) {
+    KEEP_FRAMES.store(keep, Ordering::Relaxed);
+}
+
+/// A line as a terminal would have left it. Progress bars and spinners
+/// redraw themselves with `\r` and backspaces, so the text after a carriage
+/// return overwrites the line from its start and a backspace steps back a
+/// character; escape sequences (colors, cursor moves) and any other control
+/// characters are dropped. With `--progress-frames` every redraw is kept
+/// instead, one after another. Tabs are left alone.
+pub(crate) fn clean_line(line: &str) -> Cow<'_, str> {
+    if !line.chars().any(|c| c.is_control() && c != '\t') {
+        return Cow::Borrowed(line);
+    }
+    let mut screen: Vec<char> = Vec::new(); // The characters showing, in columns
+    let mut cursor: usize = 0; // Column the next character goes in
+    let mut frames = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                if KEEP_FRAMES.load(Ordering::Relaxed) && !screen.is_empty() {
+                    frames.push(screen.drain(..).collect::<String>());
+                }
+                cursor = 0;
+            }
+            '\u{8}' => cursor = cursor.saturating_sub(1),
+            '\u{1b}' => skip_escape(&mut chars),
+            '\t' => put(&mut screen, &mut cursor, c),
+            c if c.is_control() => {}
+            c => put(&mut screen, &mut cursor, c),
+        }
+    }
+    if !screen.is_empty() || frames.is_empty() {
+        frames.push(screen.into_iter().collect());
+    }
+    Cow::Owned(frames.join(FRAME_SEPARATOR))
+}
+
+// Write `c` at the cursor, over whatever is there
+fn put(screen: &mut Vec<char>, cursor: &mut usize, c: char) {
+    match screen.get_mut(*cursor) {
+        Some(cell) => *cell = c,
+        None => screen.push(c),
+    }
+    *cursor += 1;
+}
+
+// Skip the rest of an escape sequence: CSI (`ESC [ ... final`), OSC
+// (`ESC ] ... BEL` or `ESC ] ... ESC \`) or a two-character one
+fn skip_escape(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    match chars.next() {
+        Some('[') => {
+            for c in chars.by_ref() {
+                if ('\u{40}'..='\u{7e}').contains(&c) {
+                    return;
+                }
+            }
+        }
+        Some(']') => {
+            while let Some(c) = chars.next() {
+                if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                    return;
+                }
+            }
+        }
+        _ => {}
+    }
+}
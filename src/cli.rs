@@ -0,0 +1,472 @@
+use std::error::Error;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use chrono::NaiveDateTime;
+use clap::{CommandFactory, Parser, ValueEnum};
+use crossterm::event::Event;
+use ratatui::layout::Rect;
+
+use crate::app::{parse_max_lines, reorder_window, App, LineNumbering, LoadFrom, ViewMode, DEFAULT_INITIAL_LINES};
+use crate::format::LogFormat;
+use crate::headless::{self, HeadlessOptions};
+use crate::parse::{parse_level, LogLevel};
+use crate::replay::{Recorder, Replay};
+use crate::script::Script;
+use crate::source::{Source, STDIN_TARGET};
+use crate::spill::Spill;
+use crate::term::{ColorDepth, TerminalGuard};
+use crate::theme::Theme;
+use crate::title::WindowTitle;
+use crate::{clean, config, ingest, input, ipc, links, metrics, print, replay, session, signals, theme, ui, window};
+
+/// Terminal dashboard for following and exploring log files.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct CliOptions {
+    /// Log file to follow, or - for standard input; without one, a file picker with recent files opens
+    log_path: Option<String>,
+
+    /// Lines to read from the end of the file at startup [default: 100]
+    #[arg(short = 'n', long = "lines", value_name = "N")]
+    lines: Option<usize>,
+
+    /// Start with follow mode off
+    #[arg(long)]
+    no_follow: bool,
+
+    /// Start with this filter text applied
+    #[arg(short, long, value_name = "TEXT")]
+    filter: Option<String>,
+
+    /// Only show lines logged since then, read from wherever they are in the file: a span back from now (10m, 2h, 1d) or a time (2024-05-01 10:00, 10:00)
+    #[arg(long, value_name = "WHEN", value_parser = window::parse_moment)]
+    since: Option<NaiveDateTime>,
+
+    /// Only show lines logged before then, given as for --since
+    #[arg(long, value_name = "WHEN", value_parser = window::parse_moment)]
+    until: Option<NaiveDateTime>,
+
+    /// Open at the first line of the view, or at the last and following [default: bottom]
+    #[arg(long, value_enum, value_name = "WHERE")]
+    start: Option<StartAt>,
+
+    /// Only show lines at this level or above (trace, debug, info, warning, error, fatal)
+    #[arg(short, long, value_name = "MIN", value_parser = parse_level)]
+    level: Option<LogLevel>,
+
+    /// How lines are parsed: auto, plain, json, syslog, access or a profile from the config file [default: auto, guessed per source]
+    #[arg(long, value_name = "PROFILE")]
+    format: Option<String>,
+
+    /// Color scheme [default: dark; NO_COLOR forces mono]
+    #[arg(long, value_enum, value_name = "NAME", env = "TLM_THEME")]
+    theme: Option<Theme>,
+
+    /// Show timestamps in this strftime layout, e.g. %H:%M:%S [default: as logged]
+    #[arg(long, value_name = "LAYOUT")]
+    timestamp_format: Option<String>,
+
+    /// Leave the date out of timestamps, showing it only where it changes from the line above
+    #[arg(long)]
+    compact_dates: bool,
+
+    /// Number lines by their position in the filtered view or in the file [default: view]
+    #[arg(long, value_enum, value_name = "WHICH")]
+    line_numbers: Option<LineNumbering>,
+
+    /// Fixed width of the line number column [default: fits the largest number]
+    #[arg(long, value_name = "N")]
+    line_number_width: Option<usize>,
+
+    /// Colors the terminal can show: truecolor, 256, 16 or mono [default: detected]
+    #[arg(long, value_enum, value_name = "DEPTH")]
+    colors: Option<ColorDepth>,
+
+    /// Draw with ASCII characters only, for consoles without Unicode [default: detected]
+    #[arg(long)]
+    ascii: bool,
+
+    /// Put a symbol for the level in front of each line (✖ error, ⚠ warning, ℹ info), so levels don't rely on color alone
+    #[arg(long)]
+    symbols: bool,
+
+    /// How often the file is checked for new lines, in milliseconds [default: 250]
+    #[arg(long, value_name = "MS")]
+    interval: Option<u64>,
+
+    /// Show at most N lines a second of any one message template, noting how many more were held back
+    #[arg(long, value_name = "N")]
+    throttle: Option<usize>,
+
+    /// Lines kept in memory before the oldest are evicted [default: 1000]
+    #[arg(long, value_name = "N", value_parser = parse_max_lines)]
+    max_lines: Option<usize>,
+
+    /// Read settings from this file instead of ~/.config/tui-log-monitor/config.toml
+    #[arg(long, value_name = "PATH", env = "TLM_CONFIG")]
+    config: Option<String>,
+
+    /// Open the file, format, filters, highlights and watches of a profile from the config file
+    #[arg(short, long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Restore the filters, scroll position and mark saved when this file was last closed
+    #[arg(long)]
+    resume: bool,
+
+    /// Only show what was added to the file since the monitor last quit following it
+    #[arg(long, conflicts_with_all = ["since", "until", "headless", "replay_path"])]
+    since_last_run: bool,
+
+    /// Also read rotated copies (app.log.1, app.log.2.gz, ...) when the file has fewer than --lines lines
+    #[arg(long)]
+    history: bool,
+
+    /// Keep every redraw of lines updated with carriage returns (progress bars) instead of only the last
+    #[arg(long)]
+    progress_frames: bool,
+
+    /// Slot lines stamped up to this many milliseconds earlier than the newest ones in by time [default: 0, off]
+    #[arg(long, value_name = "MS")]
+    reorder_window: Option<u64>,
+
+    /// Show the log file side by side with this one, lined up by timestamp
+    #[arg(long = "compare", value_name = "PATH")]
+    compare_path: Option<String>,
+
+    /// Keep evicted lines in a temporary scrollback file
+    #[arg(long)]
+    spill: bool,
+
+    /// Serve Prometheus metrics on this address, e.g. 127.0.0.1:9898
+    #[arg(long = "metrics", value_name = "ADDR")]
+    metrics_addr: Option<String>,
+
+    /// Forward new filtered lines to a file, '|command' or tcp://host:port
+    #[arg(long = "tee", value_name = "TARGET")]
+    tee_target: Option<String>,
+
+    /// Record ingested lines with their arrival times to a session file
+    #[arg(long = "record", value_name = "PATH")]
+    record_path: Option<String>,
+
+    /// Listen for tlm-ctl requests on a control socket [default path: $XDG_RUNTIME_DIR/tui-log-monitor/control.sock]
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "")]
+    control: Option<String>,
+
+    /// Play back a recorded session instead of following a file
+    #[arg(long = "replay", value_name = "PATH")]
+    replay_path: Option<String>,
+
+    /// Replay speed, e.g. 4x
+    #[arg(long = "speed", value_name = "SPEED", default_value = "1x", value_parser = replay::parse_speed)]
+    replay_speed: f64,
+
+    /// Run the on_line and on_alert hooks and :commands defined in this Rhai script
+    #[arg(long, value_name = "PATH")]
+    script: Option<String>,
+
+    /// Review as evidence: exports get a manifest of SHA-256 hashes of the source bytes behind each line, and every command and filter change is recorded
+    #[arg(long, conflicts_with_all = ["replay_path", "history", "throttle"])]
+    audit: bool,
+
+    /// Read cargo or rustc output: each diagnostic's notes and code frames take its level, and P lists the errors and warnings
+    #[arg(long)]
+    cargo: bool,
+
+    /// Print the lines passing the filters without the UI, firing watch rules, then exit (status 2 if an alert fired)
+    #[arg(long)]
+    headless: bool,
+
+    /// With --headless, print counts of the matching lines by level instead of the lines
+    #[arg(long, requires = "headless")]
+    summary: bool,
+
+    /// With --headless, print NDJSON records, or the summary as JSON
+    #[arg(long, requires = "headless")]
+    json: bool,
+
+    /// With standard output not a terminal, still color the printed lines by level (for less -R)
+    #[arg(long)]
+    color: bool,
+
+    /// Print a completion script for this shell (bash, zsh, fish, ...) and exit
+    #[arg(long, value_name = "SHELL")]
+    completions: Option<clap_complete::Shell>,
+}
+
+// Where the log view opens, chosen with `--start`
+#[derive(Clone, Copy, ValueEnum)]
+enum StartAt {
+    Top,
+    Bottom,
+}
+
+/// Run the monitor with `options`: set it up from the config files and the
+/// command line, then draw and handle input until the user quits. Returns
+/// the status to exit with, which only a headless run sets.
+pub fn run(options: CliOptions) -> Result<ExitCode, Box<dyn Error>> {
+    if let Some(shell) = options.completions {
+        clap_complete::generate(shell, &mut CliOptions::command(), "log_monitor", &mut io::stdout());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let log_path = options.replay_path.clone().or_else(|| options.log_path.clone());
+    let config = config::load(options.config.as_deref(), log_path.as_deref(), options.profile.as_deref())?;
+    let log_path = config.log_path.clone().unwrap_or_default();
+
+    // Create app state; flags override the config files, which override the defaults
+    let mut app = App::new(log_path);
+    app.apply_config(&config)?;
+    app.config_path = options.config.as_ref().map(PathBuf::from).or_else(config::default_path);
+    let resumed = if options.resume || config.resume == Some(true) {
+        session::load(&app.log_path)
+    } else {
+        None
+    };
+    if let Some(session) = &resumed {
+        app.restore_session(session);
+    }
+    if let Some(max_lines) = options.max_lines {
+        app.max_lines = max_lines;
+    }
+    if options.no_follow {
+        app.follow_mode = false;
+    }
+    if options.history {
+        app.history = true;
+    }
+    clean::set_keep_frames(options.progress_frames || config.progress_frames == Some(true));
+    if let Some(ms) = options.reorder_window {
+        app.reorder_window = reorder_window(ms);
+    }
+    if let Some(filter) = &options.filter {
+        app.filter_text = filter.clone();
+    }
+    if let Some(range) = window::time_range(options.since, options.until) {
+        app.time_range = Some(range);
+        app.load_from = Some(LoadFrom::Window(range.0, range.1));
+    }
+    if options.level.is_some() {
+        app.min_level = options.level;
+    }
+    if let Some(name) = &options.format {
+        app.format = LogFormat::resolve(name, &app.formats)?;
+    }
+    if let Some(theme) = options.theme {
+        app.theme = theme;
+    }
+    // crossterm drops every color under NO_COLOR, so match that everywhere
+    if theme::no_color() {
+        app.theme = Theme::Mono;
+    }
+    if let Some(colors) = options.colors {
+        app.term.colors = colors;
+    }
+    if options.ascii {
+        app.term.unicode = false;
+    }
+    if options.symbols {
+        app.show_symbols = true;
+    }
+    if let Some(per_second) = options.throttle {
+        app.set_throttle(per_second);
+    }
+    if let Some(interval) = options.interval {
+        app.poll_interval.store(interval, Ordering::Relaxed);
+    }
+    if let Some(layout) = &options.timestamp_format {
+        app.set_timestamp_format(layout).map_err(|e| format!("{} (--timestamp-format)", e))?;
+    }
+    if options.compact_dates {
+        app.compact_dates = true;
+    }
+    if let Some(numbering) = options.line_numbers {
+        app.line_numbering = numbering;
+    }
+    if options.line_number_width.is_some() {
+        app.line_number_width = options.line_number_width;
+    }
+    if let Some(path) = options.script.as_ref().or(config.script.as_ref()) {
+        app.script = Some(Script::load(path)?);
+    }
+    if options.audit {
+        app.start_audit()?;
+    }
+    if options.cargo {
+        app.start_cargo_mode();
+    }
+    if options.headless {
+        if options.replay_path.is_some() || app.log_path.is_empty() {
+            return Err("--headless needs a log file to read".into());
+        }
+        let headless_options = HeadlessOptions {
+            summary: options.summary,
+            json: options.json,
+            last_lines: options.lines, // The whole file unless -n asks for its end; the config's `lines` is for the UI
+        };
+        return Ok(headless::run(&mut app, &headless_options)?);
+    }
+    app.initial_lines = options.lines.or(config.lines).unwrap_or(DEFAULT_INITIAL_LINES);
+    match (&options.replay_path, &config.log_path) {
+        (Some(path), _) => {
+            app.replay = Some(Replay::open(path, options.replay_speed)?);
+            let id = app.next_source_id();
+            app.sources.push(Source::detached(id, path, app.format.clone()));
+        }
+        (None, Some(log_path)) => {
+            if let Some(path) = &options.record_path {
+                app.recorder = Some(Recorder::create(path, log_path)?);
+            }
+            let checkpoint = options.since_last_run.then(|| session::resume_offset(log_path)).flatten();
+            app.load_from = checkpoint.map(LoadFrom::Offset).or(app.load_from);
+            app.initialize_logs(app.initial_lines)?;
+            if options.since_last_run {
+                // The buffer keeps only the last --max-lines of them
+                app.set_status(match checkpoint {
+                    Some(offset) => match ingest::count_lines_from(log_path, offset) {
+                        Ok(added) => format!("Lines added since the last run: {}", added),
+                        Err(e) => format!("Failed to count the lines added since the last run: {}", e),
+                    },
+                    None => "No earlier run of this file; showing its last lines".to_string(),
+                });
+            }
+            if let Some(path) = &options.compare_path {
+                let message = app.start_compare(path)?;
+                app.set_status(message);
+            }
+        }
+        (None, None) if options.record_path.is_some() => return Err("--record needs a log file to follow".into()),
+        (None, None) if !io::stdout().is_terminal() => return Err("Standard output is not a terminal: give a log file to print".into()),
+        (None, None) => app.open_picker(),
+    }
+    app.update_filter();
+    if let Some(session) = &resumed {
+        app.restore_position(session);
+    }
+    // Catching up on what was missed reads from the top
+    match options.start.or(options.since_last_run.then_some(StartAt::Top)) {
+        Some(StartAt::Top) => app.scroll_to_top(),
+        Some(StartAt::Bottom) => app.resume_follow(),
+        None => {}
+    }
+    if options.spill {
+        app.spill = Some(Spill::create()?);
+    }
+    if let Some(addr) = &options.metrics_addr {
+        metrics::serve(addr, app.metrics.clone())?;
+    }
+    if let Some(target) = &options.tee_target {
+        app.start_tee(target)?;
+    }
+    // Piped into another program: print the lines as `tail -f` would instead of drawing
+    if !io::stdout().is_terminal() {
+        print::run(&mut app, options.color, !options.no_follow)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let control_path = match options.control.as_deref() {
+        Some("") => Some(ipc::default_socket_path()),
+        Some(path) => Some(PathBuf::from(path)),
+        None => (config.control == Some(true)).then(ipc::default_socket_path),
+    };
+    let _control = control_path.map(|path| ipc::serve(&path, app.handle())).transpose()?; // Removes the socket on exit
+
+    // Setup terminal, restored when the guard is dropped or on a panic
+    let mut terminal = TerminalGuard::enter()?;
+    let mut window_title = (config.title != Some(false)).then(|| WindowTitle::new(config.tmux_rename == Some(true)));
+    signals::forward(app.handle())?;
+    let events = input::spawn_reader();
+    app.input = Some(events.clone());
+
+    // Main loop
+    loop {
+        // Check for new log entries (except in help view)
+        let ingest_started = Instant::now();
+        if app.view_mode != ViewMode::HelpView && app.update_logs() {
+            app.redraw = true;
+        }
+        if app.process_requests() {
+            app.redraw = true;
+        }
+        if app.quit_requested {
+            break;
+        }
+        if std::mem::take(&mut app.suspend_requested) {
+            terminal.suspend()?;
+            if let Some(window_title) = &mut window_title {
+                window_title.reset();
+            }
+            app.redraw = true;
+        }
+        if let Some((path, line)) = app.edit_requested.take() {
+            input::pause_reader();
+            let edited = terminal.hand_over(|| links::edit(&path, line));
+            input::resume_reader();
+            if let Some(window_title) = &mut window_title {
+                window_title.reset();
+            }
+            if let Err(e) = edited? {
+                app.set_status(e);
+            }
+            app.redraw = true;
+        }
+        app.check_silence_watches();
+        app.resume_filter();
+        app.perf.record_ingest(ingest_started.elapsed());
+
+        // Draw UI, only when something changed since the last frame
+        if app.redraw {
+            app.redraw = false;
+            let render_started = Instant::now();
+            terminal.draw(|f| ui::draw(&mut app, f))?;
+            app.perf.record_render(render_started.elapsed());
+        }
+
+        let title = app.window_title();
+        if let Some(window_title) = &mut window_title {
+            window_title.update(terminal.backend_mut(), &title)?;
+        }
+
+        if app.ring_bell {
+            app.ring_bell = false;
+            let backend = terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            backend.flush()?;
+        }
+
+        // Handle input
+        if let Some(event) = app.wait_for_event(&events)? {
+            app.redraw = true;
+            if let Event::Resize(width, height) = event {
+                // Start the next frame from a clean screen of the new size
+                terminal.resize(Rect::new(0, 0, width, height))?;
+            }
+            if input::handle_event(&mut app, event, terminal.size()?.height) {
+                break;
+            }
+        }
+    }
+
+    // Restore terminal
+    drop(terminal);
+
+    // Remember where this file was left, for the next --resume
+    if !app.log_path.is_empty() && app.log_path != STDIN_TARGET {
+        if let Err(e) = session::save(&app.log_path, app.session_state()) {
+            eprintln!("Failed to save session: {}", e);
+        }
+        // ...and how far it was read, for the next --since-last-run
+        if app.replay.is_none()
+            && let Some(offset) = app.sources.first().filter(|source| source.target == app.log_path).and_then(|source| source.read_offset)
+            && let Err(e) = session::save_checkpoint(&app.log_path, offset)
+        {
+            eprintln!("Failed to save checkpoint: {}", e);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
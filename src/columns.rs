@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use ratatui::style::Color;
+use regex::Regex;
+
+use crate::config::ColumnConfig;
+use crate::{LogLevel, LogLine};
+
+/// Widest an extra column gets, however long its values are.
+pub const MAX_COLUMN_WIDTH: usize = 24;
+
+/// A column shown after the level, and a field of its own in NDJSON
+/// exports, holding a value picked out of each line: a `[[lookup]]` table
+/// or a `[[column]]` pattern.
+pub trait ExtraColumn: Sync {
+    fn name(&self) -> &str;
+    fn width(&self) -> usize;
+    fn color(&self) -> Color;
+    /// The column's value for `line`, if it has one.
+    fn value<'a>(&'a self, line: &'a str) -> Option<&'a str>;
+}
+
+/// What the values of extra columns must be for a line to be shown: equal
+/// to `wanted`, or only containing it with `partial`, ignoring case, in any
+/// of `columns`. This is what `:lookup` and `:column` filter with.
+pub struct ColumnFilter<'a> {
+    pub columns: Vec<&'a dyn ExtraColumn>,
+    pub wanted: &'a str, // Lowercase
+    pub partial: bool,
+}
+
+impl ColumnFilter<'_> {
+    pub fn matches(&self, line: &str) -> bool {
+        self.columns.iter().any(|column| {
+            column.value(line).is_some_and(|value| {
+                let value = value.to_lowercase();
+                if self.partial { value.contains(self.wanted) } else { value == self.wanted }
+            })
+        })
+    }
+}
+
+/// A `[[column]]` from the config file: a value picked out of unstructured
+/// lines by the first group of `pattern` (or all it matched, without a
+/// group), such as the request id of `[req:(\w+)]`, shown as a column of
+/// its own and usable by `:column` filters and `:group`.
+pub struct DerivedColumn {
+    pub name: String,
+    regex: Regex,
+    pub width: usize, // Of the column, enough for the longest value seen so far
+}
+
+impl DerivedColumn {
+    pub fn new(config: &ColumnConfig) -> Result<Self, String> {
+        let regex = Regex::new(&config.pattern).map_err(|e| format!("Invalid pattern for column {}: {}", config.name, e))?;
+        Ok(DerivedColumn { name: config.name.clone(), regex, width: 0 })
+    }
+
+    // Widen the column for a line that just came in
+    pub fn fit(&mut self, line: &str) {
+        if let Some(value) = self.value(line) {
+            self.width = self.width.max(value.chars().count()).min(MAX_COLUMN_WIDTH);
+        }
+    }
+}
+
+impl ExtraColumn for DerivedColumn {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn color(&self) -> Color {
+        Color::LightBlue
+    }
+
+    // What the pattern matched in `line`, if it matches
+    fn value<'a>(&'a self, line: &'a str) -> Option<&'a str> {
+        let captures = self.regex.captures(line)?;
+        captures.get(1).or_else(|| captures.get(0)).map(|value| value.as_str())
+    }
+}
+
+/// The lines sharing a value of a derived column.
+pub struct Group {
+    pub value: String,
+    pub lines: usize,
+    pub errors: usize, // Of those lines, at error level or above
+}
+
+/// The `:group` panel: how the lines shown split up by the value of a
+/// derived column, the largest group first.
+pub struct GroupPanel {
+    pub column: usize, // Index into the derived columns
+    pub groups: Vec<Group>,
+    pub without: usize, // Lines the pattern doesn't match
+    pub selected: usize,
+}
+
+impl GroupPanel {
+    pub fn new<'a>(column: usize, derived: &DerivedColumn, lines: impl Iterator<Item = &'a LogLine>) -> Self {
+        let mut groups: HashMap<String, Group> = HashMap::new();
+        let mut without = 0;
+        for log in lines {
+            let content = log.content();
+            let Some(value) = derived.value(&content) else {
+                without += 1;
+                continue;
+            };
+            let group = groups.entry(value.to_string()).or_insert_with(|| Group { value: value.to_string(), lines: 0, errors: 0 });
+            group.lines += 1;
+            if log.level.severity() >= LogLevel::Error.severity() {
+                group.errors += 1;
+            }
+        }
+        let mut groups: Vec<Group> = groups.into_values().collect();
+        groups.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.value.cmp(&b.value)));
+        GroupPanel { column, groups, without, selected: 0 }
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::VecDeque;
+
+use crate::LogLine;
+
+/// Two sources side by side, opened with `:compare`. The focused pane is
+/// scrolled and the other one follows it, lined up by timestamp.
+pub struct Compare {
+    pub sources: [u16; 2],  // Source ids shown in the left and right panes
+    pub focus: usize,       // Pane being scrolled, 0 or 1
+    pub top: Option<usize>, // First line shown in the focused pane, `None` to follow the newest
+    pub height: usize,      // Lines per pane at the last draw, for paging
+}
+
+impl Compare {
+    pub fn new(left: u16, right: u16) -> Self {
+        Compare {
+            sources: [left, right],
+            focus: 0,
+            top: None,
+            height: 0,
+        }
+    }
+
+    /// The first line shown in the focused pane, which has `len` lines.
+    pub fn focus_top(&self, len: usize) -> usize {
+        let last_page = len.saturating_sub(self.height);
+        self.top.map_or(last_page, |top| top.min(len.saturating_sub(1)))
+    }
+
+    pub fn scroll_by(&mut self, delta: isize, len: usize) {
+        let top = self.focus_top(len).saturating_add_signed(delta);
+        // Scrolling back down to the last page picks up following again
+        self.top = (top < len.saturating_sub(self.height)).then_some(top);
+    }
+}
+
+/// Buffer indices of the lines from `source` among the `filtered` ones.
+pub fn pane_lines(log_lines: &VecDeque<LogLine>, filtered: &[usize], source: u16) -> Vec<usize> {
+    filtered.iter().copied().filter(|&idx| log_lines[idx].source == source).collect()
+}
+
+/// First line of the `following` pane at or after the time of the `leading`
+/// pane's line at `top`. A top line without a timestamp uses the nearest one
+/// above it; `following.len()` means every line there is earlier.
+pub fn align(log_lines: &VecDeque<LogLine>, leading: &[usize], top: usize, following: &[usize]) -> usize {
+    let Some(time) = leading.iter().take(top + 1).rev().find_map(|&idx| log_lines[idx].time) else {
+        return 0;
+    };
+    following.iter()
+        .position(|&idx| log_lines[idx].time.is_some_and(|t| t >= time))
+        .unwrap_or(following.len())
+}
@@ -0,0 +1,69 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+
+// Newest buffered lines scanned for fields on each Tab
+pub const SCAN_LINES: usize = 5000;
+
+// Choices listed in the status bar when Tab can't narrow them down
+pub const SHOWN_CHOICES: usize = 6;
+
+/// Tab completion state of the filter prompt: the choices Tab could not
+/// narrow down, stepped through by pressing it again.
+pub struct Completion {
+    pub choices: Vec<String>,
+    pub next: usize, // Choice the next Tab puts in the prompt
+}
+
+/// Field names and `name=value` pairs seen in structured lines, written as
+/// they appear in the text so that a completed filter matches them: `key=`
+/// and `key=value` in logfmt, `"key":` and `"key":"value"` in JSON.
+#[derive(Default)]
+pub struct Fields {
+    pub names: BTreeSet<String>,
+    pub pairs: BTreeSet<String>,
+}
+
+impl Fields {
+    pub fn collect(lines: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let pair = Regex::new(r#"("[\w.-]+"\s*:\s*)("(?:[^"\\]|\\.)*"|[\w.+-]+)|\b([\w.-]+=)("[^"]*"|[^\s"]+)"#)
+            .expect("valid field pattern");
+        let mut fields = Fields::default();
+        for line in lines {
+            for caps in pair.captures_iter(line.as_ref()) {
+                let (Some(name), Some(value)) = (caps.get(1).or(caps.get(3)), caps.get(2).or(caps.get(4))) else {
+                    continue;
+                };
+                fields.names.insert(name.as_str().to_string());
+                fields.pairs.insert(format!("{}{}", name.as_str(), value.as_str()));
+            }
+        }
+        fields
+    }
+
+    /// What `typed` can be completed to: field names until a name is typed
+    /// whole, then its values. Matching ignores case.
+    pub fn choices(&self, typed: &str) -> Vec<String> {
+        let typed_lower = typed.to_lowercase();
+        let named = self.names.iter().any(|name| typed_lower.starts_with(&name.to_lowercase()));
+        let tokens = if named { &self.pairs } else { &self.names };
+        tokens.iter()
+            .filter(|token| token.len() > typed.len() && token.to_lowercase().starts_with(&typed_lower))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The longest start the `choices` share.
+pub fn common_prefix(choices: &[String]) -> &str {
+    let Some((first, rest)) = choices.split_first() else {
+        return "";
+    };
+    let mut prefix = first.as_str();
+    for choice in rest {
+        while !choice.starts_with(prefix) {
+            prefix = &prefix[..prefix.char_indices().last().map_or(0, |(i, _)| i)];
+        }
+    }
+    prefix
+}
@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use glob::Pattern;
+use serde::Deserialize;
+
+use crate::term::ColorDepth;
+use crate::theme::Theme;
+use crate::app::LineNumbering;
+
+/// Settings from the TOML config files. Everything is optional, and command
+/// line flags take precedence over whatever is set here.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub lines: Option<usize>,
+    pub max_lines: Option<usize>,
+    pub interval: Option<u64>, // Milliseconds
+    pub follow: Option<bool>,
+    pub theme: Option<Theme>,
+    pub colors: Option<ColorDepth>, // Overrides the detected color depth, as with --colors
+    pub ascii: Option<bool>,
+    pub symbols: Option<bool>, // Level glyphs in front of lines, as with --symbols
+    pub throttle: Option<usize>, // Lines shown per second per message template, as with --throttle
+    pub scrubber: Option<bool>, // Time scrubber above the log view on tall terminals, off by default
+    pub level_symbols: HashMap<String, String>, // Level name to the glyph shown for it
+    pub format: Option<String>,
+    pub resume: Option<bool>, // Restore the last session of each file, as with --resume
+    pub wrap: Option<bool>,
+    pub history: Option<bool>, // Top up a short file from its rotated copies, as with --history
+    pub progress_frames: Option<bool>, // Keep every redraw of \r-updated lines, as with --progress-frames
+    pub reorder_window: Option<u64>, // Milliseconds, as with --reorder-window
+    pub timestamp_format: Option<String>, // strftime layout for displayed timestamps
+    pub compact_dates: Option<bool>, // Dates only where they change, as with --compact-dates
+    pub line_numbers: Option<LineNumbering>, // view or file, as with --line-numbers
+    pub line_number_width: Option<usize>,
+    pub filter: Option<String>,
+    pub level: Option<String>, // Minimum level shown, as with --level
+    pub script: Option<String>, // Rhai hooks and commands, as with --script; not in a local file
+    pub control: Option<bool>, // Listen on the default control socket, as with --control
+    pub title: Option<bool>,   // Set the terminal title, on by default
+    pub tmux_rename: Option<bool>, // Rename the tmux window too, which turns its automatic-rename off for good
+    pub keymap: HashMap<String, String>, // Action name to key
+    pub formats: HashMap<String, FormatConfig>,
+    pub highlight: Vec<HighlightConfig>,
+    pub remap: Vec<RemapConfig>, // Level corrections, the first matching rule winning
+    pub lookup: Vec<LookupConfig>, // Tables mapping a value in lines to an extra column
+    pub column: Vec<ColumnConfig>, // Columns derived from unstructured lines by a regex
+    pub pair: Vec<PairConfig>,     // Start and end lines whose latency is shown on the end line
+    pub startup_markers: Option<Vec<String>>, // Regexes for lines logged on a (re)start, replacing the built-in ones
+    pub redact: Option<Vec<String>>, // Presets (email, token, card) or regexes masked in the view and exports
+    pub geoip_db: Option<String>,    // MaxMind country or city database for the addresses in lines
+    pub asn_db: Option<String>,      // MaxMind ASN database
+    pub reverse_dns: Option<bool>,   // Look up host names of the addresses in the detail view
+    pub watches: Vec<String>, // Watch specs, as typed into the watch prompt
+    pub paths: Vec<PathConfig>,
+    pub profiles: HashMap<String, ProfileConfig>,
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>, // Files the settings were read from, in the order they were applied
+    #[serde(skip)]
+    pub log_path: Option<String>, // File to follow from the selected profile
+    #[serde(skip)]
+    pub untrusted: Option<PathBuf>, // Local file whose watches, script and control were ignored
+}
+
+/// A `[[paths]]` section: settings for log files matching the `glob`, such
+/// as the format profile and theme for everything under `/var/log/nginx`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathConfig {
+    pub glob: String,
+    pub lines: Option<usize>,
+    pub max_lines: Option<usize>,
+    pub interval: Option<u64>,
+    pub follow: Option<bool>,
+    pub theme: Option<Theme>,
+    pub format: Option<String>,
+}
+
+/// A `[profiles.<name>]` bundle selected with `--profile`: the file to
+/// follow together with its format, filters, highlights and watches, so a
+/// standard view opens with one flag.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileConfig {
+    pub source: Option<String>, // Log file, used when none is given on the command line
+    pub lines: Option<usize>,
+    pub max_lines: Option<usize>,
+    pub interval: Option<u64>,
+    pub follow: Option<bool>,
+    pub theme: Option<Theme>,
+    pub format: Option<String>,
+    pub filter: Option<String>,
+    pub level: Option<String>,
+    #[serde(default)]
+    pub highlight: Vec<HighlightConfig>,
+    #[serde(default)]
+    pub remap: Vec<RemapConfig>,
+    #[serde(default)]
+    pub watches: Vec<String>,
+}
+
+/// A named format profile, selectable with `--format <name>`.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FormatConfig {
+    pub pattern: String,
+    #[serde(default = "default_timestamp_format")]
+    pub timestamp_format: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighlightConfig {
+    pub pattern: String,
+    pub color: String,
+}
+
+/// A `[[remap]]` section: the level for lines matching `pattern` or logged
+/// by `logger`, overriding the one they were parsed with.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemapConfig {
+    pub pattern: Option<String>,
+    pub logger: Option<String>,
+    pub level: String,
+}
+
+/// A `[[lookup]]` section: the value of `field` (or the first group of
+/// `pattern`) in each line, looked up in the CSV or JSON file `table` and
+/// shown in a column headed `name`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LookupConfig {
+    pub name: String,
+    pub table: String,
+    pub field: Option<String>,
+    pub pattern: Option<String>,
+    pub column: Option<String>, // CSV column or JSON key holding the value, the second column by default
+}
+
+/// A `[[column]]` section: a column headed `name` holding what the first
+/// group of `pattern` matched in each line.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnConfig {
+    pub name: String,
+    pub pattern: String,
+}
+
+/// A `[[pair]]` section: lines matching `start` and `end`, paired by the id
+/// the first group of each captures, timed from one to the other.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PairConfig {
+    pub name: String,
+    pub start: String,
+    pub end: String,
+}
+
+fn default_timestamp_format() -> String {
+    "%Y-%m-%d %H:%M:%S".to_string()
+}
+
+/// Name of the directory-local override file.
+pub const LOCAL_FILE: &str = ".tlm.toml";
+
+impl Config {
+    // Layer `other` over these settings: its values win, and its profiles,
+    // key bindings, highlights and watches are added
+    fn merge(&mut self, other: Config) {
+        self.apply(&PathConfig {
+            glob: String::new(),
+            lines: other.lines,
+            max_lines: other.max_lines,
+            interval: other.interval,
+            follow: other.follow,
+            theme: other.theme,
+            format: other.format,
+        });
+        self.colors = other.colors.or(self.colors);
+        self.ascii = other.ascii.or(self.ascii);
+        self.symbols = other.symbols.or(self.symbols);
+        self.scrubber = other.scrubber.or(self.scrubber);
+        self.throttle = other.throttle.or(self.throttle);
+        self.level_symbols.extend(other.level_symbols);
+        self.resume = other.resume.or(self.resume);
+        if other.filter.is_some() {
+            self.filter = other.filter;
+        }
+        if other.level.is_some() {
+            self.level = other.level;
+        }
+        if other.script.is_some() {
+            self.script = other.script;
+        }
+        self.control = other.control.or(self.control);
+        self.title = other.title.or(self.title);
+        self.tmux_rename = other.tmux_rename.or(self.tmux_rename);
+        self.wrap = other.wrap.or(self.wrap);
+        self.history = other.history.or(self.history);
+        self.progress_frames = other.progress_frames.or(self.progress_frames);
+        self.reorder_window = other.reorder_window.or(self.reorder_window);
+        if other.timestamp_format.is_some() {
+            self.timestamp_format = other.timestamp_format;
+        }
+        self.compact_dates = other.compact_dates.or(self.compact_dates);
+        self.line_numbers = other.line_numbers.or(self.line_numbers);
+        self.line_number_width = other.line_number_width.or(self.line_number_width);
+        self.keymap.extend(other.keymap);
+        self.formats.extend(other.formats);
+        self.highlight.extend(other.highlight);
+        // The first matching rule wins, so the more specific file's go first
+        self.remap.splice(0..0, other.remap);
+        self.lookup.extend(other.lookup);
+        self.column.extend(other.column);
+        self.pair.extend(other.pair);
+        if other.startup_markers.is_some() {
+            self.startup_markers = other.startup_markers;
+        }
+        if other.redact.is_some() {
+            self.redact = other.redact;
+        }
+        if other.geoip_db.is_some() {
+            self.geoip_db = other.geoip_db;
+        }
+        if other.asn_db.is_some() {
+            self.asn_db = other.asn_db;
+        }
+        if other.reverse_dns.is_some() {
+            self.reverse_dns = other.reverse_dns;
+        }
+        self.watches.extend(other.watches);
+        self.sources.extend(other.sources);
+        self.untrusted = other.untrusted.or(self.untrusted.take());
+    }
+
+    fn apply(&mut self, section: &PathConfig) {
+        self.lines = section.lines.or(self.lines);
+        self.max_lines = section.max_lines.or(self.max_lines);
+        self.interval = section.interval.or(self.interval);
+        self.follow = section.follow.or(self.follow);
+        self.theme = section.theme.or(self.theme);
+        if section.format.is_some() {
+            self.format = section.format.clone();
+        }
+    }
+
+    // Layer a profile over everything read from the config files
+    fn apply_profile(&mut self, profile: ProfileConfig) {
+        self.apply(&PathConfig {
+            glob: String::new(),
+            lines: profile.lines,
+            max_lines: profile.max_lines,
+            interval: profile.interval,
+            follow: profile.follow,
+            theme: profile.theme,
+            format: profile.format,
+        });
+        if profile.filter.is_some() {
+            self.filter = profile.filter;
+        }
+        if profile.level.is_some() {
+            self.level = profile.level;
+        }
+        self.highlight.extend(profile.highlight);
+        self.remap.splice(0..0, profile.remap);
+        self.watches.extend(profile.watches);
+    }
+
+    // Apply the `[[paths]]` sections whose glob matches `log_path`, later ones winning
+    fn apply_paths(&mut self, log_path: &Path) -> Result<(), String> {
+        for section in std::mem::take(&mut self.paths) {
+            let pattern = Pattern::new(&section.glob)
+                .map_err(|e| format!("Invalid glob {:?} in config: {}", section.glob, e))?;
+            if pattern.matches_path(log_path) {
+                self.apply(&section);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `$XDG_CONFIG_HOME/tui-log-monitor/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("tui-log-monitor").join("config.toml"))
+}
+
+/// Load the settings for following `log_path`: the main config from
+/// `explicit` (`--config`, which must exist) or the default path, then its
+/// `[[paths]]` sections matching the log file, then the nearest `.tlm.toml`
+/// above the log file (or else the working directory), and finally the
+/// `[profiles]` entry named by `profile`, whose source stands in for a
+/// missing `log_path`.
+pub fn load(explicit: Option<&str>, log_path: Option<&str>, profile: Option<&str>) -> Result<Config, String> {
+    let mut config = match explicit {
+        Some(path) => read(Path::new(path))?.ok_or_else(|| format!("Cannot read config {}: not found", path))?,
+        None => match default_path() {
+            Some(path) => read(&path)?.unwrap_or_default(),
+            None => Config::default(),
+        },
+    };
+    let profile = match profile {
+        Some(name) => Some(config.profiles.remove(name).ok_or_else(|| format!("Unknown profile: {}", name))?),
+        None => None,
+    };
+    config.log_path = log_path.map(String::from).or_else(|| profile.as_ref().and_then(|profile| profile.source.clone()));
+
+    let log_path = config.log_path.as_deref().unwrap_or_default();
+    let log_path = std::path::absolute(log_path).unwrap_or_else(|_| PathBuf::from(log_path));
+    config.apply_paths(&log_path)?;
+
+    let local = log_path.parent()
+        .and_then(find_local)
+        .or_else(|| std::env::current_dir().ok().as_deref().and_then(find_local));
+    let local = match local {
+        Some(path) => read(&path)?,
+        None => None,
+    };
+    if let Some(mut local) = local {
+        local.apply_paths(&log_path)?;
+        if let Some(path) = local.sources.first().filter(|path| !trusted(path)) {
+            // Anyone able to write the file could otherwise run commands as us
+            local.untrusted = Some(path.clone());
+            local.watches.clear();
+            local.script = None;
+            local.control = None;
+        }
+        // A script runs whatever it likes, so it has to be asked for explicitly
+        if local.script.is_some() {
+            return Err(format!(
+                "Cannot set script in {}: use --script or the main config",
+                local.sources[0].display()
+            ));
+        }
+        config.merge(local);
+    }
+    if let Some(profile) = profile {
+        config.apply_profile(profile);
+    }
+    Ok(config)
+}
+
+// The nearest `.tlm.toml` in `dir` or one of its ancestors
+fn find_local(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().map(|dir| dir.join(LOCAL_FILE)).find(|path| path.is_file())
+}
+
+// Whether a local file may run things, through watch actions, a script or
+// the control socket: only if it is ours and nobody else can write to it
+#[cfg(unix)]
+fn trusted(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let uid = unsafe { libc::geteuid() };
+    fs::metadata(path).is_ok_and(|metadata| metadata.uid() == uid && metadata.mode() & 0o022 == 0)
+}
+
+#[cfg(not(unix))]
+fn trusted(_path: &Path) -> bool {
+    true
+}
+
+// Parse one config file; `None` if it doesn't exist
+fn read(path: &Path) -> Result<Option<Config>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Cannot read config {}: {}", path.display(), e)),
+    };
+    let mut config: Config = toml::from_str(&text).map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+    config.sources.push(path.to_path_buf());
+    Ok(Some(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remap_patterns(config: &Config) -> Vec<&str> {
+        config.remap.iter().filter_map(|rule| rule.pattern.as_deref()).collect()
+    }
+
+    #[test]
+    fn remap_rules_of_more_specific_files_come_first() {
+        let mut config: Config = toml::from_str("[[remap]]\npattern = 'main'\nlevel = 'info'").unwrap();
+        let local: Config = toml::from_str("[[remap]]\npattern = 'local'\nlevel = 'error'").unwrap();
+        config.merge(local);
+        let profile: ProfileConfig = toml::from_str("[[remap]]\npattern = 'profile'\nlevel = 'warning'").unwrap();
+        config.apply_profile(profile);
+        assert_eq!(remap_patterns(&config), ["profile", "local", "main"]);
+    }
+}
@@ -0,0 +1,115 @@
+//! Driving a running [`App`](crate::App) from other code.
+//!
+//! [`App::handle`](crate::App::handle) returns an [`AppHandle`] that can be
+//! cloned and moved to other threads. Its requests are queued and applied by
+//! the app between frames, as key presses are, so an embedding application
+//! or an IPC layer can steer the viewer without going through the terminal.
+//!
+//! The queue is bounded: once the app falls [`MAX_QUEUED_REQUESTS`] behind,
+//! pushed lines are dropped and counted rather than piling up in memory,
+//! and other requests wait for room.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, RecvTimeoutError, Sender, TrySendError};
+
+/// Requests waiting for the app to apply them; past this, pushed lines are
+/// dropped and other requests block until the app catches up.
+pub const MAX_QUEUED_REQUESTS: usize = 10_000;
+
+/// Something an `AppHandle` asks the app to do.
+pub enum Request {
+    PushLine(String),   // Add a line to the main source, as if it had just been read
+    SetFilter(String),  // Replace the filter text, empty to clear it
+    ScrollTo(usize),    // Show this row of the filtered view at the top, leaving follow mode
+    Follow,             // Scroll to the newest lines and keep following them
+    Command(String),    // Run a `:` command (without the colon), its result shown in the status bar
+    RemoteCommand(String), // A `Command` from the control socket, which may not start a shell command
+    Suspend,            // Give the terminal back and stop, as Ctrl+Z does, until resumed with `fg`
+    Quit,
+    Answered(Box<Request>, Sender<Result<String, String>>), // Apply the request and send back how it went
+}
+
+/// A cloneable, thread-safe remote control for an `App`.
+#[derive(Clone)]
+pub struct AppHandle {
+    pub(crate) tx: Sender<Request>,
+    pub(crate) summary: Arc<Mutex<String>>, // Kept up to date by the app between frames
+    pub(crate) dropped: Arc<AtomicUsize>,   // Pushed lines dropped on a full queue, reported by the app
+}
+
+impl AppHandle {
+    /// Queue a request, returning `false` once the app is gone. A pushed
+    /// line is dropped (and `false` returned) while the queue is full;
+    /// anything else waits for room.
+    pub fn send(&self, request: Request) -> bool {
+        if !matches!(request, Request::PushLine(_)) {
+            return self.tx.send(request).is_ok();
+        }
+        match self.tx.try_send(request) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+
+    /// Apply `request` and return how it went: the message a `:` command
+    /// shows in the status bar, or its error. Waits at most `timeout` for
+    /// the app, which may be busy or stopped with Ctrl+Z.
+    pub fn call(&self, request: Request, timeout: Duration) -> Result<String, String> {
+        let (tx, rx) = bounded(1);
+        if !self.send(Request::Answered(Box::new(request), tx)) {
+            return Err("the monitor is exiting".to_string());
+        }
+        match rx.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(format!("no answer from the monitor within {}s", timeout.as_secs())),
+            Err(RecvTimeoutError::Disconnected) => Err("the monitor is exiting".to_string()),
+        }
+    }
+
+    /// Add a line to the main source; `false` if it was dropped because
+    /// the app is too far behind, or the app is gone.
+    pub fn push_line(&self, line: impl Into<String>) -> bool {
+        self.send(Request::PushLine(line.into()))
+    }
+
+    pub fn set_filter(&self, text: impl Into<String>) -> bool {
+        self.send(Request::SetFilter(text.into()))
+    }
+
+    /// Show `row` of the filtered view (counting from 0) at the top.
+    pub fn scroll_to(&self, row: usize) -> bool {
+        self.send(Request::ScrollTo(row))
+    }
+
+    pub fn follow(&self) -> bool {
+        self.send(Request::Follow)
+    }
+
+    /// Run a `:` command such as `open /var/log/new.log`, as if typed.
+    pub fn run_command(&self, command: impl Into<String>) -> bool {
+        self.send(Request::Command(command.into()))
+    }
+
+    /// Ask the app to stop in the background, as pressing Ctrl+Z does.
+    pub fn suspend(&self) -> bool {
+        self.send(Request::Suspend)
+    }
+
+    /// A one-line summary of the app: what it follows and how many errors
+    /// and warnings it has seen, such as `app.log E3 W12`.
+    pub fn summary(&self) -> String {
+        self.summary.lock().map(|summary| summary.clone()).unwrap_or_default()
+    }
+
+    /// Ask the app to exit, as pressing q does.
+    pub fn quit(&self) -> bool {
+        self.send(Request::Quit)
+    }
+}
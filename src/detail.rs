@@ -0,0 +1,134 @@
+use std::ops::Range;
+
+/// Lines longer than this many bytes are cut short in the log view, to what
+/// fits with a marker saying how much more there is; Enter shows them whole.
+pub const LONG_LINE_BYTES: usize = 512;
+
+/// Rows a long line may take up in the log view when lines are wrapped.
+pub const LONG_LINE_ROWS: usize = 3;
+
+/// One line shown whole in the detail view, wrapped to the width of the
+/// screen and paged through, for lines too long to read in the log view.
+/// It has a search of its own, apart from the filter of the log view.
+pub struct Detail {
+    pub seq: u64,                   // Sequence number of the line shown
+    pub top: usize,                 // First wrapped row shown
+    pub rows: usize,                // Wrapped rows of the line, as last drawn
+    pub height: usize,              // Rows the view had room for, as last drawn
+    pub width: usize,               // Characters in a wrapped row, as last drawn
+    pub query: String,              // Searched for in the line, ignoring case
+    pub typing: bool,               // The search prompt takes the keys
+    pub matches: Vec<Range<usize>>, // Character ranges the query matched
+    pub current: usize,             // Match last moved to
+    text: String,                   // The whole line, put together once
+    row_starts: Vec<usize>,         // Byte offset in `text` of each wrapped row, for `width`
+}
+
+impl Detail {
+    pub fn new(seq: u64, text: String) -> Self {
+        Detail {
+            seq,
+            top: 0,
+            rows: 0,
+            height: 0,
+            width: 0,
+            query: String::new(),
+            typing: false,
+            matches: Vec::new(),
+            current: 0,
+            text,
+            row_starts: Vec::new(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Lay the line out in rows of `width` characters with `height` of them
+    /// shown, wrapping it again only when the width changed.
+    pub fn wrap(&mut self, width: usize, height: usize) {
+        let width = width.max(1);
+        if width != self.width || self.row_starts.is_empty() {
+            self.row_starts = self.text.char_indices().step_by(width).map(|(at, _)| at).collect();
+            self.width = width;
+        }
+        self.rows = self.row_starts.len();
+        self.height = height;
+        self.top = self.top.min(self.rows.saturating_sub(height));
+    }
+
+    /// The text of wrapped row `row`, as last laid out by `wrap`.
+    pub fn row(&self, row: usize) -> &str {
+        let end = self.row_starts.get(row + 1).copied().unwrap_or(self.text.len());
+        self.row_starts.get(row).map_or("", |&start| &self.text[start..end])
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        let last = self.rows.saturating_sub(self.height);
+        self.top = self.top.saturating_add_signed(delta).min(last);
+    }
+
+    /// Find the query in the line again, moving to the first match from the
+    /// top of the view on, as each key typed into the prompt does.
+    pub fn search(&mut self) {
+        self.matches.clear();
+        let query: Vec<char> = self.query.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+        if query.is_empty() {
+            return;
+        }
+        // One lowercase character per character, so ranges line up with the wrapped rows
+        let chars: Vec<char> = self.text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+        let mut start = 0;
+        while start + query.len() <= chars.len() {
+            if chars[start..start + query.len()] == query[..] {
+                self.matches.push(start..start + query.len());
+                start += query.len();
+            } else {
+                start += 1;
+            }
+        }
+        let first_shown = self.top * self.width.max(1);
+        self.current = self.matches.iter().position(|found| found.start >= first_shown).unwrap_or(0);
+        self.reveal();
+    }
+
+    /// Move to the next match, or the previous one, going round at the ends.
+    pub fn step(&mut self, forward: bool) {
+        let count = self.matches.len();
+        if count == 0 {
+            return;
+        }
+        self.current = if forward { (self.current + 1) % count } else { (self.current + count - 1) % count };
+        self.reveal();
+    }
+
+    // Scroll the current match into view, if it isn't
+    fn reveal(&mut self) {
+        let Some(found) = self.matches.get(self.current) else {
+            return;
+        };
+        let row = found.start / self.width.max(1);
+        if row < self.top || row >= self.top + self.height {
+            self.top = row.saturating_sub(self.height / 2);
+        }
+    }
+}
+
+/// The first `limit` characters of `text` (columns, not bytes), and the marker for what was left
+/// off (`…[+182KB]`) if anything was.
+pub fn preview(text: &str, limit: usize) -> (&str, Option<String>) {
+    match text.char_indices().nth(limit) {
+        Some((end, _)) => (&text[..end], Some(format!("…[+{}]", format_size(text.len() - end)))),
+        None => (text, None),
+    }
+}
+
+/// A byte count the way the markers and the detail view show it: 340B, 182KB, 1.4MB.
+pub fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{}B", bytes),
+        1024..=1048575 => format!("{}KB", bytes / 1024),
+        _ => format!("{:.1}MB", bytes as f64 / 1048576.0),
+    }
+}
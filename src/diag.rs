@@ -0,0 +1,132 @@
+//! The monitor's own debug log, shown with F12.
+//!
+//! Anything that quietly changes what reaches the screen is recorded here:
+//! sources dropping, reconnecting and ending, lines that didn't match their
+//! format, lines evicted or dropped by the script, and frames over budget.
+//! The log is global so the ingest threads can write to it as well; counts
+//! that grow per line are kept in counters and turned into entries at most
+//! once a second.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use ratatui::style::Color;
+
+// Entries kept before the oldest are dropped
+const MAX_ENTRIES: usize = 1000;
+
+// Shortest time between two reports of the line counters
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Lines read as plain text because they didn't match their format profile.
+pub(crate) static UNMATCHED_LINES: AtomicU64 = AtomicU64::new(0);
+
+/// Lines evicted from the buffer to stay within `max_lines`.
+pub(crate) static EVICTED_LINES: AtomicU64 = AtomicU64::new(0);
+
+/// Lines the script's `on_line` hook dropped.
+pub(crate) static HOOK_DROPPED_LINES: AtomicU64 = AtomicU64::new(0);
+
+static LOG: Mutex<Log> = Mutex::new(Log {
+    entries: VecDeque::new(),
+    reported: [0; 3],
+    reported_at: None,
+});
+
+struct Log {
+    entries: VecDeque<Entry>,
+    reported: [u64; 3], // Counter values at the last report
+    reported_at: Option<Instant>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Topic {
+    Source,
+    Parse,
+    Drop,
+    Frame,
+}
+
+impl Topic {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Topic::Source => "source",
+            Topic::Parse => "parse",
+            Topic::Drop => "drop",
+            Topic::Frame => "frame",
+        }
+    }
+
+    pub(crate) fn color(self) -> Color {
+        match self {
+            Topic::Source => Color::Cyan,
+            Topic::Parse => Color::Magenta,
+            Topic::Drop => Color::Yellow,
+            Topic::Frame => Color::Blue,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Entry {
+    pub time: DateTime<Local>,
+    pub topic: Topic,
+    pub message: String,
+    pub repeats: u32, // Times the same message came in a row, shown once
+}
+
+/// Add an entry; one repeating the last entry only bumps its count.
+pub(crate) fn record(topic: Topic, message: impl Into<String>) {
+    let message = message.into();
+    let mut log = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(last) = log.entries.back_mut().filter(|last| last.topic == topic && last.message == message) {
+        last.repeats += 1;
+        last.time = Local::now();
+        return;
+    }
+    if log.entries.len() == MAX_ENTRIES {
+        log.entries.pop_front();
+    }
+    log.entries.push_back(Entry { time: Local::now(), topic, message, repeats: 1 });
+}
+
+/// Turn what the line counters gained since the last report into entries,
+/// unless the last report was under a second ago.
+pub(crate) fn report_counts() {
+    let mut log = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if log.reported_at.is_some_and(|at| at.elapsed() < REPORT_INTERVAL) {
+        return;
+    }
+    log.reported_at = Some(Instant::now());
+    let counters = [
+        (&UNMATCHED_LINES, Topic::Parse, "didn't match their format profile"),
+        (&EVICTED_LINES, Topic::Drop, "evicted from the buffer to stay within max_lines"),
+        (&HOOK_DROPPED_LINES, Topic::Drop, "dropped by the script's on_line hook"),
+    ];
+    let mut messages = Vec::new();
+    for (i, (counter, topic, what)) in counters.into_iter().enumerate() {
+        let count = counter.load(Ordering::Relaxed);
+        let new = count - log.reported[i];
+        log.reported[i] = count;
+        if new > 0 {
+            messages.push((topic, format!("{} lines {} ({} in all)", new, what, count)));
+        }
+    }
+    drop(log);
+    for (topic, message) in messages {
+        record(topic, message);
+    }
+}
+
+// Entries kept, for scrolling without copying them
+pub(crate) fn len() -> usize {
+    LOG.lock().unwrap_or_else(|e| e.into_inner()).entries.len()
+}
+
+/// A copy of the entries, oldest first.
+pub(crate) fn entries() -> Vec<Entry> {
+    LOG.lock().unwrap_or_else(|e| e.into_inner()).entries.iter().cloned().collect()
+}
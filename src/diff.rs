@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+// How far a template's share of its side has to move to count as changed
+const RATE_CHANGE: f64 = 2.0;
+
+// Occurrences needed on the busier side before a rate change is reported,
+// so one-off messages don't show up as doubling
+const MIN_CHANGED_COUNT: usize = 3;
+
+/// How a message template's frequency differs between the two sides.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Change {
+    New,       // Only on the right
+    Gone,      // Only on the left
+    Rate(f64), // On both, its share of the lines multiplied by this much
+}
+
+pub struct DiffRow {
+    pub change: Change,
+    pub counts: [usize; 2],
+    pub template: String,
+}
+
+/// Message templates compared between two selections with `:diff`: what is
+/// new on the right, what disappeared from the left and what became much
+/// more or less frequent.
+pub struct Diff {
+    pub labels: [String; 2],
+    pub totals: [usize; 2], // Lines on each side
+    pub rows: Vec<DiffRow>, // New, then gone, then rate changes, biggest first
+    pub unchanged: usize,   // Templates on both sides at about the same rate
+    pub top: usize,         // First row shown
+}
+
+impl Diff {
+    pub fn new(labels: [String; 2], left: &[&str], right: &[&str]) -> Self {
+        let mut counts: HashMap<String, [usize; 2]> = HashMap::new();
+        for (side, messages) in [left, right].into_iter().enumerate() {
+            for message in messages {
+                counts.entry(template(message)).or_default()[side] += 1;
+            }
+        }
+        let totals = [left.len(), right.len()];
+        let mut rows = Vec::new();
+        let mut unchanged = 0;
+        for (template, counts) in counts {
+            let change = match counts {
+                [0, _] => Change::New,
+                [_, 0] => Change::Gone,
+                [before, after] => {
+                    let ratio = (after as f64 / totals[1] as f64) / (before as f64 / totals[0] as f64);
+                    let moved = ratio >= RATE_CHANGE || ratio <= 1.0 / RATE_CHANGE;
+                    if !moved || before.max(after) < MIN_CHANGED_COUNT {
+                        unchanged += 1;
+                        continue;
+                    }
+                    Change::Rate(ratio)
+                }
+            };
+            rows.push(DiffRow { change, counts, template });
+        }
+        rows.sort_by(|a, b| {
+            let rank = |row: &DiffRow| match row.change {
+                Change::New => (0, -(row.counts[1] as f64)),
+                Change::Gone => (1, -(row.counts[0] as f64)),
+                Change::Rate(ratio) => (2, -ratio.ln().abs()),
+            };
+            let (a, b) = (rank(a), rank(b));
+            a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+        Diff {
+            labels,
+            totals,
+            rows,
+            unchanged,
+            top: 0,
+        }
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.top = self.top.saturating_add_signed(delta).min(self.rows.len().saturating_sub(1));
+    }
+}
+
+/// A message with its variable parts masked, so lines logged by the same
+/// statement compare equal: digit runs become `#`, long hex ids `<hex>` and
+/// quoted strings `"…"`.
+pub fn template(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut chars = message.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        // A quote opening a word, not an apostrophe inside one
+        let opens = (c == '"' || c == '\'')
+            && !message[..start].chars().next_back().is_some_and(|prev| prev.is_alphanumeric());
+        if let Some(len) = message[start + 1..].find(c).filter(|_| opens) {
+            out.push(c);
+            out.push('…');
+            out.push(c);
+            while chars.peek().is_some_and(|&(i, _)| i <= start + 1 + len) {
+                chars.next();
+            }
+            continue;
+        }
+        if c.is_ascii_alphanumeric() {
+            // Take the whole word to tell ids from ordinary words
+            let mut end = start + 1;
+            while let Some(&(i, next)) = chars.peek() {
+                if !next.is_ascii_alphanumeric() {
+                    break;
+                }
+                end = i + 1;
+                chars.next();
+            }
+            push_word(&mut out, &message[start..end]);
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+// A word as it appears in a template
+fn push_word(out: &mut String, word: &str) {
+    let has_digit = word.bytes().any(|b| b.is_ascii_digit());
+    if has_digit && word.len() >= 8 && word.bytes().all(|b| b.is_ascii_hexdigit()) {
+        out.push_str("<hex>");
+        return;
+    }
+    let mut in_digits = false;
+    for c in word.chars() {
+        if c.is_ascii_digit() {
+            if !in_digits {
+                out.push('#');
+            }
+            in_digits = true;
+        } else {
+            out.push(c);
+            in_digits = false;
+        }
+    }
+}
+
+/// Parse a `:diff` time range, `HH:MM-HH:MM` (seconds optional) or with
+/// dates as `YYYY-MM-DDTHH:MM-YYYY-MM-DDTHH:MM`. Times without a date fall
+/// on `day`, and an end before the start runs into the next day.
+pub fn parse_time_range(text: &str, day: NaiveDate) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let parse = |part: &str| -> Option<NaiveDateTime> {
+        ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"].iter()
+            .find_map(|layout| NaiveDateTime::parse_from_str(part, layout).ok())
+            .or_else(|| {
+                ["%H:%M:%S", "%H:%M"].iter()
+                    .find_map(|layout| NaiveTime::parse_from_str(part, layout).ok())
+                    .map(|time| day.and_time(time))
+            })
+    };
+    // Dates contain dashes too, so try every dash as the separator
+    let (start, mut end) = text.match_indices('-')
+        .find_map(|(i, _)| Some((parse(&text[..i])?, parse(&text[i + 1..])?)))?;
+    if end <= start {
+        end += Duration::days(1);
+    }
+    Some((start, end))
+}
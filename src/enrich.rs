@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crossbeam_channel::{never, unbounded, Receiver, Sender};
+use maxminddb::{geoip2, Reader};
+use regex::Regex;
+
+// IPv4 and IPv6 address candidates; only those that parse count
+const IP_PATTERN: &str = r"\b(?:\d{1,3}\.){3}\d{1,3}\b|(?:[0-9A-Fa-f]{0,4}:){2,7}[0-9A-Fa-f]{0,4}";
+
+/// What reverse DNS said about an address.
+#[derive(Clone)]
+pub enum HostName {
+    Pending,
+    Name(String),
+    Unknown, // No name, or the lookup failed
+}
+
+/// Enrichment for the IP addresses in lines: the country and autonomous
+/// system from local MaxMind databases (`geoip_db` and `asn_db` in the
+/// config), and host names by reverse DNS (`reverse_dns`), looked up in the
+/// background and remembered.
+pub struct Enricher {
+    ip: Regex,
+    country_db: Option<Reader<Vec<u8>>>,
+    asn_db: Option<Reader<Vec<u8>>>,
+    hosts: Arc<Mutex<HashMap<IpAddr, HostName>>>,
+    lookups: Option<Sender<IpAddr>>, // To the reverse DNS thread, when it runs
+    pub resolved: Receiver<()>,      // Signalled each time a host name comes in
+}
+
+impl Default for Enricher {
+    fn default() -> Self {
+        Enricher {
+            ip: Regex::new(IP_PATTERN).expect("valid IP pattern"),
+            country_db: None,
+            asn_db: None,
+            hosts: Arc::default(),
+            lookups: None,
+            resolved: never(),
+        }
+    }
+}
+
+impl Enricher {
+    pub fn new(country_db: Option<&str>, asn_db: Option<&str>, reverse_dns: bool) -> Result<Self, String> {
+        let open = |path: &str| Reader::open_readfile(path).map_err(|e| format!("Cannot open {}: {}", path, e));
+        let mut enricher = Enricher {
+            country_db: country_db.map(open).transpose()?,
+            asn_db: asn_db.map(open).transpose()?,
+            ..Enricher::default()
+        };
+        if reverse_dns {
+            let (tx, rx) = unbounded::<IpAddr>();
+            let (resolved_tx, resolved_rx) = unbounded();
+            let hosts = enricher.hosts.clone();
+            // Ends once the enricher, holding the sender, is dropped
+            thread::spawn(move || {
+                for ip in rx {
+                    let name = match dns_lookup::lookup_addr(&ip) {
+                        Ok(name) if name != ip.to_string() => HostName::Name(name),
+                        _ => HostName::Unknown,
+                    };
+                    hosts.lock().unwrap_or_else(|e| e.into_inner()).insert(ip, name);
+                    let _ = resolved_tx.send(());
+                }
+            });
+            enricher.lookups = Some(tx);
+            enricher.resolved = resolved_rx;
+        }
+        Ok(enricher)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.has_databases() || self.lookups.is_some()
+    }
+
+    pub fn has_databases(&self) -> bool {
+        self.country_db.is_some() || self.asn_db.is_some()
+    }
+
+    /// The IP addresses in `text`, in order, each once.
+    pub fn addresses(&self, text: &str) -> Vec<IpAddr> {
+        let mut found = Vec::new();
+        for ip in self.ip.find_iter(text).filter_map(|m| m.as_str().parse::<IpAddr>().ok()) {
+            if !found.contains(&ip) {
+                found.push(ip);
+            }
+        }
+        found
+    }
+
+    /// The ISO code of the country `ip` is in, such as `DE`.
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        let record: geoip2::Country = self.country_db.as_ref()?.lookup(ip).ok()?;
+        record.country?.iso_code.map(str::to_string)
+    }
+
+    /// The number and organization of the autonomous system `ip` is in.
+    pub fn asn(&self, ip: IpAddr) -> Option<(u32, String)> {
+        let record: geoip2::Asn = self.asn_db.as_ref()?.lookup(ip).ok()?;
+        Some((record.autonomous_system_number?, record.autonomous_system_organization.unwrap_or_default().to_string()))
+    }
+
+    /// The host name of `ip`, starting a lookup if it hasn't been asked for.
+    pub fn host(&self, ip: IpAddr) -> Option<HostName> {
+        let lookups = self.lookups.as_ref()?;
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let name = hosts.entry(ip).or_insert_with(|| {
+            let _ = lookups.send(ip);
+            HostName::Pending
+        });
+        Some(name.clone())
+    }
+
+    /// Whether an address in `text` is in the country (`de`), or the
+    /// autonomous system (`as13335`, `13335` or, past two letters, part of
+    /// its organization's name), `wanted` names, given in lowercase.
+    pub fn matches(&self, text: &str, wanted: &str) -> bool {
+        let number = wanted.trim_start_matches("as");
+        self.addresses(text).into_iter().any(|ip| {
+            self.country(ip).is_some_and(|country| country.to_lowercase() == wanted)
+                || self.asn(ip).is_some_and(|(asn, org)| {
+                    asn.to_string() == number || (wanted.len() > 2 && org.to_lowercase().contains(wanted))
+                })
+        })
+    }
+}
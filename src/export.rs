@@ -0,0 +1,214 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use ratatui::style::Color;
+
+use crate::notes::NOTE_ICON;
+use crate::ui::log_line_spans;
+use crate::{App, LogLevel, LogLine};
+
+// Output formats for exports, picked from the file extension
+enum ExportFormat {
+    Text,
+    Ndjson,
+    Html,
+}
+
+impl ExportFormat {
+    fn from_path(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match extension.as_deref() {
+            Some("ndjson") | Some("jsonl") | Some("json") => ExportFormat::Ndjson,
+            Some("html") | Some("htm") => ExportFormat::Html,
+            _ => ExportFormat::Text,
+        }
+    }
+}
+
+/// Write the current filtered view to a file, in a format chosen by extension:
+/// `.ndjson`/`.jsonl`/`.json` write one JSON object per line with the parsed
+/// fields, `.html`/`.htm` write a standalone page keeping level colors, and
+/// anything else writes plain text exactly as the log view shows it. Notes on
+/// lines go along in each format.
+///
+/// Returns the number of lines written.
+pub fn write_filtered(app: &App, path: &str) -> io::Result<usize> {
+    let mut out = BufWriter::new(File::create(path)?);
+    match ExportFormat::from_path(path) {
+        ExportFormat::Text => write_text(app, &mut out)?,
+        ExportFormat::Ndjson => write_ndjson(app, &mut out)?,
+        ExportFormat::Html => write_html(app, path, &mut out)?,
+    }
+    out.flush()?;
+    Ok(app.filtered_logs.len())
+}
+
+// Plain text, with timestamps and line numbers only if they are currently
+// toggled on, and notes on lines under them
+fn write_text(app: &App, out: &mut impl Write) -> io::Result<()> {
+    for (i, &idx) in app.filtered_logs.iter().enumerate() {
+        let line: String = log_line_spans(app, i, &app.log_lines[idx], None)
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        writeln!(out, "{}", line)?;
+        if let Some(note) = app.note_for(app.log_lines[idx].seq) {
+            writeln!(out, "    {} {}", NOTE_ICON, note.text)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_ndjson(app: &App, out: &mut impl Write) -> io::Result<()> {
+    for &idx in &app.filtered_logs {
+        let log = &app.log_lines[idx];
+        let source = app.source_of(log).map_or(&app.log_path, |source| &source.target);
+        let mut record = json_record(log, source);
+        if app.has_extra_columns() {
+            let content = log.content();
+            for column in app.extra_columns() {
+                if let Some(value) = column.value(&content) {
+                    record[column.name()] = value.into();
+                }
+            }
+        }
+        if let Some((_, elapsed)) = app.latencies.get(&log.seq) {
+            record["latency_ms"] = (elapsed.as_secs_f64() * 1000.0).into();
+        }
+        if let Some(note) = app.note_for(log.seq) {
+            record["note"] = note.text.clone().into();
+        }
+        writeln!(out, "{}", record)?;
+    }
+    Ok(())
+}
+
+/// A line as one NDJSON record with its parsed fields.
+pub fn json_record(log: &LogLine, source: &str) -> serde_json::Value {
+    serde_json::json!({
+        "seq": log.seq,
+        "timestamp": log.time.map(|t| t.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+        "level": log.level.as_str(),
+        "source": source,
+        "message": log.content(),
+    })
+}
+
+fn write_html(app: &App, path: &str, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html><head><meta charset=\"utf-8\"><title>{}</title>", html_escape(path))?;
+    writeln!(out, "<style>")?;
+    writeln!(out, "body {{ background: #1e1e1e; color: #d4d4d4; font-family: monospace; }}")?;
+    writeln!(out, ".meta {{ color: #808080; }}")?;
+    writeln!(out, ".note {{ color: #e5e510; font-style: italic; }}")?;
+    for level in [LogLevel::Info, LogLevel::Debug, LogLevel::Trace, LogLevel::Warning, LogLevel::Error, LogLevel::Fatal, LogLevel::Unknown] {
+        writeln!(out, ".{} {{ color: {}; }}", level.as_str().to_lowercase(), css_color(level.color()))?;
+    }
+    writeln!(out, "</style></head><body>")?;
+    writeln!(
+        out,
+        "<h3>{} &mdash; {} of {} lines{}</h3>",
+        html_escape(&app.log_path),
+        app.filtered_logs.len(),
+        app.log_lines.len(),
+        if app.filter_text.is_empty() { String::new() } else { format!(", filter: {}", html_escape(&app.filter_text)) }
+    )?;
+    writeln!(out, "<pre>")?;
+    for (i, &idx) in app.filtered_logs.iter().enumerate() {
+        let log = &app.log_lines[idx];
+        let class = log.level.as_str().to_lowercase();
+        let mut meta = String::new();
+        if app.show_line_numbers {
+            meta.push_str(&format!("{:<width$} ", app.line_number(i, log), width = app.line_number_width()));
+        }
+        if app.show_timestamps && !log.timestamp().is_empty() {
+            meta.push_str(&format!("{} ", app.display_timestamp(i, log)));
+        }
+        writeln!(
+            out,
+            "<span class=\"meta\">{}</span><span class=\"{}\"><b>[{}]</b> {}</span>",
+            html_escape(&meta),
+            class,
+            log.level.as_str(),
+            html_escape(&log.content())
+        )?;
+        if let Some(note) = app.note_for(log.seq) {
+            writeln!(out, "<span class=\"note\">    {} {}</span>", NOTE_ICON, html_escape(&note.text))?;
+        }
+    }
+    writeln!(out, "</pre></body></html>")?;
+    Ok(())
+}
+
+// Lines shown before and after each line of a timeline
+const TIMELINE_CONTEXT: usize = 2;
+
+/// Write a Markdown incident timeline, ready to paste into a postmortem: a
+/// section for each note and for the highlighted line, in the order of their
+/// lines, with the timestamp, the note and the lines around it.
+///
+/// Returns the number of entries written.
+pub fn write_timeline(app: &App, path: &str) -> io::Result<usize> {
+    // (seq while buffered, time, source, line text, note)
+    let mut entries: Vec<_> = app.notes.iter()
+        .map(|note| (note.seq, note.time, note.source.as_str(), note.line.clone(), Some(note.text.as_str())))
+        .collect();
+    let marked = app.log_lines.iter().filter(|log| log.highlighted && app.note_for(log.seq).is_none());
+    entries.extend(marked.map(|log| {
+        let source = app.source_of(log).map_or(app.log_path.as_str(), |source| source.name.as_str());
+        (Some(log.seq), log.time, source, log.content(), None)
+    }));
+    entries.sort_by_key(|entry| entry.0); // Lines gone from the buffer are the oldest
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "# Incident timeline: {}", app.log_path)?;
+    writeln!(out)?;
+    writeln!(out, "Written {} from the notes taken while reading it.", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    for (seq, time, source, line, note) in &entries {
+        let time = time.map_or_else(|| "(no timestamp)".to_string(), |time| time.format("%Y-%m-%d %H:%M:%S").to_string());
+        writeln!(out)?;
+        writeln!(out, "## {} — {}", time, note.unwrap_or("Marked line"))?;
+        writeln!(out)?;
+        let idx = seq.and_then(|seq| app.log_lines.binary_search_by_key(&seq, |log| log.seq).ok());
+        match idx {
+            Some(idx) => writeln!(out, "*{}, line {}*", source, app.log_lines[idx].line)?,
+            None => writeln!(out, "*{}, no longer buffered*", source)?,
+        }
+        writeln!(out)?;
+        writeln!(out, "```")?;
+        let Some(idx) = idx else {
+            writeln!(out, "> {}", line)?;
+            writeln!(out, "```")?;
+            continue;
+        };
+        let end = (idx + TIMELINE_CONTEXT + 1).min(app.log_lines.len());
+        for i in idx.saturating_sub(TIMELINE_CONTEXT)..end {
+            writeln!(out, "{} {}", if i == idx { ">" } else { " " }, app.log_lines[i].content())?;
+        }
+        writeln!(out, "```")?;
+    }
+    out.flush()?;
+    Ok(entries.len())
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// CSS equivalent of the terminal colors used for log levels
+fn css_color(color: Color) -> &'static str {
+    match color {
+        Color::Red => "#f14c4c",
+        Color::Yellow => "#e5e510",
+        Color::Green => "#23d18b",
+        Color::Cyan => "#29b8db",
+        Color::Gray => "#a0a0a0",
+        _ => "#d4d4d4",
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+use chrono::NaiveDateTime;
+use rayon::prelude::*;
+
+use crate::columns::ColumnFilter;
+use crate::enrich::Enricher;
+use crate::source::Source;
+use crate::tokens;
+use crate::{LogLevel, LogLine};
+
+// Below this many candidate lines the filter runs on the UI thread directly
+const PARALLEL_MIN_LINES: usize = 20_000;
+
+// Lines matched per parallel pass; cancellation is checked between passes
+const CHUNK_LINES: usize = 64 * 1024;
+
+/// What a line has to satisfy to show up in the filtered view.
+pub struct Criteria<'a> {
+    pub text_lower: &'a str, // Filter text, already lowercased
+    pub time_range: Option<(NaiveDateTime, NaiveDateTime)>,
+    pub min_level: Option<LogLevel>,
+    pub statuses: StatusClasses,
+    pub words: &'a [String], // Lowercase words picked in the token panel, all of which must appear
+    pub sources: &'a [Source], // Consulted for the per-source filters set in the sources panel
+    pub geo: Option<(&'a Enricher, &'a str)>, // Country or AS an address in the line must be in (:geo)
+    pub columns: Vec<ColumnFilter<'a>>, // Values extra columns must have (:lookup and :column)
+}
+
+impl Criteria<'_> {
+    pub fn matches(&self, log: &LogLine) -> bool {
+        if log.hidden {
+            return false;
+        }
+        let source_filter = self.sources.iter()
+            .find(|source| source.id == log.source)
+            .map(|source| &source.filter);
+        let min_level = source_filter.and_then(|filter| filter.min_level).or(self.min_level);
+        if min_level.is_some_and(|min| log.level.severity() < min.severity()) {
+            return false;
+        }
+        if self.statuses.any() && !log.status().is_some_and(|status| self.statuses.keeps(status)) {
+            return false;
+        }
+        if !self.words.iter().all(|word| tokens::has_word(log, word)) {
+            return false;
+        }
+        if !log.contains_lower(self.text_lower) {
+            return false;
+        }
+        if source_filter.is_some_and(|filter| !log.contains_lower(&filter.text_lower)) {
+            return false;
+        }
+        if let Some((enricher, wanted)) = self.geo
+            && !enricher.matches(&log.head, wanted)
+            && !enricher.matches(&log.message, wanted)
+        {
+            return false;
+        }
+        if !self.columns.is_empty() {
+            let content = log.content();
+            if !self.columns.iter().all(|filter| filter.matches(&content)) {
+                return false;
+            }
+        }
+        match (self.time_range, log.time) {
+            (None, _) => true,
+            (Some((start, end)), Some(time)) => time >= start && time < end,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// The HTTP status classes the `4` and `5` toggles narrow access logs to;
+/// with neither on, lines are not filtered by status.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatusClasses {
+    pub client_errors: bool, // 4xx
+    pub server_errors: bool, // 5xx
+}
+
+impl StatusClasses {
+    pub fn any(self) -> bool {
+        self.client_errors || self.server_errors
+    }
+
+    pub fn keeps(self, status: u16) -> bool {
+        match status / 100 {
+            4 => self.client_errors,
+            5 => self.server_errors,
+            _ => false,
+        }
+    }
+
+    /// How the filter line shows them, such as `4xx+5xx`.
+    pub fn label(self) -> String {
+        let classes: Vec<&str> = [(self.client_errors, "4xx"), (self.server_errors, "5xx")]
+            .into_iter()
+            .filter_map(|(on, label)| on.then_some(label))
+            .collect();
+        classes.join("+")
+    }
+}
+
+/// Buffer indices of the `candidates` that pass the filter, in order.
+///
+/// Large candidate sets are matched in chunks across the rayon pool, and
+/// `cancelled` is consulted between chunks so a newer keystroke can abandon a
+/// stale pass; `None` is returned in that case.
+pub fn matching_indices(
+    log_lines: &VecDeque<LogLine>,
+    candidates: &[usize],
+    criteria: &Criteria,
+    cancelled: &dyn Fn() -> bool,
+) -> Option<Vec<usize>> {
+    let matches = |&i: &usize| criteria.matches(&log_lines[i]);
+    if candidates.len() < PARALLEL_MIN_LINES {
+        return Some(candidates.iter().copied().filter(matches).collect());
+    }
+
+    let mut result = Vec::new();
+    for chunk in candidates.chunks(CHUNK_LINES) {
+        if cancelled() {
+            return None;
+        }
+        let found: Vec<usize> = chunk.par_iter().copied().filter(matches).collect();
+        result.extend(found);
+    }
+    Some(result)
+}
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use chrono::format::{Fixed, Item, Numeric, StrftimeItems};
+use chrono::{DateTime, Datelike, NaiveDateTime, Timelike};
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::config::FormatConfig;
+use crate::diag;
+use crate::LogLevel;
+
+// How far into a line the header delimiter is looked for
+const MAX_HEAD_LEN: usize = 96;
+
+// Timestamp layout of the built-in profile
+const AUTO_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// The same, with fractional seconds if there are any, for JSON time values
+const JSON_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+// Keys JSON loggers commonly put each field under, in order of preference
+const JSON_TIME_KEYS: [&str; 5] = ["timestamp", "time", "ts", "@timestamp", "datetime"];
+const JSON_LEVEL_KEYS: [&str; 4] = ["level", "severity", "lvl", "loglevel"];
+const JSON_MESSAGE_KEYS: [&str; 3] = ["msg", "message", "event"];
+
+/// Lines looked at when guessing a source's format.
+pub const DETECT_SAMPLE_LINES: usize = 50;
+
+// Share of the sample a profile has to recognize to be picked
+const DETECT_MIN_SHARE: f64 = 0.6;
+
+/// A source's format, shared with its ingest thread so it can be changed
+/// while lines keep arriving.
+pub type SharedFormat = Arc<RwLock<LogFormat>>;
+
+/// How raw lines are turned into timestamps and levels, chosen with `--format`.
+#[derive(Clone)]
+pub enum LogFormat {
+    /// Detect a leading timestamp and guess the level from keywords
+    Auto,
+    /// Show lines as they are, without timestamps or levels
+    Plain,
+    /// One JSON object per line, with the time, level and message under common keys
+    Json,
+    /// Classic syslog: `Jan  2 15:04:05 host program[pid]: message`
+    Syslog,
+    /// Common or combined web server access log, leveled by status code
+    Access,
+    /// A regex profile from the `[formats]` table of the config file
+    Custom(Arc<CustomFormat>),
+}
+
+/// Regex profile whose named groups `timestamp`, `level` and `message` pick
+/// the fields out of a line; lines it does not match are auto-detected.
+pub struct CustomFormat {
+    name: String,
+    regex: Regex,
+    timestamp_format: String,
+}
+
+/// Where the parts of a line are, as found by a format.
+pub struct Fields {
+    pub timestamp: Range<usize>, // Empty when the line has no timestamp
+    pub time: Option<NaiveDateTime>,
+    pub level: Option<LogLevel>, // `None` means guess from keywords
+    pub status: Option<u16>,     // HTTP status of an access log line
+    pub message_start: usize,
+}
+
+impl LogFormat {
+    /// Look up a profile by name: the built-in `auto`, `plain`, `json`,
+    /// `syslog` and `access`, or one defined under `[formats.<name>]` in the config.
+    pub fn resolve(name: &str, formats: &HashMap<String, FormatConfig>) -> Result<Self, String> {
+        match name {
+            "auto" => Ok(LogFormat::Auto),
+            "plain" => Ok(LogFormat::Plain),
+            "json" => Ok(LogFormat::Json),
+            "syslog" => Ok(LogFormat::Syslog),
+            "access" => Ok(LogFormat::Access),
+            _ => {
+                let profile = formats.get(name).ok_or_else(|| format!("Unknown format profile: {}", name))?;
+                let regex = Regex::new(&profile.pattern)
+                    .map_err(|e| format!("Invalid pattern for format {}: {}", name, e))?;
+                Ok(LogFormat::Custom(Arc::new(CustomFormat {
+                    name: name.to_string(),
+                    regex,
+                    timestamp_format: with_fraction(&profile.timestamp_format),
+                })))
+            }
+        }
+    }
+
+    /// The built-in profile most of `lines` follow, if any does; custom
+    /// profiles are only used when asked for.
+    pub fn detect<'a>(lines: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let sample: Vec<&str> = lines.filter(|line| !line.trim().is_empty()).take(DETECT_SAMPLE_LINES).collect();
+        if sample.is_empty() {
+            return None;
+        }
+        [LogFormat::Json, LogFormat::Access, LogFormat::Syslog].into_iter()
+            .map(|format| {
+                let recognized = sample.iter().filter(|line| format.recognizes(line)).count();
+                (format, recognized)
+            })
+            .filter(|&(_, recognized)| recognized as f64 >= sample.len() as f64 * DETECT_MIN_SHARE)
+            .max_by_key(|&(_, recognized)| recognized)
+            .map(|(format, _)| format)
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            LogFormat::Auto => "auto",
+            LogFormat::Plain => "plain",
+            LogFormat::Json => "json",
+            LogFormat::Syslog => "syslog",
+            LogFormat::Access => "access",
+            LogFormat::Custom(custom) => &custom.name,
+        }
+    }
+
+    // Whether `line` is laid out the way this profile expects
+    fn recognizes(&self, line: &str) -> bool {
+        match self {
+            LogFormat::Json => json_object(line).is_some(),
+            LogFormat::Syslog => syslog_fields(line).is_some(),
+            LogFormat::Access => access_fields(line).is_some(),
+            LogFormat::Auto | LogFormat::Plain => true,
+            LogFormat::Custom(custom) => custom.regex.is_match(line),
+        }
+    }
+
+    pub fn fields(&self, line: &str) -> Fields {
+        match self {
+            LogFormat::Auto => auto_fields(line),
+            LogFormat::Plain => Fields {
+                timestamp: 0..0,
+                time: None,
+                level: Some(LogLevel::Unknown),
+                status: None,
+                message_start: header_end(line, 0),
+            },
+            LogFormat::Json => json_fields(line).unwrap_or_else(|| unmatched_fields(line)),
+            LogFormat::Syslog => syslog_fields(line).unwrap_or_else(|| unmatched_fields(line)),
+            LogFormat::Access => access_fields(line).unwrap_or_else(|| unmatched_fields(line)),
+            LogFormat::Custom(custom) => custom.fields(line).unwrap_or_else(|| unmatched_fields(line)),
+        }
+    }
+}
+
+impl CustomFormat {
+    fn fields(&self, line: &str) -> Option<Fields> {
+        let caps = self.regex.captures(line)?;
+        let timestamp = caps.name("timestamp").map_or(0..0, |m| m.range());
+        let time = caps.name("timestamp")
+            .and_then(|m| NaiveDateTime::parse_from_str(m.as_str(), &self.timestamp_format).ok());
+        let level = caps.name("level").and_then(|m| LogLevel::from_name(m.as_str()));
+        let message_start = caps.name("message").map_or_else(|| header_end(line, timestamp.end), |m| m.start());
+        Some(Fields {
+            message_start: message_start.max(timestamp.end),
+            timestamp,
+            time,
+            level,
+            status: None,
+        })
+    }
+}
+
+// A layout that also reads fractional seconds after the seconds, when it
+// doesn't say how to; `%.f` reads nothing when there are none
+fn with_fraction(layout: &str) -> String {
+    // `%3f` and the like are internal items
+    let fraction = StrftimeItems::new(layout).any(|item| matches!(
+        item,
+        Item::Fixed(Fixed::Nanosecond | Fixed::Nanosecond3 | Fixed::Nanosecond6 | Fixed::Nanosecond9 | Fixed::Internal(_))
+            | Item::Numeric(Numeric::Nanosecond, _)
+    ));
+    if fraction {
+        return layout.to_string();
+    }
+    layout.replacen("%S", "%S%.f", 1)
+}
+
+// A line the profile doesn't fit is read as `auto` would, and counted for the diagnostics view
+fn unmatched_fields(line: &str) -> Fields {
+    diag::UNMATCHED_LINES.fetch_add(1, Ordering::Relaxed);
+    auto_fields(line)
+}
+
+// A timestamp at the start of the line (tolerating a leading '['), with the
+// message after the first "] " or ": " that follows it
+fn auto_fields(line: &str) -> Fields {
+    let start = if line.starts_with('[') { 1 } else { 0 };
+    let mut timestamp = match line.get(start..start + 19) {
+        Some(ts) if ts.as_bytes()[10] == b' ' && ts.as_bytes()[13] == b':' => start..start + 19,
+        _ => start..start,
+    };
+    let mut time = NaiveDateTime::parse_from_str(&line[timestamp.clone()], AUTO_TIMESTAMP_FORMAT).ok();
+    // Fractional seconds, `.123` or `,123`, belong to the timestamp
+    if let Some(seconds) = time
+        && matches!(line.as_bytes().get(timestamp.end), Some(b'.' | b','))
+    {
+        let digits = line[timestamp.end + 1..].bytes().take_while(u8::is_ascii_digit).count().min(9);
+        if let Ok(fraction) = line[timestamp.end + 1..timestamp.end + 1 + digits].parse::<u32>() {
+            time = seconds.with_nanosecond(fraction * 10u32.pow(9 - digits as u32));
+            timestamp.end += 1 + digits;
+        }
+    }
+    Fields {
+        message_start: header_end(line, timestamp.end),
+        timestamp,
+        time,
+        level: None,
+        status: None,
+    }
+}
+
+fn json_object(line: &str) -> Option<Map<String, Value>> {
+    if !line.trim_start().starts_with('{') {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+// The time, level and message of a JSON line. The head runs up to the
+// message value, so the timestamp only shows as such when it comes first.
+fn json_fields(line: &str) -> Option<Fields> {
+    let object = json_object(line)?;
+    let string_value = |keys: &[&str]| keys.iter().find_map(|key| object.get(*key)?.as_str());
+    let message_start = string_value(&JSON_MESSAGE_KEYS)
+        .and_then(|message| value_range(line, message))
+        .map_or(0, |range| range.start);
+    let timestamp = JSON_TIME_KEYS.iter().find_map(|key| object.get(*key));
+    let time = timestamp.and_then(|value| match value {
+        Value::String(text) => DateTime::parse_from_rfc3339(text)
+            .map(|time| time.naive_local())
+            .or_else(|_| NaiveDateTime::parse_from_str(text, JSON_TIMESTAMP_FORMAT))
+            .ok(),
+        // Epoch seconds, or milliseconds for values too large to be seconds,
+        // keeping their fraction down to the microsecond
+        Value::Number(number) => {
+            let epoch = number.as_f64()?;
+            let micros = if epoch > 1e11 { epoch * 1e3 } else { epoch * 1e6 };
+            DateTime::from_timestamp_micros(micros.round() as i64).map(|time| time.naive_utc())
+        }
+        _ => None,
+    });
+    let timestamp = timestamp
+        .and_then(Value::as_str)
+        .and_then(|text| value_range(line, text))
+        .filter(|range| range.end <= message_start)
+        .unwrap_or(0..0);
+    let level = string_value(&JSON_LEVEL_KEYS).and_then(LogLevel::from_name);
+    Some(Fields {
+        timestamp,
+        time,
+        level,
+        status: None,
+        message_start,
+    })
+}
+
+// Byte range of a JSON string value as written in `line`, when it needed no escaping
+fn value_range(line: &str, value: &str) -> Option<Range<usize>> {
+    let quoted = format!("\"{}\"", value);
+    let start = line.find(&quoted)? + 1;
+    Some(start..start + value.len())
+}
+
+// `Jan  2 15:04:05 host program[pid]: message`; the year isn't logged, so
+// the current one is assumed
+fn syslog_fields(line: &str) -> Option<Fields> {
+    let stamp = line.get(..15)?;
+    let bytes = stamp.as_bytes();
+    if bytes[3] != b' ' || bytes[6] != b' ' || bytes[9] != b':' || bytes[12] != b':' {
+        return None;
+    }
+    let year = chrono::Local::now().year();
+    let time = NaiveDateTime::parse_from_str(&format!("{} {}", year, stamp), "%Y %b %e %H:%M:%S").ok()?;
+    // Host, then the program tag ending in ':'
+    let rest = line.get(16..)?;
+    let host_end = rest.find(' ')?;
+    let tag = &rest[host_end + 1..];
+    let tag_end = tag.find(": ")?;
+    if tag[..tag_end].contains(' ') {
+        return None;
+    }
+    Some(Fields {
+        timestamp: 0..15,
+        time: Some(time),
+        level: None,
+        status: None,
+        message_start: 16 + host_end + 1 + tag_end + 2,
+    })
+}
+
+// `host ident user [02/Jan/2006:15:04:05 -0700] "GET / HTTP/1.1" 200 ...`,
+// with 5xx responses as errors and 4xx as warnings
+fn access_fields(line: &str) -> Option<Fields> {
+    let open = line.find(" [")? + 2;
+    let close = open + line[open..].find("] \"")?;
+    let time = DateTime::parse_from_str(&line[open..close], "%d/%b/%Y:%H:%M:%S %z").ok()?.naive_local();
+    let message_start = close + 2;
+    let request_end = message_start + 1 + line[message_start + 1..].find('"')?;
+    let status: u16 = line.get(request_end + 2..request_end + 5)?.parse().ok()?;
+    let level = match status {
+        500.. => LogLevel::Error,
+        400..=499 => LogLevel::Warning,
+        _ => LogLevel::Info,
+    };
+    Some(Fields {
+        timestamp: open..close,
+        time: Some(time),
+        level: Some(level),
+        status: Some(status),
+        message_start,
+    })
+}
+
+// End of the header delimiter ("] " or ": ") found after byte `from`, or
+// `from` itself when the line has none near its start
+fn header_end(line: &str, from: usize) -> usize {
+    let search_end = line.len().min(MAX_HEAD_LEN);
+    line.get(from..search_end)
+        .and_then(|header| header.find("] ").or_else(|| header.find(": ")))
+        .map_or(from, |pos| from + pos + 2)
+}
+
+/// Whether `layout` is a strftime layout chrono can display without failing.
+/// Rather than only parsing it, a sample time is written out with it, as
+/// items that parse but need a time zone (`%z`, `%Z`) fail only then.
+pub fn valid_time_layout(layout: &str) -> bool {
+    use std::fmt::Write;
+    let sample = NaiveDateTime::default();
+    write!(String::new(), "{}", sample.format(layout)).is_ok()
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use chrono::NaiveDateTime;
+
+use crate::alerts::{escalation_line, Alert};
+use crate::export::json_record;
+use crate::format::{self, LogFormat};
+use crate::script::LineAction;
+use crate::stats::LogStats;
+use crate::{filter, ingest, App, LogLevel, LogLine};
+
+/// Exit status of a `--headless` run in which a watch rule fired, so a CI
+/// step or cron job can tell it from a clean run (0) and an error (1).
+pub const ALERT_EXIT_CODE: u8 = 2;
+
+/// What a headless run writes to stdout.
+pub struct HeadlessOptions {
+    pub summary: bool,             // Counts of the matching lines instead of the lines themselves
+    pub json: bool,                // NDJSON records, or a JSON summary
+    pub last_lines: Option<usize>, // Only the end of the file rather than all of it
+}
+
+/// Run the app's file through its format, filter and watch rules without
+/// the terminal UI. Matching lines (or a summary of them) go to stdout and
+/// alerts to stderr, with the rules' commands and webhooks run as usual, and
+/// any script's hooks too (its status messages also go to stderr). Rate,
+/// silence and cooldown times go by the lines' timestamps rather than by
+/// how fast the file is read.
+/// Returns once the alerts' commands and deliveries are done, with
+/// `ALERT_EXIT_CODE` if any alert fired.
+pub fn run(app: &mut App, options: &HeadlessOptions) -> io::Result<ExitCode> {
+    let mut lines: Box<dyn Iterator<Item = io::Result<String>>> = match options.last_lines {
+        Some(count) => Box::new(ingest::read_last_lines(&app.log_path, count)?.0.into_iter().map(Ok)),
+        None => Box::new(BufReader::new(File::open(&app.log_path)?).split(b'\n').map(|line| {
+            line.map(|line| String::from_utf8_lossy(&line).trim_end_matches('\r').to_string())
+        })),
+    };
+
+    // Guess the format from the first lines, as a followed file would
+    let mut sample = Vec::new();
+    let format = match &app.format {
+        LogFormat::Auto => {
+            for line in lines.by_ref().take(format::DETECT_SAMPLE_LINES) {
+                sample.push(line?);
+            }
+            LogFormat::detect(sample.iter().map(String::as_str)).unwrap_or(LogFormat::Auto)
+        }
+        format => format.clone(),
+    };
+    let lines = sample.into_iter().map(Ok).chain(lines);
+
+    let source = Path::new(&app.log_path).file_name().map_or_else(|| app.log_path.clone(), |name| name.to_string_lossy().into_owned());
+    let filter_lower = app.filter_text.to_lowercase();
+    let drill_words = app.drill_words.clone(); // Leaves `app` free for firing alerts
+    let criteria = filter::Criteria {
+        text_lower: &filter_lower,
+        time_range: app.time_range,
+        min_level: app.min_level,
+        statuses: app.status_classes,
+        words: &drill_words,
+        sources: &[],
+        geo: None,
+        columns: Vec::new(),
+    };
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut stats = LogStats::default();
+    let mut read = 0;
+    let mut fired: BTreeMap<String, usize> = BTreeMap::new();
+    let mut clock = LineClock::new();
+    for line in lines {
+        let line = line?;
+        let mut log = LogLine::parse(&app.redactor.redact(&line), &format);
+        read += 1;
+        if let Some(script) = app.script.as_mut().filter(|script| script.on_line) {
+            let action = script.on_line(&log.content(), &source).map_err(io::Error::other)?;
+            script.take_messages().iter().for_each(|message| eprintln!("{}", message));
+            match action {
+                LineAction::Keep => {}
+                LineAction::Replace(line) => log = LogLine::parse(&line, &format),
+                LineAction::Drop => continue,
+            }
+        }
+        log.seq = read - 1;
+        log.line = read;
+        let content = log.content();
+        app.remap_level(&mut log, &content);
+
+        let previous = clock.latest;
+        let now = clock.at(log.time);
+        let mut alerts = Vec::new();
+        for (idx, rule) in app.watches.iter_mut().enumerate() {
+            // A silence rule fires on a gap between two lines' timestamps
+            let quiet_for = now - previous;
+            if rule.active() && rule.silence_expired(quiet_for) {
+                alerts.push((idx, format!("No new lines for {}s before line {}", quiet_for.as_secs(), log.line), None));
+            }
+            rule.rearm_silence();
+            let escalated = rule.escalated_from(&log);
+            if rule.active() && (escalated.is_some() || rule.matches(&content, log.level)) && rule.record_match(now) {
+                let line = match escalated {
+                    Some(from) => escalation_line(from, log.level, &content),
+                    None => content.clone(),
+                };
+                alerts.push((idx, line, Some(log.seq)));
+            }
+        }
+        for (idx, line, seq) in alerts {
+            *fired.entry(app.watches[idx].spec.clone()).or_default() += 1;
+            fire_alert(app, idx, line, seq, now, &source)?;
+        }
+
+        if !criteria.matches(&log) {
+            continue;
+        }
+        stats.count(&log, true);
+        match (options.summary, options.json) {
+            (true, _) => {}
+            (false, true) => writeln!(out, "{}", json_record(&log, &app.log_path))?,
+            (false, false) => writeln!(out, "{}", content)?,
+        }
+    }
+
+    if options.summary {
+        write_summary(&mut out, app, read, &stats, &fired, options.json)?;
+    }
+    out.flush()?;
+    app.alert_worker.finish();
+    Ok(if fired.is_empty() { ExitCode::SUCCESS } else { ExitCode::from(ALERT_EXIT_CODE) })
+}
+
+// Report an alert of rule `idx` on stderr and take the rule's actions, as
+// the app does for an alert at `now`
+fn fire_alert(app: &mut App, idx: usize, line: String, seq: Option<u64>, now: Instant, source: &str) -> io::Result<()> {
+    let rule = &mut app.watches[idx];
+    eprintln!("ALERT [{}] {}", rule.spec, line);
+    if let Some(script) = app.script.as_mut().filter(|script| script.on_alert) {
+        script.on_alert(&rule.spec, &line).map_err(io::Error::other)?;
+        script.take_messages().iter().for_each(|message| eprintln!("{}", message));
+    }
+    if rule.notify {
+        app.alert_worker.notify(&rule.spec, &line);
+    }
+    rule.run_exec(&line, now, &mut app.alert_worker);
+    if let Some(url) = &rule.webhook {
+        let alert = Alert {
+            time: chrono::Local::now(),
+            rule: rule.spec.clone(),
+            line,
+            source: source.to_string(),
+            seq,
+            acknowledged: false,
+        };
+        app.alert_worker.post_webhook(url, &rule.webhook_template, &alert);
+    }
+    Ok(())
+}
+
+// Time as the lines tell it: the first timestamp read is taken as now, and
+// later lines are that far after it as their timestamps say
+struct LineClock {
+    start: Instant,
+    first: Option<NaiveDateTime>,
+    latest: Instant, // When the latest line with a timestamp was logged
+}
+
+impl LineClock {
+    fn new() -> Self {
+        let start = Instant::now();
+        LineClock { start, first: None, latest: start }
+    }
+
+    // When a line logged at `time` happened; one without a timestamp, or
+    // with one earlier than the latest, goes with the latest
+    fn at(&mut self, time: Option<NaiveDateTime>) -> Instant {
+        if let Some(time) = time {
+            let first = *self.first.get_or_insert(time);
+            self.latest = self.latest.max(self.start + (time - first).to_std().unwrap_or_default());
+        }
+        self.latest
+    }
+}
+
+// Lines read and matched, the matches by level and the alerts by rule
+fn write_summary(
+    out: &mut impl Write,
+    app: &App,
+    read: u64,
+    stats: &LogStats,
+    fired: &BTreeMap<String, usize>,
+    json: bool,
+) -> io::Result<()> {
+    let levels = [
+        (LogLevel::Fatal, stats.fatal_count),
+        (LogLevel::Error, stats.error_count),
+        (LogLevel::Warning, stats.warning_count),
+        (LogLevel::Info, stats.info_count),
+        (LogLevel::Debug, stats.debug_count),
+        (LogLevel::Trace, stats.trace_count),
+        (LogLevel::Unknown, stats.unknown_count),
+    ];
+    if json {
+        let levels: serde_json::Map<String, serde_json::Value> = levels.iter()
+            .map(|(level, count)| (level.as_str().to_lowercase(), (*count).into()))
+            .collect();
+        let summary = serde_json::json!({
+            "file": app.log_path,
+            "lines": read,
+            "matched": stats.total_entries,
+            "levels": levels,
+            "alerts": fired,
+        });
+        return writeln!(out, "{}", summary);
+    }
+    writeln!(out, "{}: {} lines, {} matched", app.log_path, read, stats.total_entries)?;
+    for (level, count) in levels {
+        writeln!(out, "  {:<8} {}", level.as_str(), count)?;
+    }
+    if !fired.is_empty() {
+        writeln!(out, "Alerts:")?;
+        for (rule, count) in fired {
+            writeln!(out, "  {:>6}  {}", count, rule)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+use regex::Regex;
+
+/// A `[[highlight]]` rule from the config file: text matching `regex` is
+/// drawn in `color` wherever it appears in a line.
+pub struct Highlight {
+    regex: Regex,
+    color: Color,
+}
+
+impl Highlight {
+    pub fn new(pattern: &str, color: &str) -> Result<Self, String> {
+        Ok(Highlight {
+            regex: Regex::new(pattern).map_err(|e| format!("Invalid highlight pattern {:?}: {}", pattern, e))?,
+            color: parse_color(color)?,
+        })
+    }
+}
+
+/// A color by name (`red`, `lightblue`, `gray`, ...) or as `#rrggbb`.
+pub fn parse_color(name: &str) -> Result<Color, String> {
+    let lower = name.to_lowercase();
+    if let Some(hex) = lower.strip_prefix('#') {
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(format!("Invalid color: {}", name)),
+        };
+    }
+    Ok(match lower.as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return Err(format!("Invalid color: {}", name)),
+    })
+}
+
+/// Split `text` into spans in `style`, with the parts matched by `rules`
+/// recolored. Where matches overlap the earliest one wins.
+pub fn highlight_spans<'a>(text: &'a str, style: Style, rules: &[Highlight]) -> Vec<Span<'a>> {
+    let mut matches: Vec<(usize, usize, Color)> = rules.iter()
+        .flat_map(|rule| rule.regex.find_iter(text).map(|m| (m.start(), m.end(), rule.color)))
+        .filter(|(start, end, _)| start < end)
+        .collect();
+    if matches.is_empty() {
+        return vec![Span::styled(text, style)];
+    }
+    matches.sort_by_key(|&(start, _, _)| start);
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for (start, end, color) in matches {
+        if start < pos {
+            continue;
+        }
+        if start > pos {
+            spans.push(Span::styled(&text[pos..start], style));
+        }
+        spans.push(Span::styled(&text[start..end], style.fg(color)));
+        pos = end;
+    }
+    if pos < text.len() {
+        spans.push(Span::styled(&text[pos..], style));
+    }
+    spans
+}
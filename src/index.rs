@@ -0,0 +1,114 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Inverted index from lowercase byte trigrams to the sequence numbers of the
+/// buffered lines containing them.
+///
+/// Lines are evicted oldest first, so eviction only ever pops from the
+/// front. They are mostly added in sequence order too; a line inserted
+/// further back (see `renumber`) is placed so every posting list stays sorted.
+#[derive(Default)]
+pub struct TrigramIndex {
+    postings: HashMap<[u8; 3], VecDeque<u64>>,
+    entries: usize, // Total sequence numbers across all posting lists
+}
+
+impl TrigramIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index a newly buffered line by the bytes of its lowercased content.
+    pub fn insert(&mut self, seq: u64, content_lower: impl IntoIterator<Item = u8>) {
+        for trigram in trigrams(content_lower) {
+            let list = self.postings.entry(trigram).or_default();
+            match list.back() {
+                Some(&last) if last > seq => {
+                    let at = list.partition_point(|&other| other < seq);
+                    list.insert(at, seq);
+                }
+                _ => list.push_back(seq),
+            }
+            self.entries += 1;
+        }
+    }
+
+    /// Move a buffered line from `seq` to `new_seq`, which must not pass any
+    /// other line sharing one of its trigrams; when shifting a run of lines
+    /// up by one, renumber the newest first.
+    pub fn renumber(&mut self, seq: u64, new_seq: u64, content_lower: impl IntoIterator<Item = u8>) {
+        for trigram in trigrams(content_lower) {
+            let Some(list) = self.postings.get_mut(&trigram) else {
+                continue;
+            };
+            if let Ok(i) = list.binary_search(&seq) {
+                list[i] = new_seq;
+            }
+        }
+    }
+
+    /// Drop the oldest buffered line, which must be the last one still indexed
+    /// under its trigrams.
+    pub fn remove_oldest(&mut self, seq: u64, content_lower: impl IntoIterator<Item = u8>) {
+        for trigram in trigrams(content_lower) {
+            let Some(list) = self.postings.get_mut(&trigram) else {
+                continue;
+            };
+            if list.front() == Some(&seq) {
+                list.pop_front();
+                self.entries -= 1;
+            }
+            if list.is_empty() {
+                self.postings.remove(&trigram);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.postings.clear();
+        self.entries = 0;
+    }
+
+    /// Sequence numbers of lines that contain every trigram of `query_lower`, in
+    /// ascending order. Candidates still need a substring check; `None` means the
+    /// query is too short to narrow anything down.
+    pub fn candidates(&self, query_lower: &str) -> Option<Vec<u64>> {
+        let mut lists = Vec::new();
+        for trigram in trigrams(query_lower.bytes()) {
+            match self.postings.get(&trigram) {
+                Some(list) => lists.push(list),
+                None => return Some(Vec::new()),
+            }
+        }
+        lists.sort_by_key(|list| list.len());
+        let (first, rest) = lists.split_first()?;
+        let mut seqs: Vec<u64> = first.iter().copied().collect();
+        for list in rest {
+            seqs.retain(|seq| list.binary_search(seq).is_ok());
+            if seqs.is_empty() {
+                break;
+            }
+        }
+        Some(seqs)
+    }
+
+    /// Approximate heap usage of the posting lists in bytes.
+    pub fn memory_size(&self) -> usize {
+        self.entries * std::mem::size_of::<u64>()
+            + self.postings.len() * (std::mem::size_of::<[u8; 3]>() + std::mem::size_of::<VecDeque<u64>>())
+    }
+}
+
+// Distinct byte trigrams of a run of bytes, sorted
+fn trigrams(bytes: impl IntoIterator<Item = u8>) -> Vec<[u8; 3]> {
+    let mut window = [0; 3];
+    let mut trigrams = Vec::new();
+    for (i, byte) in bytes.into_iter().enumerate() {
+        window = [window[1], window[2], byte];
+        if i >= 2 {
+            trigrams.push(window);
+        }
+    }
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    trigrams
+}
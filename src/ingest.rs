@@ -0,0 +1,558 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use crossbeam_channel::{bounded, Receiver, Sender};
+
+use crate::diag::{self, Topic};
+use crate::format::{self, LogFormat, SharedFormat};
+use crate::LogLine;
+
+/// How often the tail thread checks the file when there is nothing new, unless overridden.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Upper bound on bytes read (and so on lines parsed) per batch
+const MAX_BATCH_BYTES: usize = 1024 * 1024;
+
+// Upper bound on lines per batch from a stream
+const MAX_BATCH_LINES: usize = 4096;
+
+// Block size used when scanning backwards from the end of a file
+const REVERSE_BLOCK_SIZE: u64 = 64 * 1024;
+
+// Lines looked through for a timestamp at each step of the search for a
+// time window, before giving up on that part of the file
+const PROBE_LINES: usize = 256;
+
+/// Batches the ingest thread can queue before it blocks and waits for the UI.
+pub const QUEUE_BATCHES: usize = 16;
+
+// Pause before the first reconnection attempt, doubled after each failure up to the maximum
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+// Messages from the ingest thread to the UI loop
+pub enum IngestEvent {
+    Lines(Vec<LogLine>, Option<u64>), // With the offset just past them, when read from a file
+    Error(io::Error),
+    Waiting,                 // The followed file is gone; checking until it comes back
+    Reconnecting(io::Error), // The stream dropped or the file can't be read; trying again
+    Connected,               // Reading again after waiting or reconnecting
+}
+
+// How reading a stream came to an end
+enum StreamEnd {
+    Eof,
+    Failed(io::Error),
+    Gone, // The UI dropped the receiver
+}
+
+/// Follow `path` from byte `offset` on a background thread, checking every
+/// `interval_ms` milliseconds and sending parsed batches of new lines over a
+/// bounded channel. The interval is shared, so the UI can change it while the
+/// thread runs. A file that disappears is waited for and read from the start
+/// once it is back; one that can't be read (its permissions changed, say) is
+/// retried with a growing pause and read on from where it was. The thread
+/// stops once the receiver is dropped.
+pub fn spawn_tail(path: String, offset: u64, interval_ms: Arc<AtomicU64>, format: SharedFormat) -> Receiver<IngestEvent> {
+    let (tx, rx) = bounded(QUEUE_BATCHES);
+    thread::spawn(move || {
+        let mut tail = FileTail {
+            path,
+            offset,
+            partial: Vec::new(),
+        };
+        let mut waiting = false; // Since the file went missing or unreadable, until it reads again
+        let mut delay = RECONNECT_MIN_DELAY;
+        let mut undetected = format::DETECT_SAMPLE_LINES;
+        loop {
+            let result = tail.read_new_lines();
+            if waiting && result.is_ok() {
+                waiting = false;
+                delay = RECONNECT_MIN_DELAY;
+                if tx.send(IngestEvent::Connected).is_err() {
+                    return;
+                }
+            }
+            match result {
+                Ok(lines) if lines.is_empty() => thread::sleep(Duration::from_millis(interval_ms.load(Ordering::Relaxed))),
+                Ok(lines) => {
+                    let batch = parse_batch(&lines, &format, &mut undetected);
+                    if tx.send(IngestEvent::Lines(batch, Some(tail.lines_end()))).is_err() {
+                        return; // UI has gone away
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    // Rotated away or deleted; whatever appears under the name next is new
+                    tail.offset = 0;
+                    tail.partial.clear();
+                    if !waiting && tx.send(IngestEvent::Waiting).is_err() {
+                        return;
+                    }
+                    waiting = true;
+                    thread::sleep(Duration::from_millis(interval_ms.load(Ordering::Relaxed)));
+                }
+                Err(e) => {
+                    waiting = true;
+                    if tx.send(IngestEvent::Reconnecting(e)).is_err() {
+                        return;
+                    }
+                    thread::sleep(delay);
+                    delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// Read lines from a stream (a command's output or a socket) on a background
+/// thread, sending each burst of lines as one batch. The channel disconnects
+/// when the stream ends.
+pub fn spawn_reader(reader: impl Read + Send + 'static, format: SharedFormat) -> Receiver<IngestEvent> {
+    let (tx, rx) = bounded(QUEUE_BATCHES);
+    thread::spawn(move || {
+        let mut undetected = format::DETECT_SAMPLE_LINES;
+        if let StreamEnd::Failed(e) = forward_lines(reader, &format, &mut undetected, &tx) {
+            let _ = tx.send(IngestEvent::Error(e));
+        }
+    });
+    rx
+}
+
+/// Like `spawn_reader` for a stream that can be opened again: when `first`
+/// fails, or ends while `reconnect_at_end` is set, `connect` is retried with
+/// a growing pause in between until it succeeds, and reading carries on from
+/// the new stream. A stream ending otherwise (an HTTP response read to its
+/// end, which another request would only repeat) disconnects the channel.
+pub fn spawn_reconnecting<R, F>(first: R, mut connect: F, format: SharedFormat, reconnect_at_end: bool) -> Receiver<IngestEvent>
+where
+    R: Read + Send + 'static,
+    F: FnMut() -> io::Result<R> + Send + 'static,
+{
+    let (tx, rx) = bounded(QUEUE_BATCHES);
+    thread::spawn(move || {
+        let mut reader = Some(first);
+        let mut delay = RECONNECT_MIN_DELAY;
+        let mut undetected = format::DETECT_SAMPLE_LINES;
+        loop {
+            let reason = match reader.take() {
+                Some(stream) => {
+                    delay = RECONNECT_MIN_DELAY;
+                    match forward_lines(stream, &format, &mut undetected, &tx) {
+                        StreamEnd::Gone => return,
+                        StreamEnd::Eof if !reconnect_at_end => return,
+                        StreamEnd::Eof => io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"),
+                        StreamEnd::Failed(e) => e,
+                    }
+                }
+                None => match connect() {
+                    Ok(stream) => {
+                        if tx.send(IngestEvent::Connected).is_err() {
+                            return;
+                        }
+                        reader = Some(stream);
+                        continue;
+                    }
+                    Err(e) => e,
+                },
+            };
+            if tx.send(IngestEvent::Reconnecting(reason)).is_err() {
+                return; // UI has gone away
+            }
+            thread::sleep(delay);
+            delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+        }
+    });
+    rx
+}
+
+// Send each burst of lines read from `reader` as one batch until it ends
+fn forward_lines(reader: impl Read, format: &SharedFormat, undetected: &mut usize, tx: &Sender<IngestEvent>) -> StreamEnd {
+    let mut reader = BufReader::new(reader);
+    let mut lines = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => lines.push(String::from_utf8_lossy(&buf).trim_end_matches(['\n', '\r']).to_string()),
+            Err(e) => return StreamEnd::Failed(e),
+        }
+        // Send once nothing more is buffered, so a quiet stream isn't held back
+        let flush = reader.buffer().is_empty() || lines.len() >= MAX_BATCH_LINES;
+        if flush && tx.send(IngestEvent::Lines(parse_batch(&lines, format, undetected), None)).is_err() {
+            return StreamEnd::Gone;
+        }
+        if flush {
+            lines.clear();
+        }
+    }
+    if !lines.is_empty() && tx.send(IngestEvent::Lines(parse_batch(&lines, format, undetected), None)).is_err() {
+        return StreamEnd::Gone;
+    }
+    StreamEnd::Eof
+}
+
+// Parse a batch with the source's current format. While the format is still
+// `auto`, the first `DETECT_SAMPLE_LINES` lines are also used to guess it;
+// `undetected` counts down the lines left to look at.
+fn parse_batch(lines: &[String], format: &SharedFormat, undetected: &mut usize) -> Vec<LogLine> {
+    let mut current = format.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if *undetected > 0 && matches!(current, LogFormat::Auto) {
+        if let Some(detected) = LogFormat::detect(lines.iter().map(String::as_str)) {
+            // Unless one was picked for it in the meantime
+            let mut shared = format.write().unwrap_or_else(|e| e.into_inner());
+            if matches!(*shared, LogFormat::Auto) {
+                diag::record(Topic::Parse, format!("Detected the {} format", detected.name()));
+                *shared = detected;
+            }
+            current = shared.clone();
+        }
+        *undetected = undetected.saturating_sub(lines.len());
+    }
+    lines.iter().map(|line| LogLine::parse(line, &current)).collect()
+}
+
+// Read position in a followed file
+struct FileTail {
+    path: String,
+    offset: u64,
+    partial: Vec<u8>, // Trailing bytes of a line that has no newline yet
+}
+
+impl FileTail {
+    // Offset just past the last complete line read
+    fn lines_end(&self) -> u64 {
+        self.offset - self.partial.len() as u64
+    }
+
+    // Read complete lines appended since the last call
+    fn read_new_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            // The file was truncated, start over from the beginning
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.take(MAX_BATCH_BYTES as u64).read_to_end(&mut buf)?;
+        self.offset += buf.len() as u64;
+
+        self.partial.extend_from_slice(&buf);
+        let Some(last_newline) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Ok(split_lines(&complete))
+    }
+}
+
+/// Read the last `num_lines` complete lines of a file by scanning backwards
+/// from the end in blocks, so the cost depends on the lines requested rather
+/// than on the size of the file.
+///
+/// Returns the lines, the byte offset where the first of them starts, and
+/// the offset just past the last complete line, where tailing should continue.
+pub fn read_last_lines(path: &str, num_lines: usize) -> io::Result<(Vec<String>, u64, u64)> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut data = Vec::new(); // Bytes from `pos` to the end of the file
+    let mut pos = len;
+    let mut end = None; // Just past the last newline
+    let mut start = None; // Start of the first requested line
+    let mut newlines_seen = 0;
+    while pos > 0 && start.is_none() {
+        let size = REVERSE_BLOCK_SIZE.min(pos);
+        pos -= size;
+        let mut block = vec![0; size as usize];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut block)?;
+
+        for (i, _) in block.iter().enumerate().rev().filter(|(_, b)| **b == b'\n') {
+            let after = pos + i as u64 + 1;
+            if end.is_none() {
+                end = Some(after);
+                if num_lines == 0 {
+                    start = Some(after);
+                    break;
+                }
+            } else {
+                newlines_seen += 1;
+                if newlines_seen == num_lines {
+                    start = Some(after);
+                    break;
+                }
+            }
+        }
+
+        block.extend_from_slice(&data);
+        data = block;
+    }
+
+    let Some(end) = end else {
+        return Ok((Vec::new(), 0, 0)); // No complete line yet
+    };
+    let start = start.unwrap_or(0);
+    let lines = split_lines(&data[(start - pos) as usize..(end - pos) as usize]);
+    Ok((lines, start, end))
+}
+
+/// Count the lines in the first `end` bytes of a file.
+pub fn count_lines(path: &str, end: u64) -> io::Result<u64> {
+    count_breaks(File::open(path)?.take(end))
+}
+
+/// Count the complete lines of a file from byte `start` on.
+pub fn count_lines_from(path: &str, start: u64) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    count_breaks(file)
+}
+
+fn count_breaks(mut file: impl Read) -> io::Result<u64> {
+    let mut block = vec![0; MAX_BATCH_BYTES];
+    let mut count = 0;
+    loop {
+        let read = file.read(&mut block)?;
+        if read == 0 {
+            return Ok(count);
+        }
+        count += block[..read].iter().filter(|&&b| b == b'\n').count() as u64;
+    }
+}
+
+/// Read the complete lines of a file logged within `window` (`[start, end)`),
+/// keeping the last `max_lines` of them. The first line of the window is
+/// found by binary search over the timestamps `format` reads, so only the
+/// window itself is read in full; this assumes the file is in time order,
+/// as logs nearly always are.
+///
+/// Returns the lines, the byte offset where the first of them starts, and
+/// the offset just past the last complete line of the file, where tailing
+/// should continue.
+pub fn read_window(
+    path: &str,
+    window: (NaiveDateTime, NaiveDateTime),
+    max_lines: usize,
+    format: &LogFormat,
+) -> io::Result<(Vec<String>, u64, u64)> {
+    let (_, _, end) = read_last_lines(path, 0)?;
+    let mut file = BufReader::new(File::open(path)?);
+
+    // Narrow [low, high] down to the start of the first line at or after the
+    // window's start; `low` is always the start of a line
+    let (mut low, mut high) = (0, end);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match first_timed_line(&mut file, mid, end, format)? {
+            Some((_, after, time)) if time < window.0 => low = after,
+            _ => high = mid,
+        }
+    }
+    // Lines without a timestamp right after the last one before the window,
+    // such as the rest of a stack trace, belong to that line
+    if low > 0 {
+        file.seek(SeekFrom::Start(low))?;
+        let mut raw = Vec::new();
+        while low < end {
+            raw.clear();
+            let read = file.read_until(b'\n', &mut raw)? as u64;
+            if read == 0 || format.fields(String::from_utf8_lossy(&raw).trim_end_matches(['\n', '\r'])).time.is_some() {
+                break;
+            }
+            low += read;
+        }
+    }
+
+    read_forward(&mut file, low, end, max_lines, |line| format.fields(line).time.is_some_and(|time| time >= window.1))
+}
+
+/// Read the complete lines of a file from byte `offset` on, keeping the last
+/// `max_lines` of them; a file now shorter than `offset` is read from the
+/// start. Returns the same offsets as `read_window`.
+pub fn read_from(path: &str, offset: u64, max_lines: usize) -> io::Result<(Vec<String>, u64, u64)> {
+    let (_, _, end) = read_last_lines(path, 0)?;
+    let mut file = BufReader::new(File::open(path)?);
+    read_forward(&mut file, if offset > end { 0 } else { offset }, end, max_lines, |_| false)
+}
+
+// Read lines from `pos` up to `end`, or up to the first line `stop` is true
+// for, keeping the last `max_lines`
+fn read_forward(
+    file: &mut BufReader<File>,
+    mut pos: u64,
+    end: u64,
+    max_lines: usize,
+    stop: impl Fn(&str) -> bool,
+) -> io::Result<(Vec<String>, u64, u64)> {
+    file.seek(SeekFrom::Start(pos))?;
+    let mut lines = VecDeque::new(); // Start offset and text of each kept line
+    let mut raw = Vec::new();
+    while pos < end {
+        raw.clear();
+        let read = file.read_until(b'\n', &mut raw)? as u64;
+        if read == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&raw).trim_end_matches(['\n', '\r']).to_string();
+        if stop(&line) {
+            break;
+        }
+        lines.push_back((pos, line));
+        if lines.len() > max_lines {
+            lines.pop_front();
+        }
+        pos += read;
+    }
+    let start = lines.front().map_or(pos, |&(offset, _)| offset);
+    Ok((lines.into_iter().map(|(_, line)| line).collect(), start, end))
+}
+
+// The first line with a timestamp starting at or after `pos` (which may fall
+// inside a line) and before `end`: where it starts and ends, and its time.
+// Only a bounded number of lines is looked at.
+fn first_timed_line(
+    file: &mut BufReader<File>,
+    pos: u64,
+    end: u64,
+    format: &LogFormat,
+) -> io::Result<Option<(u64, u64, NaiveDateTime)>> {
+    let mut raw = Vec::new();
+    let mut pos = pos;
+    if pos > 0 {
+        // Skip the rest of the line `pos` falls in, or just the newline before it
+        file.seek(SeekFrom::Start(pos - 1))?;
+        pos += file.read_until(b'\n', &mut raw)? as u64 - 1;
+    } else {
+        file.seek(SeekFrom::Start(0))?;
+    }
+    for _ in 0..PROBE_LINES {
+        if pos >= end {
+            break;
+        }
+        raw.clear();
+        let read = file.read_until(b'\n', &mut raw)? as u64;
+        if read == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&raw);
+        if let Some(time) = format.fields(line.trim_end_matches(['\n', '\r'])).time {
+            return Ok(Some((pos, pos + read, time)));
+        }
+        pos += read;
+    }
+    Ok(None)
+}
+
+/// Split a buffer of complete lines, dropping line endings like `BufRead::lines`.
+pub fn split_lines(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes).lines().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A file of `lines` under the temp directory, removed when dropped
+    struct TempLog(std::path::PathBuf);
+
+    impl TempLog {
+        fn new(name: &str, text: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("log_monitor-{}-{}.log", std::process::id(), name));
+            std::fs::write(&path, text).unwrap();
+            TempLog(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    // A minute of lines, one a second, each followed by an untimed one
+    fn minute_of_lines() -> String {
+        (0..60).map(|s| format!("2024-05-01 10:00:{:02} INFO tick {}\n    at frame {}\n", s, s, s)).collect()
+    }
+
+    #[test]
+    fn read_window_finds_the_first_line_in_the_window() {
+        let text = minute_of_lines() + "2024-05-01 10:01:00 INFO partial";
+        let log = TempLog::new("window", &text);
+        let (lines, start, end) =
+            read_window(log.path(), (at("2024-05-01 10:00:20"), at("2024-05-01 10:00:23")), 100, &LogFormat::Auto).unwrap();
+        assert_eq!(lines, [
+            "2024-05-01 10:00:20 INFO tick 20",
+            "    at frame 20",
+            "2024-05-01 10:00:21 INFO tick 21",
+            "    at frame 21",
+            "2024-05-01 10:00:22 INFO tick 22",
+            "    at frame 22",
+        ]);
+        assert_eq!(start, text.find("2024-05-01 10:00:20").unwrap() as u64);
+        assert_eq!(end, text.rfind('\n').unwrap() as u64 + 1);
+    }
+
+    #[test]
+    fn read_window_keeps_the_last_lines_of_a_long_window() {
+        let log = TempLog::new("window-tail", &minute_of_lines());
+        let (lines, _, _) = read_window(log.path(), (at("2024-05-01 10:00:00"), NaiveDateTime::MAX), 2, &LogFormat::Auto).unwrap();
+        assert_eq!(lines, ["2024-05-01 10:00:59 INFO tick 59", "    at frame 59"]);
+    }
+
+    #[test]
+    fn read_window_outside_the_file_is_empty() {
+        let text = minute_of_lines();
+        let log = TempLog::new("window-outside", &text);
+        let after = (at("2024-05-01 11:00:00"), NaiveDateTime::MAX);
+        let (lines, start, _) = read_window(log.path(), after, 100, &LogFormat::Auto).unwrap();
+        assert!(lines.is_empty());
+        assert_eq!(start, text.len() as u64);
+        let before = (NaiveDateTime::MIN, at("2024-05-01 09:00:00"));
+        assert!(read_window(log.path(), before, 100, &LogFormat::Auto).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn count_lines_from_counts_past_what_read_from_keeps() {
+        let text = minute_of_lines() + "2024-05-01 10:01:00 INFO partial";
+        let log = TempLog::new("count-from", &text);
+        let offset = text.find("2024-05-01 10:00:50").unwrap() as u64;
+        let (lines, _, _) = read_from(log.path(), offset, 4).unwrap();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(count_lines_from(log.path(), offset).unwrap(), 20);
+    }
+
+    #[test]
+    fn tail_reports_where_its_complete_lines_end() {
+        let text = minute_of_lines() + "2024-05-01 10:01:00 INFO partial";
+        let log = TempLog::new("tail-offset", &text);
+        let rx = spawn_tail(log.path().to_string(), 0, Arc::new(AtomicU64::new(10)), Arc::new(std::sync::RwLock::new(LogFormat::Auto)));
+        let Ok(IngestEvent::Lines(lines, offset)) = rx.recv_timeout(Duration::from_secs(5)) else {
+            panic!("no lines from the tail");
+        };
+        assert_eq!(lines.len(), 120);
+        assert_eq!(offset, Some(text.rfind('\n').unwrap() as u64 + 1));
+    }
+}
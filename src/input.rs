@@ -0,0 +1,301 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded, Receiver};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+
+use crate::app::{App, ViewMode};
+
+// Lines moved by each notch of the mouse wheel in the log view
+const WHEEL_LINES: usize = 3;
+
+// How long the reader waits for input before looking whether it should pause
+const READER_POLL: Duration = Duration::from_millis(50);
+
+// Set while another program, such as an editor, reads the terminal
+static READER_PAUSED: AtomicBool = AtomicBool::new(false);
+// Set by the reader once it has seen the pause and stopped reading
+static READER_IDLE: AtomicBool = AtomicBool::new(false);
+
+/// Read terminal events on a background thread so the UI loop can wait on
+/// input and new log lines at the same time. The thread stops after
+/// forwarding a read error or once the receiver is dropped.
+pub fn spawn_reader() -> Receiver<io::Result<Event>> {
+    let (tx, rx) = unbounded();
+    thread::spawn(move || loop {
+        if READER_PAUSED.load(Ordering::SeqCst) {
+            READER_IDLE.store(true, Ordering::SeqCst);
+            thread::sleep(READER_POLL);
+            continue;
+        }
+        let event = match event::poll(READER_POLL) {
+            Ok(false) => continue,
+            Ok(true) => event::read(),
+            Err(e) => Err(e),
+        };
+        let failed = event.is_err();
+        if tx.send(event).is_err() || failed {
+            return;
+        }
+    });
+    rx
+}
+
+/// Stop the reader taking keys from the terminal, waiting (briefly) until
+/// it has, so that a program run in the foreground gets them all.
+pub fn pause_reader() {
+    READER_PAUSED.store(true, Ordering::SeqCst);
+    let deadline = Instant::now() + READER_POLL * 10;
+    while !READER_IDLE.load(Ordering::SeqCst) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+pub fn resume_reader() {
+    READER_IDLE.store(false, Ordering::SeqCst);
+    READER_PAUSED.store(false, Ordering::SeqCst);
+}
+
+/// Apply a terminal event to `app` as the interactive loop does, paging by
+/// what fits in a terminal `height` rows tall. Returns whether it asked to quit.
+pub fn handle_event(app: &mut App, event: Event, height: u16) -> bool {
+    let page = height.saturating_sub(8) as isize;
+    if let Event::Mouse(mouse) = event {
+        if app.view_mode == ViewMode::HeatmapView && mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+            app.click_heatmap(mouse.column, mouse.row);
+        }
+        if app.view_mode == ViewMode::LogView {
+            match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+                    app.click_scrubber(mouse.column, mouse.row);
+                }
+                MouseEventKind::ScrollUp => (0..WHEEL_LINES).for_each(|_| app.scroll_up()),
+                MouseEventKind::ScrollDown => (0..WHEEL_LINES).for_each(|_| app.scroll_down()),
+                _ => {}
+            }
+        }
+    }
+    let Event::Key(key) = event else {
+        return false;
+    };
+    // Raw mode keeps the terminal from turning Ctrl+Z into SIGTSTP itself
+    if key.code == KeyCode::Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        app.suspend_requested = true;
+        return false;
+    }
+    if key.code == KeyCode::F(12) {
+        app.toggle_diagnostics();
+        return false;
+    }
+    if app.notes_panel.editing.is_some() {
+        app.handle_note_input(key.code);
+        return false;
+    }
+    if app.view_mode == ViewMode::StatsView && app.handle_stats_key(key.code) {
+        return false;
+    }
+    if app.view_mode == ViewMode::HeatmapView && app.handle_heatmap_key(key.code) {
+        return false;
+    }
+    if app.view_mode == ViewMode::AlertsView && app.handle_alerts_key(key.code) {
+        return false;
+    }
+    match app.view_mode {
+        ViewMode::FilterView => {
+            if key.code != KeyCode::Tab {
+                app.completion = None;
+            }
+            match key.code {
+                KeyCode::Tab => app.complete_filter(),
+                KeyCode::Enter => {
+                    app.view_mode = ViewMode::LogView;
+                    app.filter_editing = false;
+                    app.update_filter();
+                },
+                KeyCode::Esc => app.cancel_filter(),
+                KeyCode::Char(c) => {
+                    app.add_filter_char(c);
+                },
+                KeyCode::Backspace => {
+                    app.remove_filter_char();
+                },
+                _ => {}
+            }
+        },
+        ViewMode::BrowseView => {
+            let Some(browser) = &mut app.browser else {
+                app.close_browser();
+                return false;
+            };
+            if browser.search_editing {
+                match key.code {
+                    KeyCode::Enter => browser.start_search(),
+                    KeyCode::Esc => browser.search_editing = false,
+                    KeyCode::Char(c) => browser.search_input.push(c),
+                    KeyCode::Backspace => {
+                        browser.search_input.pop();
+                    },
+                    _ => {}
+                }
+                return false;
+            }
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('b') | KeyCode::Char('q') => app.close_browser(),
+                KeyCode::Up => browser.scroll_by(-1),
+                KeyCode::Down => browser.scroll_by(1),
+                KeyCode::PageUp => browser.scroll_by(-page),
+                KeyCode::PageDown => browser.scroll_by(page),
+                KeyCode::Home | KeyCode::Char('g') => browser.go_to_top(),
+                KeyCode::End | KeyCode::Char('G') => browser.go_to_end(page as usize),
+                KeyCode::Char('/') => {
+                    browser.search_input.clear();
+                    browser.search_editing = true;
+                },
+                KeyCode::Char('n') => browser.search_next(),
+                _ => {}
+            }
+        },
+        ViewMode::CommandView => {
+            match key.code {
+                KeyCode::Enter => app.run_command(),
+                KeyCode::Esc => {
+                    app.command_input.clear();
+                    app.sync_view_to_tab();
+                },
+                KeyCode::Char(c) => app.command_input.push(c),
+                KeyCode::Backspace => {
+                    app.command_input.pop();
+                },
+                _ => {}
+            }
+        },
+        ViewMode::SettingsView => app.handle_settings_key(key.code),
+        ViewMode::SourcesView => app.handle_sources_key(key.code),
+        ViewMode::CompareView => app.handle_compare_key(key.code),
+        ViewMode::DiffView => app.handle_diff_key(key.code, page),
+        ViewMode::DiagnosticsView => app.handle_diagnostics_key(key.code, page),
+        ViewMode::DetailView if app.detail.as_ref().is_some_and(|detail| detail.typing) => app.handle_detail_search_key(key.code),
+        ViewMode::DetailView if key.code == KeyCode::Char('a') => app.start_note(),
+        ViewMode::DetailView => app.handle_detail_key(key.code, page),
+        ViewMode::NotesView => app.handle_notes_key(key.code),
+        ViewMode::ProblemsView => app.handle_problems_key(key.code),
+        ViewMode::GroupView => app.handle_group_key(key.code),
+        ViewMode::PickerView => {
+            let Some(picker) = &mut app.picker else {
+                app.sync_view_to_tab();
+                return false;
+            };
+            match key.code {
+                // Nothing to go back to on the start screen
+                KeyCode::Esc | KeyCode::Char('q') if app.log_path.is_empty() => return true,
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    app.picker = None;
+                    app.sync_view_to_tab();
+                },
+                KeyCode::Up => picker.move_by(-1),
+                KeyCode::Down => picker.move_by(1),
+                KeyCode::PageUp => picker.move_by(-page),
+                KeyCode::PageDown => picker.move_by(page),
+                KeyCode::Backspace | KeyCode::Left => picker.go_up(),
+                KeyCode::Enter | KeyCode::Right => {
+                    if let Some(path) = picker.choose() {
+                        app.open_file(path);
+                    }
+                },
+                _ => {}
+            }
+        },
+        ViewMode::WatchView => {
+            match key.code {
+                KeyCode::Enter => app.add_watch(),
+                KeyCode::Esc => {
+                    app.sync_view_to_tab();
+                    app.watch_input.clear();
+                    app.watch_error = None;
+                },
+                KeyCode::Char(c) => {
+                    app.watch_input.push(c);
+                    app.check_watch_input();
+                },
+                KeyCode::Backspace => {
+                    app.watch_input.pop();
+                    app.check_watch_input();
+                },
+                _ => {}
+            }
+        },
+        _ => {
+            match app.keymap.translate(key) {
+                KeyCode::Char('q') => return true,
+                KeyCode::Char('f') => app.toggle_follow_mode(),
+                KeyCode::Char('t') => app.toggle_timestamps(),
+                KeyCode::Char('n') => app.toggle_line_numbers(),
+                KeyCode::Char('/') => app.start_filter(),
+                KeyCode::Char('w') => {
+                    app.view_mode = ViewMode::WatchView;
+                    app.watch_error = None;
+                },
+                KeyCode::Char(':') => app.view_mode = ViewMode::CommandView,
+                KeyCode::Enter if app.view_mode == ViewMode::LogView => app.open_detail(),
+                KeyCode::Char('O') if app.view_mode == ViewMode::LogView => app.open_link(0),
+                KeyCode::Char('a') if app.view_mode == ViewMode::LogView => app.start_note(),
+                KeyCode::Char('x') | KeyCode::Delete if app.view_mode == ViewMode::LogView => app.hide_lines(),
+                KeyCode::Char('X') if app.view_mode == ViewMode::LogView => app.unhide_last(),
+                KeyCode::Char('v') if app.view_mode == ViewMode::LogView => app.toggle_block(),
+                KeyCode::Esc if app.block_start.is_some() => app.block_start = None,
+                KeyCode::Char('A') => app.open_notes_panel(),
+                KeyCode::Char('p') => app.open_problems_panel(),
+                KeyCode::Char('[') if app.view_mode == ViewMode::LogView => app.step_scrubber(-1),
+                KeyCode::Char(']') if app.view_mode == ViewMode::LogView => app.step_scrubber(1),
+                KeyCode::Char('b') => app.open_browser(),
+                KeyCode::Char('4') => app.toggle_status_class(true),
+                KeyCode::Char('5') => app.toggle_status_class(false),
+                KeyCode::Char('s') => app.export_filtered(),
+                KeyCode::Char('d') => app.perf.show_overlay = !app.perf.show_overlay,
+                KeyCode::Char(',') => app.open_settings(),
+                KeyCode::Char('i') => app.open_sources_panel(),
+                KeyCode::Char('o') => app.open_picker(),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.clear_filter();
+                },
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo_view(),
+                KeyCode::Char('u') => app.undo_view(),
+                KeyCode::F(n @ 1..=4) => app.switch_workspace(n as usize - 1),
+                KeyCode::Tab => app.next_tab(),
+                KeyCode::BackTab => app.prev_tab(),
+                KeyCode::Up | KeyCode::Char('k') | KeyCode::PageUp if app.scroll == 0 && app.has_scrollback() => {
+                    // Scrolling up past the buffer continues into the spilled lines
+                    app.pause_follow();
+                    app.open_scrollback(page as usize);
+                },
+                KeyCode::Up | KeyCode::Char('k') => app.scroll_up(),
+                KeyCode::Down | KeyCode::Char('j') => app.scroll_down(),
+                KeyCode::PageUp => app.page_up(),
+                KeyCode::PageDown => app.page_down(),
+                KeyCode::Home | KeyCode::Char('g') => app.scroll_to_top(),
+                KeyCode::End | KeyCode::Char('G') => app.resume_follow(),
+                KeyCode::Char(' ') if app.replay.is_some() => {
+                    if let Some(replay) = &mut app.replay {
+                        replay.paused = !replay.paused;
+                    }
+                },
+                KeyCode::Left if app.replay.is_some() => app.seek_replay(-10_000.0),
+                KeyCode::Right if app.replay.is_some() => app.seek_replay(10_000.0),
+                KeyCode::Char('+') => {
+                    if let Some(replay) = &mut app.replay {
+                        replay.speed *= 2.0;
+                    }
+                },
+                KeyCode::Char('-') => {
+                    if let Some(replay) = &mut app.replay {
+                        replay.speed /= 2.0;
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,39 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Pool of shared strings, so lines repeating the same message text hold
+/// one copy between them instead of one each.
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The pooled copy of `text`, adding it if it is new.
+    pub fn intern(&mut self, text: &str) -> Arc<str> {
+        if let Some(shared) = self.strings.get(text) {
+            return shared.clone();
+        }
+        let shared: Arc<str> = Arc::from(text);
+        self.strings.insert(shared.clone());
+        shared
+    }
+
+    /// Forget strings that no buffered line refers to any more.
+    pub fn prune(&mut self) {
+        self.strings.retain(|shared| Arc::strong_count(shared) > 1);
+    }
+
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+
+    /// Approximate bytes held by the pooled strings.
+    pub fn memory_size(&self) -> usize {
+        self.strings.iter().map(|shared| shared.len() + 2 * std::mem::size_of::<usize>()).sum()
+    }
+}
@@ -0,0 +1,197 @@
+//! The control socket that lets `tlm-ctl` steer a running monitor.
+//!
+//! Started with `--control`, the monitor listens on a Unix domain socket
+//! and reads one request per line, answering each once it has been applied
+//! with a line starting with `ok` (followed by what a command reported, if
+//! anything) or `error:`:
+//!
+//! | Request            | Effect                                       |
+//! |--------------------|----------------------------------------------|
+//! | `filter <text>`    | Replace the filter, clearing it without text |
+//! | `open <target>`    | Attach a source, as `:open` does             |
+//! | `close <source>`   | Detach a source, as `:close` does            |
+//! | `command <text>`   | Run any `:` command                          |
+//! | `push <line>`      | Add a line to the main source                |
+//! | `scroll <row>`     | Show a row of the filtered view at the top   |
+//! | `follow`           | Go back to following the newest lines        |
+//! | `status`           | Answer with a summary: `ok app.log E3 W12`   |
+//! | `quit`             | Exit the monitor                             |
+//!
+//! The socket is only open to its owner, in a directory only its owner can
+//! get into. Sources and tees that run a shell command (`|command`) can't be
+//! opened through it, whichever command would open them.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::control::{AppHandle, Request};
+
+// How long a client waits for the monitor to apply its request
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the control socket goes unless `--control` names another path:
+/// `$XDG_RUNTIME_DIR/tui-log-monitor/control.sock`, or a per-user
+/// directory under the temp dir without a runtime dir.
+pub fn default_socket_path() -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| PathBuf::from(dir).join("tui-log-monitor"))
+        .unwrap_or_else(|| {
+            let user = std::env::var("USER").unwrap_or_default();
+            std::env::temp_dir().join(format!("tui-log-monitor-{}", user))
+        });
+    dir.join("control.sock")
+}
+
+/// Turn a request line into the request for the app.
+pub fn parse_request(line: &str) -> Result<Request, String> {
+    let (verb, arg) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+    let arg = arg.trim();
+    match verb {
+        "filter" => Ok(Request::SetFilter(arg.to_string())),
+        "open" | "close" if !arg.is_empty() => Ok(Request::RemoteCommand(format!("{} {}", verb, arg))),
+        "command" if !arg.is_empty() => Ok(Request::RemoteCommand(arg.trim_start_matches(':').to_string())),
+        "push" => Ok(Request::PushLine(arg.to_string())),
+        "scroll" => arg.parse().map(Request::ScrollTo).map_err(|_| format!("invalid row: {}", arg)),
+        "follow" => Ok(Request::Follow),
+        "quit" => Ok(Request::Quit),
+        "" => Err("empty request".to_string()),
+        _ => Err(format!("unknown request: {}", line.trim())),
+    }
+}
+
+/// A listening control socket, removed from the filesystem when dropped.
+pub struct ControlSocket {
+    pub path: PathBuf,
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Listen on `path` in the background, passing requests to `handle`. A
+/// socket file left behind by a monitor that didn't exit cleanly is
+/// replaced, but one another monitor is still listening on is not.
+#[cfg(unix)]
+pub fn serve(path: &Path, handle: AppHandle) -> io::Result<ControlSocket> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    // Whoever can write to the directory could swap the socket for their own
+    let metadata = std::fs::metadata(dir)?;
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let uid = unsafe { libc::geteuid() };
+    if metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} must be owned by you and closed to others (chmod 700) to hold the control socket", dir.display()),
+        ));
+    }
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("another monitor is listening on {}; pass --control with another path", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handle = handle.clone();
+            std::thread::spawn(move || serve_client(stream, handle));
+        }
+    });
+    Ok(ControlSocket { path: path.to_path_buf() })
+}
+
+#[cfg(not(unix))]
+pub fn serve(_path: &Path, _handle: AppHandle) -> io::Result<ControlSocket> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "the control socket needs Unix domain sockets"))
+}
+
+// Answer the requests of one connection until it closes or the app exits
+#[cfg(unix)]
+fn serve_client(stream: std::os::unix::net::UnixStream, handle: AppHandle) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        // Answered here rather than queued, for a status line polling it
+        if line.trim() == "status" {
+            if writeln!(writer, "ok {}", handle.summary()).is_err() {
+                return;
+            }
+            continue;
+        }
+        let reply = match parse_request(&line).and_then(|request| handle.call(request, REPLY_TIMEOUT)) {
+            Ok(message) if message.is_empty() => "ok".to_string(),
+            Ok(message) => format!("ok {}", message.replace('\n', " ")),
+            Err(e) => format!("error: {}", e.replace('\n', " ")),
+        };
+        if writeln!(writer, "{}", reply).is_err() {
+            return;
+        }
+    }
+}
+
+/// Send one request line to the monitor listening on `path` and return its reply.
+#[cfg(unix)]
+pub fn send(path: &Path, request: &str) -> io::Result<String> {
+    let mut stream = std::os::unix::net::UnixStream::connect(path)?;
+    writeln!(stream, "{}", request.replace('\n', " "))?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply.trim_end().to_string())
+}
+
+#[cfg(not(unix))]
+pub fn send(_path: &Path, _request: &str) -> io::Result<String> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "the control socket needs Unix domain sockets"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::App;
+
+    #[test]
+    fn parses_requests() {
+        assert!(matches!(parse_request("filter  error "), Ok(Request::SetFilter(text)) if text == "error"));
+        assert!(matches!(parse_request("filter"), Ok(Request::SetFilter(text)) if text.is_empty()));
+        assert!(matches!(parse_request("open app.log"), Ok(Request::RemoteCommand(text)) if text == "open app.log"));
+        assert!(matches!(parse_request("command :level error"), Ok(Request::RemoteCommand(text)) if text == "level error"));
+        assert!(matches!(parse_request("scroll 40"), Ok(Request::ScrollTo(40))));
+        assert!(matches!(parse_request("follow\n"), Ok(Request::Follow)));
+    }
+
+    #[test]
+    fn refuses_shell_commands() {
+        let marker = std::env::temp_dir().join(format!("tlm-refused-{}", std::process::id()));
+        let mut app = App::new("test.log".to_string());
+        for request in ["open |touch {}", "command :tee | touch {}", "command compare |touch {}", "command diff |touch${IFS}{} 10:00-11:00"] {
+            let request = parse_request(&request.replace("{}", &marker.display().to_string())).unwrap();
+            assert!(app.apply_request(request).is_err());
+        }
+        assert!(!marker.exists());
+        assert!(matches!(parse_request("command filter a|b"), Ok(Request::RemoteCommand(text)) if text == "filter a|b"));
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        assert_eq!(parse_request("scroll top").err().as_deref(), Some("invalid row: top"));
+        assert_eq!(parse_request("open").err().as_deref(), Some("unknown request: open"));
+        assert_eq!(parse_request("  ").err().as_deref(), Some("empty request"));
+        assert_eq!(parse_request("reboot now").err().as_deref(), Some("unknown request: reboot now"));
+    }
+}
@@ -0,0 +1,133 @@
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::poller::Poller;
+
+// How often the pods matching a selector are listed again
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Pods followed through `kubectl`: `k8s://[namespace/]<pod>` follows one
+/// pod, and `k8s://[namespace/]<selector>` (anything with an `=`, such as
+/// `app=frontend`) every running pod the label selector matches, as the
+/// `-l` of `kubectl logs` would, but with pods attached and detached as
+/// they come and go, and reattached when their container restarts.
+pub struct PodTarget {
+    pub namespace: Option<String>,
+    pub name: String, // Pod name or label selector
+}
+
+impl PodTarget {
+    pub fn parse(target: &str) -> Option<Self> {
+        let rest = target.strip_prefix("k8s://")?;
+        let (namespace, name) = match rest.split_once('/') {
+            Some((namespace, name)) => (Some(namespace.to_string()), name),
+            None => (None, rest),
+        };
+        Some(PodTarget { namespace, name: name.to_string() })
+    }
+
+    pub fn is_selector(&self) -> bool {
+        self.name.contains('=')
+    }
+
+    /// The source target following `pod`'s log from its last `tail` lines,
+    /// or from its start for `None`.
+    pub fn logs_command(&self, pod: &str, tail: Option<usize>) -> String {
+        let tail = tail.map_or_else(|| "-1".to_string(), |lines| lines.to_string());
+        self.follow(pod, &format!("--tail={}", tail))
+    }
+
+    /// The source target following `pod`'s log from `since` ago, picking up
+    /// where a source that ended left off. Only the current container's log
+    /// is shown, so after a restart that is all of the new one's.
+    pub fn logs_command_since(&self, pod: &str, since: Duration) -> String {
+        self.follow(pod, &format!("--since={}s", since.as_secs() + 1))
+    }
+
+    fn follow(&self, pod: &str, from: &str) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("|kubectl logs -f {} -n {} {}", from, namespace, pod),
+            None => format!("|kubectl logs -f {} {}", from, pod),
+        }
+    }
+
+    /// The running pods the selector matches right now, or the named pod if
+    /// it is running.
+    pub fn running_pods(&self) -> Result<Vec<String>, String> {
+        let mut command = Command::new("kubectl");
+        command.args(["get", "pods"]);
+        if self.is_selector() {
+            command.args(["-l", &self.name, "--field-selector=status.phase=Running"]);
+        } else {
+            command.arg(format!("--field-selector=metadata.name={},status.phase=Running", self.name));
+        }
+        command.args(["-o", "jsonpath={.items[*].metadata.name}"]);
+        if let Some(namespace) = &self.namespace {
+            command.args(["-n", namespace]);
+        }
+        let output = command.stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("Cannot run kubectl: {}", e))?;
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("kubectl get pods failed: {}", error.trim()));
+        }
+        let mut pods: Vec<String> = String::from_utf8_lossy(&output.stdout).split_whitespace().map(str::to_string).collect();
+        pods.sort();
+        Ok(pods)
+    }
+}
+
+/// A pod or label selector being watched: the pods it matched at the last
+/// look, with a poller listing them again every few seconds.
+pub struct PodWatch {
+    pub target: String, // As given to `:open`
+    pub pod: PodTarget,
+    pub pods: Vec<String>, // Running pods, attached as sources
+    pub gone: Vec<u16>,    // Sources of pods that went away, dropped once their lines have left the buffer
+    pub poller: Poller<Result<Vec<String>, String>>,
+}
+
+impl PodWatch {
+    /// Start watching `pod`, whose pods right now are `pods`.
+    pub fn start(target: &str, pod: PodTarget, pods: Vec<String>) -> Self {
+        let listed = PodTarget { namespace: pod.namespace.clone(), name: pod.name.clone() };
+        let mut last = Ok(pods.clone());
+        // Every listing is sent, even an unchanged one, so pods whose log
+        // ended while they kept running are looked at again
+        let poller = Poller::start(POLL_INTERVAL, move || {
+            let current = listed.running_pods();
+            if current.is_err() && current == last {
+                return None;
+            }
+            last = current.clone();
+            Some(current)
+        });
+        PodWatch { target: target.to_string(), pod, pods, gone: Vec::new(), poller }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pod_targets_with_and_without_a_namespace() {
+        let pod = PodTarget::parse("k8s://api-7d9f").unwrap();
+        assert_eq!((pod.namespace, pod.name), (None, "api-7d9f".to_string()));
+        let selected = PodTarget::parse("k8s://shop/app=web").unwrap();
+        assert_eq!(selected.namespace.as_deref(), Some("shop"));
+        assert_eq!(selected.name, "app=web");
+        assert!(selected.is_selector());
+        assert!(PodTarget::parse("/var/log/syslog").is_none());
+    }
+
+    #[test]
+    fn logs_command_follows_from_the_tail_or_the_start() {
+        let pod = PodTarget::parse("k8s://shop/app=web").unwrap();
+        assert_eq!(pod.logs_command("web-1", Some(50)), "|kubectl logs -f --tail=50 -n shop web-1");
+        let pod = PodTarget::parse("k8s://web-1").unwrap();
+        assert_eq!(pod.logs_command("web-1", None), "|kubectl logs -f --tail=-1 web-1");
+        assert_eq!(pod.logs_command_since("web-1", Duration::from_millis(4500)), "|kubectl logs -f --since=5s web-1");
+    }
+}
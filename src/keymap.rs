@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+// Remappable actions and the key each is bound to by default
+const ACTIONS: [(&str, char); 10] = [
+    ("quit", 'q'),
+    ("follow", 'f'),
+    ("timestamps", 't'),
+    ("line_numbers", 'n'),
+    ("filter", '/'),
+    ("watch", 'w'),
+    ("command", ':'),
+    ("browse", 'b'),
+    ("save", 's'),
+    ("timings", 'd'),
+];
+
+/// Key bindings from the `[keymap]` table of the config file, mapping each
+/// pressed key to the default key of the action it is bound to.
+#[derive(Default)]
+pub struct Keymap {
+    keys: HashMap<char, Option<char>>, // `None` unbinds a default key given to another action
+}
+
+impl Keymap {
+    /// Build from `action = "key"` pairs, rejecting unknown actions and keys
+    /// that are not a single character.
+    pub fn from_config(bindings: &HashMap<String, String>) -> Result<Self, String> {
+        let mut keys = HashMap::new();
+        for (action, key) in bindings {
+            let default = ACTIONS.iter()
+                .find(|(name, _)| name == action)
+                .map(|&(_, default)| default)
+                .ok_or_else(|| format!("Unknown keymap action: {}", action))?;
+            let mut chars = key.chars();
+            let (Some(pressed), None) = (chars.next(), chars.next()) else {
+                return Err(format!("Keymap key for {} must be a single character: {:?}", action, key));
+            };
+            keys.entry(default).or_insert(None);
+            keys.insert(pressed, Some(default));
+        }
+        Ok(Keymap { keys })
+    }
+
+    /// The key the default bindings know the pressed key as. Control chords
+    /// are left alone.
+    pub fn translate(&self, key: KeyEvent) -> KeyCode {
+        let code = key.code;
+        match code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => match self.keys.get(&c) {
+                Some(Some(default)) => KeyCode::Char(*default),
+                Some(None) => KeyCode::Null,
+                None => code,
+            },
+            _ => code,
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDateTime;
+use regex::Regex;
+
+use crate::config::PairConfig;
+
+// Starts waiting for their end, per rule; past this the oldest is given up on
+const MAX_OPEN: usize = 10_000;
+
+// Latencies kept per rule for the percentiles, the newest ones
+const MAX_SAMPLES: usize = 10_000;
+
+// How long a rule's percentiles stand while pairs come in
+const SUMMARY_REFRESH: Duration = Duration::from_secs(1);
+
+/// The percentiles shown for each pairing rule in the Statistics tab.
+pub const PERCENTILES: [u32; 3] = [50, 90, 99];
+
+/// A `[[pair]]` from the config file: a `start` and an `end` pattern whose
+/// first group is an id, such as a request id, pairing each end line with
+/// the start line of the same id. How long it took, from the timestamp of
+/// the start to that of the end, is shown on the end line.
+pub struct PairRule {
+    pub name: String,
+    start: Regex,
+    end: Regex,
+    open: HashMap<String, (NaiveDateTime, u64)>, // Ids started and not ended yet, with when they started and their start number
+    started: VecDeque<(u64, String)>,             // Start numbers and ids, oldest first, some of them since ended
+    starts: u64,                                  // Starts taken in, numbering the next one
+    samples: VecDeque<Duration>,                  // Latencies of the latest pairs, oldest first
+    summary: Option<(Vec<Duration>, Duration)>,   // Percentiles and longest of `samples`, as last summarized
+    summarized: Option<(Instant, usize)>,         // When that was, and `pairs` at the time
+    pub pairs: usize,                             // Ends paired with their start, all told
+}
+
+impl PairRule {
+    pub fn new(config: &PairConfig) -> Result<Self, String> {
+        let compile = |pattern: &str| {
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid pattern for pair {}: {}", config.name, e))?;
+            if regex.captures_len() < 2 {
+                return Err(format!("The patterns of pair {} need a group capturing the id", config.name));
+            }
+            Ok(regex)
+        };
+        Ok(PairRule {
+            name: config.name.clone(),
+            start: compile(&config.start)?,
+            end: compile(&config.end)?,
+            open: HashMap::new(),
+            started: VecDeque::new(),
+            starts: 0,
+            samples: VecDeque::new(),
+            summary: None,
+            summarized: None,
+            pairs: 0,
+        })
+    }
+
+    /// Take in a line stamped `time`: a start opens its id, and an end
+    /// closes it, returning how long the id was open.
+    pub fn observe(&mut self, line: &str, time: NaiveDateTime) -> Option<Duration> {
+        if let Some(id) = self.end.captures(line).and_then(|captures| captures.get(1)) {
+            let (started, _) = self.open.remove(id.as_str())?;
+            // A start stamped after its end was logged out of order; there is no latency to tell
+            let elapsed = (time - started).to_std().ok()?;
+            self.pairs += 1;
+            if self.samples.len() == MAX_SAMPLES {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(elapsed);
+            return Some(elapsed);
+        }
+        let id = self.start.captures(line)?.get(1)?.as_str();
+        if self.open.len() >= MAX_OPEN && !self.open.contains_key(id) {
+            self.give_up_oldest();
+        }
+        self.open.insert(id.to_string(), (time, self.starts));
+        self.started.push_back((self.starts, id.to_string()));
+        self.starts += 1;
+        // Ids that ended stay in `started` until they reach its front; sweep
+        // them out now and then so it can't outgrow `open` for long
+        if self.started.len() >= 2 * MAX_OPEN {
+            let open = &self.open;
+            self.started.retain(|(start, id)| open.get(id).is_some_and(|&(_, open_start)| open_start == *start));
+        }
+        None
+    }
+
+    // Stop waiting for the end of the id that started first
+    fn give_up_oldest(&mut self) {
+        while let Some((start, id)) = self.started.pop_front() {
+            if self.open.get(&id).is_some_and(|&(_, open_start)| open_start == start) {
+                self.open.remove(&id);
+                return;
+            }
+        }
+    }
+
+    /// Ids started and still waiting for their end.
+    pub fn open_count(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Summarize the latest pairs again, if more came in since the last
+    /// time and that was a while ago.
+    pub fn refresh_summary(&mut self) {
+        if self.summarized.is_some_and(|(at, pairs)| pairs == self.pairs || at.elapsed() < SUMMARY_REFRESH) {
+            return;
+        }
+        self.summary = summarize(self.samples.iter().copied().collect(), &PERCENTILES);
+        self.summarized = Some((Instant::now(), self.pairs));
+    }
+
+    /// The latencies at each of `PERCENTILES`, and the longest, over the
+    /// latest pairs as last summarized; `None` before the first pair.
+    pub fn summary(&self) -> Option<(Vec<Duration>, Duration)> {
+        self.summary.clone()
+    }
+}
+
+/// The durations at each of `percentiles` among `samples`, and the longest;
+/// `None` when there are none.
+pub fn summarize(mut samples: Vec<Duration>, percentiles: &[u32]) -> Option<(Vec<Duration>, Duration)> {
+    samples.sort_unstable();
+    let max = *samples.last()?;
+    let at = |p: u32| samples[(samples.len() * p as usize).div_ceil(100).saturating_sub(1)];
+    Some((percentiles.iter().map(|&p| at(p)).collect(), max))
+}
+
+/// A latency as short as it reads well: `850µs`, `235ms`, `1.25s`, `2m05s`.
+pub fn format_latency(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    if secs < 0.001 {
+        format!("{}µs", elapsed.as_micros())
+    } else if secs < 1.0 {
+        format!("{}ms", elapsed.as_millis())
+    } else if secs < 60.0 {
+        format!("{:.2}s", secs)
+    } else {
+        format!("{}m{:02}s", elapsed.as_secs() / 60, elapsed.as_secs() % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_opt(10, 0, 0).unwrap() + chrono::Duration::seconds(seconds.into())
+    }
+
+    #[test]
+    fn the_first_open_id_is_given_up_on_when_too_many_are_open() {
+        let config = PairConfig { name: "request".to_string(), start: r"start (\d+)".to_string(), end: r"end (\d+)".to_string() };
+        let mut rule = PairRule::new(&config).unwrap();
+        for id in 0..MAX_OPEN {
+            rule.observe(&format!("start {}", id), at(0));
+        }
+        // The first id ends, leaving the second the oldest still open
+        assert_eq!(rule.observe("end 0", at(1)), Some(Duration::from_secs(1)));
+        rule.observe("start 0", at(2));
+        rule.observe(&format!("start {}", MAX_OPEN), at(3));
+        assert_eq!(rule.open_count(), MAX_OPEN);
+        assert_eq!(rule.observe("end 1", at(4)), None);
+        assert_eq!(rule.observe("end 0", at(4)), Some(Duration::from_secs(2)));
+        assert_eq!(rule.observe("end 2", at(4)), Some(Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn summarize_picks_the_nearest_rank() {
+        let samples: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let (at, max) = summarize(samples, &PERCENTILES).unwrap();
+        assert_eq!(at, [Duration::from_millis(50), Duration::from_millis(90), Duration::from_millis(99)]);
+        assert_eq!(max, Duration::from_millis(100));
+        let (at, max) = summarize(vec![Duration::from_secs(3)], &[50]).unwrap();
+        assert_eq!((at, max), (vec![Duration::from_secs(3)], Duration::from_secs(3)));
+        assert!(summarize(Vec::new(), &PERCENTILES).is_none());
+    }
+
+    #[test]
+    fn format_latency_picks_a_unit() {
+        assert_eq!(format_latency(Duration::from_micros(850)), "850µs");
+        assert_eq!(format_latency(Duration::from_millis(235)), "235ms");
+        assert_eq!(format_latency(Duration::from_millis(1250)), "1.25s");
+        assert_eq!(format_latency(Duration::from_secs(125)), "2m05s");
+    }
+}
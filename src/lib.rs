@@ -0,0 +1,82 @@
+//! Terminal dashboard for following and exploring log files.
+//!
+//! The `log_monitor` binary is a thin wrapper around [`cli::run`]. The pieces
+//! it is built from can also be used on their own: [`parse`] turns raw lines
+//! into [`LogLine`]s, [`filter`] decides which of them are shown, [`stats`]
+//! counts them, [`source`] reads them from files, commands and sockets, and
+//! [`ui`] draws an [`App`] with ratatui. [`widget`] offers a standalone log
+//! pane for other ratatui applications, and an [`AppHandle`] steers a running
+//! app from other threads.
+
+pub mod app;
+pub mod bench;
+pub mod cli;
+pub mod config;
+pub mod control;
+pub mod filter;
+pub mod format;
+pub mod ipc;
+pub mod parse;
+pub mod source;
+pub mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod ui;
+pub mod widget;
+
+mod alerts;
+mod audit;
+mod browse;
+mod clean;
+mod columns;
+mod compare;
+mod complete;
+mod detail;
+mod diag;
+mod diff;
+mod enrich;
+mod export;
+mod headless;
+mod highlight;
+mod index;
+mod intern;
+mod ingest;
+mod input;
+mod k8s;
+mod keymap;
+mod latency;
+mod links;
+mod lookup;
+mod metrics;
+mod notes;
+mod perf;
+mod picker;
+mod poller;
+mod print;
+mod problems;
+mod redact;
+mod remap;
+mod reorder;
+mod replay;
+mod rotate;
+mod script;
+mod scrubber;
+mod session;
+mod settings;
+mod signals;
+mod spill;
+mod startup;
+mod systemd;
+mod tee;
+mod term;
+mod theme;
+mod throttle;
+mod title;
+mod tokens;
+mod undo;
+mod window;
+mod workspace;
+
+pub use app::App;
+pub use control::AppHandle;
+pub use parse::{LogLevel, LogLine};
@@ -0,0 +1,188 @@
+use std::borrow::Cow;
+use std::env;
+use std::fs::File;
+use std::io::{self, IsTerminal};
+use std::ops::Range;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Span;
+use regex::Regex;
+
+// URLs, Python's `File "path", line N`, and compiler-style `path:line[:column]`
+const LINK_PATTERN: &str = r#"(?P<url>\b(?:https?|ftp)://[^\s<>"'`]+)|File "(?P<py_path>[^"]+)", line (?P<py_line>\d+)|(?P<path>[\w.~/+-]*\.[A-Za-z]\w*):(?P<line>\d+)(?P<column>:\d+)?"#;
+
+// Extensions a `name.ext:line` without a directory or column must have to
+// count as a file, so that `db.example.com:5432` does not
+const SOURCE_EXTENSIONS: [&str; 32] = [
+    "c", "cc", "cpp", "cs", "css", "dart", "erl", "ex", "exs", "go", "h", "hpp", "hs", "html", "java", "js",
+    "json", "jsx", "kt", "lua", "ml", "php", "pl", "py", "rb", "rs", "scala", "sh", "swift", "ts", "tsx", "zig",
+];
+
+// What opens a URL in the browser
+#[cfg(target_os = "macos")]
+const URL_OPENER: &[&str] = &["open"];
+// Not `cmd /C start`, which would run what follows a `&` or `|` in the URL
+#[cfg(windows)]
+const URL_OPENER: &[&str] = &["rundll32", "url.dll,FileProtocolHandler"];
+#[cfg(not(any(target_os = "macos", windows)))]
+const URL_OPENER: &[&str] = &["xdg-open"];
+
+/// Something in a line that can be opened.
+#[derive(Debug, PartialEq)]
+pub enum Link {
+    Url(String),
+    File { path: String, line: u32 }, // Opened in `$EDITOR` at the line
+}
+
+impl Link {
+    pub fn label(&self) -> String {
+        match self {
+            Link::Url(url) => url.clone(),
+            Link::File { path, line } => format!("{}:{}", path, line),
+        }
+    }
+}
+
+/// Finds the links in lines: URLs, and references to a line of a file as
+/// compilers, test runners and stack traces print them.
+pub struct LinkFinder {
+    regex: Regex,
+}
+
+impl Default for LinkFinder {
+    fn default() -> Self {
+        LinkFinder { regex: Regex::new(LINK_PATTERN).expect("valid link pattern") }
+    }
+}
+
+impl LinkFinder {
+    /// The links in `text`, in order, with where each is.
+    pub fn find(&self, text: &str) -> Vec<(Range<usize>, Link)> {
+        self.regex.captures_iter(text).filter_map(|captures| {
+            if let Some(url) = captures.name("url") {
+                // Punctuation closing a sentence or a bracket around the URL is not part of it
+                let url = url.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}']);
+                let start = captures.get(0)?.start();
+                return Some((start..start + url.len(), Link::Url(url.to_string())));
+            }
+            let (path, line) = match (captures.name("py_path"), captures.name("path")) {
+                (Some(path), _) => (path, captures.name("py_line")?),
+                (None, Some(path)) => (path, captures.name("line")?),
+                _ => return None,
+            };
+            let extension = path.as_str().rsplit('.').next().unwrap_or_default();
+            let bare = captures.name("path").is_some() && !path.as_str().contains('/') && captures.name("column").is_none();
+            if bare && !SOURCE_EXTENSIONS.contains(&extension) {
+                return None;
+            }
+            let link = Link::File { path: path.as_str().to_string(), line: line.as_str().parse().ok()? };
+            Some((captures.get(0)?.range(), link))
+        }).collect()
+    }
+
+    /// `spans`, which together spell out `text`, with the links in it underlined.
+    pub fn underline<'a>(&self, text: &str, spans: Vec<Span<'a>>) -> Vec<Span<'a>> {
+        let ranges: Vec<Range<usize>> = self.find(text).into_iter().map(|(range, _)| range).collect();
+        if ranges.is_empty() {
+            return spans;
+        }
+        let piece = |content: Cow<'a, str>, style: Style, start: usize| {
+            let linked = ranges.iter().any(|range| range.contains(&start));
+            Span::styled(content, if linked { style.add_modifier(Modifier::UNDERLINED) } else { style })
+        };
+        let mut underlined = Vec::new();
+        let mut pos = 0;
+        for span in spans {
+            let end = pos + span.content.len();
+            let (mut rest, mut start) = (span.content, pos);
+            for cut in ranges.iter().flat_map(|range| [range.start, range.end]).filter(|&cut| cut > pos && cut < end) {
+                if cut <= start {
+                    continue; // One link ends where the next starts
+                }
+                let (head, tail) = split(rest, cut - start);
+                underlined.push(piece(head, span.style, start));
+                (rest, start) = (tail, cut);
+            }
+            underlined.push(piece(rest, span.style, start));
+            pos = end;
+        }
+        underlined
+    }
+}
+
+// Split span text at byte `at`, keeping borrowed text borrowed
+fn split(content: Cow<'_, str>, at: usize) -> (Cow<'_, str>, Cow<'_, str>) {
+    match content {
+        Cow::Borrowed(text) => (Cow::Borrowed(&text[..at]), Cow::Borrowed(&text[at..])),
+        Cow::Owned(text) => (Cow::Owned(text[..at].to_string()), Cow::Owned(text[at..].to_string())),
+    }
+}
+
+/// Open `url` in the browser, without waiting for it.
+pub fn open_url(url: &str) -> Result<(), String> {
+    let mut child = Command::new(URL_OPENER[0])
+        .args(&URL_OPENER[1..])
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Cannot run {}: {}", URL_OPENER[0], e))?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Edit `path` at `line` in `$VISUAL` or `$EDITOR` (`vi` without either),
+/// waiting until the editor exits. It gets the terminal to itself.
+pub fn edit(path: &Path, line: u32) -> Result<(), String> {
+    let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let mut command = Command::new(program);
+    command.args(words).arg(format!("+{}", line)).arg(path);
+    // With lines coming in on stdin, the editor reads the keyboard from the terminal
+    if !io::stdin().is_terminal() && let Ok(tty) = File::open("/dev/tty") {
+        command.stdin(tty);
+    }
+    let status = command.status().map_err(|e| format!("Cannot run {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(text: &str) -> Vec<Link> {
+        LinkFinder::default().find(text).into_iter().map(|(_, link)| link).collect()
+    }
+
+    fn file(path: &str, line: u32) -> Link {
+        Link::File { path: path.to_string(), line }
+    }
+
+    #[test]
+    fn finds_urls_without_closing_punctuation() {
+        let text = "see (https://example.com/docs?q=1).";
+        let found = LinkFinder::default().find(text);
+        assert_eq!(found, [(5..33, Link::Url("https://example.com/docs?q=1".to_string()))]);
+    }
+
+    #[test]
+    fn finds_file_references() {
+        assert_eq!(links("  --> src/main.rs:4:18"), [file("src/main.rs", 4)]);
+        assert_eq!(links(r#"  File "/app/run.py", line 12, in main"#), [file("/app/run.py", 12)]);
+        assert_eq!(links("panicked at lib.rs:7"), [file("lib.rs", 7)]);
+    }
+
+    #[test]
+    fn a_bare_host_and_port_is_not_a_file() {
+        assert!(links("connecting to db.example.com:5432").is_empty());
+        assert_eq!(links("config/db.example.com:5432"), [file("config/db.example.com", 5432)]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use ratatui::style::Color;
+use regex::Regex;
+
+use crate::columns::{ExtraColumn, MAX_COLUMN_WIDTH};
+use crate::config::LookupConfig;
+
+/// A `[[lookup]]` table from the config file: a value picked out of each
+/// line, the `field` of a logfmt or JSON line or the first group of a
+/// `pattern`, mapped to what the table has for it, such as a customer id
+/// to the customer's name.
+pub struct LookupTable {
+    pub name: String,
+    key: Regex,
+    values: HashMap<String, String>,
+    pub width: usize, // Of the column, enough for the longest value
+}
+
+impl LookupTable {
+    pub fn new(config: &LookupConfig) -> Result<Self, String> {
+        let key = match (&config.field, &config.pattern) {
+            (Some(field), None) => {
+                let field = regex::escape(field);
+                format!(r#"(?:\b{}=|"{}"\s*:\s*)"?([^\s",}}]+)"#, field, field)
+            }
+            (None, Some(pattern)) => pattern.clone(),
+            _ => return Err(format!("Lookup {} needs either a field or a pattern", config.name)),
+        };
+        let key = Regex::new(&key).map_err(|e| format!("Invalid lookup pattern for {}: {}", config.name, e))?;
+        if key.captures_len() < 2 {
+            return Err(format!("The pattern of lookup {} needs a group capturing the key", config.name));
+        }
+        let text = fs::read_to_string(&config.table).map_err(|e| format!("Cannot read {}: {}", config.table, e))?;
+        let is_json = Path::new(&config.table).extension().is_some_and(|extension| extension == "json");
+        let values = if is_json {
+            json_table(&text, config.column.as_deref())
+        } else {
+            csv_table(&text, config.column.as_deref())
+        }
+        .map_err(|e| format!("Invalid lookup table {}: {}", config.table, e))?;
+        let width = values.values().map(|value| value.chars().count()).max().unwrap_or(0).min(MAX_COLUMN_WIDTH);
+        Ok(LookupTable { name: config.name.clone(), key, values, width })
+    }
+
+}
+
+impl ExtraColumn for LookupTable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn color(&self) -> Color {
+        Color::Magenta
+    }
+
+    // What the table has for the key in `line`, if it has a key the table knows
+    fn value<'a>(&'a self, line: &'a str) -> Option<&'a str> {
+        let key = self.key.captures(line)?.get(1)?;
+        self.values.get(key.as_str()).map(String::as_str)
+    }
+}
+
+// A CSV table with a header row: keys from the first column, values from the
+// one named `column` or else the second
+fn csv_table(text: &str, column: Option<&str>) -> Result<HashMap<String, String>, String> {
+    let mut rows = text.lines().filter(|line| !line.trim().is_empty()).map(csv_fields);
+    let header = rows.next().ok_or("it is empty")?;
+    let column = match column {
+        Some(name) => header.iter().position(|heading| heading == name).ok_or_else(|| format!("no column {}", name))?,
+        None if header.len() > 1 => 1,
+        None => return Err("it needs a key and a value column".to_string()),
+    };
+    Ok(rows
+        .filter_map(|mut row| {
+            let value = row.get_mut(column).map(std::mem::take)?;
+            Some((row.swap_remove(0), value))
+        })
+        .collect())
+}
+
+// The fields of a CSV row, unquoting those in double quotes
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().expect("a field").push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().expect("a field").push(c),
+        }
+    }
+    fields.into_iter().map(|field| field.trim().to_string()).collect()
+}
+
+// A JSON object of keys to values, or to objects whose `column` is the value
+fn json_table(text: &str, column: Option<&str>) -> Result<HashMap<String, String>, String> {
+    let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    Ok(object
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = match (value, column) {
+                (serde_json::Value::Object(mut fields), Some(column)) => fields.remove(column)?,
+                (value, _) => value,
+            };
+            let value = match value {
+                serde_json::Value::String(text) => text,
+                serde_json::Value::Null | serde_json::Value::Object(_) => return None,
+                other => other.to_string(),
+            };
+            Some((key, value))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(pattern: &str) -> LookupConfig {
+        LookupConfig {
+            name: "customer".to_string(),
+            table: "customers.csv".to_string(),
+            field: None,
+            pattern: Some(pattern.to_string()),
+            column: None,
+        }
+    }
+
+    #[test]
+    fn a_pattern_needs_a_group_capturing_the_key() {
+        let error = LookupTable::new(&config(r"customer \d+")).err().unwrap();
+        assert_eq!(error, "The pattern of lookup customer needs a group capturing the key");
+        // With a group, it gets as far as reading the table
+        let error = LookupTable::new(&config(r"customer (\d+)")).err().unwrap();
+        assert!(error.starts_with("Cannot read customers.csv"), "{}", error);
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crate::LogLevel;
+
+// Counters published on the optional /metrics endpoint
+#[derive(Default)]
+pub struct Metrics {
+    pub lines_ingested: AtomicU64,
+    pub info_lines: AtomicU64,
+    pub debug_lines: AtomicU64,
+    pub trace_lines: AtomicU64,
+    pub warning_lines: AtomicU64,
+    pub error_lines: AtomicU64,
+    pub fatal_lines: AtomicU64,
+    pub unknown_lines: AtomicU64,
+    pub alerts_fired: AtomicU64,
+    pub buffered_lines: AtomicU64,
+    pub buffer_bytes: AtomicU64,
+    ingest_rate_bits: AtomicU64, // f64 lines/sec stored as raw bits
+}
+
+impl Metrics {
+    pub fn record_line(&self, level: LogLevel) {
+        self.lines_ingested.fetch_add(1, Ordering::Relaxed);
+        let counter = match level {
+            LogLevel::Info => &self.info_lines,
+            LogLevel::Debug => &self.debug_lines,
+            LogLevel::Trace => &self.trace_lines,
+            LogLevel::Warning => &self.warning_lines,
+            LogLevel::Error => &self.error_lines,
+            LogLevel::Fatal => &self.fatal_lines,
+            LogLevel::Unknown => &self.unknown_lines,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_ingest_rate(&self, lines_per_sec: f64) {
+        self.ingest_rate_bits.store(lines_per_sec.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn ingest_rate(&self) -> f64 {
+        f64::from_bits(self.ingest_rate_bits.load(Ordering::Relaxed))
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP log_monitor_lines_ingested_total Log lines read from the source.\n");
+        out.push_str("# TYPE log_monitor_lines_ingested_total counter\n");
+        out.push_str(&format!("log_monitor_lines_ingested_total {}\n", self.lines_ingested.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP log_monitor_lines_by_level_total Log lines read, by detected level.\n");
+        out.push_str("# TYPE log_monitor_lines_by_level_total counter\n");
+        for (level, counter) in [
+            ("info", &self.info_lines),
+            ("debug", &self.debug_lines),
+            ("trace", &self.trace_lines),
+            ("warning", &self.warning_lines),
+            ("error", &self.error_lines),
+            ("fatal", &self.fatal_lines),
+            ("unknown", &self.unknown_lines),
+        ] {
+            out.push_str(&format!(
+                "log_monitor_lines_by_level_total{{level=\"{}\"}} {}\n",
+                level,
+                counter.load(Ordering::Relaxed)
+            ));
+        }
+
+        out.push_str("# HELP log_monitor_alerts_fired_total Watch rule firings.\n");
+        out.push_str("# TYPE log_monitor_alerts_fired_total counter\n");
+        out.push_str(&format!("log_monitor_alerts_fired_total {}\n", self.alerts_fired.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP log_monitor_buffered_lines Lines currently held in the buffer.\n");
+        out.push_str("# TYPE log_monitor_buffered_lines gauge\n");
+        out.push_str(&format!("log_monitor_buffered_lines {}\n", self.buffered_lines.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP log_monitor_buffer_bytes Approximate memory held by the line buffer.\n");
+        out.push_str("# TYPE log_monitor_buffer_bytes gauge\n");
+        out.push_str(&format!("log_monitor_buffer_bytes {}\n", self.buffer_bytes.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP log_monitor_ingest_rate Lines ingested per second, recently.\n");
+        out.push_str("# TYPE log_monitor_ingest_rate gauge\n");
+        out.push_str(&format!("log_monitor_ingest_rate {:.3}\n", self.ingest_rate()));
+        out
+    }
+}
+
+/// Bind `addr` and serve `GET /metrics` from a background thread.
+pub fn serve(addr: &str, metrics: Arc<Metrics>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = handle_request(stream, &metrics);
+        }
+    });
+    Ok(())
+}
+
+fn handle_request(mut stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", metrics.render())
+    } else {
+        ("404 Not Found", "Not Found\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
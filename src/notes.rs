@@ -0,0 +1,25 @@
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// Drawn in front of lines that have a note.
+pub const NOTE_ICON: &str = "✎";
+
+/// A free-text note attached to a log line with `a`, so the story of an
+/// incident can be written down next to the lines it is about.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub text: String,
+    pub line: String,                // The line as read, kept once it leaves the buffer
+    pub source: String,
+    pub time: Option<NaiveDateTime>, // When the line was logged, if it says
+    pub created: DateTime<Local>,
+    #[serde(skip)]
+    pub seq: Option<u64>, // Sequence number of the line while it is buffered
+}
+
+/// The annotations panel opened with `A`, and the note being typed.
+#[derive(Default)]
+pub struct NotesPanel {
+    pub selected: usize,
+    pub editing: Option<(u64, String)>, // Line the note is for and the text so far
+}
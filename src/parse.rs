@@ -0,0 +1,293 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use ratatui::style::Color;
+
+use crate::clean::clean_line;
+use crate::format::LogFormat;
+use crate::intern::Interner;
+
+pub(crate) fn parse_level(name: &str) -> Result<LogLevel, String> {
+    LogLevel::from_name(name).ok_or(format!("unknown level: {}", name))
+}
+
+/// A line of a log with its timestamp and level picked out.
+///
+/// The line is stored in two parts: a per-line head (timestamp, level, ids) and
+/// the message after it, which repeats across many lines and so is interned.
+pub struct LogLine {
+    pub(crate) seq: u64, // Stable sequence number, survives eviction of older lines
+    pub(crate) source: u16, // Id of the source it came from
+    pub(crate) line: u64,   // Line number within its source
+    pub(crate) head: Box<str>,
+    pub(crate) message: Arc<str>,
+    pub(crate) head_lower: Box<str>,     // Cached lowercase parts for case-insensitive filtering
+    pub(crate) message_lower: Arc<str>,
+    pub(crate) timestamp_start: u8,      // Byte range of the timestamp within `head`, empty if none
+    pub(crate) timestamp_len: u8,
+    pub(crate) time: Option<NaiveDateTime>,
+    pub(crate) level: LogLevel,
+    pub(crate) status: u16, // HTTP status of an access log line, 0 for other lines
+    pub(crate) highlighted: bool,
+    pub(crate) restart: bool, // Matched a startup marker
+    pub(crate) hidden: bool,  // Taken out of the view by hand (x)
+}
+
+impl LogLine {
+    /// Parse a raw line into its timestamp and level, tidying up carriage
+    /// returns and control characters first; its sequence number is
+    /// assigned when it joins a buffer.
+    pub fn parse(line: &str, format: &LogFormat) -> Self {
+        let line = clean_line(line);
+        let line = line.as_ref();
+        let fields = format.fields(line);
+        let (head, message) = line.split_at(fields.message_start);
+        
+        // The timestamp range is stored in a byte each, so one further in is dropped
+        let timestamp = if fields.timestamp.end <= u8::MAX as usize { fields.timestamp } else { 0..0 };
+        
+        // Lowercase once at ingest so filtering never has to
+        let head_lower = head.to_lowercase();
+        let message_lower = message.to_lowercase();
+        
+        LogLine {
+            seq: 0,
+            source: 0,
+            line: 0,
+            level: fields.level.unwrap_or_else(|| {
+                LogLevel::from_lowercase_line(&[head_lower.as_str(), message_lower.as_str()].concat())
+            }),
+            head: head.into(),
+            message: message.into(),
+            head_lower: head_lower.into_boxed_str(),
+            message_lower: message_lower.into(),
+            timestamp_start: timestamp.start as u8,
+            timestamp_len: timestamp.len() as u8,
+            time: fields.time,
+            status: fields.status.unwrap_or(0),
+            highlighted: false,
+            restart: false,
+            hidden: false,
+        }
+    }
+
+    // Share the message text with other lines repeating it
+    pub(crate) fn intern(&mut self, interner: &mut Interner) {
+        self.message = interner.intern(&self.message);
+        self.message_lower = interner.intern(&self.message_lower);
+    }
+
+    /// The line as it was read.
+    pub fn content(&self) -> String {
+        format!("{}{}", self.head, self.message)
+    }
+
+    // The bytes of the lowercase line, without putting it together
+    pub(crate) fn lower_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.head_lower.bytes().chain(self.message_lower.bytes())
+    }
+
+    /// The timestamp as written in the line, empty if it has none.
+    pub fn timestamp(&self) -> &str {
+        let start = self.timestamp_start as usize;
+        &self.head[start..start + self.timestamp_len as usize]
+    }
+
+    /// When the line was logged, if its timestamp could be read.
+    pub fn time(&self) -> Option<NaiveDateTime> {
+        self.time
+    }
+
+    pub fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    /// The HTTP status of an access log line.
+    pub fn status(&self) -> Option<u16> {
+        Some(self.status).filter(|&status| status != 0)
+    }
+
+    /// The message after the timestamp and level.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // Whether the lowercase line contains `needle` (already lowercased),
+    // including matches that straddle the head and the message
+    pub(crate) fn contains_lower(&self, needle: &str) -> bool {
+        if self.head_lower.contains(needle) || self.message_lower.contains(needle) {
+            return true;
+        }
+        let (head, message, needle) = (self.head_lower.as_bytes(), self.message_lower.as_bytes(), needle.as_bytes());
+        (1..needle.len()).any(|split| head.ends_with(&needle[..split]) && message.starts_with(&needle[split..]))
+    }
+
+    // Approximate heap and inline bytes held by this line alone, for memory
+    // accounting; interned messages are counted once by the interner
+    pub(crate) fn memory_size(&self) -> usize {
+        std::mem::size_of::<LogLine>() + self.head.len() + self.head_lower.len()
+    }
+}
+
+// Words made of letters, from the start of a line, searched for a level name
+const LEVEL_HEADER_WORDS: usize = 5;
+
+// Which keyword wins when a line without a level name has several
+const KEYWORD_PRECEDENCE: [LogLevel; 7] = [
+    LogLevel::Fatal,
+    LogLevel::Error,
+    LogLevel::Warning,
+    LogLevel::Debug,
+    LogLevel::Trace,
+    LogLevel::Info,
+    LogLevel::Unknown,
+];
+
+/// Severity of a line, used for coloring and the minimum level filter.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum LogLevel {
+    Info,
+    Debug,
+    Trace,
+    Warning,
+    Error,
+    Fatal,
+    Unknown,
+}
+
+impl LogLevel {
+    /// Guess the level of a line from the words in it.
+    pub fn from_line(line: &str) -> Self {
+        Self::from_lowercase_line(&line.to_lowercase())
+    }
+
+    // Same as `from_line` for a line that is already lowercased. A level
+    // named among the first words, where log headers put it, decides;
+    // otherwise the most telling keyword anywhere does, as a whole word so
+    // that `traceback` or `informational` don't count
+    pub(crate) fn from_lowercase_line(line_lower: &str) -> Self {
+        // Special case for common macOS log formats
+        if line_lower.contains("asl sender statistics") {
+            return LogLevel::Info;
+        }
+        
+        let words = || line_lower.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty());
+        let named = words()
+            .filter(|word| word.chars().all(char::is_alphabetic))
+            .take(LEVEL_HEADER_WORDS)
+            .find_map(Self::from_level_word);
+        if let Some(level) = named {
+            return level;
+        }
+        words().map(Self::from_keyword).min_by_key(|level| KEYWORD_PRECEDENCE.iter().position(|l| l == level))
+            .unwrap_or(LogLevel::Unknown)
+    }
+
+    // The level a word names outright, as in `ERROR`, `[warn]` or `level=info`
+    fn from_level_word(word: &str) -> Option<Self> {
+        match word {
+            "fatal" | "crit" | "critical" | "emerg" | "panic" => Some(LogLevel::Fatal),
+            "error" | "err" => Some(LogLevel::Error),
+            "warn" | "warning" => Some(LogLevel::Warning),
+            "info" | "notice" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+
+    // The level a word in the message hints at
+    fn from_keyword(word: &str) -> Self {
+        match word {
+            "fatal" | "panic" | "panicked" | "critical" => LogLevel::Fatal,
+            "error" | "errors" | "fail" | "fails" | "failed" | "failing" | "failure" | "failures" => LogLevel::Error,
+            // Exception and error types such as `KeyError` or `IOException`
+            _ if word.ends_with("error") || word.ends_with("exception") => LogLevel::Error,
+            "warn" | "warning" | "warnings" => LogLevel::Warning,
+            "debug" => LogLevel::Debug,
+            "trace" => LogLevel::Trace,
+            "info" | "notice" => LogLevel::Info,
+            _ => LogLevel::Unknown,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            LogLevel::Info => Color::Green,
+            LogLevel::Debug => Color::Cyan,
+            LogLevel::Trace => Color::DarkGray,
+            LogLevel::Warning => Color::Yellow,
+            LogLevel::Error => Color::Red,
+            LogLevel::Fatal => Color::LightMagenta,
+            LogLevel::Unknown => Color::Gray,
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE",
+            LogLevel::Warning => "WARNING",
+            LogLevel::Error => "ERROR",
+            LogLevel::Fatal => "FATAL",
+            LogLevel::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// One letter for the level, where there is no room for its name.
+    pub fn letter(&self) -> &str {
+        match self {
+            LogLevel::Unknown => "?",
+            level => &level.as_str()[..1],
+        }
+    }
+
+    /// Ordering used by the minimum level filter; lines without a level rank lowest.
+    pub fn severity(self) -> u8 {
+        match self {
+            LogLevel::Unknown => 0,
+            LogLevel::Trace => 1,
+            LogLevel::Debug => 2,
+            LogLevel::Info => 3,
+            LogLevel::Warning => 4,
+            LogLevel::Error => 5,
+            LogLevel::Fatal => 6,
+        }
+    }
+
+    /// Look a level up by name, as in `--level warn`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            "warn" | "warning" => Some(LogLevel::Warning),
+            "error" => Some(LogLevel::Error),
+            "fatal" | "critical" | "panic" => Some(LogLevel::Fatal),
+            "unknown" => Some(LogLevel::Unknown),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_named_level_beats_keywords_in_the_message() {
+        assert!(LogLevel::from_line("2024-05-01 10:00:00 INFO retrying after the request failed") == LogLevel::Info);
+        assert!(LogLevel::from_line("May  1 10:00:00 web app[42]: WARN error budget at 80%") == LogLevel::Warning);
+        assert!(LogLevel::from_line("[debug] cache miss, no error") == LogLevel::Debug);
+    }
+
+    #[test]
+    fn keywords_count_as_whole_words() {
+        assert!(LogLevel::from_line("Traceback (most recent call last):") == LogLevel::Unknown);
+        assert!(LogLevel::from_line("Sending informational digest to the team") == LogLevel::Unknown);
+        assert!(LogLevel::from_line("Connection attempt failed: timeout") == LogLevel::Error);
+        assert!(LogLevel::from_line("raised KeyError: 'user' while handling the debug hook") == LogLevel::Error);
+        assert!(LogLevel::from_line("thread main panicked at src/main.rs:4:5") == LogLevel::Fatal);
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use crate::diag::{self, Topic};
+
+// Ingest plus render time a frame should fit in to keep typing responsive
+pub const FRAME_BUDGET: Duration = Duration::from_millis(33);
+
+// How long rendering stays degraded after the last frame over budget
+const DEGRADE_HOLD: Duration = Duration::from_secs(2);
+
+// Weight of the newest sample in the running averages
+const SMOOTHING: f64 = 0.1;
+
+/// Render and ingest timings of recent frames, and whether rendering should
+/// currently drop its optional work to stay within the frame budget.
+#[derive(Default)]
+pub struct FramePerf {
+    pub ingest: Duration, // Last frame
+    pub render: Duration,
+    pub ingest_avg: Duration,
+    pub render_avg: Duration,
+    pub frames: u64,
+    pub slow_frames: u64,
+    pub degraded_until: Option<Instant>,
+    pub show_overlay: bool,
+}
+
+impl FramePerf {
+    pub fn record_ingest(&mut self, elapsed: Duration) {
+        self.ingest = elapsed;
+        self.ingest_avg = smooth(self.ingest_avg, elapsed);
+    }
+
+    /// Record a drawn frame and check the frame against the budget.
+    pub fn record_render(&mut self, elapsed: Duration) {
+        self.render = elapsed;
+        self.render_avg = smooth(self.render_avg, elapsed);
+        self.frames += 1;
+        if self.ingest + self.render > FRAME_BUDGET {
+            // Only the frame that starts a slow stretch, not every one in it
+            if !self.degraded() {
+                diag::record(Topic::Frame, format!(
+                    "Frame over budget: ingest {:.1} ms + render {:.1} ms; charts and colors off for {} s",
+                    self.ingest.as_secs_f64() * 1000.0,
+                    elapsed.as_secs_f64() * 1000.0,
+                    DEGRADE_HOLD.as_secs(),
+                ));
+            }
+            self.slow_frames += 1;
+            self.degraded_until = Some(Instant::now() + DEGRADE_HOLD);
+        }
+    }
+
+    /// Whether a recent frame went over budget, so charts and styling are skipped.
+    pub fn degraded(&self) -> bool {
+        self.degraded_until.is_some_and(|until| Instant::now() < until)
+    }
+}
+
+fn smooth(average: Duration, sample: Duration) -> Duration {
+    if average.is_zero() {
+        return sample;
+    }
+    average.mul_f64(1.0 - SMOOTHING) + sample.mul_f64(SMOOTHING)
+}
@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// One row of the file picker.
+pub enum PickerEntry {
+    Recent(String), // A recently opened file, listed above the directory
+    Parent,
+    Dir(String),
+    File(String),
+}
+
+/// File-open dialog: recently opened files, then the contents of `dir`.
+pub struct FilePicker {
+    pub dir: PathBuf,
+    pub entries: Vec<PickerEntry>,
+    pub selected: usize,
+    pub error: Option<String>, // Why the directory couldn't be listed
+    recent: Vec<String>,
+}
+
+impl FilePicker {
+    pub fn open(dir: PathBuf, recent: Vec<String>) -> Self {
+        let mut picker = FilePicker {
+            dir,
+            entries: Vec::new(),
+            selected: 0,
+            error: None,
+            recent,
+        };
+        picker.list();
+        picker
+    }
+
+    // Rebuild the rows for the current directory, directories first, hidden entries left out
+    fn list(&mut self) {
+        self.entries = self.recent.iter().cloned().map(PickerEntry::Recent).collect();
+        if self.dir.parent().is_some() {
+            self.entries.push(PickerEntry::Parent);
+        }
+        self.error = None;
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        match fs::read_dir(&self.dir) {
+            Ok(read) => {
+                for entry in read.flatten() {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    if name.starts_with('.') {
+                        continue;
+                    }
+                    // Follows symlinks, so a link to a directory can be entered
+                    if entry.path().is_dir() {
+                        dirs.push(name);
+                    } else {
+                        files.push(name);
+                    }
+                }
+            }
+            Err(e) => self.error = Some(format!("Cannot list {}: {}", self.dir.display(), e)),
+        }
+        dirs.sort();
+        files.sort();
+        self.entries.extend(dirs.into_iter().map(PickerEntry::Dir));
+        self.entries.extend(files.into_iter().map(PickerEntry::File));
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn move_by(&mut self, delta: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Enter the selected directory, or return the selected file to open.
+    pub fn choose(&mut self) -> Option<String> {
+        match self.entries.get(self.selected)? {
+            PickerEntry::Recent(path) => Some(path.clone()),
+            PickerEntry::File(name) => Some(self.dir.join(name).to_string_lossy().into_owned()),
+            PickerEntry::Dir(name) => {
+                self.dir = self.dir.join(name);
+                self.selected = 0;
+                self.list();
+                None
+            }
+            PickerEntry::Parent => {
+                self.go_up();
+                None
+            }
+        }
+    }
+
+    /// Show the parent directory, with the one just left selected.
+    pub fn go_up(&mut self) {
+        let Some(parent) = self.dir.parent().map(PathBuf::from) else {
+            return;
+        };
+        let left = self.dir.file_name().map(|name| name.to_string_lossy().into_owned());
+        self.dir = parent;
+        self.list();
+        self.selected = self.entries.iter()
+            .position(|entry| matches!(entry, PickerEntry::Dir(name) if Some(name) == left.as_ref()))
+            .unwrap_or(0);
+    }
+}
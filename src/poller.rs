@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, Receiver};
+
+/// A background thread asking something for its state every `interval`,
+/// such as `kubectl` for the pods a selector matches. What it is told to
+/// pass on arrives on `rx`; the thread stops once the poller is dropped.
+pub struct Poller<T> {
+    pub rx: Receiver<T>,
+    stop: Arc<AtomicBool>, // Tells the thread to stop asking
+}
+
+impl<T: Send + 'static> Poller<T> {
+    /// Call `poll` now and then every `interval`, sending what it returns
+    /// unless that is `None`.
+    pub fn start(interval: Duration, mut poll: impl FnMut() -> Option<T> + Send + 'static) -> Self {
+        let (tx, rx) = bounded(1);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                if let Some(value) = poll()
+                    && tx.send(value).is_err()
+                {
+                    return;
+                }
+                thread::sleep(interval);
+            }
+        });
+        Poller { rx, stop }
+    }
+}
+
+impl<T> Drop for Poller<T> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,65 @@
+use std::io::{self, Write};
+
+use crossbeam_channel::unbounded;
+use crossterm::style::{ResetColor, SetForegroundColor};
+use ratatui::style::Color;
+
+use crate::parse::LogLine;
+use crate::source::SourceState;
+use crate::term;
+use crate::App;
+
+/// Follow the app's sources without the terminal UI, as when standard output
+/// is not a terminal: like `tail -f`, the lines passing the filters are
+/// written to stdout as they arrive, starting with those read at startup.
+/// Lines are colored by level when `color` is set. Without `follow`, or
+/// once every source has ended, it returns; a reader that goes away, such
+/// as `head`, ends it too.
+pub fn run(app: &mut App, color: bool, follow: bool) -> io::Result<()> {
+    let (_keys, events) = unbounded(); // No keys to wait for
+    let mut out = io::stdout().lock();
+    // Those read at startup are in the view; later ones are taken as they
+    // come in, before a burst bigger than the buffer pushes them out of it
+    let startup = app.filtered_logs.iter()
+        .map(|&idx| &app.log_lines[idx])
+        .map(|log| (log.content(), line_color(app, log)))
+        .collect();
+    app.printing = Some(startup);
+    loop {
+        app.update_logs();
+        match write_new_lines(app, &mut out, color) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+        let ended = match &app.replay {
+            Some(replay) => replay.finished(),
+            None => app.sources.iter().all(|source| matches!(source.state, SourceState::Ended | SourceState::Failed)),
+        };
+        if !follow || ended {
+            return Ok(());
+        }
+        app.wait_for_event(&events)?;
+    }
+}
+
+/// The color `log` is printed in: that of its level, or of its status code,
+/// as far as the terminal shows colors.
+pub(crate) fn line_color(app: &App, log: &LogLine) -> Color {
+    let level_color = match log.status() {
+        Some(status) => app.theme.status_color(status),
+        None => app.theme.level_color(log.level),
+    };
+    term::reduce(level_color, app.term.colors)
+}
+
+// Write the lines taken in since the last call, oldest first
+fn write_new_lines(app: &mut App, out: &mut impl Write, color: bool) -> io::Result<()> {
+    for (line, shown) in app.printing.as_mut().map(std::mem::take).unwrap_or_default() {
+        if color && shown != Color::Reset {
+            writeln!(out, "{}{}{}", SetForegroundColor(shown.into()), line, ResetColor)?;
+        } else {
+            writeln!(out, "{}", line)?;
+        }
+    }
+    out.flush()
+}
@@ -0,0 +1,148 @@
+use regex::Regex;
+
+use crate::LogLevel;
+
+// `error[E0308]: mismatched types`, `warning: unused variable: `x``
+const HEADER_PATTERN: &str = r"^(error|warning)(?:\[(\w+)\])?: (.+)$";
+
+// `  --> src/main.rs:4:18`, where the diagnostic points
+const LOCATION_PATTERN: &str = r"^\s*--> (.+):(\d+):(\d+)$";
+
+// Cargo's progress lines, which end a diagnostic rather than continue it
+const PROGRESS_PATTERN: &str = r"^\s+(Compiling|Checking|Building|Finished|Running|Documenting|Downloading|Downloaded|Updating|Locking|Blocking|Fresh)\b";
+
+// Headers that sum up the build rather than point at a problem
+const SUMMARY_PATTERN: &str = r"^(?:aborting due to|could not compile|build failed|`[^`]+` \([^)]+\) generated \d+ warnings?|\d+ warnings? emitted)";
+
+/// One rustc diagnostic: its `error` or `warning` header, and where it points.
+pub struct Problem {
+    pub level: LogLevel,                      // Error or Warning
+    pub code: Option<String>,                 // Such as E0308
+    pub message: String,
+    pub location: Option<(String, u32, u32)>, // File, line and column of the first `-->`
+    pub seq: Option<u64>,                     // The header line, `None` once it was taken out of the buffer
+}
+
+/// The build output read in cargo mode (`--cargo`): each diagnostic's
+/// notes and code frames take the level of its header, so they stay with
+/// it under a level filter, and the diagnostics are listed in the problems
+/// panel. A new build starting after one finished clears the list.
+pub struct Problems {
+    pub list: Vec<Problem>,
+    pub selected: usize, // In the problems panel
+    open: bool,          // The last diagnostic still takes the lines that follow
+    finished: bool,      // Cargo said the build finished or failed
+    header: Regex,
+    location: Regex,
+    progress: Regex,
+    summary: Regex,
+}
+
+impl Default for Problems {
+    fn default() -> Self {
+        Problems {
+            list: Vec::new(),
+            selected: 0,
+            open: false,
+            finished: false,
+            header: Regex::new(HEADER_PATTERN).expect("valid header pattern"),
+            location: Regex::new(LOCATION_PATTERN).expect("valid location pattern"),
+            progress: Regex::new(PROGRESS_PATTERN).expect("valid progress pattern"),
+            summary: Regex::new(SUMMARY_PATTERN).expect("valid summary pattern"),
+        }
+    }
+}
+
+impl Problems {
+    /// Take in the line numbered `seq`, returning the level it gets as part
+    /// of a diagnostic, if it is one.
+    pub fn observe(&mut self, line: &str, seq: u64) -> Option<LogLevel> {
+        if let Some(captures) = self.header.captures(line) {
+            let level = if &captures[1] == "error" { LogLevel::Error } else { LogLevel::Warning };
+            let message = &captures[3];
+            self.open = !self.summary.is_match(message);
+            if message.starts_with("could not compile") || message.starts_with("build failed") {
+                self.finished = true;
+            }
+            if self.open {
+                self.list.push(Problem {
+                    level,
+                    code: captures.get(2).map(|code| code.as_str().to_string()),
+                    message: message.to_string(),
+                    location: None,
+                    seq: Some(seq),
+                });
+            }
+            return Some(level);
+        }
+        if line.trim().is_empty() || self.progress.is_match(line) {
+            self.open = false;
+            if let Some(captures) = self.progress.captures(line) {
+                match &captures[1] {
+                    "Finished" => self.finished = true,
+                    "Compiling" | "Checking" if self.finished => {
+                        self.finished = false;
+                        self.list.clear();
+                        self.selected = 0;
+                    }
+                    _ => {}
+                }
+            }
+            return None;
+        }
+        let problem = self.list.last_mut().filter(|_| self.open)?;
+        if problem.location.is_none()
+            && let Some(captures) = self.location.captures(line)
+            && let (Ok(number), Ok(column)) = (captures[2].parse(), captures[3].parse())
+        {
+            problem.location = Some((captures[1].to_string(), number, column));
+        }
+        Some(problem.level)
+    }
+
+    pub fn count(&self, level: LogLevel) -> usize {
+        self.list.iter().filter(|problem| problem.level == level).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observe_all(problems: &mut Problems, lines: &[&str]) -> Vec<Option<LogLevel>> {
+        lines.iter().enumerate().map(|(seq, line)| problems.observe(line, seq as u64)).collect()
+    }
+
+    #[test]
+    fn a_diagnostic_takes_its_lines_until_a_blank_one() {
+        let mut problems = Problems::default();
+        let levels = observe_all(&mut problems, &[
+            "   Compiling demo v0.1.0",
+            "error[E0308]: mismatched types",
+            "  --> src/main.rs:4:18",
+            "   |",
+            "",
+            "after",
+        ]);
+        assert!(levels == [None, Some(LogLevel::Error), Some(LogLevel::Error), Some(LogLevel::Error), None, None]);
+        let problem = &problems.list[0];
+        assert_eq!(problem.code.as_deref(), Some("E0308"));
+        assert_eq!(problem.message, "mismatched types");
+        assert_eq!(problem.location, Some(("src/main.rs".to_string(), 4, 18)));
+        assert_eq!(problem.seq, Some(1));
+    }
+
+    #[test]
+    fn summaries_are_not_listed_and_a_new_build_clears_the_list() {
+        let mut problems = Problems::default();
+        observe_all(&mut problems, &[
+            "warning: unused variable: `x`",
+            "",
+            "warning: `demo` (bin \"demo\") generated 1 warning",
+            "    Finished dev [unoptimized] target(s) in 0.5s",
+        ]);
+        assert_eq!((problems.count(LogLevel::Warning), problems.count(LogLevel::Error)), (1, 0));
+        problems.observe("   Compiling demo v0.1.0", 4);
+        assert!(problems.list.is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+
+/// Patterns that `redact` can name instead of spelling out a regex.
+pub const REDACT_PRESETS: [(&str, &str); 3] = [
+    ("email", r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b"),
+    // Bearer tokens, JWTs, and the value of key=value pairs with a secret-sounding key
+    ("token", r"(?i)\bbearer\s+([A-Za-z0-9._~+/=-]+)|\b(eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+)|\b(?:api[_-]?key|access[_-]?key|token|secret|password|passwd)[\x22']?\s*[=:]\s*[\x22']?([^\s\x22',;&]+)"),
+    // Card numbers pass the Luhn check too, which rules out most other long numbers
+    ("card", r"\b(?:\d[ -]?){12,18}\d\b"),
+];
+
+// A redaction rule: what a match is replaced with, and what it matches
+struct RedactRule {
+    label: String,
+    regex: Regex,
+    luhn: bool, // Only mask matches whose digits pass the Luhn check
+}
+
+/// The `redact` rules from the config file: what they match in a line is
+/// masked as `[email]`, `[token]`, `[card]` or, for a regex of one's own,
+/// `[redacted]`, in the view and everything exported from it. A regex with
+/// capture groups masks only what they matched, keeping the rest of the
+/// match (`user=(\w+)` shows `user=[redacted]`).
+#[derive(Default)]
+pub struct Redactor {
+    rules: Vec<RedactRule>,
+}
+
+impl Redactor {
+    pub fn new(patterns: &[impl AsRef<str>]) -> Result<Self, String> {
+        let rules = patterns.iter().map(|pattern| {
+            let pattern = pattern.as_ref();
+            let (label, source) = match REDACT_PRESETS.iter().find(|(name, _)| *name == pattern) {
+                Some((name, regex)) => (name.to_string(), *regex),
+                None => ("redacted".to_string(), pattern),
+            };
+            let regex = Regex::new(source).map_err(|e| format!("Invalid redact pattern {}: {}", pattern, e))?;
+            Ok(RedactRule { luhn: label == "card", label, regex })
+        });
+        Ok(Redactor { rules: rules.collect::<Result<_, String>>()? })
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// `text` with what the rules match masked; borrowed if nothing matched.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for rule in &self.rules {
+            let mut masked = String::new();
+            let (mut copied, mut changed) = (0, false);
+            for captures in rule.regex.captures_iter(&text) {
+                let whole = captures.get(0).expect("a match has group 0");
+                if rule.luhn && !luhn(whole.as_str()) {
+                    continue;
+                }
+                // The groups that took part, or the whole match for a pattern without any
+                let mut spans: Vec<_> = captures.iter().skip(1).flatten().collect();
+                if spans.is_empty() {
+                    spans.push(whole);
+                }
+                for span in spans {
+                    // Nested groups were masked along with the group around them
+                    if span.is_empty() || span.start() < copied {
+                        continue;
+                    }
+                    changed = true;
+                    masked.push_str(&text[copied..span.start()]);
+                    masked.push('[');
+                    masked.push_str(&rule.label);
+                    masked.push(']');
+                    copied = span.end();
+                }
+            }
+            if changed {
+                masked.push_str(&text[copied..]);
+                text = Cow::Owned(masked);
+            }
+        }
+        text
+    }
+}
+
+// Whether the digits of `number` have a valid Luhn check digit
+fn luhn(number: &str) -> bool {
+    let sum: u32 = number.chars()
+        .filter_map(|c| c.to_digit(10))
+        .collect::<Vec<_>>()
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match digit * (1 + i as u32 % 2) {
+            doubled if doubled > 9 => doubled - 9,
+            value => value,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luhn_accepts_valid_card_numbers_with_separators() {
+        assert!(luhn("4111 1111 1111 1111"));
+        assert!(luhn("5500-0000-0000-0004"));
+        assert!(luhn("378282246310005"));
+    }
+
+    #[test]
+    fn luhn_rejects_a_wrong_check_digit() {
+        assert!(!luhn("4111 1111 1111 1112"));
+        assert!(!luhn("1234567812345678"));
+    }
+}
@@ -0,0 +1,43 @@
+use regex::Regex;
+
+use crate::parse::{parse_level, LogLevel};
+
+/// A `[[remap]]` rule from the config file: lines matching `pattern`, or
+/// logged by `logger`, get `level` in place of the one they were parsed with.
+pub struct LevelRule {
+    regex: Regex,
+    level: LogLevel,
+}
+
+impl LevelRule {
+    pub fn new(pattern: Option<&str>, logger: Option<&str>, level: &str) -> Result<Self, String> {
+        let level = parse_level(level).map_err(|e| format!("Invalid remap level: {}", e))?;
+        let pattern = match (pattern, logger) {
+            (Some(pattern), None) => pattern.to_string(),
+            (None, Some(logger)) => logger_pattern(logger),
+            _ => return Err("A remap rule needs either a pattern or a logger".to_string()),
+        };
+        Ok(LevelRule {
+            regex: Regex::new(&pattern).map_err(|e| format!("Invalid remap pattern {:?}: {}", pattern, e))?,
+            level,
+        })
+    }
+}
+
+/// The level the first rule matching `line` gives it, if any does.
+pub fn remapped_level(rules: &[LevelRule], line: &str) -> Option<LogLevel> {
+    rules.iter().find(|rule| rule.regex.is_match(line)).map(|rule| rule.level)
+}
+
+// Where loggers write their name: a JSON key, a logfmt pair, `[name]` in the
+// header or the syslog program. Child loggers (`security.auth`) count too.
+fn logger_pattern(logger: &str) -> String {
+    let name = format!(r"{}(?:\.[\w$]+)*", regex::escape(logger));
+    [
+        format!(r#""(?:logger|logger_name|name|component)"\s*:\s*"{}""#, name),
+        format!(r#"\b(?:logger|component)="?{}\b"#, name),
+        format!(r"\[{}\]", name),
+        format!(r"\s{}(?:\[\d+\])?:\s", name),
+    ]
+    .join("|")
+}
@@ -0,0 +1,34 @@
+use std::collections::VecDeque;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::LogLine;
+
+// Most buffered lines looked back over for a late line's place, bounding
+// the cost of a line arriving far out of order
+const MAX_SCAN_LINES: usize = 2000;
+
+/// Buffer position for a line stamped `time`: in front of the run of newest
+/// lines stamped later, provided none of them is more than `window` later.
+/// Lines without a timestamp belong to the line above them and are never
+/// separated from it. `log_lines.len()` means the line is appended.
+pub fn insert_position(log_lines: &VecDeque<LogLine>, time: NaiveDateTime, window: Duration) -> usize {
+    let mut position = log_lines.len();
+    for (scanned, (idx, log)) in log_lines.iter().enumerate().rev().enumerate() {
+        if scanned == MAX_SCAN_LINES {
+            return log_lines.len();
+        }
+        let Some(logged) = log.time else {
+            continue;
+        };
+        if logged <= time {
+            break;
+        }
+        if logged - time > window {
+            // Too late to reorder; it stays where it arrived
+            return log_lines.len();
+        }
+        position = idx;
+    }
+    position
+}
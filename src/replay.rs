@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::time::Instant;
+
+// First line of a session recording
+const SESSION_HEADER: &str = "# tlm-session v1";
+
+/// Records every ingested line with its arrival time, one
+/// `<milliseconds since start>\t<line>` entry per line.
+pub struct Recorder {
+    out: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &str, source: &str) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{} {} {}", SESSION_HEADER, chrono::Local::now().to_rfc3339(), source)?;
+        Ok(Recorder {
+            out,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.out, "{}\t{}", self.started.elapsed().as_millis(), line)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Plays a recorded session back at a configurable speed, with pause and seek.
+pub struct Replay {
+    entries: Vec<(u64, String)>, // (arrival offset in ms, line)
+    position: usize,             // Next entry to emit
+    clock_ms: f64,               // Current position on the recording's timeline
+    pub speed: f64,
+    pub paused: bool,
+    last_tick: Instant,
+}
+
+impl Replay {
+    pub fn open(path: &str, speed: f64) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.starts_with(SESSION_HEADER) {
+                continue;
+            }
+            let Some((offset, content)) = line.split_once('\t') else {
+                continue;
+            };
+            if let Ok(offset) = offset.parse() {
+                entries.push((offset, content.to_string()));
+            }
+        }
+        Ok(Replay {
+            entries,
+            position: 0,
+            clock_ms: 0.0,
+            speed,
+            paused: false,
+            last_tick: Instant::now(),
+        })
+    }
+
+    /// Advance the replay clock and return the lines that became due.
+    pub fn due_lines(&mut self) -> Vec<String> {
+        let elapsed = self.last_tick.elapsed().as_secs_f64() * 1000.0;
+        self.last_tick = Instant::now();
+        if !self.paused {
+            self.clock_ms = (self.clock_ms + elapsed * self.speed).min(self.duration_ms() as f64);
+        }
+        let mut lines = Vec::new();
+        while let Some((offset, line)) = self.entries.get(self.position) {
+            if *offset as f64 > self.clock_ms {
+                break;
+            }
+            lines.push(line.clone());
+            self.position += 1;
+        }
+        lines
+    }
+
+    /// Move the clock by `delta_ms` (negative seeks backwards). Returns true if
+    /// the replay restarted from the beginning, in which case the caller must
+    /// clear its buffer before draining `due_lines` again.
+    pub fn seek(&mut self, delta_ms: f64) -> bool {
+        self.clock_ms = (self.clock_ms + delta_ms).clamp(0.0, self.duration_ms() as f64);
+        self.last_tick = Instant::now();
+        if delta_ms < 0.0 {
+            self.position = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn position_ms(&self) -> u64 {
+        self.clock_ms as u64
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.entries.last().map_or(0, |(offset, _)| *offset)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.position >= self.entries.len()
+    }
+}
+
+/// Parse a replay speed such as `4x`, `0.5x` or `2`.
+pub fn parse_speed(text: &str) -> Result<f64, String> {
+    text.trim_end_matches(['x', 'X'])
+        .parse::<f64>()
+        .ok()
+        .filter(|speed| *speed > 0.0)
+        .ok_or_else(|| format!("Invalid replay speed: {}", text))
+}
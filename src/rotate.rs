@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+
+use crate::ingest;
+
+/// Rotated copies of `path` in its directory, newest first: `app.log.1`,
+/// `app.log.2.gz` and so on, as logrotate names them.
+pub fn siblings(path: &str) -> Vec<PathBuf> {
+    let path = Path::new(path);
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|name| name.to_str())) else {
+        return Vec::new();
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut rotated: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let suffix = file_name.to_str()?.strip_prefix(name)?.strip_prefix('.')?;
+            let number = suffix.strip_suffix(".gz").unwrap_or(suffix).parse().ok()?;
+            Some((number, entry.path()))
+        })
+        .collect();
+    rotated.sort();
+    rotated.into_iter().map(|(_, path)| path).collect()
+}
+
+/// The last `count` lines of a rotated file, gunzipping `.gz` files, and the
+/// number of lines in front of them (only counted for plain files when
+/// `count_before` is set, since that takes a pass over the file).
+pub fn read_tail(path: &Path, count: usize, count_before: bool) -> io::Result<(Vec<String>, u64)> {
+    let path_str = path.to_string_lossy();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut lines = VecDeque::with_capacity(count.min(64 * 1024));
+        let mut total = 0;
+        for line in BufReader::new(GzDecoder::new(File::open(path)?)).split(b'\n') {
+            let line = line?;
+            total += 1;
+            if lines.len() == count {
+                lines.pop_front();
+            }
+            if count > 0 {
+                lines.push_back(String::from_utf8_lossy(&line).trim_end_matches('\r').to_string());
+            }
+        }
+        let before = total - lines.len() as u64;
+        return Ok((lines.into(), before));
+    }
+    let (lines, start_offset, _) = ingest::read_last_lines(&path_str, count)?;
+    let before = if count_before { ingest::count_lines(&path_str, start_offset)? } else { 0 };
+    Ok((lines, before))
+}
@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::bounded;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Scope, AST};
+
+// Operations a single hook call may take before it is stopped, so a runaway
+// loop in a script reports an error instead of freezing the monitor
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+// Prefix of script functions exposed as `:` commands, e.g. `command_geo` as `:geo`
+const COMMAND_PREFIX: &str = "command_";
+
+// Longest a command run with `exec` may take before it is killed, as hooks
+// run on the UI thread
+const EXEC_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What the `on_line` hook decided for a line.
+pub enum LineAction {
+    Keep,
+    Replace(String), // Parsed again in place of the original text
+    Drop,
+}
+
+/// A Rhai script loaded with `--script`, whose hooks run as lines arrive and
+/// alerts fire, and whose `command_<name>` functions become `:<name>` commands.
+///
+/// Besides the Rhai standard library, scripts can call `status(text)` to show
+/// a message in the status bar and `exec(command)` to run a shell command and
+/// get its trimmed output, given up on after `EXEC_TIMEOUT`.
+pub struct Script {
+    pub path: PathBuf,
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    pub on_line: bool, // Whether the hooks are defined (and haven't been turned off by an error)
+    pub on_alert: bool,
+    pub commands: Vec<String>,            // Names of the `:` commands the script defines
+    messages: Rc<RefCell<Vec<String>>>, // Texts passed to `status` since they were last taken
+}
+
+impl Script {
+    /// Compile the script at `path` and run its top-level statements once.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let sink = messages.clone();
+        engine.register_fn("status", move |text: &str| sink.borrow_mut().push(text.to_string()));
+        let sink = messages.clone();
+        engine.register_fn("exec", move |command: &str| {
+            exec(command).unwrap_or_else(|| {
+                sink.borrow_mut().push(format!("exec timed out after {}s: {}", EXEC_TIMEOUT.as_secs(), command));
+                String::new()
+            })
+        });
+        // `print` would write over the interface
+        let sink = messages.clone();
+        engine.on_print(move |text| sink.borrow_mut().push(text.to_string()));
+        engine.on_debug(|_, _, _| {});
+
+        let error = |e: Box<EvalAltResult>| format!("Script {}: {}", path, e);
+        let ast = engine.compile_file(PathBuf::from(path)).map_err(error)?;
+        let mut scope = Scope::new();
+        engine.run_ast_with_scope(&mut scope, &ast).map_err(error)?;
+        let defines = |name: &str, params: usize| ast.iter_functions().any(|f| f.name == name && f.params.len() == params);
+        let on_line = defines("on_line", 2);
+        let on_alert = defines("on_alert", 2);
+        let mut commands: Vec<String> = ast.iter_functions()
+            .filter(|f| f.params.len() == 1)
+            .filter_map(|f| f.name.strip_prefix(COMMAND_PREFIX).map(String::from))
+            .collect();
+        commands.sort();
+        Ok(Script {
+            path: Path::new(path).to_path_buf(),
+            engine,
+            ast,
+            scope,
+            on_line,
+            on_alert,
+            commands,
+            messages,
+        })
+    }
+
+    /// Run `on_line(line, source)` on a freshly read line. A string result
+    /// replaces the line, `false` drops it and anything else keeps it.
+    pub fn on_line(&mut self, line: &str, source: &str) -> Result<LineAction, String> {
+        let result = self.call("on_line", (line.to_string(), source.to_string()))?;
+        if result.is_string() {
+            return Ok(LineAction::Replace(result.into_string().unwrap_or_default()));
+        }
+        if result.as_bool() == Ok(false) {
+            return Ok(LineAction::Drop);
+        }
+        Ok(LineAction::Keep)
+    }
+
+    /// Run `on_alert(rule, line)` after a watch rule fired.
+    pub fn on_alert(&mut self, rule: &str, line: &str) -> Result<(), String> {
+        self.call("on_alert", (rule.to_string(), line.to_string())).map(|_| ())
+    }
+
+    /// Run the script's `:name` command with the rest of the command line,
+    /// or `None` if it defines no such command. Its result is shown in the status bar.
+    pub fn command(&mut self, name: &str, arg: &str) -> Option<Result<String, String>> {
+        if !self.commands.iter().any(|command| command == name) {
+            return None;
+        }
+        let result = self.call(&format!("{}{}", COMMAND_PREFIX, name), (arg.to_string(),));
+        Some(result.map(|value| if value.is_unit() { String::new() } else { value.to_string() }))
+    }
+
+    /// Messages the script passed to `status` or `print`, oldest first.
+    pub fn take_messages(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.messages.borrow_mut())
+    }
+
+    fn call(&mut self, name: &str, args: impl FuncArgs) -> Result<Dynamic, String> {
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options(options, &mut self.scope, &self.ast, name, args)
+            .map_err(|e| format!("{} in {}: {}", name, self.path.display(), e))
+    }
+}
+
+// Run a shell command for a script, returning its trimmed output (empty if it
+// failed), or `None` if it was killed for taking longer than `EXEC_TIMEOUT`
+fn exec(command: &str) -> Option<String> {
+    let spawned = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = spawned else {
+        return Some(String::new());
+    };
+    let Some(mut stdout) = child.stdout.take() else {
+        return Some(String::new());
+    };
+    // The output is read on a thread of its own, so a command that hangs can be given up on
+    let (tx, rx) = bounded(1);
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        let _ = tx.send(output);
+    });
+    let output = rx.recv_timeout(EXEC_TIMEOUT).ok();
+    if output.is_none() {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+    output.map(|output| String::from_utf8_lossy(&output).trim().to_string())
+}
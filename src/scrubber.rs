@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{LogLevel, LogLine};
+
+/// Rows the scrubber takes above the log view: line density, then errors.
+pub const SCRUBBER_ROWS: u16 = 2;
+
+/// The log view is only given a scrubber when it has this many rows.
+pub const MIN_VIEW_HEIGHT: u16 = 16;
+
+// Glyphs for a column's share of the busiest one, lowest first
+const DENSITY_GLYPHS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+
+// Lines counted at most; bigger views are sampled evenly, which keeps the
+// shape while the scrubber is redrawn every frame
+const SAMPLE_LINES: usize = 50_000;
+
+/// The filtered view's time span cut into one slice per column, with the
+/// lines and errors logged in each, for the scrubber above the log view.
+pub struct Timeline {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub columns: Vec<(usize, usize)>, // Lines and errors (ERROR and up) in each slice
+}
+
+impl Timeline {
+    /// Spread the timestamped lines of the view over `width` columns; `None`
+    /// when they span no time at all.
+    pub fn build(log_lines: &VecDeque<LogLine>, filtered: &[usize], width: usize) -> Option<Self> {
+        let step = filtered.len().div_ceil(SAMPLE_LINES).max(1);
+        let sampled = || filtered.iter().step_by(step).filter_map(|&i| Some((log_lines[i].time?, log_lines[i].level)));
+        let (start, end) = sampled().fold(None, |span, (time, _)| match span {
+            None => Some((time, time)),
+            Some((start, end)) => Some((time.min(start), time.max(end))),
+        })?;
+        if end <= start || width == 0 {
+            return None;
+        }
+        let mut timeline = Timeline { start, end, columns: vec![(0, 0); width] };
+        for (time, level) in sampled() {
+            let column = timeline.column(time);
+            let (lines, errors) = &mut timeline.columns[column];
+            *lines += 1;
+            if level.severity() >= LogLevel::Error.severity() {
+                *errors += 1;
+            }
+        }
+        Some(timeline)
+    }
+
+    /// The column `time` falls in.
+    pub fn column(&self, time: NaiveDateTime) -> usize {
+        let span = (self.end - self.start).num_milliseconds().max(1);
+        let offset = (time - self.start).num_milliseconds().clamp(0, span);
+        ((offset as i128 * self.columns.len() as i128 / span as i128) as usize).min(self.columns.len() - 1)
+    }
+
+    /// When the slice of `column` starts.
+    pub fn moment(&self, column: usize) -> NaiveDateTime {
+        let span = (self.end - self.start).num_milliseconds();
+        let offset = span as i128 * column.min(self.columns.len() - 1) as i128 / self.columns.len() as i128;
+        self.start + Duration::milliseconds(offset as i64)
+    }
+}
+
+/// The glyph for `count` out of the busiest column's `max`, blank for none.
+pub fn density_glyph(count: usize, max: usize) -> &'static str {
+    if count == 0 || max == 0 {
+        return " ";
+    }
+    DENSITY_GLYPHS[((count * DENSITY_GLYPHS.len()).div_ceil(max) - 1).min(DENSITY_GLYPHS.len() - 1)]
+}
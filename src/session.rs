@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::notes::Note;
+
+/// Where an investigation of one log file left off, saved on exit and
+/// restored with `--resume`.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Session {
+    pub filter: String,
+    pub min_level: Option<String>,
+    pub time_range: Option<(NaiveDateTime, NaiveDateTime)>,
+    pub follow: bool,
+    pub tab: usize,
+    pub show_timestamps: bool,
+    pub show_line_numbers: bool,
+    pub scroll_line: Option<String>, // Text of the line scrolled to, found again by content
+    pub scroll_from_end: usize,      // Fallback position when that line is gone
+    pub mark: Option<String>,        // Text of the highlighted line
+    pub browse_top: Option<usize>,   // First line shown in browse mode, if it was open
+    pub notes: Vec<Note>,            // Annotations, found again by the text of their lines
+}
+
+/// How far a log file had been read when the monitor last quit, for
+/// `--since-last-run`.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Checkpoint {
+    pub offset: u64,         // Just past the last complete line
+    pub inode: Option<u64>,  // Tells a rotated file from the one read, where the platform has inodes
+}
+
+// Files opened recently, kept for the file picker
+const MAX_RECENT_FILES: usize = 10;
+
+/// `name` in `$XDG_STATE_HOME/tui-log-monitor`, falling back to `~/.local/state`.
+fn state_path(name: &str) -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("tui-log-monitor").join(name))
+}
+
+// Sessions are keyed by the absolute path of the log file
+fn key(log_path: &str) -> String {
+    std::path::absolute(log_path).map_or_else(|_| log_path.to_string(), |path| path.to_string_lossy().into_owned())
+}
+
+// A missing or damaged state file only means there is nothing to restore
+fn read_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    fs::read_to_string(path).ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// The saved session for `log_path`, if there is one.
+pub fn load(log_path: &str) -> Option<Session> {
+    read_json::<BTreeMap<String, Session>>(&state_path("sessions.json")?).remove(&key(log_path))
+}
+
+/// Save the session for `log_path`, keeping those of other files.
+pub fn save(log_path: &str, session: Session) -> io::Result<()> {
+    let Some(path) = state_path("sessions.json") else {
+        return Ok(());
+    };
+    let mut sessions: BTreeMap<String, Session> = read_json(&path);
+    sessions.insert(key(log_path), session);
+    write_json(&path, &sessions)
+}
+
+/// Where to pick `log_path` up from after the last run: the offset saved on
+/// leaving, or the start of the file if it has been rotated or truncated
+/// since. `None` when it was never read to the end before.
+pub fn resume_offset(log_path: &str) -> Option<u64> {
+    let checkpoints: BTreeMap<String, Checkpoint> = read_json(&state_path("checkpoints.json")?);
+    let checkpoint = checkpoints.get(&key(log_path))?;
+    let metadata = fs::metadata(log_path).ok()?;
+    let same_file = checkpoint.inode.is_none() || checkpoint.inode == inode(&metadata);
+    Some(if same_file && checkpoint.offset <= metadata.len() { checkpoint.offset } else { 0 })
+}
+
+/// Remember that `log_path` has been read up to `offset`, just past the
+/// last line taken in, keeping the checkpoints of other files.
+pub fn save_checkpoint(log_path: &str, offset: u64) -> io::Result<()> {
+    let Some(path) = state_path("checkpoints.json") else {
+        return Ok(());
+    };
+    let inode = inode(&fs::metadata(log_path)?);
+    let mut checkpoints: BTreeMap<String, Checkpoint> = read_json(&path);
+    checkpoints.insert(key(log_path), Checkpoint { offset, inode });
+    write_json(&path, &checkpoints)
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Recently opened files, most recent first.
+pub fn recent_files() -> Vec<String> {
+    state_path("recent.json").map(|path| read_json(&path)).unwrap_or_default()
+}
+
+/// Put `log_path` at the top of the recent files.
+pub fn add_recent_file(log_path: &str) -> io::Result<()> {
+    let Some(path) = state_path("recent.json") else {
+        return Ok(());
+    };
+    let log_path = key(log_path);
+    let mut recent: Vec<String> = read_json(&path);
+    recent.retain(|other| *other != log_path);
+    recent.insert(0, log_path);
+    recent.truncate(MAX_RECENT_FILES);
+    write_json(&path, &recent)
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write a temporary file and rename it over, so a crash never leaves half a file
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(value)?)?;
+    fs::rename(&tmp, path)
+}
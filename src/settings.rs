@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::Path;
+
+use toml_edit::{DocumentMut, Item};
+
+/// Rows of the settings panel, in display order.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    PollInterval,
+    BufferSize,
+    Wrap,
+    TimestampFormat,
+    Theme,
+    Symbols,
+}
+
+pub const SETTINGS: [Setting; 6] = [
+    Setting::PollInterval,
+    Setting::BufferSize,
+    Setting::Wrap,
+    Setting::TimestampFormat,
+    Setting::Theme,
+    Setting::Symbols,
+];
+
+/// Timestamp layouts the panel cycles through; empty shows timestamps as logged.
+pub const TIMESTAMP_PRESETS: [&str; 5] = ["", "%H:%M:%S", "%H:%M:%S%.3f", "%Y-%m-%d %H:%M:%S", "%d %b %H:%M:%S"];
+
+impl Setting {
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::PollInterval => "Poll interval (ms)",
+            Setting::BufferSize => "Buffer size (lines)",
+            Setting::Wrap => "Wrap long lines",
+            Setting::TimestampFormat => "Timestamp format",
+            Setting::Theme => "Theme",
+            Setting::Symbols => "Level symbols",
+        }
+    }
+
+    /// Whether Enter opens a text prompt for the value rather than toggling it.
+    pub fn editable(self) -> bool {
+        matches!(self, Setting::PollInterval | Setting::BufferSize | Setting::TimestampFormat)
+    }
+}
+
+/// State of the settings popup opened with `,`.
+#[derive(Default)]
+pub struct SettingsPanel {
+    pub selected: usize,
+    pub input: Option<String>, // Text being typed for the selected setting
+    pub message: Option<String>, // Result of the last save, or why an edit was rejected
+}
+
+impl SettingsPanel {
+    pub fn setting(&self) -> Setting {
+        SETTINGS[self.selected]
+    }
+}
+
+/// Write `values` into the config file at `path` as top-level keys, keeping
+/// the rest of the file (comments included) as it was.
+pub fn save(path: &Path, values: Vec<(&str, Item)>) -> Result<(), String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e)),
+    };
+    let mut doc: DocumentMut = text.parse().map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+    for (key, item) in values {
+        doc[key] = item;
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    }
+    fs::write(path, doc.to_string()).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
@@ -0,0 +1,34 @@
+use std::io;
+
+use crate::control::AppHandle;
+
+/// Turn SIGTERM, SIGINT and SIGHUP into a clean exit and SIGTSTP into a
+/// suspend, by passing them to the app as requests from a background
+/// thread. Between frames the app then restores the terminal, flushes tee
+/// and recording output and saves the session, as when q is pressed.
+#[cfg(unix)]
+pub fn forward(handle: AppHandle) -> io::Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGTSTP};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP, SIGTSTP])?;
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            let sent = match signal {
+                SIGTSTP => handle.suspend(),
+                _ => handle.quit(),
+            };
+            if !sent {
+                return;
+            }
+        }
+    });
+    Ok(())
+}
+
+// The console delivers Ctrl+C as a key press in raw mode, and there is
+// nothing to suspend to
+#[cfg(not(unix))]
+pub fn forward(_handle: AppHandle) -> io::Result<()> {
+    Ok(())
+}
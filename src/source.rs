@@ -0,0 +1,410 @@
+use std::io;
+use std::net::{Shutdown, TcpStream};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDateTime;
+use crossbeam_channel::Receiver;
+use ratatui::style::Color;
+
+use crate::format::{self, LogFormat, SharedFormat};
+use crate::ingest::{self, IngestEvent};
+use crate::LogLevel;
+
+// Colors given to sources in the order they are attached
+const SOURCE_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Green,
+    Color::LightBlue,
+    Color::LightRed,
+];
+
+// Longest source name shown in front of its lines
+const MAX_NAME_LEN: usize = 16;
+
+// How long opening a URL may take before giving up
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The target reading the monitor's own standard input, as in `cargo build 2>&1 | log_monitor -`.
+pub const STDIN_TARGET: &str = "-";
+
+/// Levels the sources panel steps through with ←/→; `None` follows the global level.
+pub const LEVEL_STEPS: [Option<LogLevel>; 7] = [
+    None,
+    Some(LogLevel::Trace),
+    Some(LogLevel::Debug),
+    Some(LogLevel::Info),
+    Some(LogLevel::Warning),
+    Some(LogLevel::Error),
+    Some(LogLevel::Fatal),
+];
+
+/// Where a source stands, as shown in the sources panel.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SourceState {
+    Connected,      // Being read
+    WaitingForFile, // The file is gone, checked until it is back
+    Reconnecting,   // The stream dropped and is being reopened
+    Ended,          // The command exited or the stream was closed for good
+    Failed,         // Stopped with an error
+    Replay,         // Fed from a recording rather than a stream of its own
+}
+
+impl SourceState {
+    pub fn label(self) -> &'static str {
+        match self {
+            SourceState::Connected => "connected",
+            SourceState::WaitingForFile => "waiting for file",
+            SourceState::Reconnecting => "reconnecting",
+            SourceState::Ended => "ended",
+            SourceState::Failed => "failed",
+            SourceState::Replay => "replay",
+        }
+    }
+}
+
+/// What one source's lines must satisfy on top of the global filter.
+#[derive(Clone, Default)]
+pub struct SourceFilter {
+    pub text: String,                // As typed, shown in the sources panel
+    pub text_lower: String,          // Matched against lines
+    pub min_level: Option<LogLevel>, // Replaces the global minimum level for this source
+    pub hidden: bool,                // Leave all of its lines out of the view
+}
+
+impl SourceFilter {
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.text_lower = text.to_lowercase();
+    }
+
+    pub fn active(&self) -> bool {
+        !self.text.is_empty() || self.min_level.is_some() || self.hidden
+    }
+
+    /// Move the minimum level `step` places along `LEVEL_STEPS`.
+    pub fn step_level(&mut self, step: isize) {
+        let current = LEVEL_STEPS.iter().position(|&level| level == self.min_level).unwrap_or(0) as isize;
+        let next = (current + step).rem_euclid(LEVEL_STEPS.len() as isize) as usize;
+        self.min_level = LEVEL_STEPS[next];
+    }
+}
+
+/// State of the sources panel opened with `i`.
+#[derive(Default)]
+pub struct SourcesPanel {
+    pub selected: usize,
+    pub input: Option<String>, // Filter text being typed for the selected source
+}
+
+/// One stream of lines in the merged view: the file given at startup, or a
+/// file, command or socket attached with `:open`.
+pub struct Source {
+    pub id: u16,        // Tag carried by its lines, never reused
+    pub name: String,   // Short label shown in front of its lines
+    pub target: String, // Path, `|command` or URL it was opened from
+    pub color: Color,
+    pub rx: Option<Receiver<IngestEvent>>, // New lines, `None` once the source ended
+    pub lines: u64,                        // Lines read so far, numbering the next one
+    pub last_seq: Option<u64>,             // Its newest buffered line, which untimed lines from it follow
+    pub state: SourceState,
+    pub error: Option<String>,             // Why it stopped or last lost its connection
+    pub filter: SourceFilter,              // Narrows this source's lines in the view
+    pub format: SharedFormat,              // How its lines are parsed, guessed while still `auto`
+    pub last_line_at: Option<Instant>,     // When its newest line arrived
+    pub lines_per_sec: f64,                // Arrival rate over the last sample window
+    pub read_offset: Option<u64>,          // In a followed file, the offset just past the lines taken in
+    rate_window: (Instant, u64),           // Start of the current rate sample and `lines` at that time
+    child: Option<Child>,                  // Command whose output is read
+    stream: Arc<Mutex<Option<TcpStream>>>, // Socket being read, shut down on close
+}
+
+impl Source {
+    fn new(id: u16, target: &str, format: SharedFormat, rx: Option<Receiver<IngestEvent>>) -> Self {
+        Source {
+            id,
+            name: short_name(target),
+            target: target.to_string(),
+            color: SOURCE_COLORS[id as usize % SOURCE_COLORS.len()],
+            rx,
+            lines: 0,
+            last_seq: None,
+            state: SourceState::Connected,
+            error: None,
+            filter: SourceFilter::default(),
+            format,
+            last_line_at: None,
+            lines_per_sec: 0.0,
+            read_offset: None,
+            rate_window: (Instant::now(), 0),
+            child: None,
+            stream: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// A source with no stream of its own, such as a recording being replayed.
+    pub fn detached(id: u16, target: &str, format: LogFormat) -> Self {
+        let mut source = Source::new(id, target, Arc::new(RwLock::new(format)), None);
+        source.state = SourceState::Replay;
+        source
+    }
+
+    /// Follow a log file from its last `initial_lines` lines, which are
+    /// returned to be ingested first. With `count_before`, the lines in
+    /// front of those are counted so line numbers match the file. An `auto`
+    /// format is guessed from those lines when they follow a known layout.
+    pub fn open_file(
+        id: u16,
+        path: &str,
+        initial_lines: usize,
+        count_before: bool,
+        interval_ms: Arc<AtomicU64>,
+        format: LogFormat,
+    ) -> io::Result<(Self, Vec<String>)> {
+        let read = ingest::read_last_lines(path, initial_lines)?;
+        let format = detect_format(format, &read.0);
+        Source::follow_file(id, path, read, count_before, interval_ms, format)
+    }
+
+    /// Like `open_file`, but starting from the lines logged within `window`
+    /// (at most `max_lines` of them, the latest) instead of the last lines.
+    /// An `auto` format is guessed from the end of the file first, so the
+    /// window can be found by its timestamps.
+    pub fn open_window(
+        id: u16,
+        path: &str,
+        window: (NaiveDateTime, NaiveDateTime),
+        max_lines: usize,
+        count_before: bool,
+        interval_ms: Arc<AtomicU64>,
+        format: LogFormat,
+    ) -> io::Result<(Self, Vec<String>)> {
+        let (sample, _, _) = ingest::read_last_lines(path, format::DETECT_SAMPLE_LINES)?;
+        let format = detect_format(format, &sample);
+        let read = ingest::read_window(path, window, max_lines, &format)?;
+        Source::follow_file(id, path, read, count_before, interval_ms, format)
+    }
+
+    /// Like `open_file`, but starting from byte `offset` (at most `max_lines`
+    /// lines, the latest), where an earlier run left off.
+    pub fn open_from(
+        id: u16,
+        path: &str,
+        offset: u64,
+        max_lines: usize,
+        count_before: bool,
+        interval_ms: Arc<AtomicU64>,
+        format: LogFormat,
+    ) -> io::Result<(Self, Vec<String>)> {
+        let read = ingest::read_from(path, offset, max_lines)?;
+        let format = detect_format(format, &read.0);
+        Source::follow_file(id, path, read, count_before, interval_ms, format)
+    }
+
+    // Tail `path` from the end of the lines read, which start at `start_offset`
+    fn follow_file(
+        id: u16,
+        path: &str,
+        (lines, start_offset, end_offset): (Vec<String>, u64, u64),
+        count_before: bool,
+        interval_ms: Arc<AtomicU64>,
+        format: LogFormat,
+    ) -> io::Result<(Self, Vec<String>)> {
+        let format = Arc::new(RwLock::new(format));
+        let rx = ingest::spawn_tail(path.to_string(), end_offset, interval_ms, format.clone());
+        let mut source = Source::new(id, path, format, Some(rx));
+        source.read_offset = Some(end_offset);
+        if count_before {
+            source.lines = ingest::count_lines(path, start_offset)?;
+        }
+        Ok((source, lines))
+    }
+
+    /// Attach `target`: `|command` reads the output of a shell command,
+    /// `tcp://host:port` reads from a socket, `http://` and `https://` URLs
+    /// are streamed, `-` reads standard input, and anything else is a file
+    /// opened as with `open_file`. Sockets and URLs are reconnected to when
+    /// they drop, but a URL whose response was read to its end has ended;
+    /// the first connection has to succeed. Streams left on the `auto`
+    /// format have it guessed from their first lines.
+    pub fn open(
+        id: u16,
+        target: &str,
+        initial_lines: usize,
+        count_before: bool,
+        interval_ms: Arc<AtomicU64>,
+        format: LogFormat,
+    ) -> io::Result<(Self, Vec<String>)> {
+        if let Some(command) = target.strip_prefix('|') {
+            let mut child = Command::new("sh")
+                .arg("-c")
+                .arg(command.trim())
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()?;
+            let stdout = child.stdout.take().ok_or_else(|| io::Error::other("command has no output"))?;
+            let format = Arc::new(RwLock::new(format));
+            let rx = ingest::spawn_reader(stdout, format.clone());
+            let mut source = Source::new(id, target, format, Some(rx));
+            source.child = Some(child);
+            Ok((source, Vec::new()))
+        } else if target == STDIN_TARGET {
+            let format = Arc::new(RwLock::new(format));
+            let rx = ingest::spawn_reader(io::stdin(), format.clone());
+            Ok((Source::new(id, target, format, Some(rx)), Vec::new()))
+        } else if let Some(addr) = target.strip_prefix("tcp://") {
+            // The socket in use is kept where `drop` can shut it down
+            let current = Arc::new(Mutex::new(None));
+            let connect = {
+                let (addr, current) = (addr.to_string(), current.clone());
+                move || {
+                    let stream = TcpStream::connect(&addr)?;
+                    *current.lock().unwrap_or_else(|e| e.into_inner()) = Some(stream.try_clone()?);
+                    Ok(stream)
+                }
+            };
+            let first = connect()?;
+            let format = Arc::new(RwLock::new(format));
+            let rx = ingest::spawn_reconnecting(first, connect, format.clone(), true);
+            let mut source = Source::new(id, target, format, Some(rx));
+            source.stream = current;
+            Ok((source, Vec::new()))
+        } else if target.starts_with("http://") || target.starts_with("https://") {
+            let url = target.to_string();
+            let connect = move || {
+                ureq::AgentBuilder::new()
+                    .timeout_connect(CONNECT_TIMEOUT)
+                    .build()
+                    .get(&url)
+                    .call()
+                    .map(|response| response.into_reader())
+                    .map_err(|e| io::Error::other(e.to_string()))
+            };
+            let first = connect()?;
+            let format = Arc::new(RwLock::new(format));
+            let rx = ingest::spawn_reconnecting(first, connect, format.clone(), false);
+            Ok((Source::new(id, target, format, Some(rx)), Vec::new()))
+        } else {
+            Source::open_file(id, target, initial_lines, count_before, interval_ms, format)
+        }
+    }
+
+    /// Open the target again after it failed or ended, reading only what
+    /// comes next. Its lines, filter and format are kept.
+    pub fn reopen(&mut self, interval_ms: Arc<AtomicU64>) -> io::Result<()> {
+        let (mut fresh, _) = Source::open(self.id, &self.target, 0, false, interval_ms, self.current_format())?;
+        // The old reader goes with `fresh`, whose drop cleans it up
+        std::mem::swap(&mut self.rx, &mut fresh.rx);
+        std::mem::swap(&mut self.child, &mut fresh.child);
+        std::mem::swap(&mut self.stream, &mut fresh.stream);
+        std::mem::swap(&mut self.format, &mut fresh.format);
+        self.state = SourceState::Connected;
+        self.error = None;
+        self.mark_caught_up();
+        Ok(())
+    }
+
+    /// Stop reading the source for `reason`, keeping the lines it gave.
+    pub fn detach(&mut self, reason: &str) {
+        self.rx = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        self.state = SourceState::Ended;
+        self.error = Some(reason.to_string());
+    }
+
+    /// What is wrong with the source, if it is failing or waiting, for the
+    /// banner above the view.
+    pub fn problem(&self) -> Option<String> {
+        let error = self.error.as_deref().unwrap_or("unknown error");
+        match self.state {
+            SourceState::WaitingForFile => Some(format!("{} is gone; waiting for it to come back", self.name)),
+            SourceState::Reconnecting => Some(format!("{}: {}; retrying", self.name, error)),
+            SourceState::Failed => Some(format!("{} stopped: {} (:retry to reopen)", self.name, error)),
+            _ => None,
+        }
+    }
+
+    /// Measure the rate and last-line age from here on, so the lines read
+    /// when the source was opened don't count as arrivals.
+    pub fn mark_caught_up(&mut self) {
+        self.last_line_at = None;
+        self.rate_window = (Instant::now(), self.lines);
+    }
+
+    /// Refresh `lines_per_sec` once `window` has passed since the last
+    /// sample, returning whether it changed.
+    pub fn sample_rate(&mut self, window: Duration) -> bool {
+        let (started, lines_at_start) = self.rate_window;
+        let elapsed = started.elapsed();
+        if elapsed < window {
+            return false;
+        }
+        let rate = self.lines.saturating_sub(lines_at_start) as f64 / elapsed.as_secs_f64();
+        self.rate_window = (Instant::now(), self.lines);
+        let changed = rate != self.lines_per_sec;
+        self.lines_per_sec = rate;
+        changed
+    }
+
+    /// The format its lines are parsed with now.
+    pub fn current_format(&self) -> LogFormat {
+        self.format.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Whether the source is a file, rather than a command, socket or URL.
+    pub fn is_file(&self) -> bool {
+        !self.target.starts_with('|') && !self.target.contains("://") && self.target != STDIN_TARGET
+    }
+
+    /// Whether `key` (a name or a 1-based position in the sources list) names this source.
+    pub fn matches(&self, key: &str, position: usize) -> bool {
+        self.name == key || self.target == key || key.parse::<usize>().ok() == Some(position + 1)
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        // Unblock the reader thread, which then finds the channel gone and stops
+        // rather than reconnecting
+        self.rx = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if let Some(stream) = self.stream.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+// An `auto` format narrowed down to the layout `lines` follow, if they follow one
+fn detect_format(format: LogFormat, lines: &[String]) -> LogFormat {
+    match format {
+        LogFormat::Auto => LogFormat::detect(lines.iter().map(String::as_str)).unwrap_or(LogFormat::Auto),
+        format => format,
+    }
+}
+
+// A short label for a target: the file or command name, or the host
+fn short_name(target: &str) -> String {
+    let name = if let Some(command) = target.strip_prefix('|') {
+        let program = command.split_whitespace().next().unwrap_or("command");
+        Path::new(program).file_name().and_then(|name| name.to_str()).unwrap_or(program)
+    } else if let Some(addr) = target.strip_prefix("tcp://") {
+        addr
+    } else if let Some(url) = target.strip_prefix("http://").or_else(|| target.strip_prefix("https://")) {
+        url.split('/').next().unwrap_or(url)
+    } else {
+        Path::new(target).file_name().and_then(|name| name.to_str()).unwrap_or(target)
+    };
+    name.chars().take(MAX_NAME_LEN).collect()
+}
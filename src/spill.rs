@@ -0,0 +1,57 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// Append-only temporary file holding the lines evicted from the live buffer,
+/// so history beyond `--max-lines` can still be browsed while memory stays
+/// bounded. The file is removed when the spill is dropped.
+pub struct Spill {
+    pub path: String,
+    out: BufWriter<File>,
+    pub lines: u64, // Lines spilled so far
+}
+
+impl Spill {
+    pub fn create() -> io::Result<Self> {
+        let path: PathBuf = std::env::temp_dir().join(format!("log_monitor-{}.spill", std::process::id()));
+        let mut options = OpenOptions::new();
+        options.append(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600); // Log lines may be sensitive
+        }
+        let file = options.open(&path)?;
+        Ok(Spill {
+            path: path.to_string_lossy().into_owned(),
+            out: BufWriter::new(file),
+            lines: 0,
+        })
+    }
+
+    pub fn append(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.out, "{}", line)?;
+        self.lines += 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Forget everything spilled so far, e.g. when a replay starts over.
+    /// The scrollback is browsed with plain reads rather than a memory map,
+    /// so truncating it under an open reader is safe.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.out.get_ref().set_len(0)?;
+        self.lines = 0;
+        Ok(())
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
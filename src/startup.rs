@@ -0,0 +1,40 @@
+use regex::RegexSet;
+
+/// Lines taken to mean the service or machine just started, unless the
+/// config's `startup_markers` lists others.
+pub const DEFAULT_STARTUP_MARKERS: [&str; 5] = [
+    r"(?i)\bstarting application\b",
+    r"(?i)\bapplication (?:started|starting)\b",
+    r"\bStarted \w+ in [\d.]+ seconds",      // Spring Boot
+    r"\bLinux version \d",                   // Kernel boot
+    r"\bsystemd \d+ running in system mode", // First systemd line of a boot
+];
+
+/// The startup marker patterns: a line matching any of them starts a
+/// restart banner in the log view and counts as a restart in the stats.
+pub struct StartupMarkers {
+    set: RegexSet,
+}
+
+impl StartupMarkers {
+    pub fn new(patterns: &[impl AsRef<str>]) -> Result<Self, String> {
+        let set = RegexSet::new(patterns.iter().map(AsRef::as_ref))
+            .map_err(|e| format!("Invalid startup marker: {}", e))?;
+        Ok(StartupMarkers { set })
+    }
+
+    pub fn matches(&self, line: &str) -> bool {
+        self.set.is_match(line)
+    }
+
+    /// Whether any patterns are set; `startup_markers = []` turns detection off.
+    pub fn enabled(&self) -> bool {
+        !self.set.is_empty()
+    }
+}
+
+impl Default for StartupMarkers {
+    fn default() -> Self {
+        StartupMarkers::new(&DEFAULT_STARTUP_MARKERS).expect("default startup markers are valid")
+    }
+}
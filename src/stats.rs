@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::parse::{LogLevel, LogLine};
+
+/// Latest lines whose timestamps the inter-arrival times are taken from.
+pub const ARRIVAL_LINES: usize = 1000;
+
+/// Counts of the lines in a buffer by level, hour, day, time bucket and
+/// source, kept up to date with `count` as lines come and go.
+#[derive(Default)]
+pub struct LogStats {
+    pub total_entries: usize,
+    pub fatal_count: usize,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub info_count: usize,
+    pub debug_count: usize,
+    pub trace_count: usize,
+    pub unknown_count: usize,
+    pub restarts: usize, // Lines matching a startup marker
+    pub entries_by_hour: HashMap<String, usize>,
+    pub entries_by_day_hour: BTreeMap<NaiveDate, [HeatmapCell; 24]>,
+    pub entries_by_source: HashMap<u16, usize>,
+    entries_by_bucket: [BTreeMap<NaiveDateTime, usize>; 4], // Lines by the start of their bucket, for each `Bucket` size
+}
+
+impl LogStats {
+    /// Count a line joining (`added`) or leaving the buffer.
+    pub fn count(&mut self, log: &LogLine, added: bool) {
+        fn step(n: &mut usize, added: bool) {
+            if added { *n += 1 } else { *n -= 1 }
+        }
+        step(&mut self.total_entries, added);
+        step(match log.level {
+            LogLevel::Fatal => &mut self.fatal_count,
+            LogLevel::Error => &mut self.error_count,
+            LogLevel::Warning => &mut self.warning_count,
+            LogLevel::Info => &mut self.info_count,
+            LogLevel::Debug => &mut self.debug_count,
+            LogLevel::Trace => &mut self.trace_count,
+            LogLevel::Unknown => &mut self.unknown_count,
+        }, added);
+        if log.restart {
+            step(&mut self.restarts, added);
+        }
+        
+        // Group by hour for chart
+        if let Some(time) = log.time {
+            let hour = format!("{:02}", time.hour());
+            let count = self.entries_by_hour.entry(hour.clone()).or_insert(0);
+            step(count, added);
+            if *count == 0 {
+                self.entries_by_hour.remove(&hour);
+            }
+        }
+        
+        // Group by each bucket size for the stats view's chart
+        if let Some(time) = log.time {
+            for bucket in Bucket::ALL {
+                let counts = &mut self.entries_by_bucket[bucket.index()];
+                let start = bucket.start(time);
+                let count = counts.entry(start).or_insert(0);
+                step(count, added);
+                if *count == 0 {
+                    counts.remove(&start);
+                }
+            }
+        }
+        
+        // Group by day and hour for the heatmap
+        if let Some(time) = log.time {
+            let row = self.entries_by_day_hour.entry(time.date()).or_default();
+            let cell = &mut row[time.hour() as usize];
+            step(&mut cell.total, added);
+            if log.level.severity() >= LogLevel::Error.severity() {
+                step(&mut cell.errors, added);
+            }
+            if row.iter().all(|cell| cell.total == 0) {
+                self.entries_by_day_hour.remove(&time.date());
+            }
+        }
+        
+        let count = self.entries_by_source.entry(log.source).or_insert(0);
+        step(count, added);
+        if *count == 0 {
+            self.entries_by_source.remove(&log.source);
+        }
+    }
+
+    /// Lines with a timestamp counted by the start of their bucket; buckets
+    /// without lines are left out.
+    pub fn bucket_counts(&self, bucket: Bucket) -> &BTreeMap<NaiveDateTime, usize> {
+        &self.entries_by_bucket[bucket.index()]
+    }
+}
+
+/// Line counts for one hour of one day in the heatmap.
+#[derive(Default, Clone, Copy)]
+pub struct HeatmapCell {
+    pub total: usize,
+    pub errors: usize,
+}
+
+/// How much time each bar of the distribution chart in the stats view covers,
+/// stepped through with `[` and `]`.
+#[derive(PartialEq, Clone, Copy)]
+pub enum Bucket {
+    TenSeconds,
+    Minute,
+    FiveMinutes,
+    Hour,
+}
+
+impl Bucket {
+    const ALL: [Bucket; 4] = [Bucket::TenSeconds, Bucket::Minute, Bucket::FiveMinutes, Bucket::Hour];
+
+    pub fn seconds(self) -> u32 {
+        match self {
+            Bucket::TenSeconds => 10,
+            Bucket::Minute => 60,
+            Bucket::FiveMinutes => 300,
+            Bucket::Hour => 3600,
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|&bucket| bucket == self).unwrap_or(0)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Bucket::TenSeconds => "10s",
+            Bucket::Minute => "1m",
+            Bucket::FiveMinutes => "5m",
+            Bucket::Hour => "1h",
+        }
+    }
+
+    /// The next smaller (`-1`) or larger (`1`) size, stopping at either end.
+    pub fn step(self, delta: isize) -> Self {
+        Self::ALL[self.index().saturating_add_signed(delta).min(Self::ALL.len() - 1)]
+    }
+
+    /// The start of the bucket `time` falls in.
+    pub fn start(self, time: NaiveDateTime) -> NaiveDateTime {
+        let secs = time.num_seconds_from_midnight();
+        let start = NaiveTime::from_num_seconds_from_midnight_opt(secs - secs % self.seconds(), 0).unwrap_or_default();
+        time.date().and_time(start)
+    }
+}
+
+/// The last `n` buckets up to the newest one counted, oldest first, with
+/// quiet buckets in between kept as zero so gaps show.
+pub fn recent_buckets(counts: &BTreeMap<NaiveDateTime, usize>, bucket: Bucket, n: usize) -> Vec<(NaiveDateTime, usize)> {
+    let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        return Vec::new();
+    };
+    let step = Duration::seconds(bucket.seconds() as i64);
+    let mut rows: Vec<_> = std::iter::successors(Some(last), |&start| Some(start - step))
+        .take_while(|&start| start >= first)
+        .take(n)
+        .map(|start| (start, counts.get(&start).copied().unwrap_or(0)))
+        .collect();
+    rows.reverse();
+    rows
+}
+
+/// The gaps between the timestamps of consecutive lines among the latest
+/// `ARRIVAL_LINES`, in arrival order. A line stamped before the one ahead
+/// of it, as merged sources can be, leaves no gap.
+pub fn arrival_gaps<'a>(logs: impl DoubleEndedIterator<Item = &'a LogLine>) -> Vec<std::time::Duration> {
+    let times: Vec<NaiveDateTime> = logs.rev().take(ARRIVAL_LINES).filter_map(|log| log.time).collect();
+    times.windows(2).filter_map(|pair| (pair[0] - pair[1]).to_std().ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::LogFormat;
+
+    #[test]
+    fn bucket_counts_follow_lines_coming_and_going() {
+        let lines: Vec<LogLine> = ["10:00:05", "10:00:15", "10:00:55", "10:01:05"]
+            .iter()
+            .map(|time| LogLine::parse(&format!("2024-05-01 {} INFO tick", time), &LogFormat::Auto))
+            .collect();
+        let mut stats = LogStats::default();
+        lines.iter().for_each(|log| stats.count(log, true));
+        let at = |time: &str| NaiveDateTime::parse_from_str(&format!("2024-05-01 {}", time), "%Y-%m-%d %H:%M:%S").unwrap();
+        let minutes: Vec<_> = stats.bucket_counts(Bucket::Minute).iter().map(|(&start, &count)| (start, count)).collect();
+        assert_eq!(minutes, [(at("10:00:00"), 3), (at("10:01:00"), 1)]);
+        stats.count(&lines[0], false);
+        assert_eq!(stats.bucket_counts(Bucket::TenSeconds).len(), 3);
+        assert_eq!(stats.bucket_counts(Bucket::Hour).get(&at("10:00:00")), Some(&3));
+    }
+}
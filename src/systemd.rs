@@ -0,0 +1,170 @@
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::poller::Poller;
+use crate::LogLevel;
+
+// How often a followed unit's state is asked for again
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// What `systemctl show` is asked for
+const PROPERTIES: &str = "--property=Id,ActiveState,SubState,NRestarts,MainPID";
+
+/// The unit a `|journalctl …` source follows, given with `-u`, `--unit` or
+/// `--user-unit`, and whether it is a user unit.
+pub fn journal_unit(target: &str) -> Option<(String, bool)> {
+    let mut words = target.strip_prefix('|')?.split_whitespace();
+    words.by_ref().find(|word| word.rsplit('/').next() == Some("journalctl"))?;
+    // Only journalctl's own arguments, not those of a command it is piped into
+    let words: Vec<&str> = words.take_while(|word| !matches!(*word, "|" | "||" | "&&" | ";")).collect();
+    let mut user = words.contains(&"--user");
+    for (idx, word) in words.iter().enumerate() {
+        let unit = match *word {
+            "-u" | "--unit" => words.get(idx + 1).copied(),
+            "--user-unit" => {
+                user = true;
+                words.get(idx + 1).copied()
+            }
+            _ => word.strip_prefix("--unit=")
+                .or_else(|| word.strip_prefix("--user-unit=").inspect(|_| user = true))
+                .or_else(|| word.strip_prefix("-u").filter(|rest| !rest.is_empty())),
+        };
+        if let Some(unit) = unit.map(|unit| unit.trim_matches(['\'', '"'])).filter(|unit| !unit.is_empty()) {
+            return Some((unit.to_string(), user));
+        }
+    }
+    None
+}
+
+/// Where a unit stands, as `systemctl show` has it.
+#[derive(Clone, Default, PartialEq)]
+pub struct UnitState {
+    pub id: String,     // Full unit name, such as `nginx.service`
+    pub active: String, // active, reloading, inactive, failed, activating or deactivating
+    pub sub: String,    // Such as running, exited or dead
+    pub restarts: u32,  // Automatic restarts since the unit was last started by hand
+    pub main_pid: u32,  // 0 while no process runs
+}
+
+impl UnitState {
+    fn parse(text: &str) -> Self {
+        let mut state = UnitState::default();
+        for (key, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            match key {
+                "Id" => state.id = value.to_string(),
+                "ActiveState" => state.active = value.to_string(),
+                "SubState" => state.sub = value.to_string(),
+                "NRestarts" => state.restarts = value.parse().unwrap_or(0),
+                "MainPID" => state.main_pid = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+        state
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.active, self.sub)
+    }
+
+    /// What the change from `old` says about the service, as the text of a
+    /// marker line with its level, and whether the service started again;
+    /// `None` when nothing worth a marker changed.
+    pub fn change_from(&self, old: &UnitState) -> Option<(String, LogLevel, bool)> {
+        let started = self.active == "active" && old.active != "active";
+        let new_process = self.main_pid != 0 && old.main_pid != 0 && self.main_pid != old.main_pid;
+        if self.restarts > old.restarts || (self.active == "active" && (started || new_process)) {
+            let mut text = format!("{} restarted: {}", self.id, self.label());
+            if self.main_pid != 0 {
+                text.push_str(&format!(", pid {}", self.main_pid));
+            }
+            if self.restarts > 0 {
+                text.push_str(&format!(", automatic restart #{}", self.restarts));
+            }
+            return Some((text, LogLevel::Warning, true));
+        }
+        if self.active == old.active && self.sub == old.sub {
+            return None;
+        }
+        let level = match self.active.as_str() {
+            "failed" => LogLevel::Error,
+            "inactive" | "deactivating" => LogLevel::Warning,
+            _ => LogLevel::Info,
+        };
+        Some((format!("{} is {}", self.id, self.label()), level, false))
+    }
+}
+
+// Ask systemd where `unit` stands
+fn query(unit: &str, user: bool) -> Result<UnitState, String> {
+    let mut command = Command::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    let output = command.args(["show", PROPERTIES, "--", unit])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Cannot run systemctl: {}", e))?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("systemctl show failed: {}", error.trim()));
+    }
+    Ok(UnitState::parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// A unit whose journal is followed by a source, with a poller asking
+/// systemd for its state every couple of seconds.
+pub struct UnitWatch {
+    pub unit: String,
+    pub source: u16,              // Id of the journalctl source
+    pub state: Option<UnitState>, // At the last look, once there was one
+    pub poller: Poller<Result<UnitState, String>>,
+}
+
+impl UnitWatch {
+    /// Start watching `unit` for the source numbered `source`. Only changes
+    /// are sent.
+    pub fn start(unit: &str, user: bool, source: u16) -> Self {
+        let polled = unit.to_string();
+        let mut last = None;
+        let poller = Poller::start(POLL_INTERVAL, move || {
+            let current = query(&polled, user);
+            if last.as_ref() == Some(&current) {
+                return None;
+            }
+            last = Some(current.clone());
+            Some(current)
+        });
+        UnitWatch { unit: unit.to_string(), source, state: None, poller }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(target: &str) -> Option<(String, bool)> {
+        journal_unit(target)
+    }
+
+    #[test]
+    fn finds_the_unit_in_each_spelling() {
+        assert_eq!(unit("|journalctl -f -u nginx"), Some(("nginx".to_string(), false)));
+        assert_eq!(unit("|journalctl -f --unit=nginx.service"), Some(("nginx.service".to_string(), false)));
+        assert_eq!(unit("|/usr/bin/journalctl -unginx -f"), Some(("nginx".to_string(), false)));
+        assert_eq!(unit("|journalctl -f -u 'nginx'"), Some(("nginx".to_string(), false)));
+    }
+
+    #[test]
+    fn tells_user_units() {
+        assert_eq!(unit("|journalctl --user-unit=sync -f"), Some(("sync".to_string(), true)));
+        assert_eq!(unit("|journalctl --user-unit sync"), Some(("sync".to_string(), true)));
+        assert_eq!(unit("|journalctl --user -u sync"), Some(("sync".to_string(), true)));
+    }
+
+    #[test]
+    fn ignores_other_commands_and_what_journalctl_is_piped_into() {
+        assert_eq!(unit("|journalctl -f | grep -u nginx"), None);
+        assert_eq!(unit("|tail -f -u nginx"), None);
+        assert_eq!(unit("journalctl -u nginx"), None);
+    }
+}
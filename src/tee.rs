@@ -0,0 +1,154 @@
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, Receiver, Sender, TryRecvError, TrySendError};
+
+// Lines waiting for the writer thread; past this, new ones are dropped
+// rather than stalling the UI behind a slow target
+const MAX_QUEUED_LINES: usize = 10_000;
+
+// How long closing a tee waits for the lines still queued to be written
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Where tee'd lines go: a file, the stdin of a command, or a TCP connection
+enum TeeSink {
+    File(BufWriter<std::fs::File>),
+    Pipe(Child),
+    Tcp(TcpStream),
+}
+
+/// Forwards every line passing the current filter to a downstream target in
+/// real time, making the monitor an interactive grep stage in a pipeline.
+/// Lines are written on a thread of its own, so a slow target never holds
+/// up the UI.
+pub struct Tee {
+    pub target: String,
+    pub dropped: usize, // Lines dropped because the target fell too far behind
+    lines: Option<Sender<String>>,
+    error: Arc<Mutex<Option<io::Error>>>, // What stopped the writer thread, if anything did
+    done: Receiver<()>,                   // Disconnects once the writer thread has written all it was sent
+}
+
+impl Tee {
+    /// Open a tee target: `|command` pipes into a shell command,
+    /// `tcp://host:port` connects to a TCP endpoint, and anything else is a
+    /// file path that gets appended to.
+    pub fn open(target: &str) -> io::Result<Self> {
+        let sink = if let Some(command) = target.strip_prefix('|') {
+            let child = Command::new("sh")
+                .arg("-c")
+                .arg(command.trim())
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+            TeeSink::Pipe(child)
+        } else if let Some(addr) = target.strip_prefix("tcp://") {
+            TeeSink::Tcp(TcpStream::connect(addr)?)
+        } else {
+            let file = OpenOptions::new().create(true).append(true).open(target)?;
+            TeeSink::File(BufWriter::new(file))
+        };
+        let (lines, queue) = bounded(MAX_QUEUED_LINES);
+        let (finished, done) = bounded(0);
+        let error = Arc::new(Mutex::new(None));
+        let failed = error.clone();
+        thread::spawn(move || {
+            if let Err(e) = write_lines(sink, queue) {
+                *failed.lock().unwrap() = Some(e);
+            }
+            drop(finished);
+        });
+        Ok(Tee {
+            target: target.to_string(),
+            dropped: 0,
+            lines: Some(lines),
+            error,
+            done,
+        })
+    }
+
+    /// Queue a line for the target, failing once the target has failed.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let Some(lines) = &self.lines else {
+            return Ok(());
+        };
+        match lines.try_send(line.to_string()) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => Err(self.take_error()),
+        }
+    }
+
+    /// Report whether the writer thread stopped; it flushes on its own
+    /// whenever it runs out of lines. Called once per batch of new lines.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.error.lock().unwrap().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn take_error(&self) -> io::Error {
+        self.error.lock().unwrap().take()
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "tee target closed"))
+    }
+}
+
+impl Drop for Tee {
+    fn drop(&mut self) {
+        // Closing the queue ends the writer thread once it has written what
+        // is left; a command it pipes into is left to finish on its own
+        self.lines = None;
+        let _ = self.done.recv_timeout(DRAIN_TIMEOUT);
+    }
+}
+
+// The writer thread: write lines as they come, flushing whenever the queue
+// runs dry, until the tee is closed or the target fails
+fn write_lines(mut sink: TeeSink, queue: Receiver<String>) -> io::Result<()> {
+    let result = loop {
+        let line = match queue.try_recv() {
+            Ok(line) => line,
+            Err(TryRecvError::Empty) => {
+                if let Err(e) = sink_writer(&mut sink).and_then(|writer| writer.flush()) {
+                    break Err(e);
+                }
+                match queue.recv() {
+                    Ok(line) => line,
+                    Err(_) => break Ok(()),
+                }
+            }
+            Err(TryRecvError::Disconnected) => break Ok(()),
+        };
+        if let Err(e) = sink_writer(&mut sink).and_then(|writer| writeln!(writer, "{}", line)) {
+            break Err(e);
+        }
+    };
+    let _ = sink_writer(&mut sink).and_then(|writer| writer.flush());
+    if let TeeSink::Pipe(mut child) = sink {
+        // Closing stdin lets the command finish; this thread reaps it
+        drop(child.stdin.take());
+        let _ = child.wait();
+    }
+    result
+}
+
+fn sink_writer(sink: &mut TeeSink) -> io::Result<&mut dyn Write> {
+    Ok(match sink {
+        TeeSink::File(file) => file,
+        TeeSink::Pipe(child) => child
+            .stdin
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "tee command closed its input"))?,
+        TeeSink::Tcp(stream) => stream,
+    })
+}
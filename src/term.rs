@@ -0,0 +1,310 @@
+use std::io::{self, Stdout, Write};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+use crossterm::cursor::Show;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::Widget;
+use ratatui::Terminal;
+use serde::Deserialize;
+
+use crate::theme;
+
+/// How many colors the terminal can show, detected from `COLORTERM` and
+/// `TERM` or chosen with `--colors`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+pub enum ColorDepth {
+    /// 24-bit RGB colors
+    #[value(name = "truecolor")]
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    /// The xterm 256-color palette
+    #[value(name = "256")]
+    #[serde(rename = "256")]
+    Ansi256,
+    /// The 16 basic ANSI colors
+    #[value(name = "16")]
+    #[serde(rename = "16")]
+    Ansi16,
+    /// No colors at all
+    #[value(name = "mono")]
+    #[serde(rename = "mono")]
+    Mono,
+}
+
+/// What the terminal can display; the finished frame is degraded to fit.
+#[derive(Clone, Copy)]
+pub struct Capabilities {
+    pub colors: ColorDepth,
+    pub unicode: bool, // Box drawing, block and arrow glyphs render correctly
+}
+
+impl Capabilities {
+    /// Guess from the environment: `NO_COLOR`, `COLORTERM` and `TERM` for
+    /// colors, the locale and `TERM` for Unicode.
+    pub fn detect() -> Self {
+        let term = std::env::var("TERM").unwrap_or_default();
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let colors = if theme::no_color() {
+            ColorDepth::Mono
+        } else if matches!(colorterm.as_str(), "truecolor" | "24bit") || term.ends_with("-direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else if term == "dumb" || (term.is_empty() && !cfg!(windows)) {
+            ColorDepth::Mono
+        } else if term.is_empty() {
+            // The Windows console has no TERM but handles RGB colors
+            ColorDepth::TrueColor
+        } else {
+            ColorDepth::Ansi16
+        };
+
+        // The first locale variable that is set decides, as in setlocale(3)
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty());
+        let utf8 = match locale {
+            Some(locale) => {
+                let locale = locale.to_ascii_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            }
+            None => cfg!(windows),
+        };
+        // Serial consoles and hardware terminals have no glyphs beyond ASCII
+        let unicode = utf8 && term != "dumb" && !term.starts_with("vt");
+        Capabilities { colors, unicode }
+    }
+
+    /// Whether the frame can be drawn as it is.
+    pub fn full(&self) -> bool {
+        self.colors == ColorDepth::TrueColor && self.unicode
+    }
+}
+
+// Whether the terminal is in raw mode on the alternate screen, so it is put
+// back exactly once, by the guard or by the panic hook, whichever comes first
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// Save the terminal's window and icon titles on its title stack, and put
+// them back, so whatever title the monitor set is undone when it lets go
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// The terminal switched to raw mode, the alternate screen and mouse
+/// capture for as long as the guard lives, with its title saved. Dropping
+/// it puts the terminal back, as does a panic anywhere in the meantime, so
+/// the shell is left usable whichever way the monitor exits.
+pub struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalGuard {
+    pub fn enter() -> io::Result<Self> {
+        install_panic_hook();
+        let mut guard = TerminalGuard { terminal: Terminal::new(CrosstermBackend::new(io::stdout()))? };
+        // A failure half way is cleaned up by dropping the guard
+        guard.take_over()?;
+        Ok(guard)
+    }
+
+    /// Give the terminal back to the shell and stop, as Ctrl+Z does outside
+    /// raw mode, then take it over again and redraw everything once resumed.
+    #[cfg(unix)]
+    pub fn suspend(&mut self) -> io::Result<()> {
+        restore();
+        signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+        self.take_over()
+    }
+
+    #[cfg(not(unix))]
+    pub fn suspend(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Give the terminal back for `run`, such as an editor, then take it
+    /// over again and redraw everything.
+    pub fn hand_over<T>(&mut self, run: impl FnOnce() -> T) -> io::Result<T> {
+        restore();
+        let result = run();
+        self.take_over()?;
+        Ok(result)
+    }
+
+    fn take_over(&mut self) -> io::Result<()> {
+        enable_raw_mode()?;
+        ACTIVE.store(true, Ordering::SeqCst);
+        execute!(self.terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+        write!(self.terminal.backend_mut(), "{}", PUSH_TITLE)?;
+        self.terminal.clear()
+    }
+}
+
+impl Deref for TerminalGuard {
+    type Target = Terminal<CrosstermBackend<Stdout>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+// Leave raw mode, the alternate screen and mouse capture, show the cursor
+// and put the title back, if the terminal is still set up for the interface
+fn restore() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+    let mut out = io::stdout();
+    let _ = write!(out, "{}", POP_TITLE).and_then(|()| out.flush());
+}
+
+// Restore the terminal before the panic message is printed, which would
+// otherwise land on the alternate screen and vanish with it
+fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        previous(info);
+    }));
+}
+
+/// Drawn over a finished frame to bring it within the terminal's
+/// capabilities: colors are mapped to the nearest the terminal has, and
+/// without Unicode every glyph is replaced with an ASCII look-alike.
+pub struct Degrade(pub Capabilities);
+
+impl Widget for Degrade {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                cell.fg = reduce(cell.fg, self.0.colors);
+                cell.bg = reduce(cell.bg, self.0.colors);
+                if !self.0.unicode && !cell.symbol.is_ascii() {
+                    let ascii = ascii_symbol(&cell.symbol);
+                    cell.set_char(ascii);
+                }
+            }
+        }
+    }
+}
+
+// The nearest color to `color` a terminal of `depth` can show
+pub(crate) fn reduce(color: Color, depth: ColorDepth) -> Color {
+    match (depth, color) {
+        (ColorDepth::TrueColor, _) | (_, Color::Reset) => color,
+        (ColorDepth::Mono, _) => Color::Reset,
+        (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_indexed(r, g, b)),
+        (ColorDepth::Ansi256, _) => color,
+        (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => nearest_basic((r, g, b)),
+        (ColorDepth::Ansi16, Color::Indexed(i)) => nearest_basic(indexed_rgb(i)),
+        (ColorDepth::Ansi16, _) => color,
+    }
+}
+
+// The basic colors with the RGB values xterm gives them, in palette order
+const BASIC: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// Channel levels of the 6x6x6 color cube at indexes 16 to 231
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => BASIC[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+// The closer of the nearest color cube entry and the nearest gray
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| (0..6).min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs()).unwrap_or(0) as u8;
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    [cube, gray].into_iter()
+        .min_by_key(|&index| distance(indexed_rgb(index), (r, g, b)))
+        .unwrap_or(cube)
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC.iter()
+        .min_by_key(|(_, basic)| distance(*basic, rgb))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+// An ASCII stand-in for a glyph, '?' for text the terminal can't show
+fn ascii_symbol(symbol: &str) -> char {
+    match symbol {
+        "─" | "━" | "═" | "╌" | "┄" => '-',
+        "│" | "┃" | "║" | "╎" | "┆" => '|',
+        "┌" | "┐" | "└" | "┘" | "╭" | "╮" | "╰" | "╯" | "├" | "┤" | "┬" | "┴" | "┼"
+        | "┏" | "┓" | "┗" | "┛" | "╔" | "╗" | "╚" | "╝" | "╠" | "╣" | "╦" | "╩" | "╬" => '+',
+        "█" | "▉" | "▊" | "▋" | "▌" | "▍" | "▎" | "▏" | "▀" | "▄" | "▇" | "▆" | "▅" => '#',
+        "▓" => '+',
+        "▒" => ':',
+        "░" | "▁" | "▂" | "▃" => '.',
+        "•" | "·" | "∙" | "…" => '.',
+        "↑" => '^',
+        "↓" => 'v',
+        "←" => '<',
+        "→" => '>',
+        "↵" => '|',
+        "✎" => '*',
+        "×" | "✖" => 'x',
+        "⚠" => '!',
+        "ℹ" => 'i',
+        "‼" => 'X',
+        _ => '?',
+    }
+}
@@ -0,0 +1,183 @@
+//! Deterministic rendering for snapshot tests of the interface.
+//!
+//! A [`Harness`] drives an [`App`] with scripted lines and key presses, with
+//! no file, terminal or background threads involved, and renders it into an
+//! in-memory buffer that can be compared as plain text:
+//!
+//! ```
+//! use log_monitor::testing::{Harness, Step};
+//! use crossterm::event::KeyCode;
+//!
+//! let mut harness = Harness::new(80, 20);
+//! let screen = harness.run(&[
+//!     Step::Line("2024-05-01 10:00:00 ERROR db timeout"),
+//!     Step::Line("2024-05-01 10:00:01 INFO retrying"),
+//!     Step::Key(KeyCode::Char('/')),
+//!     Step::Text("db"),
+//!     Step::Key(KeyCode::Enter),
+//! ]);
+//! assert!(screen.contains("Logs (1/2)"));
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+use ratatui::backend::{Backend, TestBackend};
+use ratatui::buffer::Buffer;
+use ratatui::Terminal;
+
+use crate::source::Source;
+use crate::term::{Capabilities, ColorDepth};
+use crate::{input, ui, App};
+
+/// Set to update snapshot files with the current rendering instead of
+/// comparing against them.
+pub const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// One step of a scripted session.
+pub enum Step<'a> {
+    Line(&'a str),  // A line arriving from the source
+    Key(KeyCode),   // A key press without modifiers
+    Text(&'a str),  // Each character typed in turn
+    Resize(u16, u16),
+}
+
+/// An app fed lines and keys by a test and drawn to an in-memory terminal.
+///
+/// The terminal is assumed to show truecolor and Unicode whatever the
+/// environment says, so renderings don't depend on where the tests run.
+pub struct Harness {
+    app: App,
+    terminal: Terminal<TestBackend>,
+    quit: bool,
+}
+
+impl Harness {
+    /// A harness with an empty app and a `width` by `height` terminal.
+    pub fn new(width: u16, height: u16) -> Self {
+        Harness::with_app(App::new("test.log".to_string()), width, height)
+    }
+
+    /// A harness around an app set up by the caller, e.g. with `apply_config`.
+    pub fn with_app(mut app: App, width: u16, height: u16) -> Self {
+        app.term = Capabilities { colors: ColorDepth::TrueColor, unicode: true };
+        if app.sources.is_empty() {
+            let id = app.next_source_id();
+            app.sources.push(Source::detached(id, &app.log_path, app.format.clone()));
+        }
+        let terminal = Terminal::new(TestBackend::new(width, height)).expect("test backend");
+        Harness { app, terminal, quit: false }
+    }
+
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    /// Whether a key or an `AppHandle` asked the app to quit.
+    pub fn quit(&self) -> bool {
+        self.quit || self.app.quit_requested
+    }
+
+    /// Add lines as if they had just been read from the source.
+    pub fn push_lines(&mut self, lines: &[&str]) {
+        self.app.push_lines(lines);
+    }
+
+    /// Handle a key press, as typed in the terminal.
+    pub fn press(&mut self, code: KeyCode) {
+        self.press_with(code, KeyModifiers::NONE);
+    }
+
+    pub fn press_with(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let key = KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        };
+        let height = self.terminal.backend().size().map_or(0, |size| size.height);
+        self.quit |= input::handle_event(&mut self.app, Event::Key(key), height);
+    }
+
+    /// Type each character of `text` in turn.
+    pub fn type_text(&mut self, text: &str) {
+        text.chars().for_each(|c| self.press(KeyCode::Char(c)));
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.terminal.backend_mut().resize(width, height);
+        let _ = self.terminal.autoresize();
+    }
+
+    /// Play the steps in order and render the result.
+    pub fn run(&mut self, steps: &[Step]) -> String {
+        for step in steps {
+            match step {
+                Step::Line(line) => self.push_lines(&[line]),
+                Step::Key(code) => self.press(*code),
+                Step::Text(text) => self.type_text(text),
+                Step::Resize(width, height) => self.resize(*width, *height),
+            }
+        }
+        self.render()
+    }
+
+    /// Draw a frame and return it as text, one line per row.
+    pub fn render(&mut self) -> String {
+        buffer_text(self.render_buffer())
+    }
+
+    /// Apply any `AppHandle` requests, then draw a frame and return the
+    /// cells, for tests that check styles too.
+    pub fn render_buffer(&mut self) -> &Buffer {
+        self.app.process_requests();
+        let app = &mut self.app;
+        self.terminal.draw(|f| ui::draw(app, f)).expect("drawing to a test backend");
+        self.terminal.backend().buffer()
+    }
+}
+
+/// The symbols of `buffer` row by row, without trailing spaces.
+pub fn buffer_text(buffer: &Buffer) -> String {
+    let width = buffer.area.width.max(1) as usize;
+    let rows: Vec<String> = buffer.content
+        .chunks(width)
+        .map(|row| row.iter().map(|cell| cell.symbol.as_str()).collect::<String>().trim_end().to_string())
+        .collect();
+    rows.join("\n") + "\n"
+}
+
+/// Compare `actual` with the snapshot file `<dir>/<name>.txt`, panicking
+/// with both renderings if they differ, or if the snapshot is missing. With
+/// `UPDATE_SNAPSHOTS` set they are written instead, so a new or changed view
+/// is reviewed as a diff of the files.
+pub fn assert_snapshot(dir: impl AsRef<Path>, name: &str, actual: &str) {
+    let path = dir.as_ref().join(format!("{}.txt", name));
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        fs::create_dir_all(dir.as_ref()).expect("creating the snapshot directory");
+        fs::write(&path, actual).expect("writing the snapshot");
+        return;
+    }
+    match fs::read_to_string(&path) {
+        Ok(expected) => assert!(
+            expected == actual,
+            "snapshot {} differs (set {}=1 to accept the new rendering)\n--- expected\n{}--- actual\n{}",
+            path.display(),
+            UPDATE_ENV,
+            expected,
+            actual
+        ),
+        Err(e) => panic!(
+            "snapshot {} cannot be read: {} (set {}=1 to write it)\n--- actual\n{}",
+            path.display(),
+            e,
+            UPDATE_ENV,
+            actual
+        ),
+    }
+}
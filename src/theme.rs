@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::Widget;
+use serde::Deserialize;
+
+use crate::LogLevel;
+
+/// Color scheme for log levels, chosen with `--theme`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Bright level colors for dark terminal backgrounds
+    Dark,
+    /// Deeper level colors that stay readable on light backgrounds
+    Light,
+    /// Blue, yellow and orange levels that stay apart without telling red from green
+    Deuteranopia,
+    /// No colors anywhere, only the terminal's defaults (also chosen by `NO_COLOR`)
+    Mono,
+}
+
+impl Theme {
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+            Theme::Deuteranopia => "deuteranopia",
+            Theme::Mono => "mono",
+        }
+    }
+
+    /// The theme `step` places away from this one, wrapping around.
+    pub fn cycle(self, step: isize) -> Self {
+        let themes = Theme::value_variants();
+        let current = themes.iter().position(|&theme| theme == self).unwrap_or(0);
+        themes[(current as isize + step).rem_euclid(themes.len() as isize) as usize]
+    }
+
+    pub fn level_color(self, level: LogLevel) -> Color {
+        match self {
+            Theme::Dark => level.color(),
+            Theme::Light => match level {
+                LogLevel::Info => Color::Blue,
+                LogLevel::Debug => Color::DarkGray,
+                LogLevel::Trace => Color::Gray,
+                LogLevel::Warning => Color::Magenta,
+                LogLevel::Error => Color::Red,
+                LogLevel::Fatal => Color::LightRed,
+                LogLevel::Unknown => Color::Reset,
+            },
+            // Okabe-Ito colors, chosen to stay distinct with color vision deficiencies
+            Theme::Deuteranopia => match level {
+                LogLevel::Info => Color::Rgb(86, 180, 233),
+                LogLevel::Debug => Color::DarkGray,
+                LogLevel::Trace => Color::Gray,
+                LogLevel::Warning => Color::Rgb(240, 228, 66),
+                LogLevel::Error => Color::Rgb(230, 159, 0),
+                LogLevel::Fatal => Color::Rgb(213, 94, 0),
+                LogLevel::Unknown => Color::Reset,
+            },
+            Theme::Mono => Color::Reset,
+        }
+    }
+
+    /// Color of an access log line by its HTTP status class, in the colors
+    /// of the levels: 2xx as info, 3xx as debug, 4xx as warning, 5xx as error.
+    pub fn status_color(self, status: u16) -> Color {
+        let level = match status / 100 {
+            2 => LogLevel::Info,
+            3 => LogLevel::Debug,
+            4 => LogLevel::Warning,
+            5 => LogLevel::Error,
+            _ => LogLevel::Unknown,
+        };
+        self.level_color(level)
+    }
+}
+
+/// Glyphs shown in front of the level tag with `--symbols`, so levels differ
+/// in shape as well as color. Any of them can be replaced in the
+/// `[level_symbols]` table of the config file.
+pub struct LevelSymbols {
+    glyphs: [String; 7], // Fatal, error, warning, info, debug, trace, unknown
+}
+
+impl Default for LevelSymbols {
+    fn default() -> Self {
+        LevelSymbols { glyphs: ["‼", "✖", "⚠", "ℹ", "·", "∙", " "].map(String::from) }
+    }
+}
+
+impl LevelSymbols {
+    /// The defaults with the glyphs given as `level = "glyph"` pairs swapped in.
+    pub fn from_config(glyphs: &HashMap<String, String>) -> Result<Self, String> {
+        let mut symbols = LevelSymbols::default();
+        for (name, glyph) in glyphs {
+            let level = LogLevel::from_name(name).ok_or_else(|| format!("Unknown level in level_symbols: {}", name))?;
+            if glyph.chars().count() != 1 {
+                return Err(format!("Level symbol for {} must be a single character: {:?}", name, glyph));
+            }
+            symbols.glyphs[Self::slot(level)] = glyph.clone();
+        }
+        Ok(symbols)
+    }
+
+    pub fn glyph(&self, level: LogLevel) -> &str {
+        &self.glyphs[Self::slot(level)]
+    }
+
+    fn slot(level: LogLevel) -> usize {
+        match level {
+            LogLevel::Fatal => 0,
+            LogLevel::Error => 1,
+            LogLevel::Warning => 2,
+            LogLevel::Info => 3,
+            LogLevel::Debug => 4,
+            LogLevel::Trace => 5,
+            LogLevel::Unknown => 6,
+        }
+    }
+}
+
+/// Drawn over a finished frame to reset every cell to the terminal's default
+/// colors, keeping bold, reversed and other modifiers so selections still show.
+pub struct Monochrome;
+
+impl Widget for Monochrome {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).set_fg(Color::Reset).set_bg(Color::Reset);
+            }
+        }
+    }
+}
+
+/// Whether the `NO_COLOR` convention asks for output without colors.
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Timelike};
+
+use crate::diff;
+use crate::LogLine;
+
+// Templates remembered at most; past that, those not seen in the latest
+// second are forgotten
+const MAX_TEMPLATES: usize = 10_000;
+
+/// The display throttle set with `--throttle N`: at most `per_second` lines
+/// of any one message template are shown for each second of log time, and
+/// the last one shown says how many more were held back, so a repeat storm
+/// stays readable without hiding that it is happening. Lines without a
+/// timestamp are always shown.
+pub struct Throttle {
+    pub per_second: usize,
+    seconds: HashMap<String, (NaiveDateTime, usize, u64)>, // Template to the second last seen, lines shown in it and the last shown
+    pub suppressed: HashMap<u64, usize>, // Sequence number of a shown line to the lines of its template held back after it
+    latest: Option<NaiveDateTime>,       // Newest second seen
+}
+
+impl Throttle {
+    pub fn new(per_second: usize) -> Self {
+        Throttle { per_second, seconds: HashMap::new(), suppressed: HashMap::new(), latest: None }
+    }
+
+    /// Forget every count, before the view is filtered again from scratch.
+    pub fn reset(&mut self) {
+        self.seconds.clear();
+        self.suppressed.clear();
+        self.latest = None;
+    }
+
+    /// Whether `log` is shown, lines being taken in order; a line held back
+    /// is counted against the last one shown of its template.
+    pub fn admit(&mut self, log: &LogLine) -> bool {
+        let Some(time) = log.time() else {
+            return true;
+        };
+        let second = time.with_nanosecond(0).unwrap_or(time);
+        if self.latest.is_none_or(|latest| second > latest) {
+            self.latest = Some(second);
+            if self.seconds.len() > MAX_TEMPLATES {
+                self.seconds.retain(|_, &mut (seen, _, _)| seen >= second);
+            }
+        }
+        let entry = self.seconds.entry(diff::template(log.message())).or_insert((second, 0, log.seq));
+        if entry.0 != second {
+            *entry = (second, 0, log.seq);
+        }
+        if entry.1 < self.per_second {
+            entry.1 += 1;
+            entry.2 = log.seq;
+            return true;
+        }
+        *self.suppressed.entry(entry.2).or_insert(0) += 1;
+        false
+    }
+
+    /// Drop the counts of lines evicted from the buffer, those before `seq`.
+    pub fn forget_before(&mut self, seq: u64) {
+        self.suppressed.retain(|&shown, _| shown >= seq);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::LogFormat;
+
+    fn line(seq: u64, text: &str) -> LogLine {
+        let mut log = LogLine::parse(text, &LogFormat::Auto);
+        log.seq = seq;
+        log
+    }
+
+    #[test]
+    fn holds_back_a_template_past_its_lines_per_second() {
+        let mut throttle = Throttle::new(2);
+        let admitted: Vec<bool> = [
+            "2024-05-01 10:00:00 ERROR retry 1 failed",
+            "2024-05-01 10:00:00 ERROR retry 2 failed",
+            "2024-05-01 10:00:00 ERROR retry 3 failed",
+            "2024-05-01 10:00:00 INFO cache warmed",
+            "2024-05-01 10:00:00 ERROR retry 4 failed",
+            "2024-05-01 10:00:01 ERROR retry 5 failed",
+            "retry 6 failed",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(seq, text)| throttle.admit(&line(seq as u64, text)))
+        .collect();
+        assert_eq!(admitted, [true, true, false, true, false, true, true]);
+        assert_eq!(throttle.suppressed, HashMap::from([(1, 2)]));
+    }
+
+    #[test]
+    fn forgets_counts_of_evicted_lines() {
+        let mut throttle = Throttle::new(1);
+        for seq in 0..3 {
+            throttle.admit(&line(seq, "2024-05-01 10:00:00 WARN disk almost full"));
+        }
+        throttle.forget_before(1);
+        assert!(throttle.suppressed.is_empty());
+    }
+}
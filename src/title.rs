@@ -0,0 +1,76 @@
+use std::env;
+use std::io::{self, Write};
+
+use crossterm::terminal::SetTitle;
+use crossterm::QueueableCommand;
+
+/// Keeps the terminal's title, and optionally the name of the tmux window
+/// the monitor runs in, on the file followed and its error count, so a pane
+/// in the background shows that something went wrong without being looked
+/// at. The terminal's own title is put back by `TerminalGuard`.
+pub struct WindowTitle {
+    shown: String, // Title last written, not written again until it changes
+    tmux: bool,    // Rename the tmux window, which names windows with an escape of its own
+}
+
+impl WindowTitle {
+    /// Renaming the tmux window, when running inside tmux, is left to
+    /// `tmux_rename`: tmux turns automatic-rename off for a window renamed
+    /// this way, and no escape turns it back on.
+    pub fn new(tmux_rename: bool) -> Self {
+        let tmux = tmux_rename && env::var_os("TMUX").is_some_and(|value| !value.is_empty());
+        WindowTitle { shown: String::new(), tmux }
+    }
+
+    /// Show `title`, if it isn't shown already. Control characters, which
+    /// a source's name may carry, are left out so they can't end the
+    /// escape sequence early.
+    pub fn update(&mut self, out: &mut impl Write, title: &str) -> io::Result<()> {
+        if title == self.shown {
+            return Ok(());
+        }
+        let text: String = title.chars().filter(|c| !c.is_control()).collect();
+        out.queue(SetTitle(&text))?;
+        // Renames the window when tmux's allow-rename is on; the title above is the pane's
+        if self.tmux {
+            write!(out, "\x1bk{}\x1b\\", text)?;
+        }
+        out.flush()?;
+        self.shown = title.to_string();
+        Ok(())
+    }
+
+    /// Forget the title shown, as the terminal has had its own put back
+    /// meanwhile, so the next update shows it again.
+    pub fn reset(&mut self) {
+        self.shown.clear();
+    }
+}
+
+/// `app.log - 3 errors`, or the name alone before any error.
+pub fn title_text(name: &str, errors: u64) -> String {
+    match errors {
+        0 => name.to_string(),
+        1 => format!("{} - 1 error", name),
+        count => format!("{} - {} errors", name, count),
+    }
+}
+
+/// The short form `tlm-ctl status` answers with, for a status line such as
+/// tmux's: `app.log E3 W12`.
+pub fn status_text(name: &str, errors: u64, warnings: u64) -> String {
+    format!("{} E{} W{}", name, errors, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_characters_stay_out_of_the_title() {
+        let mut title = WindowTitle { shown: String::new(), tmux: true };
+        let mut out = Vec::new();
+        title.update(&mut out, "evil\x1b\\\x07name - 1 error").unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b]0;evil\\name - 1 error\x07\x1bkevil\\name - 1 error\x1b\\");
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::LogLine;
+
+/// Newest filtered lines the token panel counts words in.
+pub const SCAN_LINES: usize = 10_000;
+
+/// Tokens listed in the panel.
+pub const TOP_TOKENS: usize = 20;
+
+/// How long the panel's counts stand while lines come in; a filter change
+/// counts again at once.
+pub const REFRESH: Duration = Duration::from_secs(1);
+
+// Shorter tokens tell too little to be worth listing
+const MIN_TOKEN_LEN: usize = 3;
+
+// Words too common in any log to say anything about this one, level names
+// included since the gauges above count those already
+const STOPWORDS: [&str; 48] = [
+    "the", "and", "for", "with", "from", "this", "that", "not", "are", "was", "were", "has", "have", "had",
+    "but", "all", "any", "can", "will", "into", "out", "its", "our", "your", "you", "they", "been", "than",
+    "then", "when", "after", "before", "http", "https", "www", "com", "null", "true", "false", "none",
+    "trace", "debug", "info", "notice", "warn", "warning", "error", "fatal",
+];
+
+/// The most frequent informative words in the messages of `lines`, with
+/// how many lines use them, most frequent first. Words are lowercased;
+/// stopwords and anything with a digit in it (ids, counts, times) are left out.
+pub fn top_tokens<'a>(lines: impl Iterator<Item = &'a LogLine>, limit: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut seen = Vec::new();
+    for log in lines {
+        // Each line counts once per word, however often it repeats it
+        seen.clear();
+        for token in words(&log.message_lower) {
+            if token.len() < MIN_TOKEN_LEN
+                || token.chars().any(|c| c.is_ascii_digit())
+                || STOPWORDS.contains(&token)
+                || seen.contains(&token)
+            {
+                continue;
+            }
+            seen.push(token);
+            *counts.entry(token).or_insert(0) += 1;
+        }
+    }
+    let mut tokens: Vec<(String, usize)> = counts.into_iter().map(|(token, count)| (token.to_string(), count)).collect();
+    tokens.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    tokens.truncate(limit);
+    tokens
+}
+
+// The words of a lowercase message, as the panel counts them
+fn words(message_lower: &str) -> impl Iterator<Item = &str> {
+    message_lower.split(|c: char| !c.is_alphanumeric() && c != '_')
+}
+
+/// Whether the message of `log` has `word` (lowercase) as a whole word, as
+/// the panel counted it, so picking `user` doesn't also keep `username`.
+pub fn has_word(log: &LogLine, word: &str) -> bool {
+    log.message_lower.contains(word) && words(&log.message_lower).any(|token| token == word)
+}
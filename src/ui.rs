@@ -0,0 +1,1724 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Tabs, Wrap},
+    Frame,
+};
+
+use crate::app::{App, HeatmapMetric, ViewMode, SCROLL_LEAD, STATUS_TIMEOUT};
+use crate::diff::Change;
+use crate::enrich::HostName;
+use crate::parse::{LogLevel, LogLine};
+use crate::picker::PickerEntry;
+use crate::scrubber::{self, Timeline};
+use crate::settings::SETTINGS;
+use crate::source::SourceState;
+use crate::stats::{Bucket, HeatmapCell};
+use crate::term::{ColorDepth, Degrade};
+use crate::theme::{Monochrome, Theme};
+use crate::{compare, complete, detail, diag, highlight, latency, notes, perf, stats};
+
+// Tab titles, in the order Tab/BackTab cycles through them
+pub(crate) const TAB_TITLES: [&str; 5] = ["Logs", "Statistics", "Heatmap", "Alerts", "Help"];
+// The same, as short as they go on a narrow terminal
+const NARROW_TAB_TITLES: [&str; 5] = ["Logs", "Stats", "Heat", "Alerts", "Help"];
+
+// Terminals narrower than this, such as phone SSH clients, get a compact
+// layout: no margin, no filter row while nothing filters, a short status
+// line without the key hints, and levels as a single letter
+pub(crate) const NARROW_WIDTH: u16 = 60;
+
+// Width of a single heatmap cell and of the day label column, in terminal cells
+pub(crate) const HEATMAP_CELL_WIDTH: u16 = 3;
+pub(crate) const HEATMAP_LABEL_WIDTH: u16 = 11;
+// Cell fill for each palette level when the heatmap can't be colored
+const HEATMAP_SHADES: [&str; 4] = ["░░", "▒▒", "▓▓", "██"];
+
+// Columns kept free for the marker ending a long line, as wide as `…[+999.9MB]`
+const LONG_LINE_MARKER_WIDTH: usize = 11;
+
+// Columns of the stats view's token panel, beside the chart
+const TOKEN_PANEL_WIDTH: u16 = 34;
+
+// Addresses of a line listed under it in the detail view
+const MAX_DETAIL_ADDRESSES: usize = 6;
+
+// Links of a line listed under it in the detail view, one for each digit key
+const MAX_DETAIL_LINKS: usize = 9;
+
+/// Draw the whole interface for `app`: the tab row, the current view, the
+/// status bar and any popup that is open.
+pub fn draw<B: ratatui::backend::Backend>(app: &mut App, f: &mut Frame<B>) {
+    let size = f.size();
+    app.narrow = size.width < NARROW_WIDTH;
+
+    // Top-level layout
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(if app.narrow { 0 } else { 1 })
+        .constraints([
+            Constraint::Length(3), // Tab row + filter
+            Constraint::Min(1),    // Content area
+            Constraint::Length(1), // Help text
+        ])
+        .split(size);
+
+    // Render tabs
+    let unread = app.unread_alerts();
+    let titles = if app.narrow { NARROW_TAB_TITLES } else { TAB_TITLES };
+    let titles = titles.iter().map(|t| match *t {
+        "Alerts" if unread > 0 => Line::from(Span::styled(
+            format!("Alerts ({})", unread),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        _ => Line::from(*t),
+    });
+    let tabs = Tabs::new(titles.collect())
+        .block(Block::default().borders(Borders::BOTTOM))
+        .highlight_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+        .select(app.selected_tab);
+    f.render_widget(tabs, chunks[0]);
+
+    // A failing source gets a banner over the view until it recovers
+    let content = match app.source_problem() {
+        Some(problem) if chunks[1].height > 1 => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(1)])
+                .split(chunks[1]);
+            draw_source_banner(app, f, rows[0], problem);
+            rows[1]
+        }
+        _ => chunks[1],
+    };
+
+    // Render the appropriate content based on view mode
+    match app.view_mode {
+        ViewMode::LogView => draw_log_view(app, f, content),
+        ViewMode::StatsView => {
+            app.refresh_top_tokens();
+            app.pairs.iter_mut().for_each(latency::PairRule::refresh_summary);
+            draw_stats_view(app, f, content)
+        }
+        ViewMode::HeatmapView => draw_heatmap_view(app, f, content),
+        ViewMode::AlertsView => draw_alerts_view(app, f, content),
+        ViewMode::HelpView => draw_help_view(f, content),
+        ViewMode::FilterView => {
+            // When in filter mode, still show logs but focus on filter input
+            draw_log_view(app, f, content);
+        }
+        ViewMode::WatchView | ViewMode::CommandView | ViewMode::SettingsView | ViewMode::SourcesView | ViewMode::NotesView | ViewMode::ProblemsView | ViewMode::GroupView => {
+            draw_log_view(app, f, content)
+        }
+        ViewMode::BrowseView => draw_browse_view(app, f, content),
+        ViewMode::CompareView => draw_compare_view(app, f, content),
+        ViewMode::DiffView => draw_diff_view(app, f, content),
+        ViewMode::PickerView => draw_picker_view(app, f, content),
+        ViewMode::DiagnosticsView => draw_diagnostics_view(app, f, content),
+        ViewMode::DetailView => draw_detail_view(app, f, content),
+    }
+
+    // Status bar at bottom
+    let status_text = match app.view_mode {
+        ViewMode::FilterView => match &app.completion {
+            Some(completion) => {
+                let shown = completion.choices.iter().take(complete::SHOWN_CHOICES).cloned().collect::<Vec<_>>().join("  ");
+                match completion.choices.len().checked_sub(complete::SHOWN_CHOICES) {
+                    Some(more) if more > 0 => format!("Tab: {}  (+{} more)", shown, more),
+                    _ => format!("Tab: {}", shown),
+                }
+            }
+            None if app.narrow => format!("Filter: {}", app.filter_text),
+            None => format!("Filter: {} (Press Enter to apply, Esc to cancel)", app.filter_text),
+        },
+        ViewMode::WatchView if app.narrow => format!("Watch: {}", app.watch_input),
+        ViewMode::WatchView => format!("Watch: {} (Press Enter to add, Esc to cancel)", app.watch_input),
+        ViewMode::CommandView => format!(":{}", app.command_input),
+        ViewMode::BrowseView => match &app.browser {
+            Some(browser) if browser.search_editing => format!("Search: {}", browser.search_input),
+            Some(browser) => browser.message.clone(),
+            None => String::new(),
+        },
+        _ if app.alert_flashing() && let Some(alert) = app.alerts.last() => {
+            format!("ALERT [{}] {}", alert.rule, alert.line)
+        }
+        _ if app.status_message.as_ref().is_some_and(|(_, at)| at.elapsed() < STATUS_TIMEOUT) => {
+            app.status_message.as_ref().unwrap().0.clone()
+        }
+        _ if app.narrow => {
+            let alerts = if app.alerts.is_empty() { String::new() } else { format!(" | {} alerts", app.alerts.len()) };
+            let follow = if app.follow_mode { "Follow" } else { "Paused" };
+            format!("{} | {}/{}{}", follow, app.filtered_logs.len(), app.log_lines.len(), alerts)
+        }
+        _ => {
+            let filter_status = if !app.filter_text.is_empty() {
+                format!(" | Filter: {}", app.filter_text)
+            } else {
+                String::new()
+            };
+        
+            let replay_status = match &app.replay {
+                Some(replay) => format!(
+                    " | Replay: {:.1}s/{:.1}s x{}{}",
+                    replay.position_ms() as f64 / 1000.0,
+                    replay.duration_ms() as f64 / 1000.0,
+                    replay.speed,
+                    if replay.paused { " PAUSED" } else if replay.finished() { " DONE" } else { "" }
+                ),
+                None => String::new(),
+            };
+            let tee_status = match &app.tee {
+                Some(tee) if tee.dropped > 0 => format!(" | Tee: {} ({} dropped)", tee.target, tee.dropped),
+                Some(tee) => format!(" | Tee: {}", tee.target),
+                None => String::new(),
+            };
+            let alert_status = if !app.alerts.is_empty() {
+                format!(" | Alerts: {}", app.alerts.len())
+            } else {
+                String::new()
+            };
+        
+            format!(
+                "{}Follow: {} | Lines: {}/{}{}{}{}{}", 
+                if app.view_mode == ViewMode::LogView { "" } else { "Log File: {} | " },
+                if app.follow_mode { "ON" } else { "OFF" },
+                app.filtered_logs.len(),
+                app.log_lines.len(),
+                filter_status,
+                replay_status,
+                tee_status,
+                alert_status
+            )
+        }
+    };
+
+    let help_text = match app.view_mode {
+        _ if app.notes_panel.editing.is_some() => "Enter: Save Note (empty removes it) | Esc: Cancel",
+        ViewMode::FilterView => "Tab: Complete Field | Enter: Apply Filter | Esc: Cancel",
+        ViewMode::WatchView => "level:<name> or regex, then '; bell' / '; notify' | Enter: Add | Esc: Cancel",
+        ViewMode::BrowseView => "↑/↓/PgUp/PgDn: Scroll | g/G: Top/End | /: Search | n: Next | Esc: Back to Live View",
+        ViewMode::CompareView => "↑/↓/PgUp/PgDn: Scroll | ←/→: Switch Pane | g/G: Top/Follow | Esc: Close",
+        ViewMode::DiffView => "↑/↓/PgUp/PgDn: Scroll | g/G: Top/End | Esc: Close",
+        ViewMode::DiagnosticsView => "↑/↓/PgUp/PgDn: Scroll | g/G: Top/Follow | F12/Esc: Close",
+        ViewMode::DetailView => "↑/↓/PgUp/PgDn: Scroll | g/G: Top/End | ←/→: Previous/Next Line | /: Search | 1-9: Open Link | A: Note | Esc: Close",
+        ViewMode::NotesView => "↑/↓: Select | Enter: Go to Line | E: Edit | X: Remove | Esc: Close",
+        ViewMode::ProblemsView => "↑/↓: Select | Enter: Go to Line | E: Open in $EDITOR | Esc: Close",
+        ViewMode::GroupView => "↑/↓: Select | Enter: Show the Group's Lines | Esc: Close",
+        ViewMode::CommandView => "write <path>[.ndjson|.html] | timeline <path.md> | tee <target|off> | throttle <n|off> | open <target> | close <source> | diff <a> <b> | Enter: Run | Esc: Cancel",
+        ViewMode::SettingsView => "↑/↓: Select | ←/→: Change | Enter: Edit | S: Save to Config | Esc: Close",
+        ViewMode::SourcesView => "↑/↓: Select | Space: Show/Hide | ←/→: Level | Enter: Filter | X: Clear | Esc: Close",
+        ViewMode::PickerView => "↑/↓: Select | Enter: Open | Backspace: Parent Directory | Esc: Close",
+        ViewMode::LogView => "↑/↓: Scroll | PgUp/PgDn: Page | F: Follow | /: Filter | W: Watch | S: Save | T: Timestamps | N: Line# | Tab: Switch View",
+        ViewMode::StatsView => "Tab: Switch View | R: Refresh Stats | [/]: Finer/Coarser Buckets | ↑/↓ Enter: Filter by Word",
+        ViewMode::HeatmapView => "Arrows: Move | Enter: Show Hour | E: Volume/Errors | Tab: Switch View",
+        ViewMode::AlertsView => "↑/↓: Select | Enter: Go to Line | A: Ack (Shift: All) | X: Clear Acked | Z: Snooze 5m | M: Mute | U: Unsnooze",
+        ViewMode::HelpView => "Tab: Switch View | Q: Quit",
+    };
+
+    let status_style = if app.alert_flashing() {
+        Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    let status_bar = Paragraph::new(status_text)
+        .style(status_style);
+    f.render_widget(status_bar, chunks[2]);
+
+    // Show help text at bottom right, where there is room for it beside the status
+    if !app.narrow {
+        let help = Paragraph::new(help_text)
+            .style(Style::default().fg(Color::DarkGray))
+            .alignment(ratatui::layout::Alignment::Right);
+        f.render_widget(help, chunks[2]);
+    }
+
+    // Special case for filter input mode
+    if app.view_mode == ViewMode::FilterView {
+        // Create a popup for filter input, counting what the filter keeps as it is typed
+        let area = centered_rect(if app.narrow { 100 } else { 60 }, 3, size);
+        let matched = app.filtered_logs.len();
+        let (title, border) = if app.filter_stale {
+            ("Enter Filter Pattern - counting…".to_string(), Color::White)
+        } else if matched == 0 && !app.log_lines.is_empty() {
+            ("Enter Filter Pattern - matches nothing, Esc puts the last filter back".to_string(), Color::Red)
+        } else {
+            let total = app.log_lines.len();
+            (format!("Enter Filter Pattern - would match {} / {} lines", group_digits(matched), group_digits(total)), Color::White)
+        };
+        let filter_input = Paragraph::new(format!("Filter: {}", app.filter_text))
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title));
+        f.render_widget(filter_input, area);
+    }
+
+    // Popup for adding a watch pattern
+    if app.view_mode == ViewMode::WatchView {
+        let area = centered_rect(if app.narrow { 100 } else { 60 }, 3, size);
+        let (title, border) = match &app.watch_error {
+            // Regex errors end with the line saying what is wrong
+            Some(e) => (format!("Add Watch - {}", e.lines().last().unwrap_or(e)), Color::Red),
+            None => ("Add Watch (level:<name> or regex)".to_string(), Color::White),
+        };
+        let watch_input = Paragraph::new(format!("Watch: {}", app.watch_input))
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title));
+        f.render_widget(watch_input, area);
+    }
+    
+    if app.view_mode == ViewMode::SettingsView {
+        draw_settings_popup(app, f, size);
+    }
+    
+    if app.view_mode == ViewMode::SourcesView {
+        draw_sources_popup(app, f, size);
+    }
+    
+    if app.view_mode == ViewMode::NotesView {
+        draw_notes_popup(app, f, size);
+    }
+    
+    if app.view_mode == ViewMode::ProblemsView {
+        draw_problems_popup(app, f, size);
+    }
+    
+    if app.view_mode == ViewMode::GroupView {
+        draw_group_popup(app, f, size);
+    }
+    
+    // Popup for typing a note, over whichever view it was started from
+    if let Some((seq, input)) = &app.notes_panel.editing {
+        let area = centered_rect(if app.narrow { 100 } else { 60 }, 3, size);
+        let line = app.log_lines.binary_search_by_key(seq, |log| log.seq).ok().map(|idx| &app.log_lines[idx]);
+        let title = match line {
+            Some(log) => format!("Note on line {} (empty removes it)", log.line),
+            None => "Note (empty removes it)".to_string(),
+        };
+        let note_input = Paragraph::new(format!("{} {}_", notes::NOTE_ICON, input))
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(Clear, area);
+        f.render_widget(note_input, area);
+    }
+    
+    if app.perf.show_overlay {
+        draw_perf_overlay(app, f, size);
+    }
+    
+    if app.theme == Theme::Mono {
+        f.render_widget(Monochrome, size);
+    }
+    if !app.term.full() {
+        f.render_widget(Degrade(app.term), size);
+    }
+}
+
+fn draw_log_view<B: ratatui::backend::Backend>(app: &mut App, f: &mut Frame<B>, area: Rect) {
+    // The time scrubber goes on top when there is room for it and time to draw it
+    app.scrubber_area = Rect::default();
+    let timeline = (app.show_scrubber && area.height >= scrubber::MIN_VIEW_HEIGHT && !app.perf.degraded())
+        .then(|| Timeline::build(&app.log_lines, &app.filtered_logs, area.width as usize))
+        .flatten();
+    let area = match timeline {
+        Some(timeline) => {
+            let parts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(scrubber::SCRUBBER_ROWS), Constraint::Min(1)])
+                .split(area);
+            draw_scrubber(app, f, &timeline, parts[0]);
+            parts[1]
+        }
+        None => area,
+    };
+    
+    let filtering = app.filtering();
+    
+    // Split into filter area and logs area
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(if app.narrow && !filtering { 0 } else { 1 }), // Filter display, collapsed while narrow and unfiltered
+            Constraint::Min(1),    // Logs
+        ])
+        .split(area);
+    
+    // Show current filter if any
+    let filter_text = if app.filter_text.is_empty() {
+        "No filter applied"
+    } else {
+        &app.filter_text
+    };
+    let time_text = match app.time_range {
+        // Left open on one side by --since or --until
+        Some((start, end)) if end == NaiveDateTime::MAX => format!(" | Time: since {} (Ctrl+C to clear)", start.format("%Y-%m-%d %H:%M")),
+        Some((start, end)) if start == NaiveDateTime::MIN => format!(" | Time: until {} (Ctrl+C to clear)", end.format("%Y-%m-%d %H:%M")),
+        Some((start, end)) => format!(" | Time: {} to {} (Ctrl+C to clear)", start.format("%Y-%m-%d %H:%M"), end.format("%H:%M")),
+        None => String::new(),
+    };
+    let level_text = match app.min_level {
+        Some(level) => format!(" | Level: {}+", level.as_str()),
+        None => String::new(),
+    };
+    let status_text = if app.status_classes.any() {
+        format!(" | Status: {}", app.status_classes.label())
+    } else {
+        String::new()
+    };
+    let words_text = if app.drill_words.is_empty() {
+        String::new()
+    } else {
+        format!(" | Words: {}", app.drill_words.join(" + "))
+    };
+    let throttle_text = match &app.throttle {
+        Some(throttle) => format!(" | Throttle: {}/s per message", throttle.per_second),
+        None => String::new(),
+    };
+    let source_text = match app.sources.iter().filter(|source| source.filter.active()).count() {
+        0 => String::new(),
+        count => format!(" | Per-source: {} (I to edit)", count),
+    };
+    
+    let filter_display = Paragraph::new(format!(
+        "Filter: {}{}{}{}{}{}{}",
+        filter_text, time_text, level_text, status_text, words_text, throttle_text, source_text
+    ))
+        .style(Style::default().fg(
+            if filtering { Color::Yellow } else { Color::DarkGray }
+        ));
+    f.render_widget(filter_display, chunks[0]);
+    
+    // Determine visible range for scrolling, then build rows only for those lines
+    let start_idx = std::cmp::min(app.scroll.saturating_sub(SCROLL_LEAD), app.filtered_logs.len());
+    // Inside the borders of the list
+    let logs_height = chunks[1].height.saturating_sub(2) as usize;
+    let end_idx = std::cmp::min(start_idx + logs_height, app.filtered_logs.len());
+    app.log_view_range = start_idx..end_idx;
+    
+    // Room for a long line, cut short to fit with its marker
+    let mut row_width = chunks[1].width.saturating_sub(2) as usize;
+    if app.wrap_lines {
+        row_width *= detail::LONG_LINE_ROWS;
+    }
+    let degraded = app.perf.degraded();
+    let selection = app.block_range();
+    let visible_rows: Vec<(Line, Style)> = app.filtered_logs[start_idx..end_idx]
+        .iter()
+        .enumerate()
+        .map(|(offset, &idx)| {
+            let log = &app.log_lines[idx];
+            if degraded {
+                // Plain text while over the frame budget
+                let (message, more) = if log.head.len() + log.message.len() > detail::LONG_LINE_BYTES {
+                    detail::preview(&log.message, row_width.saturating_sub(log.head.chars().count() + LONG_LINE_MARKER_WIDTH))
+                } else {
+                    (&*log.message, None)
+                };
+                let mut spans = vec![Span::raw(&*log.head), Span::raw(message)];
+                spans.extend(more.map(Span::raw));
+                return (Line::from(spans), Style::default());
+            }
+            // Restarts stand out as a band across the view
+            let mut style = if log.restart { Style::default().bg(Color::DarkGray) } else { Style::default() };
+            if selection.as_ref().is_some_and(|selection| selection.contains(&idx)) {
+                style = style.bg(Color::Blue);
+            }
+            if log.highlighted {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            if app.note_for(log.seq).is_none() {
+                return (Line::from(log_line_spans(app, start_idx + offset, log, Some(row_width))), style);
+            }
+            // Annotated lines start with an icon
+            let mut spans = vec![Span::styled(format!("{} ", notes::NOTE_ICON), Style::default().fg(Color::Yellow))];
+            spans.extend(log_line_spans(app, start_idx + offset, log, Some(row_width.saturating_sub(2))));
+            (Line::from(spans), style)
+        })
+        .collect();
+    
+    // Render the logs list
+    // Show the ingest rate while lines are arriving
+    let pressure = if app.lines_per_sec >= 1.0 {
+        format!(" | {:.0} lines/sec", app.lines_per_sec)
+    } else {
+        String::new()
+    };
+    let mut title = vec![Span::raw(format!("Logs ({}/{}){}", app.filtered_logs.len(), app.log_lines.len(), pressure))];
+    // Which workspace is shown, unless it is the first and unnamed
+    let workspace = &app.workspaces[app.workspace];
+    if app.workspace > 0 || workspace.name.is_some() {
+        title.push(Span::raw(" "));
+        let label = format!(" {} ", workspace.label(app.workspace));
+        title.push(Span::styled(label, Style::default().fg(Color::Black).bg(Color::Cyan)));
+    }
+    if app.redactor.enabled() {
+        title.push(Span::raw(" "));
+        title.push(Span::styled(" REDACTED ", Style::default().fg(Color::Black).bg(Color::Magenta)));
+    }
+    if app.audit.is_some() {
+        title.push(Span::raw(" "));
+        title.push(Span::styled(" AUDIT ", Style::default().fg(Color::White).bg(Color::Blue)));
+    }
+    // The build's diagnostics so far, in cargo mode
+    if let Some(problems) = &app.problems {
+        for (level, name, color) in [(LogLevel::Error, "error", Color::Red), (LogLevel::Warning, "warning", Color::Yellow)] {
+            let count = problems.count(level);
+            if count > 0 {
+                title.push(Span::raw(" "));
+                let label = format!(" {} ", counted(count, name));
+                title.push(Span::styled(label, Style::default().fg(Color::Black).bg(color)));
+            }
+        }
+    }
+    // Where each systemd unit whose journal is followed stands
+    for watch in &app.unit_watches {
+        let Some(state) = &watch.state else {
+            continue;
+        };
+        let color = match state.active.as_str() {
+            "active" => Color::Green,
+            "failed" => Color::Red,
+            "inactive" => Color::Gray,
+            _ => Color::Yellow,
+        };
+        title.push(Span::raw(" "));
+        let label = format!(" {}: {} ", state.id, state.label());
+        title.push(Span::styled(label, Style::default().fg(Color::Black).bg(color)));
+    }
+    if app.hidden_lines > 0 {
+        title.push(Span::raw(" "));
+        let label = format!(" {} hidden (Shift+X) ", group_digits(app.hidden_lines));
+        title.push(Span::styled(label, Style::default().fg(Color::White).bg(Color::DarkGray)));
+    }
+    if app.block_start.is_some() {
+        title.push(Span::raw(" "));
+        title.push(Span::styled(" BLOCK (x hides, v drops) ", Style::default().fg(Color::White).bg(Color::Blue)));
+    }
+    // Say how much arrived since follow mode was paused by moving up
+    if !app.follow_mode {
+        let unseen = match app.unseen_lines {
+            0 => String::new(),
+            1 => " — 1 new line".to_string(),
+            count => format!(" — {} new lines", group_digits(count)),
+        };
+        title.push(Span::raw(" "));
+        title.push(Span::styled(
+            format!(" PAUSED{} (End to follow) ", unseen),
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        ));
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Line::from(title));
+    
+    if app.wrap_lines {
+        // Long lines continue on the rows below; lines past the bottom are cut off
+        let lines: Vec<Line> = visible_rows.into_iter().map(|(mut line, style)| {
+            for span in &mut line.spans {
+                span.style = style.patch(span.style);
+            }
+            line
+        }).collect();
+        f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), chunks[1]);
+        return;
+    }
+    let visible_items: Vec<ListItem> = visible_rows.into_iter().map(|(line, style)| ListItem::new(line).style(style)).collect();
+    let logs_list = List::new(visible_items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    
+    f.render_widget(logs_list, chunks[1]);
+}
+
+// The filtered view's lines (top row) and errors (bottom row) across its
+// time span, with the column of the top line of the view reversed as the cursor
+fn draw_scrubber<B: ratatui::backend::Backend>(app: &mut App, f: &mut Frame<B>, timeline: &Timeline, area: Rect) {
+    app.scrubber_area = area;
+    let cursor = app.scrubber_time().map(|time| timeline.column(time));
+    let max_lines = timeline.columns.iter().map(|&(lines, _)| lines).max().unwrap_or(0);
+    let max_errors = timeline.columns.iter().map(|&(_, errors)| errors).max().unwrap_or(0);
+    let row = |count: fn(&(usize, usize)) -> usize, max: usize, color: Color| -> Line {
+        Line::from(timeline.columns.iter().enumerate().map(|(column, counts)| {
+            let style = Style::default().fg(color);
+            let style = if cursor == Some(column) { style.add_modifier(Modifier::REVERSED) } else { style };
+            Span::styled(scrubber::density_glyph(count(counts), max), style)
+        }).collect::<Vec<_>>())
+    };
+    let lines = vec![
+        row(|&(lines, _)| lines, max_lines, Color::Cyan),
+        row(|&(_, errors)| errors, max_errors, app.theme.level_color(LogLevel::Error)),
+    ];
+    f.render_widget(Paragraph::new(lines), area);
+}
+
+// Format a log line the way the log view shows it; `view_idx` is its position
+// in the filtered view. A very long line is cut short to fit in `width`
+// columns, with a marker for the rest; without a width it is kept whole.
+pub(crate) fn log_line_spans<'a>(app: &App, view_idx: usize, log: &'a LogLine, width: Option<usize>) -> Vec<Span<'a>> {
+    let mut parts = Vec::new();
+    
+    // Mark where each rotated file begins
+    if let Some(name) = app.file_markers.get(&log.seq) {
+        parts.push(Span::styled(format!(" {} ", name), Style::default().add_modifier(Modifier::REVERSED)));
+        parts.push(Span::raw(" "));
+    }
+    
+    // Mark the first line of a new day, when the view runs past midnight
+    if let Some(time) = log.time
+        && app.day_above(view_idx).is_some_and(|day| day != time.date())
+    {
+        parts.push(Span::styled(format!(" {} ", time.format("%a %Y-%m-%d")), Style::default().fg(Color::Cyan).add_modifier(Modifier::REVERSED)));
+        parts.push(Span::raw(" "));
+    }
+    
+    // Open a banner where the service started again
+    if log.restart {
+        parts.push(Span::styled(" RESTART ", Style::default().fg(Color::Yellow).add_modifier(Modifier::REVERSED | Modifier::BOLD)));
+        parts.push(Span::raw(" "));
+    }
+    
+    // Tag lines with their source once several are merged
+    if let Some(source) = app.source_of(log).filter(|_| app.sources.len() > 1) {
+        let width = app.sources.iter().map(|source| source.name.chars().count()).max().unwrap_or(0);
+        parts.push(Span::styled(
+            format!("{:<width$} ", source.name, width = width),
+            Style::default().fg(source.color),
+        ));
+    }
+    
+    // Add line number if enabled
+    if app.show_line_numbers {
+        parts.push(Span::styled(
+            format!("{:<width$} ", app.line_number(view_idx, log), width = app.line_number_width()),
+            Style::default().fg(Color::DarkGray)
+        ));
+    }
+    
+    // Add timestamp if enabled
+    if app.show_timestamps && !log.timestamp().is_empty() {
+        parts.push(Span::styled(
+            format!("{} ", app.display_timestamp(view_idx, log)),
+            Style::default().fg(Color::DarkGray)
+        ));
+    }
+    
+    // Access log lines are colored by their status class rather than level
+    let color = match log.status() {
+        Some(status) => app.theme.status_color(status),
+        None => app.theme.level_color(log.level),
+    };
+    
+    // Add log level indicator, after its symbol when those are on
+    if app.show_symbols {
+        parts.push(Span::styled(
+            format!("{} ", app.symbols.glyph(log.level)),
+            Style::default().fg(color).add_modifier(Modifier::BOLD)
+        ));
+    }
+    // A letter will do on a narrow screen
+    let level = if app.narrow && width.is_some() { log.level.letter() } else { log.level.as_str() };
+    parts.push(Span::styled(
+        format!("[{}] ", level),
+        Style::default().fg(color).add_modifier(Modifier::BOLD)
+    ));
+    
+    // A column for each lookup table and [[column]] pattern, blank where the
+    // line has no value for it
+    if app.has_extra_columns() {
+        let content = log.content();
+        for column in app.extra_columns() {
+            let width = column.width();
+            let value: String = column.value(&content).unwrap_or_default().chars().take(width).collect();
+            parts.push(Span::styled(format!("{:<width$} ", value, width = width), Style::default().fg(column.color())));
+        }
+    }
+    
+    // Add the main content, recolored where highlight rules match
+    let style = Style::default().fg(color);
+    let (message, more) = match width {
+        Some(width) if log.head.len() + log.message.len() > detail::LONG_LINE_BYTES => {
+            let used: usize = parts.iter().map(Span::width).sum::<usize>() + log.head.chars().count();
+            detail::preview(&log.message, width.saturating_sub(used + LONG_LINE_MARKER_WIDTH))
+        }
+        _ => (&*log.message, None),
+    };
+    parts.extend(app.links.underline(&log.head, highlight::highlight_spans(&log.head, style, &app.highlights)));
+    parts.extend(app.links.underline(message, highlight::highlight_spans(message, style, &app.highlights)));
+    if let Some(more) = more {
+        parts.push(Span::styled(more, Style::default().fg(Color::DarkGray)));
+    }
+    
+    // How long the pair this line ends took since its start
+    if let Some((rule, elapsed)) = app.latencies.get(&log.seq) {
+        let label = format!(" [{} {}]", app.pairs[*rule].name, latency::format_latency(*elapsed));
+        parts.push(Span::styled(label, Style::default().fg(Color::Cyan)));
+    }
+    
+    // Say how many lines like this one the throttle held back after it
+    if let Some(count) = app.throttle.as_ref().and_then(|throttle| throttle.suppressed.get(&log.seq)) {
+        parts.push(Span::styled(format!(" +{} suppressed", count), Style::default().fg(Color::DarkGray)));
+    }
+    
+    parts
+}
+
+// Debug overlay with frame timings, toggled with D
+fn draw_perf_overlay<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, size: Rect) {
+    let perf = &app.perf;
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    let lines = vec![
+        Line::from(format!("render  {:6.2} ms (avg {:.2})", ms(perf.render), ms(perf.render_avg))),
+        Line::from(format!("ingest  {:6.2} ms (avg {:.2})", ms(perf.ingest), ms(perf.ingest_avg))),
+        Line::from(format!("budget  {:6.2} ms", ms(perf::FRAME_BUDGET))),
+        Line::from(format!("slow    {} of {} frames", perf.slow_frames, perf.frames)),
+        Line::from(format!("rate    {:.0} lines/sec", app.lines_per_sec)),
+        Line::from(Span::styled(
+            if perf.degraded() { "degraded: charts and colors off" } else { "full rendering" },
+            Style::default().fg(if perf.degraded() { Color::Yellow } else { Color::Green }),
+        )),
+    ];
+    let width = 36.min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(size.width.saturating_sub(width + 1), 1, width, height);
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Frame timings")),
+        area,
+    );
+}
+
+// Settings popup, opened with ','
+fn draw_settings_popup<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, size: Rect) {
+    let mut lines: Vec<Line> = SETTINGS.iter().enumerate().map(|(i, &setting)| {
+        let selected = i == app.settings.selected;
+        let value = match &app.settings.input {
+            Some(input) if selected => format!("{}_", input),
+            _ => app.setting_value(setting),
+        };
+        let style = if selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        Line::from(Span::styled(format!("{:<22}{}", setting.label(), value), style))
+    }).collect();
+    lines.push(Line::from(""));
+    lines.push(match &app.settings.message {
+        Some(message) => Line::from(Span::styled(message.clone(), Style::default().fg(Color::Yellow))),
+        None => Line::from(Span::styled("S saves to the config file", Style::default().fg(Color::DarkGray))),
+    });
+    let width = 60.min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(size.width.saturating_sub(width) / 2, size.height.saturating_sub(height) / 2, width, height);
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Settings")),
+        area,
+    );
+}
+
+// Sources panel: each source's health and the level and filter applied to its lines
+fn draw_sources_popup<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, size: Rect) {
+    let header = format!("{:<3}{:<4}{:<17}{:<17}{:>8}{:>8}  {:<9}{:<10}{}", "#", "", "Source", "State", "Lines/s", "Last", "Format", "Level", "Filter");
+    let mut lines = vec![Line::from(Span::styled(header, Style::default().add_modifier(Modifier::BOLD)))];
+    lines.extend(app.sources.iter().enumerate().map(|(i, source)| {
+        let selected = i == app.sources_panel.selected;
+        let level = match source.filter.min_level {
+            Some(level) => format!("{}+", level.as_str()),
+            None => "global".to_string(),
+        };
+        let filter = match &app.sources_panel.input {
+            Some(input) if selected => format!("{}_", input),
+            _ => source.filter.text.clone(),
+        };
+        let last = source.last_line_at.map_or("-".to_string(), |at| format_age(at.elapsed()));
+        let state_color = match source.state {
+            SourceState::Connected | SourceState::Replay => Color::Green,
+            SourceState::WaitingForFile | SourceState::Reconnecting => Color::Yellow,
+            SourceState::Ended => Color::DarkGray,
+            SourceState::Failed => Color::Red,
+        };
+        let mut style = if selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        if source.filter.hidden {
+            style = style.add_modifier(Modifier::DIM);
+        }
+        Line::from(vec![
+            Span::styled(format!("{:<3}{:<4}", i + 1, if source.filter.hidden { "[ ]" } else { "[x]" }), style),
+            Span::styled(format!("{:<17}", source.name), style.fg(source.color)),
+            Span::styled(format!("{:<17}", source.state.label()), style.fg(state_color)),
+            Span::styled(format!("{:>8.1}{:>8}  ", source.lines_per_sec, last), style),
+            Span::styled(format!("{:<9.8}", source.current_format().name()), style.fg(Color::DarkGray)),
+            Span::styled(format!("{:<10}{}", level, filter), style),
+        ])
+    }));
+    lines.push(Line::from(""));
+    let detail = app.sources.get(app.sources_panel.selected).map(|source| match &source.error {
+        Some(error) => Span::styled(format!("{}: {}", source.target, error), Style::default().fg(Color::Red)),
+        None => Span::styled(source.target.clone(), Style::default().fg(Color::DarkGray)),
+    });
+    lines.extend(detail.map(Line::from));
+    lines.push(Line::from(Span::styled(
+        "A level here replaces the global one; filters narrow further; :format <#> <profile> re-parses",
+        Style::default().fg(Color::DarkGray),
+    )));
+    let width = 100.min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(size.width.saturating_sub(width) / 2, size.height.saturating_sub(height) / 2, width, height);
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Sources")),
+        area,
+    );
+}
+
+// Annotations panel: each note with the time and text of its line
+fn draw_notes_popup<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, size: Rect) {
+    let mut lines: Vec<Line> = app.notes.iter().enumerate().map(|(i, note)| {
+        let selected = i == app.notes_panel.selected;
+        let mut style = if selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        if note.seq.is_none() {
+            style = style.add_modifier(Modifier::DIM); // Its line is no longer buffered
+        }
+        let time = note.time.map_or_else(|| "-".to_string(), |time| time.format("%H:%M:%S").to_string());
+        Line::from(vec![
+            Span::styled(format!("{:<10}", time), style.fg(Color::DarkGray)),
+            Span::styled(format!("{} ", note.text), style.fg(Color::Yellow)),
+            Span::styled(format!("{}: {}", note.source, note.line), style),
+        ])
+    }).collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No notes yet: press a on a line in the log view to write one",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let width = 100.min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(size.width.saturating_sub(width) / 2, size.height.saturating_sub(height) / 2, width, height);
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(format!("Notes ({})", app.notes.len()))),
+        area,
+    );
+}
+
+// Problems panel: the diagnostics of the build output, in the order they came
+fn draw_problems_popup<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, size: Rect) {
+    let Some(problems) = &app.problems else {
+        return;
+    };
+    let location_width = problems.list.iter()
+        .filter_map(|problem| problem.location.as_ref())
+        .map(|(path, line, column)| format!("{}:{}:{}", path, line, column).chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<Line> = problems.list.iter().enumerate().map(|(i, problem)| {
+        let style = if i == problems.selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        let (label, color) = match (&problem.code, problem.level) {
+            (Some(code), LogLevel::Error) => (code.clone(), Color::Red),
+            (None, LogLevel::Error) => ("error".to_string(), Color::Red),
+            (_, _) => ("warning".to_string(), Color::Yellow),
+        };
+        let location = problem.location.as_ref().map_or_else(String::new, |(path, line, column)| format!("{}:{}:{}", path, line, column));
+        Line::from(vec![
+            Span::styled(format!("{:<8}", label), style.fg(color)),
+            Span::styled(format!("{:<width$}  ", location, width = location_width), style.fg(Color::Cyan)),
+            Span::styled(problem.message.clone(), style),
+        ])
+    }).collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled("No problems in the build output", Style::default().fg(Color::DarkGray))));
+    }
+    let title = format!(
+        "Problems ({}, {})",
+        counted(problems.count(LogLevel::Error), "error"),
+        counted(problems.count(LogLevel::Warning), "warning")
+    );
+    let width = 100.min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(size.width.saturating_sub(width) / 2, size.height.saturating_sub(height) / 2, width, height);
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+// Lines shown split up by the value of a derived column, the largest group first
+fn draw_group_popup<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, size: Rect) {
+    let Some(panel) = &app.group_panel else {
+        return;
+    };
+    let height = (panel.groups.len().max(1) as u16 + 2).min(size.height);
+    let rows = height.saturating_sub(2) as usize;
+    // Keep the selected group in sight
+    let first = (panel.selected + 1).saturating_sub(rows);
+    let value_width = panel.groups.iter().map(|group| group.value.chars().count()).max().unwrap_or(0).min(40);
+    let mut lines: Vec<Line> = panel.groups.iter().enumerate().skip(first).take(rows).map(|(i, group)| {
+        let style = if i == panel.selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        let value: String = group.value.chars().take(value_width).collect();
+        let mut spans = vec![
+            Span::styled(format!("{:<width$}  ", value, width = value_width), style.fg(Color::LightBlue)),
+            Span::styled(format!("{:>7}", group.lines), style),
+        ];
+        if group.errors > 0 {
+            spans.push(Span::styled(format!("  {}", counted(group.errors, "error")), style.fg(Color::Red)));
+        }
+        Line::from(spans)
+    }).collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled("No line shown has a value for this column", Style::default().fg(Color::DarkGray))));
+    }
+    let column = &app.columns[panel.column].name;
+    let title = format!("Lines by {} ({}, {} without)", column, counted(panel.groups.len(), "value"), counted(panel.without, "line"));
+    let width = 70.min(size.width);
+    let area = Rect::new(size.width.saturating_sub(width) / 2, size.height.saturating_sub(height) / 2, width, height);
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+// A count of something: 1 error, 2 errors
+fn counted(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+// A count with thousands separated: 50,000
+fn group_digits(n: usize) -> String {
+    let digits: Vec<char> = n.to_string().chars().collect();
+    let groups: Vec<String> = digits.rchunks(3).rev().map(|group| group.iter().collect()).collect();
+    groups.join(",")
+}
+
+// How long ago something happened, in the largest whole unit
+fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        0 => "now".to_string(),
+        secs @ 1..=59 => format!("{}s", secs),
+        secs @ 60..=3599 => format!("{}m", secs / 60),
+        secs => format!("{}h", secs / 3600),
+    }
+}
+
+// File picker: recent files, then the entries of the directory being browsed
+fn draw_picker_view<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, area: Rect) {
+    let Some(picker) = &app.picker else {
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+    
+    // Keep the selected row in view
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    let first = (picker.selected + 1).saturating_sub(height);
+    let items: Vec<ListItem> = picker.entries.iter().enumerate().skip(first).take(height).map(|(i, entry)| {
+        let (text, color) = match entry {
+            PickerEntry::Recent(path) => (format!("recent  {}", path), Color::Cyan),
+            PickerEntry::Parent => ("../".to_string(), Color::Blue),
+            PickerEntry::Dir(name) => (format!("{}/", name), Color::Blue),
+            PickerEntry::File(name) => (name.clone(), Color::Reset),
+        };
+        let style = Style::default().fg(color);
+        ListItem::new(text).style(if i == picker.selected { style.add_modifier(Modifier::REVERSED) } else { style })
+    }).collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!("Open file: {}", picker.dir.display())));
+    f.render_widget(list, chunks[0]);
+    
+    let (message, color) = match &picker.error {
+        Some(e) => (e.clone(), Color::Red),
+        None if app.log_path.is_empty() => ("Choose a log file to follow".to_string(), Color::DarkGray),
+        None => (format!("Following {}", app.log_path), Color::DarkGray),
+    };
+    f.render_widget(Paragraph::new(message).style(Style::default().fg(color)), chunks[1]);
+}
+
+fn draw_stats_view<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, area: Rect) {
+    let summary_height = if app.sources.len() > 1 { 4 } else { 3 };
+    // Time between the latest lines, going by their timestamps, when the
+    // panels below still fit with its row
+    let arrivals = latency::summarize(stats::arrival_gaps(app.log_lines.iter()), &latency::PERCENTILES)
+        .filter(|_| area.height > summary_height + app.pairs.len() as u16 + 3 + 6);
+    let latency_rows = app.pairs.len() + arrivals.is_some() as usize;
+    // Split the area into different sections for statistics
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(summary_height), // Summary, plus a line of per-source counts
+            Constraint::Length(if latency_rows == 0 { 0 } else { latency_rows as u16 + 2 }), // Inter-arrival times, and latency of each [[pair]]
+            Constraint::Length(6), // Log level distribution
+            Constraint::Min(1),    // Lines over time
+        ])
+        .split(area);
+    
+    // Summary statistics
+    // How often the service bounced, when startup markers are looked for
+    let restarts = if app.startup_markers.enabled() {
+        format!(" | Restarts: {}", app.stats.restarts)
+    } else {
+        String::new()
+    };
+    // What the buffer would hold once full, at the size of the lines held so far
+    let when_full = match app.log_lines.len() {
+        0 => String::new(),
+        held => format!(", ~{:.1} MB when full", (app.buffer_bytes / held) as f64 * app.max_lines as f64 / (1024.0 * 1024.0)),
+    };
+    let mut summary = vec![Line::from(format!(
+        "Total Log Entries: {}{} | Fatal: {} | Errors: {} | Warnings: {} | Info: {} | Debug: {} | Trace: {} | Buffer: {}/{} lines ({}%), {:.1} MB{} (shared messages {:.1} MB, index {:.1} MB)",
+        app.stats.total_entries,
+        restarts,
+        app.stats.fatal_count,
+        app.stats.error_count,
+        app.stats.warning_count,
+        app.stats.info_count,
+        app.stats.debug_count,
+        app.stats.trace_count,
+        app.log_lines.len(),
+        app.max_lines,
+        app.log_lines.len() * 100 / app.max_lines,
+        app.buffer_bytes as f64 / (1024.0 * 1024.0),
+        when_full,
+        app.interner.memory_size() as f64 / (1024.0 * 1024.0),
+        app.index.memory_size() as f64 / (1024.0 * 1024.0)
+    ))];
+    if app.sources.len() > 1 {
+        let mut counts = vec![Span::raw("Sources: ")];
+        for (i, source) in app.sources.iter().enumerate() {
+            if i > 0 {
+                counts.push(Span::raw(" | "));
+            }
+            let count = app.stats.entries_by_source.get(&source.id).copied().unwrap_or(0);
+            counts.push(Span::styled(format!("{}: {}", source.name, count), Style::default().fg(source.color)));
+        }
+        summary.push(Line::from(counts));
+    }
+    let summary = Paragraph::new(summary)
+        .block(Block::default().borders(Borders::ALL).title("Summary"));
+    f.render_widget(summary, chunks[0]);
+    
+    // Percentiles of the time between lines, and of the latency of each
+    // pairing rule over its latest pairs
+    if latency_rows > 0 {
+        let percentiles = |text: &mut String, (values, max): (Vec<Duration>, Duration)| {
+            for (p, value) in latency::PERCENTILES.iter().zip(values) {
+                text.push_str(&format!(" | p{} {}", p, latency::format_latency(value)));
+            }
+            text.push_str(&format!(" | max {}", latency::format_latency(max)));
+        };
+        let mut lines = Vec::new();
+        if let Some(summary) = arrivals {
+            let mut text = format!("Between lines (latest {})", stats::ARRIVAL_LINES);
+            percentiles(&mut text, summary);
+            lines.push(Line::from(text));
+        }
+        lines.extend(app.pairs.iter().map(|pair| {
+            let mut text = format!("{}: {} | open {}", pair.name, counted(pair.pairs, "pair"), pair.open_count());
+            if let Some(summary) = pair.summary() {
+                percentiles(&mut text, summary);
+            }
+            Line::from(text)
+        }));
+        let latencies = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Latency"));
+        f.render_widget(latencies, chunks[1]);
+    }
+    
+    // Charts are optional work, skipped while frames are over budget
+    if app.perf.degraded() {
+        let note = Paragraph::new("Charts paused while the monitor is under load")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(Block::default().borders(Borders::ALL).title("Distribution"));
+        f.render_widget(note, chunks[2]);
+        return;
+    }
+    
+    // Log level distribution, one gauge per level
+    let levels = [
+        ("Fatal", LogLevel::Fatal, app.stats.fatal_count),
+        ("Errors", LogLevel::Error, app.stats.error_count),
+        ("Warnings", LogLevel::Warning, app.stats.warning_count),
+        ("Info", LogLevel::Info, app.stats.info_count),
+        ("Debug", LogLevel::Debug, app.stats.debug_count),
+        ("Trace", LogLevel::Trace, app.stats.trace_count),
+        ("Unknown", LogLevel::Unknown, app.stats.unknown_count),
+    ];
+    let horizontal_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, levels.len() as u32); 7])
+        .split(chunks[2]);
+    
+    let total = app.stats.total_entries as f64;
+    
+    if total > 0.0 {
+        for ((title, level, count), area) in levels.into_iter().zip(horizontal_chunks.iter()) {
+            let pct = (count as f64 / total) * 100.0;
+            let color = match level {
+                LogLevel::Unknown => Color::Gray,
+                _ => app.theme.level_color(level),
+            };
+            let gauge = Gauge::default()
+                .block(Block::default().title(title).borders(Borders::ALL))
+                .gauge_style(Style::default().fg(color))
+                .use_unicode(app.term.unicode)
+                .percent(pct as u16)
+                .label(format!("{:.1}%", pct));
+            f.render_widget(gauge, *area);
+        }
+    }
+    
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(20), Constraint::Length(TOKEN_PANEL_WIDTH)])
+        .split(chunks[3]);
+    draw_token_panel(app, f, bottom[1]);
+    
+    // Lines per time bucket, the newest that fit, one row each
+    let bucket = app.stats_bucket;
+    let block = Block::default()
+        .title(format!("Messages per {} ([/]: finer/coarser)", bucket.label()))
+        .borders(Borders::ALL);
+    let inner = block.inner(bottom[0]);
+    f.render_widget(block, bottom[0]);
+    let rows = stats::recent_buckets(app.stats.bucket_counts(bucket), bucket, inner.height as usize);
+    let max = rows.iter().map(|&(_, count)| count).max().unwrap_or(0).max(1);
+    let layout = if bucket == Bucket::TenSeconds { "%Y-%m-%d %H:%M:%S" } else { "%Y-%m-%d %H:%M" };
+    let bar_width = (inner.width as usize).saturating_sub(19 + 2 + 8);
+    let lines: Vec<Line> = rows.iter().map(|&(start, count)| {
+        let bar = "█".repeat((count * bar_width).div_ceil(max));
+        Line::from(vec![
+            Span::styled(format!("{:<19} ", start.format(layout)), Style::default().fg(Color::DarkGray)),
+            Span::styled(bar, Style::default().fg(Color::Cyan)),
+            Span::raw(format!(" {}", count)),
+        ])
+    }).collect();
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+// The most frequent words of the filtered lines, the selected one ready to
+// narrow the view with Enter
+fn draw_token_panel<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, area: Rect) {
+    let top = app.top_tokens();
+    let selected = app.token_selected.min(top.len().saturating_sub(1));
+    let width = (area.width as usize).saturating_sub(2 + 8);
+    let items: Vec<ListItem> = top.iter().enumerate().map(|(i, (word, count))| {
+        let style = if i == selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        ListItem::new(format!("{:<width$} {:>7}", word, count, width = width)).style(style)
+    }).collect();
+    let title = if app.drill_words.is_empty() {
+        "Top words (Enter: filter)".to_string()
+    } else {
+        format!("Top words in +{}", app.drill_words.join(" +"))
+    };
+    f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+fn draw_browse_view<B: ratatui::backend::Backend>(app: &mut App, f: &mut Frame<B>, area: Rect) {
+    let Some(browser) = &mut app.browser else {
+        return;
+    };
+    let height = area.height.saturating_sub(2) as usize;
+    let top = browser.top;
+    let lines: Vec<Line> = browser.visible_lines(height)
+        .into_iter()
+        .map(|(n, text)| {
+            let level = LogLevel::from_line(&text);
+            let text = app.redactor.redact(&text).into_owned();
+            Line::from(vec![
+                Span::styled(format!("{:<8} ", n + 1), Style::default().fg(Color::DarkGray)),
+                Span::styled(text, Style::default().fg(app.theme.level_color(level))),
+            ])
+        })
+        .collect();
+    let (count, complete) = browser.line_count();
+    let title = format!(
+        "Browse: {} (line {} of {}{}){}",
+        browser.path,
+        top + 1,
+        count,
+        if complete { "" } else { "+" },
+        if app.redactor.enabled() { " — redacted" } else { "" }
+    );
+    let view = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(view, area);
+}
+
+// Two sources side by side, the unfocused pane lined up with the focused one by timestamp
+fn draw_compare_view<B: ratatui::backend::Backend>(app: &mut App, f: &mut Frame<B>, area: Rect) {
+    let panes = app.compare_panes();
+    let Some(compare) = &mut app.compare else {
+        return;
+    };
+    compare.height = area.height.saturating_sub(2) as usize;
+    let focus = compare.focus;
+    let mut tops = [0; 2];
+    tops[focus] = compare.focus_top(panes[focus].len());
+    tops[1 - focus] = compare::align(&app.log_lines, &panes[focus], tops[focus], &panes[1 - focus]);
+    let (sources, height) = (compare.sources, compare.height);
+    
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    for side in 0..2 {
+        let name = app.sources.iter()
+            .find(|source| source.id == sources[side])
+            .map_or("?", |source| source.name.as_str());
+        let lines: Vec<Line> = panes[side]
+            .iter()
+            .skip(tops[side])
+            .take(height)
+            .map(|&idx| {
+                let log = &app.log_lines[idx];
+                let style = Style::default().fg(app.theme.level_color(log.level));
+                let mut spans = highlight::highlight_spans(&log.head, style, &app.highlights);
+                spans.extend(highlight::highlight_spans(&log.message, style, &app.highlights));
+                Line::from(spans)
+            })
+            .collect();
+        let border_style = if side == focus {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        let title = format!(
+            "{} ({} of {}){}",
+            name,
+            (tops[side] + 1).min(panes[side].len()),
+            panes[side].len(),
+            if side == focus && app.compare.as_ref().is_some_and(|compare| compare.top.is_none()) { " following" } else { "" }
+        );
+        let pane = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).border_style(border_style).title(title));
+        f.render_widget(pane, columns[side]);
+    }
+}
+
+// Template diff: counts on each side, with new and gone templates first
+fn draw_diff_view<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, area: Rect) {
+    let Some(diff) = &app.diff else {
+        return;
+    };
+    let header = format!("{:<8}{:>8}{:>8}  Template", "Change", "Left", "Right");
+    let mut lines = vec![Line::from(Span::styled(header, Style::default().add_modifier(Modifier::BOLD)))];
+    lines.extend(diff.rows.iter().skip(diff.top).map(|row| {
+        let (change, color) = match row.change {
+            Change::New => ("NEW".to_string(), Color::Green),
+            Change::Gone => ("GONE".to_string(), Color::Red),
+            Change::Rate(ratio) if ratio > 1.0 => (format!("x{:.1}", ratio), Color::Yellow),
+            Change::Rate(ratio) => (format!("/{:.1}", 1.0 / ratio), Color::Cyan),
+        };
+        Line::from(vec![
+            Span::styled(format!("{:<8}", change), Style::default().fg(color)),
+            Span::raw(format!("{:>8}{:>8}  {}", row.counts[0], row.counts[1], row.template)),
+        ])
+    }));
+    if diff.rows.is_empty() {
+        lines.push(Line::from(Span::styled("No differences", Style::default().fg(Color::DarkGray))));
+    }
+    let title = format!(
+        "Diff: {} ({} lines) vs {} ({} lines), {} templates unchanged",
+        diff.labels[0], diff.totals[0], diff.labels[1], diff.totals[1], diff.unchanged
+    );
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+fn draw_detail_view<B: ratatui::backend::Backend>(app: &mut App, f: &mut Frame<B>, area: Rect) {
+    let Some(idx) = app.detail.as_ref().and_then(|detail| app.log_lines.binary_search_by_key(&detail.seq, |log| log.seq).ok()) else {
+        let gone = Paragraph::new(Span::styled("The line has been evicted from the buffer", Style::default().fg(Color::DarkGray)));
+        f.render_widget(gone.block(Block::default().borders(Borders::ALL).title("Line")), area);
+        return;
+    };
+    let log = &app.log_lines[idx];
+    // The line's note goes above it
+    let area = match app.note_for(log.seq) {
+        Some(note) if area.height > 6 => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(1)])
+                .split(area);
+            let text = Paragraph::new(Span::styled(note.text.clone(), Style::default().fg(Color::Yellow)));
+            f.render_widget(text.block(Block::default().borders(Borders::ALL).title(format!("{} Note", notes::NOTE_ICON))), rows[0]);
+            rows[1]
+        }
+        _ => area,
+    };
+    let content = app.detail.as_ref().map_or("", |detail| detail.text());
+    // What is known about the line's addresses goes under it
+    let addresses = if app.enricher.enabled() { app.enricher.addresses(content) } else { Vec::new() };
+    let shown = addresses.len().min(MAX_DETAIL_ADDRESSES) as u16;
+    let area = if shown > 0 && area.height > shown + 5 {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(shown + 2)])
+            .split(area);
+        let lines: Vec<Line> = addresses.iter()
+            .take(MAX_DETAIL_ADDRESSES)
+            .map(|&ip| Line::from(address_spans(app, ip)))
+            .collect();
+        f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Addresses")), rows[1]);
+        rows[0]
+    } else {
+        area
+    };
+    // And its links, numbered for opening
+    let links = app.links.find(content);
+    let shown = links.len().min(MAX_DETAIL_LINKS) as u16;
+    let area = if shown > 0 && area.height > shown + 5 {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(shown + 2)])
+            .split(area);
+        let lines: Vec<Line> = links.iter()
+            .take(MAX_DETAIL_LINKS)
+            .enumerate()
+            .map(|(i, (_, link))| Line::from(vec![
+                Span::styled(format!("{}  ", i + 1), Style::default().fg(Color::DarkGray)),
+                Span::styled(link.label(), Style::default().fg(Color::Cyan).add_modifier(Modifier::UNDERLINED)),
+            ]))
+            .collect();
+        f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Links")), rows[1]);
+        rows[0]
+    } else {
+        area
+    };
+    // The search prompt goes under the line while there is a search
+    let searching = app.detail.as_ref().is_some_and(|detail| detail.typing || !detail.query.is_empty());
+    let (area, prompt_area) = if searching && area.height > 3 {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(area);
+        (rows[0], Some(rows[1]))
+    } else {
+        (area, None)
+    };
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let height = area.height.saturating_sub(2) as usize;
+    let size = detail::format_size(content.len());
+    let source = app.source_of(log).map_or_else(String::new, |source| format!("{} ", source.name));
+    let style = Style::default().fg(app.theme.level_color(log.level));
+    let Some(detail) = &mut app.detail else {
+        return;
+    };
+    detail.wrap(width, height);
+    let found = Style::default().fg(Color::Black).bg(Color::Yellow);
+    // Only the rows shown are cut up, however long the line
+    let lines: Vec<Line> = (detail.top..(detail.top + height).min(detail.rows))
+        .map(|row| {
+            // Cut the row where matches start and end, marking the current one apart
+            let text = detail.row(row);
+            let mut bounds: Vec<usize> = text.char_indices().map(|(at, _)| at).collect();
+            bounds.push(text.len());
+            let start = row * width;
+            let end = start + bounds.len() - 1;
+            let byte = |at: usize| bounds[at - start];
+            let mut spans = Vec::new();
+            let mut pos = start;
+            let first = detail.matches.partition_point(|range| range.end <= start);
+            for (i, range) in detail.matches.iter().enumerate().skip(first).take_while(|(_, range)| range.start < end) {
+                let (from, to) = (range.start.max(start), range.end.min(end));
+                if from > pos {
+                    spans.push(Span::styled(&text[byte(pos)..byte(from)], style));
+                }
+                let marked = if i == detail.current { found.bg(Color::LightRed) } else { found };
+                spans.push(Span::styled(&text[byte(from)..byte(to)], marked));
+                pos = to;
+            }
+            if pos < end {
+                spans.push(Span::styled(&text[byte(pos)..], style));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    if let Some(prompt_area) = prompt_area {
+        let cursor = if detail.typing { "_" } else { "" };
+        let count = match detail.matches.len() {
+            _ if detail.query.is_empty() => String::new(),
+            0 => "no match".to_string(),
+            total => format!("{} of {}", detail.current + 1, total),
+        };
+        let prompt = Line::from(vec![
+            Span::styled(format!(" Search: {}{}  ", detail.query, cursor), Style::default().fg(Color::Yellow)),
+            Span::styled(count, Style::default().fg(Color::DarkGray)),
+        ]);
+        f.render_widget(Paragraph::new(prompt), prompt_area);
+    }
+    let title = format!(
+        "Line {}{} [{}] {} | {} | rows {}-{} of {}",
+        source,
+        log.line,
+        log.level.as_str(),
+        log.timestamp(),
+        size,
+        (detail.top + 1).min(detail.rows),
+        (detail.top + height).min(detail.rows),
+        detail.rows
+    );
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+// An address in the detail view, with its country, autonomous system and host name as far as they are known
+fn address_spans(app: &App, ip: IpAddr) -> Vec<Span<'static>> {
+    let mut spans = vec![Span::styled(format!("{:<15}  ", ip), Style::default().fg(Color::Cyan))];
+    if let Some(country) = app.enricher.country(ip) {
+        spans.push(Span::raw(format!("{}  ", country)));
+    }
+    if let Some((asn, org)) = app.enricher.asn(ip) {
+        spans.push(Span::raw(format!("AS{} {}  ", asn, org)));
+    }
+    match app.enricher.host(ip) {
+        Some(HostName::Name(name)) => spans.push(Span::styled(name, Style::default().fg(Color::Green))),
+        Some(HostName::Pending) => spans.push(Span::styled("resolving…", Style::default().fg(Color::DarkGray))),
+        Some(HostName::Unknown) => spans.push(Span::styled("no host name", Style::default().fg(Color::DarkGray))),
+        None => {}
+    }
+    spans
+}
+
+fn draw_diagnostics_view<B: ratatui::backend::Backend>(app: &mut App, f: &mut Frame<B>, area: Rect) {
+    let entries = diag::entries();
+    let rows = area.height.saturating_sub(2) as usize;
+    app.diag_rows = rows;
+    let top = app.diag_top.unwrap_or(entries.len().saturating_sub(rows)).min(entries.len());
+    let mut lines: Vec<Line> = entries[top..].iter()
+        .take(rows)
+        .map(|entry| {
+            let repeats = if entry.repeats > 1 { format!(" (x{})", entry.repeats) } else { String::new() };
+            Line::from(vec![
+                Span::styled(entry.time.format("%H:%M:%S%.3f ").to_string(), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("{:<7}", entry.topic.label()), Style::default().fg(entry.topic.color())),
+                Span::raw(format!("{}{}", entry.message, repeats)),
+            ])
+        })
+        .collect();
+    if entries.is_empty() {
+        lines.push(Line::from(Span::styled("Nothing logged yet", Style::default().fg(Color::DarkGray))));
+    }
+    let title = format!(
+        "Diagnostics: {} entries{} | {} frames, {} over budget",
+        entries.len(),
+        if app.diag_top.is_some() { "" } else { ", following" },
+        app.perf.frames,
+        app.perf.slow_frames
+    );
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+fn draw_alerts_view<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Registered watches
+            Constraint::Min(1),    // Alert history
+        ])
+        .split(area);
+    
+    let watches = if app.watches.is_empty() {
+        "No watches registered (press W in the log view to add one)".to_string()
+    } else {
+        let rules: Vec<String> = app.watches
+            .iter()
+            .map(|w| match w.state_label() {
+                label if label.is_empty() => w.spec.clone(),
+                label => format!("{} ({})", w.spec, label),
+            })
+            .collect();
+        format!("Watching: {}", rules.join(" | "))
+    };
+    f.render_widget(Paragraph::new(watches).style(Style::default().fg(Color::DarkGray)), chunks[0]);
+    
+    // Newest alerts first, scrolled so the selection stays visible
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let first = (app.alerts_selected + 1).saturating_sub(height.max(1));
+    let items: Vec<ListItem> = app.alerts
+        .iter()
+        .rev()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, alert)| {
+            let rule_style = if alert.acknowledged {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            };
+            let item = ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", alert.time.format("%Y-%m-%d %H:%M:%S")), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("[{}] ", alert.rule), rule_style),
+                Span::styled(format!("{}: ", alert.source), Style::default().fg(Color::DarkGray)),
+                Span::raw(alert.line.clone()),
+            ]));
+            if i == app.alerts_selected {
+                item.style(Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                item
+            }
+        })
+        .collect();
+    
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Alerts ({} unread / {} total)", app.unread_alerts(), app.alerts.len())));
+    f.render_widget(list, chunks[1]);
+}
+
+fn draw_heatmap_view<B: ratatui::backend::Backend>(app: &mut App, f: &mut Frame<B>, area: Rect) {
+    let metric_name = match app.heatmap_metric {
+        HeatmapMetric::Volume => "volume",
+        HeatmapMetric::Errors => "errors",
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Activity by Hour × Day (colored by {})", metric_name));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Hour header
+            Constraint::Min(1),    // Grid
+            Constraint::Length(1), // Selected cell details
+        ])
+        .split(inner);
+    
+    if app.stats.entries_by_day_hour.is_empty() {
+        let empty = Paragraph::new("No timestamped log entries to plot")
+            .style(Style::default().fg(Color::DarkGray));
+        f.render_widget(empty, chunks[1]);
+        return;
+    }
+    
+    // Hour header
+    let mut header = format!("{:width$}", "", width = HEATMAP_LABEL_WIDTH as usize);
+    for hour in 0..24 {
+        header.push_str(&format!("{:<width$}", format!("{:02}", hour), width = HEATMAP_CELL_WIDTH as usize));
+    }
+    f.render_widget(Paragraph::new(header).style(Style::default().fg(Color::DarkGray)), chunks[0]);
+    
+    // Scale colors relative to the busiest cell
+    let value = |cell: &HeatmapCell| match app.heatmap_metric {
+        HeatmapMetric::Volume => cell.total,
+        HeatmapMetric::Errors => cell.errors,
+    };
+    let max = app.stats.entries_by_day_hour
+        .values()
+        .flat_map(|row| row.iter().map(value))
+        .max()
+        .unwrap_or(0);
+    let palette = match app.heatmap_metric {
+        HeatmapMetric::Volume => [Color::Blue, Color::Cyan, Color::Green, Color::Yellow],
+        HeatmapMetric::Errors => [Color::Magenta, Color::LightRed, Color::Red, Color::LightYellow],
+    };
+    
+    // Without colors the busier cells are shaded with glyphs instead
+    let colorless = app.theme == Theme::Mono || app.term.colors == ColorDepth::Mono;
+    
+    // Grid, scrolled so the cursor row stays visible
+    app.heatmap_area = chunks[1];
+    let first_row = heatmap_first_row(app.heatmap_cursor.0, chunks[1].height);
+    let lines: Vec<Line> = app.stats.entries_by_day_hour
+        .iter()
+        .enumerate()
+        .skip(first_row)
+        .take(chunks[1].height as usize)
+        .map(|(day_idx, (date, row))| {
+            let mut spans = vec![Span::styled(
+                format!("{:<width$}", date.format("%Y-%m-%d"), width = HEATMAP_LABEL_WIDTH as usize),
+                Style::default().fg(Color::DarkGray),
+            )];
+            for (hour, cell) in row.iter().enumerate() {
+                let v = value(cell);
+                let level = (v > 0).then(|| ((v - 1) * palette.len() / max).min(palette.len() - 1));
+                let mut style = match level {
+                    Some(level) => Style::default().bg(palette[level]),
+                    None => Style::default().bg(Color::Black),
+                };
+                if (day_idx, hour) == app.heatmap_cursor {
+                    style = style.fg(Color::White).add_modifier(Modifier::BOLD);
+                }
+                let text = match level {
+                    _ if (day_idx, hour) == app.heatmap_cursor => "[]",
+                    Some(level) if colorless => HEATMAP_SHADES[level],
+                    _ => "  ",
+                };
+                spans.push(Span::styled(
+                    format!("{:<width$}", text, width = HEATMAP_CELL_WIDTH as usize - 1),
+                    style,
+                ));
+                spans.push(Span::raw(" "));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), chunks[1]);
+    
+    // Details of the selected cell
+    let (day, hour) = app.heatmap_cursor;
+    if let Some((date, row)) = app.stats.entries_by_day_hour.iter().nth(day) {
+        let cell = row[hour];
+        let details = Paragraph::new(format!(
+            "{} {:02}:00-{:02}:00 | {} lines | {} errors | Enter: show these logs",
+            date.format("%Y-%m-%d"),
+            hour,
+            (hour + 1) % 24,
+            cell.total,
+            cell.errors
+        ))
+        .style(Style::default().fg(Color::White));
+        f.render_widget(details, chunks[2]);
+    }
+}
+
+// First day row shown in a heatmap grid of the given height so the cursor row is visible
+pub(crate) fn heatmap_first_row(cursor_row: usize, height: u16) -> usize {
+    (cursor_row + 1).saturating_sub(height.max(1) as usize)
+}
+
+// One line naming the first failing source, red once it has stopped and
+// yellow while it is being retried
+fn draw_source_banner<B: ratatui::backend::Backend>(app: &App, f: &mut Frame<B>, area: Rect, (problem, others): (String, usize)) {
+    let stopped = app.sources.iter().any(|source| source.state == SourceState::Failed);
+    let style = if stopped {
+        Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Black).bg(Color::Yellow)
+    };
+    let text = match others {
+        0 => format!(" {}", problem),
+        _ => format!(" {} (and {} more, I: Sources)", problem, others),
+    };
+    f.render_widget(Paragraph::new(text).style(style), area);
+}
+
+fn draw_help_view<B: ratatui::backend::Backend>(f: &mut Frame<B>, area: Rect) {
+    let text = vec![
+        Line::from(vec![Span::styled("Log Monitor - Keyboard Shortcuts", Style::default().add_modifier(Modifier::BOLD))]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("General", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+        ]),
+        Line::from("Tab: Switch between views (Logs, Statistics, Heatmap, Alerts, Help)"),
+        Line::from("Q: Quit the application"),
+        Line::from("Ctrl+Z: Suspend to the shell, 'fg' to come back"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Log View", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+        ]),
+        Line::from("↑/↓ or K/J: Scroll up/down (also the mouse wheel)"),
+        Line::from("PgUp/PgDn: Page up/down"),
+        Line::from("Home/G: First line | End/Shift+G: Last line, following new ones again"),
+        Line::from("[/]: Step the time scrubber on top back/forward (or click and drag it) to jump to that moment"),
+        Line::from("F: Toggle follow mode (auto-scroll to new logs); any move up pauses it"),
+        Line::from("Enter: Show the highlighted line (or the bottom one) whole, for lines cut short with …[+size]"),
+        Line::from("/ in the Enter view: Search the line; N and Shift+N go to the next and previous match"),
+        Line::from("Shift+O: Open the first URL (in the browser) or path:line (in $EDITOR) of the highlighted line; 1-9 in the Enter view"),
+        Line::from("A: Write a note on the highlighted line (or the bottom one), shown with ✎ and in exports"),
+        Line::from("Shift+A: Notes panel: every note, Enter goes to its line, E edits and X removes it"),
+        Line::from("X/Delete: Hide the highlighted line (or the bottom one); Shift+X brings the last hidden back"),
+        Line::from("V: Start a block selection there, up to the line moved to; X hides the block, V or Esc drops it"),
+        Line::from("P: Problems panel (--cargo): each build error and warning, Enter goes to it and E opens its code in $EDITOR"),
+        Line::from("T: Toggle timestamps display"),
+        Line::from("N: Toggle line numbers"),
+        Line::from("/: Enter filter mode"),
+        Line::from("W: Add a watch pattern (level:<name>, silence:<secs>, escalation or regex)"),
+        Line::from("   options: '; bell', '; notify', '; exec=<cmd>', '; webhook=<url>', '; rate=<count>/<secs>'"),
+        Line::from("Ctrl+C: Clear current filter, time range and status toggles"),
+        Line::from("U: Undo the last change to the filters, level, time range or hidden lines; Ctrl+R redoes it"),
+        Line::from("F1-F4: Switch workspace (own filters, hidden lines and scroll); :workspace <name> names it"),
+        Line::from("4 / 5: Show only access log lines with 4xx / 5xx statuses (both for either; again to turn off)"),
+        Line::from("S: Save the filtered view to filtered-<time>.log"),
+        Line::from("B: Browse the whole file (read from disk, with / search and n for next match)"),
+        Line::from("↑/PgUp at the top: Browse lines evicted from the buffer (--spill)"),
+        Line::from(":buffer <lines>|+|-: Resize the buffer at runtime, evicting the oldest lines if it shrinks"),
+        Line::from(":write <path>: Save the filtered view (.ndjson / .html for JSON or colored HTML)"),
+        Line::from(":timeline <path>: Write a Markdown timeline of the notes and highlighted line, with the lines around them"),
+        Line::from(":throttle <n>|off: Show at most n lines a second of each message template, with a +count suppressed note"),
+        Line::from(":tee <target>: Forward new filtered lines to a file, '|command' or tcp://host:port"),
+        Line::from(":open <target>: Merge in another file, '|command', tcp://host:port, http(s) URL or k8s:// pods"),
+        Line::from("'|journalctl -u <unit>' sources: Show the unit's systemd state in the title, marking its restarts"),
+        Line::from(":close <source>: Detach a source by name or number, removing its lines"),
+        Line::from(":retry [source]: Reopen the sources that stopped, or the one named"),
+        Line::from(":format <source> <profile>: Parse a source as auto, plain, json, syslog, access or a config profile"),
+        Line::from(":compare <target>: Show the main file side by side with another, lined up by timestamp"),
+        Line::from(":diff <a> <b>: New, gone and more or less frequent messages between two time ranges or sources"),
+        Line::from(":geo <country|ASN>|off: Show only lines with an address there (needs geoip_db or asn_db)"),
+        Line::from(":lookup <text>|off: Show only lines whose [[lookup]] column contains the text"),
+        Line::from(":column <name>=<value>|off: Show only lines whose [[column]] has the value"),
+        Line::from(":group [name]: Count the lines shown by the value of a [[column]]; Enter shows one group"),
+        Line::from("[[pair]] rules: End lines show the time since their start; the Statistics tab has percentiles"),
+        Line::from(":<name> <arg>: Run a command_<name> function from the --script file"),
+        Line::from("D: Toggle the frame timing overlay"),
+        Line::from("F12: Diagnostics: the monitor's own log of reconnects, unparsed and dropped lines and slow frames"),
+        Line::from(",: Settings (poll interval, buffer size, wrap, timestamp format, theme, level symbols)"),
+        Line::from("I: Sources panel: state, rate and last line of each source; Space hides one, ←/→ and Enter set its level and filter"),
+        Line::from("O: Open another file (recent files first, then the current directory)"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Replay (--replay)", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+        ]),
+        Line::from("Space: Pause/resume playback"),
+        Line::from("←/→: Seek 10 seconds back/forward"),
+        Line::from("+/-: Double/halve the playback speed"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Filter Mode", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+        ]),
+        Line::from("Tab: Complete a field name or value (key=value, \"key\":\"value\") from the newest lines; again for the next"),
+        Line::from("Enter: Apply filter"),
+        Line::from("Esc: Cancel, going back to the filter from before"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Statistics View", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+        ]),
+        Line::from("R: Refresh statistics"),
+        Line::from("[/]: Count lines over time in finer or coarser buckets (10s, 1m, 5m, 1h)"),
+        Line::from("↑/↓, Enter: Pick one of the top words of the filtered lines to show only lines with it too"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Heatmap View", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+        ]),
+        Line::from("Arrows: Move between hour/day cells"),
+        Line::from("E: Color by volume or error count"),
+        Line::from("Enter/Click: Show the logs for the selected hour"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Alerts View", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+        ]),
+        Line::from("↑/↓: Select an alert"),
+        Line::from("Enter: Jump to the matching line in the log view"),
+        Line::from("A: Acknowledge selected alert (Shift+A: acknowledge all)"),
+        Line::from("X: Clear acknowledged alerts"),
+        Line::from("Z: Snooze the selected alert's rule for 5 more minutes"),
+        Line::from("M: Mute/unmute the selected alert's rule for this session"),
+        Line::from("U: Clear the rule's snooze and mute"),
+    ];
+
+    let help_text = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title("Help"))
+        .alignment(ratatui::layout::Alignment::Left);
+    
+    f.render_widget(help_text, area);
+}
+
+
+/// Helper function to create a centered rect using a percentage of the available rect
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+
@@ -0,0 +1,67 @@
+use chrono::NaiveDateTime;
+
+use crate::filter::StatusClasses;
+use crate::LogLevel;
+
+// Changes kept to step back through; past this the oldest is forgotten
+const MAX_UNDO: usize = 100;
+
+/// What narrows the view, as far as undo and redo go: the filters, the level
+/// and time range, and the lines hidden by hand.
+#[derive(Clone, Default, PartialEq)]
+pub struct ViewState {
+    pub filter_text: String,
+    pub min_level: Option<LogLevel>,
+    pub time_range: Option<(NaiveDateTime, NaiveDateTime)>,
+    pub status_classes: StatusClasses,
+    pub drill_words: Vec<String>,
+    pub geo_filter: Option<String>,
+    pub lookup_filter: Option<String>,
+    pub column_filter: Option<(usize, String)>,
+    pub hidden: Vec<Vec<u64>>, // Sequence numbers of the batches hidden with x, the latest last
+}
+
+/// The view states applied before the current one, to go back to with `u`,
+/// and those undone since, to go forward to again with Ctrl+R.
+#[derive(Default)]
+pub struct ViewHistory {
+    undo: Vec<ViewState>,
+    redo: Vec<ViewState>,
+    applied: ViewState, // The state the view was last filtered with
+}
+
+impl ViewHistory {
+    /// Note that the view was filtered with `state`; a change from the
+    /// state before can be undone, and what was undone can't be redone
+    /// any more.
+    pub fn record(&mut self, state: ViewState) {
+        if state == self.applied {
+            return;
+        }
+        if self.undo.len() == MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(std::mem::replace(&mut self.applied, state));
+        self.redo.clear();
+    }
+
+    /// The state to go back to, if any.
+    pub fn undo(&mut self) -> Option<ViewState> {
+        let state = self.undo.pop()?;
+        self.redo.push(std::mem::replace(&mut self.applied, state.clone()));
+        Some(state)
+    }
+
+    /// The state undone last, to apply again.
+    pub fn redo(&mut self) -> Option<ViewState> {
+        let state = self.redo.pop()?;
+        self.undo.push(std::mem::replace(&mut self.applied, state.clone()));
+        Some(state)
+    }
+
+    // The hidden batches of every state kept, so their sequence numbers
+    // can follow the lines when the buffer is renumbered
+    pub fn hidden_mut(&mut self) -> impl Iterator<Item = &mut Vec<Vec<u64>>> {
+        self.undo.iter_mut().chain(&mut self.redo).chain([&mut self.applied]).map(|state| &mut state.hidden)
+    }
+}
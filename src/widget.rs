@@ -0,0 +1,278 @@
+use std::collections::VecDeque;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, StatefulWidget, Widget};
+
+use crate::filter::{Criteria, StatusClasses};
+use crate::format::LogFormat;
+use crate::stats::LogStats;
+use crate::{LogLevel, LogLine};
+
+/// Lines kept by a `LogBuffer` unless it is given another limit.
+pub const DEFAULT_BUFFER_LINES: usize = 10_000;
+
+/// The state behind a `LogViewerWidget`: a bounded buffer of parsed lines,
+/// the filter narrowing them and where the view is scrolled to.
+///
+/// Lines are pushed in as raw text and parsed with the buffer's format;
+/// once the limit is reached the oldest are evicted.
+pub struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    max_lines: usize,
+    format: LogFormat,
+    next_seq: u64,           // Sequence number of the next line pushed
+    filter_lower: String,    // Filter text, already lowercased
+    min_level: Option<LogLevel>,
+    filtered: VecDeque<u64>, // Sequence numbers of the lines passing the filter, oldest first
+    top: Option<usize>,      // First filtered line shown, `None` to follow the newest
+    height: usize,           // Lines shown at the last render, for paging
+    stats: LogStats,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        LogBuffer::new(DEFAULT_BUFFER_LINES)
+    }
+}
+
+impl LogBuffer {
+    /// An empty buffer keeping at most `max_lines` lines, parsed with the `auto` format.
+    pub fn new(max_lines: usize) -> Self {
+        LogBuffer {
+            lines: VecDeque::new(),
+            max_lines: max_lines.max(1),
+            format: LogFormat::Auto,
+            next_seq: 0,
+            filter_lower: String::new(),
+            min_level: None,
+            filtered: VecDeque::new(),
+            top: None,
+            height: 0,
+            stats: LogStats::default(),
+        }
+    }
+
+    /// Parse the lines pushed from here on with `format`.
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Add a raw line at the bottom, evicting the oldest one when full.
+    pub fn push(&mut self, line: &str) {
+        let mut log = LogLine::parse(line, &self.format);
+        log.seq = self.next_seq;
+        log.line = self.next_seq + 1;
+        self.next_seq += 1;
+        if self.matches(&log) {
+            self.filtered.push_back(log.seq);
+        }
+        self.stats.count(&log, true);
+        self.lines.push_back(log);
+        if self.lines.len() <= self.max_lines {
+            return;
+        }
+        let Some(evicted) = self.lines.pop_front() else {
+            return;
+        };
+        self.stats.count(&evicted, false);
+        if self.filtered.front() == Some(&evicted.seq) {
+            self.filtered.pop_front();
+            self.top = self.top.map(|top| top.saturating_sub(1));
+        }
+    }
+
+    /// Add several raw lines, as `push` does.
+    pub fn extend<I, S>(&mut self, lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for line in lines {
+            self.push(line.as_ref());
+        }
+    }
+
+    /// Drop every line, keeping the filter.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.filtered.clear();
+        self.stats = LogStats::default();
+        self.top = None;
+    }
+
+    /// Only show lines containing `text`, ignoring case; empty shows everything.
+    pub fn set_filter(&mut self, text: &str) {
+        self.filter_lower = text.to_lowercase();
+        self.refilter();
+    }
+
+    /// Only show lines at `level` or above; `None` shows every level.
+    pub fn set_min_level(&mut self, level: Option<LogLevel>) {
+        self.min_level = level;
+        self.refilter();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Lines passing the filter.
+    pub fn filtered_len(&self) -> usize {
+        self.filtered.len()
+    }
+
+    /// The lines passing the filter, oldest first.
+    pub fn filtered_lines(&self) -> impl Iterator<Item = &LogLine> {
+        self.filtered.iter().map(|&seq| self.line(seq))
+    }
+
+    /// Counts of the buffered lines by level, hour and day.
+    pub fn stats(&self) -> &LogStats {
+        &self.stats
+    }
+
+    /// Whether the view sticks to the newest lines as they arrive.
+    pub fn following(&self) -> bool {
+        self.top.is_none()
+    }
+
+    /// Scroll back to the newest lines and keep following them.
+    pub fn follow(&mut self) {
+        self.top = None;
+    }
+
+    /// Scroll by `delta` lines, negative towards older ones. Scrolling back
+    /// down to the newest lines picks up following again.
+    pub fn scroll_by(&mut self, delta: isize) {
+        let last_page = self.last_page();
+        let top = self.top.unwrap_or(last_page).saturating_add_signed(delta).min(last_page);
+        self.top = (top < last_page).then_some(top);
+    }
+
+    /// Scroll by a page of the height last rendered.
+    pub fn page_by(&mut self, pages: isize) {
+        self.scroll_by(pages.saturating_mul(self.height.max(1) as isize));
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.top = Some(0).filter(|_| self.last_page() > 0);
+    }
+
+    // First filtered line of the last full page
+    fn last_page(&self) -> usize {
+        self.filtered.len().saturating_sub(self.height)
+    }
+
+    fn line(&self, seq: u64) -> &LogLine {
+        let first_seq = self.next_seq - self.lines.len() as u64;
+        &self.lines[(seq - first_seq) as usize]
+    }
+
+    fn matches(&self, log: &LogLine) -> bool {
+        let criteria = Criteria {
+            text_lower: &self.filter_lower,
+            time_range: None,
+            min_level: self.min_level,
+            statuses: StatusClasses::default(),
+            words: &[],
+            sources: &[],
+            geo: None,
+            columns: Vec::new(),
+        };
+        criteria.matches(log)
+    }
+
+    fn refilter(&mut self) {
+        self.filtered = self.lines.iter().filter(|log| self.matches(log)).map(|log| log.seq).collect();
+        self.top = None;
+    }
+}
+
+/// A scrolling, level-colored log pane for embedding in other ratatui
+/// applications, drawn from a `LogBuffer`.
+///
+/// ```no_run
+/// # use log_monitor::widget::{LogBuffer, LogViewerWidget};
+/// # use ratatui::widgets::{Block, Borders};
+/// # fn draw<B: ratatui::backend::Backend>(f: &mut ratatui::Frame<B>, logs: &mut LogBuffer) {
+/// let widget = LogViewerWidget::new().block(Block::default().borders(Borders::ALL).title("Logs"));
+/// f.render_stateful_widget(widget, f.size(), logs);
+/// # }
+/// ```
+#[derive(Default)]
+pub struct LogViewerWidget<'a> {
+    block: Option<Block<'a>>,
+    hide_timestamps: bool,
+    line_numbers: bool,
+}
+
+impl<'a> LogViewerWidget<'a> {
+    pub fn new() -> Self {
+        LogViewerWidget::default()
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Show or hide the timestamps in front of the lines (shown by default).
+    pub fn timestamps(mut self, show: bool) -> Self {
+        self.hide_timestamps = !show;
+        self
+    }
+
+    /// Number lines by their position in the stream (hidden by default).
+    pub fn line_numbers(mut self, show: bool) -> Self {
+        self.line_numbers = show;
+        self
+    }
+
+    fn spans<'b>(&self, log: &'b LogLine, number_width: usize) -> Line<'b> {
+        let meta = Style::default().fg(Color::DarkGray);
+        let mut parts = Vec::new();
+        if self.line_numbers {
+            parts.push(Span::styled(format!("{:<width$} ", log.line, width = number_width), meta));
+        }
+        if !self.hide_timestamps && !log.timestamp().is_empty() {
+            parts.push(Span::styled(format!("{} ", log.timestamp()), meta));
+        }
+        let style = Style::default().fg(log.level.color());
+        parts.push(Span::styled(format!("[{}] ", log.level.as_str()), style.add_modifier(Modifier::BOLD)));
+        parts.push(Span::styled(&*log.head, style));
+        parts.push(Span::styled(&*log.message, style));
+        Line::from(parts)
+    }
+}
+
+impl StatefulWidget for LogViewerWidget<'_> {
+    type State = LogBuffer;
+
+    fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut LogBuffer) {
+        let inner = match self.block.take() {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        state.height = inner.height as usize;
+        let top = state.top.map_or(state.last_page(), |top| top.min(state.last_page()));
+        let number_width = state.next_seq.to_string().len();
+        let lines: Vec<Line> = state.filtered
+            .range(top..)
+            .take(state.height)
+            .map(|&seq| self.spans(state.line(seq), number_width))
+            .collect();
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// Read a `--since` or `--until` moment: a span back from now (`90s`, `10m`,
+/// `2h`, `1d`) or a timestamp, `2024-05-01 10:00[:00]` (a `T` works as the
+/// separator too) or `10:00[:00]` for today.
+pub fn parse_moment(text: &str) -> Result<NaiveDateTime, String> {
+    let now = Local::now().naive_local();
+    if let Some(span) = parse_span(text) {
+        return Ok(now - span);
+    }
+    let text = text.trim().replacen('T', " ", 1);
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"].iter()
+        .find_map(|layout| NaiveDateTime::parse_from_str(&text, layout).ok())
+        .or_else(|| NaiveDate::parse_from_str(&text, "%Y-%m-%d").ok().and_then(|day| day.and_hms_opt(0, 0, 0)))
+        .or_else(|| {
+            ["%H:%M:%S", "%H:%M"].iter()
+                .find_map(|layout| NaiveTime::parse_from_str(&text, layout).ok())
+                .map(|time| now.date().and_time(time))
+        })
+        .ok_or_else(|| format!("expected a span such as 10m or 2h, or a time such as 2024-05-01 10:00: {}", text))
+}
+
+// `10m` and the like: a whole number of seconds, minutes, hours or days
+fn parse_span(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let unit = text.chars().last()?;
+    let count: i64 = text[..text.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        's' => Some(Duration::seconds(count)),
+        'm' => Some(Duration::minutes(count)),
+        'h' => Some(Duration::hours(count)),
+        'd' => Some(Duration::days(count)),
+        _ => None,
+    }
+}
+
+/// The time range `--since` and `--until` leave, open on the side not given.
+pub fn time_range(since: Option<NaiveDateTime>, until: Option<NaiveDateTime>) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    if since.is_none() && until.is_none() {
+        return None;
+    }
+    Some((since.unwrap_or(NaiveDateTime::MIN), until.unwrap_or(NaiveDateTime::MAX)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn parses_timestamps_with_or_without_seconds() {
+        assert_eq!(parse_moment("2024-05-01 10:00:30"), Ok(at("2024-05-01 10:00:30")));
+        assert_eq!(parse_moment("2024-05-01T10:00"), Ok(at("2024-05-01 10:00:00")));
+        assert_eq!(parse_moment(" 2024-05-01 "), Ok(at("2024-05-01 00:00:00")));
+    }
+
+    #[test]
+    fn times_of_day_are_today() {
+        let today = Local::now().date_naive();
+        assert_eq!(parse_moment("09:15"), Ok(today.and_hms_opt(9, 15, 0).unwrap()));
+        assert_eq!(parse_moment("23:59:59"), Ok(today.and_hms_opt(23, 59, 59).unwrap()));
+    }
+
+    #[test]
+    fn spans_count_back_from_now() {
+        for (text, span) in [("90s", Duration::seconds(90)), ("10m", Duration::minutes(10)), ("2h", Duration::hours(2)), ("1d", Duration::days(1))] {
+            let expected = Local::now().naive_local() - span;
+            let parsed = parse_moment(text).unwrap();
+            assert!((parsed - expected).num_seconds().abs() <= 1, "{}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        for text in ["", "10x", "yesterday", "2024-13-01", "25:00"] {
+            assert!(parse_moment(text).is_err(), "{}", text);
+        }
+    }
+}
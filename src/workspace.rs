@@ -0,0 +1,54 @@
+use crate::notes::Note;
+use crate::undo::{ViewHistory, ViewState};
+
+/// How many workspaces there are, switched between with F1 to F4.
+pub const WORKSPACES: usize = 4;
+
+/// One of the views kept over the same buffered lines, such as "errors
+/// only" next to the full stream. The one shown lives in the app itself;
+/// the others wait here with their filters, hidden lines, notes, mark and
+/// place.
+pub struct Workspace {
+    pub name: Option<String>, // Given with :workspace
+    pub view: ViewState,
+    pub history: ViewHistory, // Its own undo and redo
+    pub anchor: Option<u64>,  // Line at the top of the view when not following, while it is buffered
+    pub mark: Option<u64>,    // Line highlighted by the last jump, while it is buffered
+    pub notes: Vec<Note>,
+    pub follow_mode: bool,
+}
+
+// A workspace not shown yet starts on the full stream, following it
+impl Default for Workspace {
+    fn default() -> Self {
+        Workspace {
+            name: None,
+            view: ViewState::default(),
+            history: ViewHistory::default(),
+            anchor: None,
+            mark: None,
+            notes: Vec::new(),
+            follow_mode: true,
+        }
+    }
+}
+
+impl Workspace {
+    /// `F2`, or `F2 errors` once named.
+    pub fn label(&self, index: usize) -> String {
+        match &self.name {
+            Some(name) => format!("F{} {}", index + 1, name),
+            None => format!("F{}", index + 1),
+        }
+    }
+
+    // The hidden batches of this workspace, shown or kept in its history
+    pub fn hidden_mut(&mut self) -> impl Iterator<Item = &mut Vec<Vec<u64>>> {
+        self.history.hidden_mut().chain([&mut self.view.hidden])
+    }
+
+    // The lines this workspace points at: its anchor, its mark and those of its notes
+    pub fn seqs_mut(&mut self) -> impl Iterator<Item = &mut Option<u64>> {
+        [&mut self.anchor, &mut self.mark].into_iter().chain(self.notes.iter_mut().map(|note| &mut note.seq))
+    }
+}
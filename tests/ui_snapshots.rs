@@ -0,0 +1,651 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use log_monitor::config::{ColumnConfig, Config, LookupConfig, PairConfig};
+use log_monitor::control::MAX_QUEUED_REQUESTS;
+use log_monitor::testing::{assert_snapshot, Harness, Step};
+use log_monitor::App;
+use ratatui::style::Modifier;
+
+const SNAPSHOTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots");
+
+const LINES: [&str; 4] = [
+    "2024-05-01 10:00:00 INFO server started on :8080",
+    "2024-05-01 10:00:02 DEBUG loaded 12 routes",
+    "2024-05-01 10:00:05 WARN slow query took 1200ms",
+    "2024-05-01 10:00:09 ERROR db timeout after 3 retries",
+];
+
+fn harness_with_lines() -> Harness {
+    let mut harness = Harness::new(80, 14);
+    harness.push_lines(&LINES);
+    harness
+}
+
+#[test]
+fn log_view() {
+    let mut harness = harness_with_lines();
+    assert_snapshot(SNAPSHOTS, "log_view", &harness.render());
+}
+
+#[test]
+fn filter_prompt_and_result() {
+    let mut harness = harness_with_lines();
+    let prompt = harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("db")]);
+    assert_snapshot(SNAPSHOTS, "filter_prompt", &prompt);
+    let filtered = harness.run(&[Step::Key(KeyCode::Enter)]);
+    assert_snapshot(SNAPSHOTS, "filter_applied", &filtered);
+}
+
+#[test]
+fn filter_prompt_counts_and_cancels() {
+    let mut harness = Harness::new(80, 40);
+    harness.push_lines(&LINES);
+    let prompt = harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("db")]);
+    assert!(prompt.contains("would match 1 / 4 lines"), "{}", prompt);
+    let nothing = harness.run(&[Step::Text("x")]);
+    assert!(nothing.contains("matches nothing"), "{}", nothing);
+    let cancelled = harness.run(&[Step::Key(KeyCode::Esc)]);
+    assert!(cancelled.contains("Logs (4/4)"), "{}", cancelled);
+}
+
+#[test]
+fn filter_completes_fields() {
+    let mut harness = Harness::new(140, 14);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO service=checkout-svc status=200",
+        "2024-05-01 10:00:01 INFO service=cart-svc status=200",
+        "2024-05-01 10:00:02 ERROR service=checkout-svc status=503",
+    ]);
+    let name = harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("ser"), Step::Key(KeyCode::Tab)]);
+    assert!(name.contains("Filter: service="), "{}", name);
+    let choices = harness.run(&[Step::Key(KeyCode::Tab), Step::Key(KeyCode::Tab)]);
+    assert!(choices.contains("Tab: service=cart-svc  service=checkout-svc"), "{}", choices);
+    let chosen = harness.run(&[Step::Key(KeyCode::Tab), Step::Key(KeyCode::Tab)]);
+    assert!(chosen.contains("Logs (2/3)"), "{}", chosen);
+}
+
+#[test]
+fn time_scrubber() {
+    let mut app = App::new("test.log".to_string());
+    app.apply_config(&Config { scrubber: Some(true), ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 100, 24);
+    for i in 0..40 {
+        let level = if i % 10 == 9 { "ERROR" } else { "INFO" };
+        harness.push_lines(&[&format!("2024-05-01 10:{:02}:00 {} request {}", i, level, i)]);
+    }
+    let screen = harness.render();
+    assert!(screen.lines().any(|row| row.trim_start().starts_with('█')), "{}", screen);
+    let stepped = harness.run(&[Step::Key(KeyCode::Char('[')), Step::Key(KeyCode::Char('['))]);
+    assert!(stepped.contains("PAUSED") && stepped.contains("INFO request 28"), "{}", stepped);
+}
+
+#[test]
+fn throttled_repeat_storm() {
+    let mut app = App::new("test.log".to_string());
+    app.apply_config(&Config { throttle: Some(2), ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 100, 14);
+    for i in 0..6 {
+        harness.push_lines(&[&format!("2024-05-01 10:00:00 WARN retry db-{}", i)]);
+    }
+    harness.push_lines(&["2024-05-01 10:00:00 INFO cache warmed", "2024-05-01 10:00:01 WARN retry db-7"]);
+    let screen = harness.render();
+    assert!(screen.contains("Logs (4/8)") && screen.contains("db-1 +4 suppressed"), "{}", screen);
+}
+
+#[test]
+fn hidden_timestamps_and_line_numbers() {
+    let mut harness = harness_with_lines();
+    let screen = harness.run(&[Step::Key(KeyCode::Char('t')), Step::Key(KeyCode::Char('n'))]);
+    assert_snapshot(SNAPSHOTS, "bare_lines", &screen);
+}
+
+#[test]
+fn lines_arriving_while_scrolled_up() {
+    let mut harness = Harness::new(80, 16);
+    for i in 0..30 {
+        harness.push_lines(&[&format!("2024-05-01 10:00:{:02} INFO request {}", i, i)]);
+    }
+    let screen = harness.run(&[
+        Step::Key(KeyCode::PageUp),
+        Step::Line("2024-05-01 10:00:30 ERROR late failure"),
+    ]);
+    assert_snapshot(SNAPSHOTS, "scrolled_up", &screen);
+    let following = harness.run(&[Step::Key(KeyCode::End)]);
+    assert!(!following.contains("PAUSED"), "{}", following);
+}
+
+#[test]
+fn redrawn_and_colored_lines() {
+    let mut harness = Harness::new(80, 12);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO download  10%\r2024-05-01 10:00:00 INFO download  55%\r2024-05-01 10:00:00 INFO download 100%",
+        "2024-05-01 10:00:01 \x1b[31mERROR\x1b[0m disk full\x07",
+        "2024-05-01 10:00:02 INFO spinner -\x08\\\x08|\x08/ done",
+    ]);
+    assert_snapshot(SNAPSHOTS, "redrawn_lines", &harness.render());
+}
+
+#[test]
+fn long_line_preview_and_detail() {
+    let mut app = App::new("test.log".to_string());
+    app.apply_config(&Config { wrap: Some(true), ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 80, 16);
+    let payload = format!("2024-05-01 10:00:00 INFO payload={}", "QUJD".repeat(50_000));
+    harness.push_lines(&[&payload, "2024-05-01 10:00:01 INFO next"]);
+    let screen = harness.render();
+    assert!(screen.contains("…[+195KB]"), "{}", screen);
+    let detail = harness.run(&[Step::Key(KeyCode::Up), Step::Key(KeyCode::Enter), Step::Key(KeyCode::Left)]);
+    assert_snapshot(SNAPSHOTS, "long_line_detail", &detail);
+}
+
+#[test]
+fn search_in_detail_view() {
+    let mut harness = Harness::new(80, 16);
+    let frames: Vec<String> = (0..120).map(|i| format!("at handler_{}(app.py:{})", i, i * 3)).collect();
+    let trace = format!("2024-05-01 10:00:00 ERROR KeyError: 'user' {}", frames.join(" "));
+    harness.push_lines(&[&trace]);
+    harness.render();
+    harness.run(&[Step::Key(KeyCode::Enter)]);
+    let search = harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("handler_11")]);
+    assert!(search.contains("Search: handler_11_  1 of 11"), "{}", search);
+    let next = harness.run(&[Step::Key(KeyCode::Enter), Step::Key(KeyCode::Char('n')), Step::Key(KeyCode::Char('n'))]);
+    assert_snapshot(SNAPSHOTS, "detail_search", &next);
+    let previous = harness.run(&[Step::Key(KeyCode::Char('N'))]);
+    assert!(previous.contains("2 of 11"), "{}", previous);
+    let dropped = harness.run(&[Step::Key(KeyCode::Esc)]);
+    assert!(!dropped.contains("Search:") && dropped.contains("Line test.log 1"), "{}", dropped);
+}
+
+#[test]
+fn detail_view_of_a_line_with_accents() {
+    let mut harness = Harness::new(100, 12);
+    let line = format!("2024-05-01 10:00:00 INFO {}", "café crème brûlée ".repeat(40));
+    harness.push_lines(&[&line]);
+    harness.render();
+    harness.run(&[Step::Key(KeyCode::Enter)]);
+    let search = harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("BRÛLÉE"), Step::Key(KeyCode::Enter)]);
+    assert!(search.contains("1 of 40") && search.contains("café crème brûlée café"), "{}", search);
+    let end = harness.run(&[Step::Key(KeyCode::End)]);
+    assert!(end.contains("rows 6-8 of 8") && end.contains("│ café crème brûlée café"), "{}", end);
+}
+
+#[test]
+fn notes_on_lines() {
+    let mut harness = harness_with_lines();
+    harness.render();
+    harness.run(&[Step::Key(KeyCode::Char('a')), Step::Text("paged on-call"), Step::Key(KeyCode::Enter)]);
+    let annotated = harness.render();
+    assert!(annotated.lines().any(|row| row.contains("│✎ 4") && row.contains("db timeout")), "{}", annotated);
+    let panel = harness.run(&[Step::Key(KeyCode::Char('A'))]);
+    assert_snapshot(SNAPSHOTS, "notes_panel", &panel);
+}
+
+#[test]
+fn notes_of_evicted_lines_are_dimmed() {
+    let mut app = App::new("test.log".to_string());
+    app.apply_config(&Config { max_lines: Some(4), ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 80, 14);
+    harness.push_lines(&LINES);
+    harness.render();
+    harness.run(&[Step::Key(KeyCode::Char('a')), Step::Text("paged on-call"), Step::Key(KeyCode::Enter)]);
+    harness.push_lines(&["2024-05-01 10:00:05 INFO pool resized"]);
+    harness.run(&[Step::Key(KeyCode::Up), Step::Key(KeyCode::Up), Step::Key(KeyCode::Up)]);
+    harness.run(&[Step::Key(KeyCode::Char('a')), Step::Text("still here"), Step::Key(KeyCode::Enter)]);
+    harness.push_lines(&LINES);
+    harness.run(&[Step::Key(KeyCode::Char('A'))]);
+    let buffer = harness.render_buffer();
+    let row_of = |text: &str| (0..buffer.area.height).find(|&y| {
+        (0..buffer.area.width).map(|x| buffer.get(x, y).symbol.as_str()).collect::<String>().contains(text)
+    });
+    let dimmed = |y: u16| (0..buffer.area.width).any(|x| buffer.get(x, y).modifier.contains(Modifier::DIM));
+    assert!(row_of("paged on-call").is_some_and(dimmed) && row_of("still here").is_some_and(dimmed));
+}
+
+#[test]
+fn hiding_lines_by_hand() {
+    let mut harness = Harness::new(80, 14);
+    let lines: Vec<String> = (1..=30).map(|i| format!("2024-05-01 10:00:{:02} INFO request {} served", i, i)).collect();
+    harness.push_lines(&lines.iter().map(String::as_str).collect::<Vec<_>>());
+    harness.render();
+    let hidden = harness.run(&[Step::Key(KeyCode::Char('x'))]);
+    assert!(hidden.contains("Logs (29/30)") && hidden.contains(" 1 hidden (Shift+X) "), "{}", hidden);
+    assert!(!hidden.contains("request 25 "), "{}", hidden);
+    harness.run(&[Step::Key(KeyCode::Char('v')), Step::Key(KeyCode::Char('k')), Step::Key(KeyCode::Char('k'))]);
+    assert_snapshot(SNAPSHOTS, "hidden_block", &harness.render());
+    let block = harness.run(&[Step::Key(KeyCode::Char('x'))]);
+    assert!(block.contains("Logs (26/30)") && block.contains(" 4 hidden (Shift+X) ") && !block.contains("request 23 "), "{}", block);
+    let restored = harness.run(&[Step::Key(KeyCode::Char('X'))]);
+    assert!(restored.contains("Logs (29/30)") && restored.contains("request 23 "), "{}", restored);
+    let all = harness.run(&[Step::Key(KeyCode::Char('X'))]);
+    assert!(all.contains("Logs (30/30)") && !all.contains("hidden (Shift+X)"), "{}", all);
+}
+
+#[test]
+fn undo_and_redo_view_changes() {
+    let mut harness = harness_with_lines();
+    harness.render();
+    harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("out"), Step::Key(KeyCode::Enter)]);
+    let filtered = harness.run(&[Step::Key(KeyCode::Char('x'))]);
+    assert!(filtered.contains("Logs (1/4)"), "{}", filtered);
+    harness.press_with(KeyCode::Char('c'), KeyModifiers::CONTROL);
+    assert!(harness.render().contains("Logs (3/4)"));
+    let undone = harness.run(&[Step::Key(KeyCode::Char('u'))]);
+    assert_snapshot(SNAPSHOTS, "undo_view", &undone);
+    let hid = harness.run(&[Step::Key(KeyCode::Char('u'))]);
+    assert!(hid.contains("Logs (2/4)") && hid.contains("Filter: out"), "{}", hid);
+    harness.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert!(harness.render().contains("Logs (1/4)"));
+    let start = harness.run(&[Step::Key(KeyCode::Char('u')), Step::Key(KeyCode::Char('u')), Step::Key(KeyCode::Char('u'))]);
+    assert!(start.contains("Logs (4/4)") && start.contains("Filter: No filter applied"), "{}", start);
+}
+
+#[test]
+fn workspaces_keep_their_views() {
+    let mut harness = harness_with_lines();
+    harness.render();
+    harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("out"), Step::Key(KeyCode::Enter)]);
+    let second = harness.run(&[Step::Key(KeyCode::F(2))]);
+    assert!(second.contains("Logs (4/4)  F2 ") && second.contains("Filter: No filter applied"), "{}", second);
+    harness.run(&[Step::Key(KeyCode::Char(':')), Step::Text("workspace errors"), Step::Key(KeyCode::Enter)]);
+    let named = harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("db"), Step::Key(KeyCode::Enter)]);
+    assert_snapshot(SNAPSHOTS, "workspace_named", &named);
+    let first = harness.run(&[Step::Key(KeyCode::F(1))]);
+    assert!(first.contains("Logs (2/4)──") && first.contains("Filter: out"), "{}", first);
+    let back = harness.run(&[Step::Key(KeyCode::F(2))]);
+    assert!(back.contains("Logs (1/4)  F2 errors ") && back.contains("Filter: db"), "{}", back);
+}
+
+#[test]
+fn workspaces_keep_their_notes_and_place() {
+    let mut harness = harness_with_lines();
+    harness.push_lines(&LINES);
+    harness.push_lines(&LINES);
+    harness.render();
+    harness.run(&[Step::Key(KeyCode::Up), Step::Key(KeyCode::Up), Step::Key(KeyCode::Up)]);
+    harness.run(&[Step::Key(KeyCode::Char('a')), Step::Text("first deploy"), Step::Key(KeyCode::Enter)]);
+    let first = harness.render();
+    let second = harness.run(&[Step::Key(KeyCode::F(2))]);
+    assert!(!second.contains('✎') && second.contains("db timeout"), "{}", second);
+    harness.push_lines(&["2024-05-01 10:00:12 INFO pool resized", "2024-05-01 10:00:14 INFO request served"]);
+    harness.run(&[Step::Key(KeyCode::Char('a')), Step::Text("after resize"), Step::Key(KeyCode::Enter)]);
+    let back = harness.run(&[Step::Key(KeyCode::F(1))]);
+    assert_eq!(back, first.replace("Logs (12/12)", "Logs (14/14)"));
+    let again = harness.run(&[Step::Key(KeyCode::F(2))]);
+    assert!(again.contains('✎') && !again.contains("first deploy"), "{}", again);
+}
+
+#[test]
+fn resizing_the_buffer() {
+    let mut harness = Harness::new(200, 12);
+    harness.push_lines(&LINES);
+    let shrunk = harness.run(&[Step::Key(KeyCode::Char(':')), Step::Text("buffer 2"), Step::Key(KeyCode::Enter)]);
+    assert!(shrunk.contains("Logs (2/2)") && !shrunk.contains("server started"), "{}", shrunk);
+    let stats = harness.run(&[
+        Step::Key(KeyCode::Char(':')),
+        Step::Text("buffer +"),
+        Step::Key(KeyCode::Enter),
+        Step::Key(KeyCode::Tab),
+    ]);
+    assert!(stats.contains("Buffer: 2/4 lines (50%)") && stats.contains("MB when full"), "{}", stats);
+}
+
+#[test]
+fn sub_second_timestamps() {
+    let mut app = App::new("test.log".to_string());
+    app.apply_config(&Config { timestamp_format: Some("%H:%M:%S%.3f".to_string()), ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 120, 20);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00.000 INFO request received",
+        "2024-05-01 10:00:00.250 DEBUG cache miss",
+        "2024-05-01 10:00:00.500 INFO response sent",
+    ]);
+    let logs = harness.render();
+    assert!(logs.contains("10:00:00.250 DEBUG cache miss"), "{}", logs);
+    let stats = harness.run(&[Step::Key(KeyCode::Tab)]);
+    assert!(stats.contains("Between lines (latest 1000) | p50 250ms"), "{}", stats);
+}
+
+#[test]
+fn timestamp_formats_needing_a_zone_are_rejected() {
+    let mut app = App::new("test.log".to_string());
+    for layout in ["%H:%M %z", "%Z", "%Q"] {
+        let config = Config { timestamp_format: Some(layout.to_string()), ..Config::default() };
+        assert!(app.apply_config(&config).is_err(), "{}", layout);
+    }
+    assert!(app.apply_config(&Config { timestamp_format: Some("%H:%M".to_string()), ..Config::default() }).is_ok());
+}
+
+#[test]
+fn day_boundary_markers() {
+    let mut app = App::new("test.log".to_string());
+    app.apply_config(&Config { compact_dates: Some(true), ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 80, 13);
+    harness.push_lines(&[
+        "2024-05-01 23:59:58 INFO nightly backup started",
+        "2024-05-01 23:59:59 DEBUG snapshot taken",
+        "2024-05-02 00:00:01 WARN backup slower than usual",
+        "2024-05-02 00:00:04 INFO nightly backup done",
+    ]);
+    assert_snapshot(SNAPSHOTS, "day_boundary", &harness.render());
+}
+
+#[test]
+fn narrow_terminal() {
+    let mut harness = Harness::new(50, 10);
+    harness.push_lines(&LINES);
+    assert_snapshot(SNAPSHOTS, "narrow_log_view", &harness.render());
+    let filtered = harness.run(&[Step::Key(KeyCode::Char('/')), Step::Text("db"), Step::Key(KeyCode::Enter)]);
+    assert!(filtered.contains("Filter: db") && filtered.contains("[E] 2024-05-01"), "{}", filtered);
+}
+
+#[test]
+fn level_symbols() {
+    let mut app = App::new("test.log".to_string());
+    let config = Config {
+        symbols: Some(true),
+        level_symbols: [("debug".to_string(), "D".to_string())].into(),
+        ..Config::default()
+    };
+    app.apply_config(&config).unwrap();
+    let mut harness = Harness::with_app(app, 80, 12);
+    harness.push_lines(&LINES);
+    assert_snapshot(SNAPSHOTS, "level_symbols", &harness.render());
+}
+
+#[test]
+fn top_words_drill_down() {
+    let mut harness = Harness::new(100, 30);
+    harness.push_lines(&LINES);
+    let stats = harness.run(&[Step::Key(KeyCode::Tab)]);
+    assert!(stats.contains("Top words"), "{}", stats);
+    let logs = harness.run(&[Step::Key(KeyCode::Down), Step::Key(KeyCode::Enter), Step::Key(KeyCode::BackTab)]);
+    assert!(logs.contains("Words: query") && logs.contains("Logs (1/4)"), "{}", logs);
+}
+
+#[test]
+fn drilling_into_a_word_keeps_whole_words_only() {
+    let mut harness = Harness::new(100, 30);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO user signed in",
+        "2024-05-01 10:00:01 INFO user signed out",
+        "2024-05-01 10:00:02 INFO username changed",
+    ]);
+    harness.run(&[Step::Key(KeyCode::Tab)]);
+    let logs = harness.run(&[Step::Key(KeyCode::Down), Step::Key(KeyCode::Enter), Step::Key(KeyCode::BackTab)]);
+    assert!(logs.contains("Words: user") && logs.contains("Logs (2/3)"), "{}", logs);
+}
+
+#[test]
+fn access_log_status_toggles() {
+    let mut app = App::new("access.log".to_string());
+    app.apply_config(&Config { format: Some("access".to_string()), ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 100, 14);
+    harness.push_lines(&[
+        r#"10.0.0.1 - - [01/May/2024:10:00:00 +0000] "GET / HTTP/1.1" 200 512"#,
+        r#"10.0.0.2 - - [01/May/2024:10:00:01 +0000] "GET /old HTTP/1.1" 301 0"#,
+        r#"10.0.0.3 - - [01/May/2024:10:00:02 +0000] "GET /missing HTTP/1.1" 404 12"#,
+        r#"10.0.0.4 - - [01/May/2024:10:00:03 +0000] "POST /pay HTTP/1.1" 503 0"#,
+    ]);
+    harness.render();
+    let server = harness.run(&[Step::Key(KeyCode::Char('5'))]);
+    assert!(server.contains("Logs (1/4)") && server.contains("Status: 5xx"), "{}", server);
+    let both = harness.run(&[Step::Key(KeyCode::Char('4'))]);
+    assert!(both.contains("Logs (2/4)") && both.contains("Status: 4xx+5xx"), "{}", both);
+    let all = harness.run(&[Step::Key(KeyCode::Char('4')), Step::Key(KeyCode::Char('5'))]);
+    assert!(all.contains("Logs (4/4)"), "{}", all);
+}
+
+#[test]
+fn escalation_watch() {
+    let mut harness = Harness::new(100, 14);
+    harness.run(&[Step::Key(KeyCode::Char('w')), Step::Text("escalation"), Step::Key(KeyCode::Enter)]);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 WARN retry 1 of 3 for order 1042",
+        "2024-05-01 10:00:05 WARN retry 2 of 3 for order 1042",
+        "2024-05-01 10:00:07 INFO order 1042 paid",
+        "2024-05-01 10:01:00 ERROR retry 3 of 3 for order 1043",
+    ]);
+    let alerts = harness.run(&[Step::Key(KeyCode::Tab), Step::Key(KeyCode::Tab), Step::Key(KeyCode::Tab)]);
+    assert!(alerts.contains("Alerts (1 unread / 1 total)"), "{}", alerts);
+    assert!(alerts.contains("WARNING -> ERROR: 2024-05-01 10:01:00 ERROR retry"), "{}", alerts);
+}
+
+#[test]
+fn clearing_alerts_while_flashing() {
+    let mut harness = Harness::new(200, 14);
+    harness.run(&[Step::Key(KeyCode::Char('w')), Step::Text("timeout"), Step::Key(KeyCode::Enter)]);
+    harness.push_lines(&LINES);
+    let alerts = harness.run(&[Step::Key(KeyCode::Tab), Step::Key(KeyCode::Tab), Step::Key(KeyCode::Tab)]);
+    assert!(alerts.contains("ALERT [timeout]"), "{}", alerts);
+    let cleared = harness.run(&[Step::Key(KeyCode::Char('A')), Step::Key(KeyCode::Char('x'))]);
+    assert!(cleared.contains("Alerts (0 unread / 0 total)") && !cleared.contains("ALERT ["), "{}", cleared);
+}
+
+#[test]
+fn late_lines_slotted_into_a_filtered_view() {
+    let mut app = App::new("test.log".to_string());
+    app.apply_config(&Config { reorder_window: Some(60_000), max_lines: Some(6), filter: Some("job".to_string()), ..Config::default() })
+        .unwrap();
+    let mut harness = Harness::with_app(app, 80, 16);
+    harness.app_mut().update_filter();
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO job 1 queued",
+        "2024-05-01 10:00:04 INFO job 2 queued",
+        "2024-05-01 10:00:05 INFO job 2 done",
+    ]);
+    harness.push_lines(&[
+        "2024-05-01 10:00:02 INFO cache warmed",
+        "2024-05-01 10:00:03 WARN job 1 slow",
+        "2024-05-01 10:00:01 INFO job 1 started",
+        "2024-05-01 10:00:06 INFO job 3 queued",
+        "2024-05-01 10:00:00 INFO job 0 too late",
+        "2024-05-01 10:00:05 INFO job 3 started",
+    ]);
+    let incremental = harness.render();
+    assert!(incremental.contains("job 3 started") && !incremental.contains("job 0"), "{}", incremental);
+    harness.app_mut().update_filter();
+    assert_eq!(incremental, harness.render());
+}
+
+#[test]
+fn restart_banners() {
+    let mut harness = Harness::new(100, 20);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO Starting application v1.4.2",
+        "2024-05-01 10:05:00 ERROR out of memory",
+        "2024-05-01 10:05:03 INFO Starting application v1.4.2",
+        "2024-05-01 10:05:04 INFO server started on :8080",
+    ]);
+    let logs = harness.render();
+    assert_eq!(logs.matches(" RESTART ").count(), 2, "{}", logs);
+    let stats = harness.run(&[Step::Key(KeyCode::Tab)]);
+    assert!(stats.contains("Total Log Entries: 4 | Restarts: 2 |"), "{}", stats);
+}
+
+#[test]
+fn redacted_lines() {
+    let mut app = App::new("test.log".to_string());
+    let redact = ["email", "token", "card", r"user=(\w+)"].map(String::from).to_vec();
+    app.apply_config(&Config { redact: Some(redact), ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 140, 12);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO login user=alice from alice@example.com",
+        "2024-05-01 10:00:01 INFO paid with 4111 1111 1111 1111 for order 1234567890123",
+        "2024-05-01 10:00:02 WARN rejected Authorization: Bearer abc.def-123 password=hunter2",
+    ]);
+    assert_snapshot(SNAPSHOTS, "redacted_lines", &harness.render());
+}
+
+#[test]
+fn lookup_column_and_filter() {
+    let mut app = App::new("test.log".to_string());
+    let lookup = LookupConfig {
+        name: "customer".to_string(),
+        table: concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/customers.csv").to_string(),
+        field: Some("customer_id".to_string()),
+        pattern: None,
+        column: Some("name".to_string()),
+    };
+    app.apply_config(&Config { lookup: vec![lookup], ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 120, 12);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO checkout customer_id=1042 total=99.50",
+        "2024-05-01 10:00:01 INFO checkout customer_id=2001 total=0",
+        "2024-05-01 10:00:02 WARN health check slow",
+    ]);
+    assert_snapshot(SNAPSHOTS, "lookup_column", &harness.render());
+    let filtered = harness.run(&[Step::Key(KeyCode::Char(':')), Step::Text("lookup acme"), Step::Key(KeyCode::Enter)]);
+    assert!(filtered.contains("Logs (1/3)"), "{}", filtered);
+}
+
+#[test]
+fn derived_column_filter_and_group() {
+    let mut app = App::new("test.log".to_string());
+    let column = ColumnConfig { name: "req".to_string(), pattern: r"\[req:(\w+)\]".to_string() };
+    app.apply_config(&Config { column: vec![column], ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 100, 12);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO [req:a41f] GET /cart",
+        "2024-05-01 10:00:01 INFO [req:b07c] GET /health",
+        "2024-05-01 10:00:02 ERROR [req:a41f] payment declined",
+        "2024-05-01 10:00:03 INFO cache warmed",
+    ]);
+    assert_snapshot(SNAPSHOTS, "derived_column", &harness.render());
+    let grouped = harness.run(&[Step::Key(KeyCode::Char(':')), Step::Text("group req"), Step::Key(KeyCode::Enter)]);
+    assert_snapshot(SNAPSHOTS, "derived_column_groups", &grouped);
+    let filtered = harness.run(&[Step::Key(KeyCode::Enter)]);
+    assert!(filtered.contains("Logs (2/4)"), "{}", filtered);
+}
+
+#[test]
+fn paired_event_latency() {
+    let mut app = App::new("test.log".to_string());
+    let pair = PairConfig {
+        name: "request".to_string(),
+        start: r"request (\w+) started".to_string(),
+        end: r"request (\w+) (?:done|failed)".to_string(),
+    };
+    app.apply_config(&Config { pair: vec![pair], ..Config::default() }).unwrap();
+    let mut harness = Harness::with_app(app, 110, 14);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 INFO request r1 started",
+        "2024-05-01 10:00:01 INFO request r2 started",
+        "2024-05-01 10:00:03 INFO request r1 done",
+        "2024-05-01 10:02:06 ERROR request r2 failed",
+    ]);
+    assert_snapshot(SNAPSHOTS, "pair_latency", &harness.render());
+    let stats = harness.run(&[Step::Key(KeyCode::Tab)]);
+    assert!(stats.contains("request: 2 pairs | open 0 | p50 3.00s | p90 2m05s | p99 2m05s | max 2m05s"), "{}", stats);
+}
+
+#[test]
+fn links_in_detail_view() {
+    let mut harness = Harness::new(150, 16);
+    harness.push_lines(&[
+        "2024-05-01 10:00:00 ERROR build failed at src/missing.rs:42:7, see https://ci.example.com/runs/981.",
+    ]);
+    harness.render();
+    let detail = harness.run(&[Step::Key(KeyCode::Enter)]);
+    assert_snapshot(SNAPSHOTS, "detail_links", &detail);
+    let opened = harness.run(&[Step::Key(KeyCode::Char('1'))]);
+    assert!(opened.contains("No such file: src/missing.rs"), "{}", opened);
+}
+
+#[test]
+fn cargo_problems() {
+    let mut app = App::new("build.log".to_string());
+    app.start_cargo_mode();
+    let mut harness = Harness::with_app(app, 100, 18);
+    harness.push_lines(&[
+        "   Compiling broken v0.1.0 (/tmp/broken)",
+        "warning: unused variable: `unused`",
+        " --> src/main.rs:3:9",
+        "  |",
+        "3 |     let unused = 5;",
+        "  |         ^^^^^^ help: if this is intentional, prefix it with an underscore: `_unused`",
+        "",
+        "error[E0308]: mismatched types",
+        " --> src/main.rs:2:18",
+        "  |",
+        "2 |     let x: u32 = \"a\";",
+        "  |            ---   ^^^ expected `u32`, found `&str`",
+        "",
+        "error: could not compile `broken` (bin \"broken\") due to 1 previous error; 1 warning emitted",
+    ]);
+    assert_snapshot(SNAPSHOTS, "cargo_log", &harness.render());
+    let panel = harness.run(&[Step::Key(KeyCode::Char('p'))]);
+    assert_snapshot(SNAPSHOTS, "cargo_problems", &panel);
+}
+
+#[test]
+fn problems_follow_their_lines_when_a_source_is_closed() {
+    let mut app = App::new("build.log".to_string());
+    app.start_cargo_mode();
+    let mut harness = Harness::with_app(app, 200, 18);
+    harness.push_lines(&["   Compiling broken v0.1.0 (/tmp/broken)", "error[E0308]: mismatched types", " --> src/main.rs:2:18"]);
+    harness.app_mut().open_source("|printf 'noise 1\\nnoise 2\\n'").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !harness.render().contains("noise 2") && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(20));
+        harness.app_mut().update_logs();
+    }
+    harness.run(&[Step::Key(KeyCode::Char(':')), Step::Text("close 2"), Step::Key(KeyCode::Enter)]);
+    let jumped = harness.run(&[Step::Key(KeyCode::Char('p')), Step::Key(KeyCode::Enter)]);
+    assert!(!jumped.contains("evicted") && jumped.contains("PAUSED"), "{}", jumped);
+}
+
+#[test]
+fn audit_mode() {
+    let mut app = App::new("test.log".to_string());
+    app.start_audit().unwrap();
+    let mut harness = Harness::with_app(app, 200, 10);
+    harness.push_lines(&LINES);
+    let screen = harness.run(&[Step::Key(KeyCode::Char(':')), Step::Text("throttle 2"), Step::Key(KeyCode::Enter)]);
+    assert!(screen.contains("Logs (4/4)  AUDIT "), "{}", screen);
+    assert!(screen.contains("No throttle in audit mode"), "{}", screen);
+}
+
+#[test]
+fn help_tab() {
+    let mut harness = Harness::new(100, 40);
+    let screen = harness.run(&[Step::Key(KeyCode::BackTab)]);
+    assert_snapshot(SNAPSHOTS, "help_tab", &screen);
+}
+
+#[test]
+fn quit_key() {
+    let mut harness = Harness::new(80, 10);
+    harness.press(KeyCode::Char('q'));
+    assert!(harness.quit());
+}
+
+#[test]
+fn driven_through_a_handle() {
+    let mut harness = Harness::new(80, 14);
+    let handle = harness.app().handle();
+    std::thread::spawn(move || {
+        for line in LINES {
+            handle.push_line(line);
+        }
+        handle.set_filter("slow");
+    })
+    .join()
+    .unwrap();
+    assert_snapshot(SNAPSHOTS, "handle_filtered", &harness.render());
+    harness.app().handle().quit();
+    harness.render();
+    assert!(harness.quit());
+}
+
+#[test]
+fn a_full_handle_queue_drops_pushed_lines() {
+    let mut harness = Harness::new(200, 12);
+    let handle = harness.app().handle();
+    let queued = (0..MAX_QUEUED_REQUESTS + 3).filter(|n| handle.push_line(format!("line {}", n))).count();
+    assert_eq!(queued, MAX_QUEUED_REQUESTS);
+    let screen = harness.render();
+    assert!(screen.contains("Dropped 3 pushed lines"), "{}", screen);
+    assert!(handle.push_line("after"));
+}